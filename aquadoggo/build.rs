@@ -0,0 +1,27 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Generate gRPC service and messages, they are (de)serialized from and to the JSON RPC API
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(false)
+        .type_attribute(
+            ".aquadoggo",
+            "#[derive(serde::Serialize, serde::Deserialize)] #[serde(rename_all = \"camelCase\")]",
+        )
+        .field_attribute("Document.fields", "#[serde(with = \"crate::grpc::json\")]")
+        .field_attribute("Document.relations", "#[serde(default)]")
+        .field_attribute(
+            "FieldFilter.value",
+            "#[serde(with = \"crate::grpc::json\")]",
+        )
+        .field_attribute(
+            "DocumentGroup.group",
+            "#[serde(with = \"crate::grpc::json\")]",
+        )
+        .field_attribute(
+            "DocumentGroup.values",
+            "#[serde(with = \"crate::grpc::json\")]",
+        )
+        .compile(&["proto/aquadoggo.proto"], &["proto"])?;
+
+    Ok(())
+}
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS schema_fields (
+    schema            VARCHAR(132)      NOT NULL,
+    name              VARCHAR(63)       NOT NULL,
+    field_type        VARCHAR(16)       NOT NULL,
+    PRIMARY KEY (schema, name)
+);
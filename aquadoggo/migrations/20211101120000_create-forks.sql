@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS forks (
+    author            VARCHAR(64)       NOT NULL,
+    log_id            BIGINT            NOT NULL,
+    seq_num           BIGINT            NOT NULL,
+    entry_hash        VARCHAR(132)      NOT NULL,
+    fork_entry_bytes  TEXT              NOT NULL,
+    fork_entry_hash   VARCHAR(132)      NOT NULL PRIMARY KEY
+);
@@ -0,0 +1,3 @@
+CREATE INDEX IF NOT EXISTS logs_author_log_id ON logs (author, log_id);
+CREATE INDEX IF NOT EXISTS logs_schema ON logs (schema);
+CREATE INDEX IF NOT EXISTS materializer_tasks_status ON materializer_tasks (status);
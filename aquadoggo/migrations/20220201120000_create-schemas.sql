@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS schemas (
+    schema            VARCHAR(132)      NOT NULL,
+    author            VARCHAR(64)       NOT NULL,
+    PRIMARY KEY (schema)
+);
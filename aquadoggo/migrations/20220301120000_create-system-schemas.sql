@@ -0,0 +1,22 @@
+CREATE TABLE IF NOT EXISTS key_groups (
+    key_group         VARCHAR(132)      NOT NULL,
+    owner             VARCHAR(64)       NOT NULL,
+    name              TEXT              NOT NULL,
+    PRIMARY KEY (key_group)
+);
+
+CREATE TABLE IF NOT EXISTS key_group_members (
+    membership        VARCHAR(132)      NOT NULL,
+    key_group         VARCHAR(132)      NOT NULL,
+    member            VARCHAR(64)       NOT NULL,
+    added_by          VARCHAR(64)       NOT NULL,
+    PRIMARY KEY (membership)
+);
+
+CREATE TABLE IF NOT EXISTS author_profiles (
+    author            VARCHAR(64)       NOT NULL,
+    display_name      TEXT              NOT NULL,
+    entry_hash        VARCHAR(132)      NOT NULL,
+    seq_num           BIGINT            NOT NULL,
+    PRIMARY KEY (author)
+);
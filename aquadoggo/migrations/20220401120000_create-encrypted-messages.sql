@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS encrypted_messages (
+    entry_hash        VARCHAR(132)      NOT NULL,
+    key_group         VARCHAR(132)      NOT NULL,
+    author            VARCHAR(64)       NOT NULL,
+    seq_num           BIGINT            NOT NULL,
+    ciphertext        TEXT              NOT NULL,
+    PRIMARY KEY (entry_hash)
+);
+
+CREATE INDEX IF NOT EXISTS encrypted_messages_key_group ON encrypted_messages (key_group);
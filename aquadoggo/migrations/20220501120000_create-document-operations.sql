@@ -0,0 +1,11 @@
+CREATE TABLE IF NOT EXISTS document_operations (
+    entry_hash        VARCHAR(132)      NOT NULL,
+    schema            VARCHAR(132)      NOT NULL,
+    document_id       VARCHAR(132)      NOT NULL,
+    author            VARCHAR(64)       NOT NULL,
+    log_id            BIGINT            NOT NULL,
+    seq_num           BIGINT            NOT NULL,
+    PRIMARY KEY (entry_hash)
+);
+
+CREATE INDEX IF NOT EXISTS document_operations_document_id ON document_operations (document_id);
@@ -0,0 +1,5 @@
+CREATE TABLE IF NOT EXISTS materializer_tasks (
+    entry_hash        VARCHAR(132)      NOT NULL PRIMARY KEY,
+    status            VARCHAR(16)       NOT NULL,
+    retries           BIGINT            NOT NULL
+);
@@ -0,0 +1,3 @@
+CREATE INDEX logs_author_log_id ON logs (author, log_id);
+CREATE INDEX logs_schema ON logs ("schema");
+CREATE INDEX materializer_tasks_status ON materializer_tasks (status);
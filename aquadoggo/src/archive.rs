@@ -0,0 +1,215 @@
+use std::io::{BufRead, Write};
+
+use anyhow::{Context, Result};
+use p2panda_rs::entry::EntrySigned;
+use p2panda_rs::message::MessageEncoded;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::db::models::{Entry, MaterializerTask};
+use crate::db::{EntryStore, Pool, SqlStorage};
+use crate::rpc::{store_entry, Subscriptions};
+
+/// Number of entries loaded from the database at once during export.
+const EXPORT_PAGE_SIZE: i64 = 500;
+
+/// Entry with its message payload as written to export files.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct ArchivedEntry {
+    entry_encoded: EntrySigned,
+    message_encoded: MessageEncoded,
+}
+
+/// Write all stored entries as one JSON object per line, returns the number of exported entries.
+///
+/// Entries are ordered by author, log id and sequence number, importing them in this order makes
+/// sure that backlinks and skiplinks are always stored before the entries referring to them.
+pub async fn export_entries<W: Write>(pool: &Pool, mut writer: W) -> Result<usize> {
+    let mut exported = 0;
+    let mut offset = 0;
+
+    loop {
+        let entries = Entry::query(pool, None, None, None, EXPORT_PAGE_SIZE, offset).await?;
+        if entries.is_empty() {
+            break;
+        }
+        offset += entries.len() as i64;
+
+        for entry in entries {
+            // Entries with deleted payloads can not be verified during import
+            let payload_bytes = match entry.payload_bytes {
+                Some(payload_bytes) => payload_bytes,
+                None => {
+                    warn!("Skip entry {} without payload", entry.entry_hash.as_str());
+                    continue;
+                }
+            };
+
+            let archived = ArchivedEntry {
+                entry_encoded: EntrySigned::new(&entry.entry_bytes)?,
+                message_encoded: MessageEncoded::new(&payload_bytes)?,
+            };
+
+            serde_json::to_writer(&mut writer, &archived)?;
+            writer.write_all(b"\n")?;
+            exported += 1;
+        }
+    }
+
+    writer.flush()?;
+
+    Ok(exported)
+}
+
+/// Validate and store entries written by `export_entries`, returns the number of imported
+/// entries.
+///
+/// Every entry gets verified like a published one before it is stored. Entries which are already
+/// stored get skipped. Imported entries are queued for materialization, which happens the next
+/// time the node starts.
+pub async fn import_entries<R: BufRead>(pool: &Pool, reader: R) -> Result<usize> {
+    let storage = SqlStorage::new(pool.clone());
+    let subscriptions = Subscriptions::new();
+    let mut imported = 0;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let archived: ArchivedEntry = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid entry on line {}", index + 1))?;
+
+        if storage
+            .entry_by_hash(&archived.entry_encoded.hash())
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        store_entry(
+            &storage,
+            &subscriptions,
+            &archived.entry_encoded,
+            &archived.message_encoded,
+        )
+        .await
+        .with_context(|| format!("Could not import entry on line {}", index + 1))?;
+
+        MaterializerTask::insert(pool, &archived.entry_encoded.hash()).await?;
+        imported += 1;
+    }
+
+    Ok(imported)
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use super::{export_entries, import_entries};
+
+    use crate::db::models::{Entry, MaterializerTask};
+    use crate::db::SqlStorage;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, initialize_db};
+
+    #[async_std::test]
+    async fn export_and_import() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2]
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        for (entry, message) in [(&entry_1, &message_1), (&entry_2, &message_2)].iter() {
+            store_entry(&storage, &Subscriptions::new(), entry, message)
+                .await
+                .unwrap();
+        }
+
+        let mut file = Vec::new();
+        assert_eq!(export_entries(&pool, &mut file).await.unwrap(), 2);
+
+        // Import into empty database
+        let pool = initialize_db().await;
+        assert_eq!(import_entries(&pool, file.as_slice()).await.unwrap(), 2);
+
+        let entries = Entry::query(&pool, None, None, None, 10, 0).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].entry_hash, entry_2.hash());
+
+        // Imported entries are queued for materialization
+        let tasks = MaterializerTask::pending(&pool, 10).await.unwrap();
+        assert_eq!(tasks.len(), 2);
+
+        // Entries which already exist get skipped
+        assert_eq!(import_entries(&pool, file.as_slice()).await.unwrap(), 0);
+    }
+
+    #[async_std::test]
+    async fn reject_entries_with_missing_backlinks() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        for (entry, message) in [(&entry_1, &message_1), (&entry_2, &message_2)].iter() {
+            store_entry(&storage, &Subscriptions::new(), entry, message)
+                .await
+                .unwrap();
+        }
+
+        let mut file = Vec::new();
+        export_entries(&pool, &mut file).await.unwrap();
+
+        // Drop first entry from export, the second one can not be verified without its backlink
+        let file: Vec<&str> = std::str::from_utf8(&file).unwrap().lines().collect();
+        let pool = initialize_db().await;
+        let error = import_entries(&pool, file[1].as_bytes()).await.unwrap_err();
+        assert_eq!(error.to_string(), "Could not import entry on line 1");
+    }
+}
@@ -0,0 +1,191 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::{bail, Result};
+use async_std::future::Future;
+use async_std::process::Command;
+use async_std::sync::Mutex;
+use async_std::task;
+use futures::{pin_mut, select, FutureExt};
+use sqlx::any::AnyKind;
+use sqlx::query;
+use tracing::{error, info, instrument};
+
+use crate::db::Pool;
+
+/// File name prefix of database snapshots in the backup directory.
+const BACKUP_FILE_PREFIX: &str = "aquadoggo-backup-";
+
+/// Creates database snapshots in a backup directory and removes old ones.
+///
+/// SQLite databases are copied with `VACUUM INTO` while the node is running, PostgreSQL databases
+/// are dumped with `pg_dump` which needs to be installed on the host.
+#[derive(Debug)]
+pub struct Backups {
+    pool: Pool,
+    database_url: String,
+    directory: PathBuf,
+    retention: usize,
+    lock: Mutex<()>,
+}
+
+impl Backups {
+    /// Returns a backup handler writing to the given directory, keeping the `retention` most
+    /// recent snapshots or all of them when it is 0.
+    pub fn new(pool: Pool, database_url: &str, directory: &Path, retention: usize) -> Self {
+        Self {
+            pool,
+            database_url: database_url.to_owned(),
+            directory: directory.to_path_buf(),
+            retention,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Write a snapshot of the database to the backup directory and remove old snapshots, returns
+    /// the path of the new snapshot.
+    #[instrument(skip(self), fields(directory = %self.directory.display()))]
+    pub async fn create(&self) -> Result<PathBuf> {
+        // Only run one backup at a time, scheduled and requested backups could overlap otherwise
+        let _guard = self.lock.lock().await;
+
+        fs::create_dir_all(&self.directory)?;
+
+        let extension = match self.pool.any_kind() {
+            AnyKind::Sqlite => "sqlite3",
+            AnyKind::Postgres => "pgdump",
+            _ => bail!("Backups are only supported for SQLite and PostgreSQL databases"),
+        };
+
+        // Never overwrite an existing snapshot, even when two backups are created within the same
+        // millisecond
+        let mut timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        while self.backup_path(timestamp, extension).exists() {
+            timestamp += 1;
+        }
+        let path = self.backup_path(timestamp, extension);
+
+        match self.pool.any_kind() {
+            AnyKind::Sqlite => {
+                query("VACUUM INTO $1")
+                    .bind(path.to_str().unwrap())
+                    .execute(&self.pool)
+                    .await?;
+            }
+            _ => {
+                let status = Command::new("pg_dump")
+                    .arg("--format=custom")
+                    .arg("--file")
+                    .arg(&path)
+                    .arg(&self.database_url)
+                    .status()
+                    .await?;
+
+                if !status.success() {
+                    bail!("pg_dump failed with {}", status);
+                }
+            }
+        }
+
+        info!("Created database backup {}", path.display());
+
+        self.remove_old_backups()?;
+
+        Ok(path)
+    }
+
+    /// Returns the path of a new snapshot file.
+    fn backup_path(&self, timestamp: u128, extension: &str) -> PathBuf {
+        self.directory
+            .join(format!("{}{}.{}", BACKUP_FILE_PREFIX, timestamp, extension))
+    }
+
+    /// Delete all but the most recent snapshots according to the retention setting.
+    fn remove_old_backups(&self) -> Result<()> {
+        if self.retention == 0 {
+            return Ok(());
+        }
+
+        let mut backups: Vec<(u128, PathBuf)> = fs::read_dir(&self.directory)?
+            .filter_map(|entry| {
+                let path = entry.ok()?.path();
+                let timestamp = path
+                    .file_stem()?
+                    .to_str()?
+                    .strip_prefix(BACKUP_FILE_PREFIX)?
+                    .parse()
+                    .ok()?;
+                Some((timestamp, path))
+            })
+            .collect();
+
+        // Sort by timestamp with the most recent snapshots first
+        backups.sort_by(|a, b| b.0.cmp(&a.0));
+
+        for (_, path) in backups.into_iter().skip(self.retention) {
+            info!("Remove old database backup {}", path.display());
+            fs::remove_file(path)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create a database backup in every interval until the shutdown future resolves.
+///
+/// Failed backups are logged and retried in the next interval.
+pub async fn start_backups(
+    backups: &Backups,
+    interval: Duration,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let shutdown = shutdown.fuse();
+    pin_mut!(shutdown);
+
+    loop {
+        select! {
+            _ = task::sleep(interval).fuse() => {
+                if let Err(err) = backups.create().await {
+                    error!("Could not create database backup: {}", err);
+                }
+            },
+            _ = shutdown => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use rand::Rng;
+
+    use super::Backups;
+
+    use crate::test_helpers::initialize_db;
+
+    #[async_std::test]
+    async fn create_backups_with_retention() {
+        let pool = initialize_db().await;
+
+        let directory = std::env::temp_dir().join(format!(
+            "aquadoggo-backups-{}",
+            rand::thread_rng().gen::<u32>()
+        ));
+        let backups = Backups::new(pool, "sqlite::memory:", &directory, 2);
+
+        let mut paths = Vec::new();
+        for _ in 0..3 {
+            paths.push(backups.create().await.unwrap());
+        }
+
+        // Only the two most recent backups are kept
+        assert!(!paths[0].exists());
+        assert!(paths[1].exists());
+        assert!(paths[2].exists());
+        assert_eq!(fs::read_dir(&directory).unwrap().count(), 2);
+
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,432 @@
+//! Typed client for the JSON RPC API of aquadoggo.
+//!
+//! Requests and responses are the same types the node uses to handle RPC calls, so Rust
+//! applications can talk to a node without building JSON RPC messages by hand:
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), aquadoggo::client::ClientError> {
+//! use aquadoggo::client::RpcClient;
+//!
+//! let client = RpcClient::new("http://localhost:2020")?;
+//! let node_info = client.get_node_info().await?;
+//! println!("Connected to aquadoggo {}", node_info.version);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Subscriptions are only available via WebSocket and are not covered by this client.
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use surf::Url;
+
+pub use crate::db::models::{Entry, Fork, LogSummary, SchemaSummary};
+pub use crate::db::query_builder::{Aggregate, Operator, Order, Value as FieldValue};
+pub use crate::materializer::{DocumentFields, DocumentGroup, FieldAggregate, FieldFilter};
+pub use crate::rpc::request::{
+    AggregateDocumentsRequest, AuthorProfileRequest, DocumentRequest, EncryptedMessagesRequest,
+    EntriesByAuthorRequest, EntryArgsBatchRequest, EntryArgsRequest, EntryByHashRequest,
+    ForksRequest, KeyGroupRequest, LogsByAuthorRequest, PrunePayloadsRequest,
+    PublishEntriesRequest, PublishEntryRequest, PurgeAuthorRequest, PurgeLogRequest,
+    QueryDocumentsRequest, QueryEntriesRequest, ValidateEntryRequest,
+};
+pub use crate::rpc::response::{
+    AggregateDocumentsResponse, AuthorProfileResponse, CreateBackupResponse, DocumentResponse,
+    EncryptedMessagesResponse, EntriesByAuthorResponse, EntryArgsBatchResponse, EntryArgsResponse,
+    EntryByHashResponse, ForksResponse, KeyGroupResponse, LogsByAuthorResponse, NodeInfoResponse,
+    PrunePayloadsResponse, PublishEntriesResponse, PublishEntryResponse, PurgeResponse,
+    QueryDocumentsResponse, QueryEntriesResponse, SchemasResponse, StatsResponse,
+    ValidateEntryResponse,
+};
+
+/// Represents all the ways an RPC call can fail.
+#[derive(thiserror::Error, Debug)]
+pub enum ClientError {
+    /// The given node URL could not be parsed.
+    #[error("Invalid node URL: {0}")]
+    InvalidUrl(#[from] surf::http::url::ParseError),
+
+    /// The request could not be sent or the node responded with an unexpected status.
+    #[error("HTTP request failed: {0}")]
+    Http(String),
+
+    /// The node returned an error for this call.
+    #[error("RPC error {code}: {message}")]
+    Rpc {
+        /// JSON RPC error code.
+        code: i64,
+
+        /// Error message given by the node.
+        message: String,
+    },
+
+    /// Parameters could not be encoded or the result could not be decoded.
+    #[error("Invalid RPC message: {0}")]
+    InvalidMessage(#[from] serde_json::Error),
+}
+
+/// Error object of a JSON RPC response.
+#[derive(Deserialize, Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+/// JSON RPC response containing either a result or an error.
+#[derive(Deserialize, Debug)]
+struct RpcResponse {
+    #[serde(default)]
+    result: Value,
+    error: Option<RpcError>,
+}
+
+/// Client calling the RPC methods of a node via HTTP.
+#[derive(Debug)]
+pub struct RpcClient {
+    client: surf::Client,
+    url: Url,
+    token: Option<String>,
+    next_id: AtomicU64,
+}
+
+impl RpcClient {
+    /// Returns a client for the node served at the given URL, for example
+    /// `http://localhost:2020`.
+    pub fn new(url: &str) -> Result<Self, ClientError> {
+        Ok(Self {
+            client: surf::Client::new(),
+            url: Url::parse(url)?,
+            token: None,
+            next_id: AtomicU64::new(1),
+        })
+    }
+
+    /// Send the given bearer token with every request, required to publish entries on nodes
+    /// restricting write methods and to call admin methods with the admin token.
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_owned());
+        self
+    }
+
+    /// Call a method and decode its result.
+    ///
+    /// Pass `()` as parameters for methods without parameters.
+    async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+    ) -> Result<R, ClientError> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+
+        let mut body = json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+        });
+
+        let params = serde_json::to_value(params)?;
+        if !params.is_null() {
+            body["params"] = params;
+        }
+
+        let mut request = self.client.post(self.url.clone()).body(body);
+        if let Some(token) = &self.token {
+            request = request.header("Authorization", format!("Bearer {}", token));
+        }
+
+        let mut response = request
+            .await
+            .map_err(|err| ClientError::Http(err.to_string()))?;
+
+        if !response.status().is_success() {
+            return Err(ClientError::Http(format!(
+                "Node responded with {}",
+                response.status()
+            )));
+        }
+
+        let response: RpcResponse = response
+            .body_json()
+            .await
+            .map_err(|err| ClientError::Http(err.to_string()))?;
+
+        match response.error {
+            Some(error) => Err(ClientError::Rpc {
+                code: error.code,
+                message: error.message,
+            }),
+            None => Ok(serde_json::from_value(response.result)?),
+        }
+    }
+
+    /// Count documents and find minimum and maximum values of their fields
+    /// (`panda_aggregateDocuments`).
+    pub async fn aggregate_documents(
+        &self,
+        request: &AggregateDocumentsRequest,
+    ) -> Result<AggregateDocumentsResponse, ClientError> {
+        self.call("panda_aggregateDocuments", request).await
+    }
+
+    /// Write a snapshot of the database, requires the admin token (`panda_createBackup`).
+    pub async fn create_backup(&self) -> Result<CreateBackupResponse, ClientError> {
+        self.call("panda_createBackup", ()).await
+    }
+
+    /// Return all stored schemas (`panda_getAllSchemas`).
+    pub async fn get_all_schemas(&self) -> Result<SchemasResponse, ClientError> {
+        self.call("panda_getAllSchemas", ()).await
+    }
+
+    /// Return the profile of an author and the key groups the author is a member of, `None` when
+    /// the author has no profile (`panda_getAuthorProfile`).
+    pub async fn get_author_profile(
+        &self,
+        request: &AuthorProfileRequest,
+    ) -> Result<Option<AuthorProfileResponse>, ClientError> {
+        self.call("panda_getAuthorProfile", request).await
+    }
+
+    /// Return the materialized state of a document, `None` when it does not exist
+    /// (`panda_getDocument`).
+    pub async fn get_document(
+        &self,
+        request: &DocumentRequest,
+    ) -> Result<Option<DocumentResponse>, ClientError> {
+        self.call("panda_getDocument", request).await
+    }
+
+    /// Return the messages encrypted for a key group, requires a signature of a member, `None`
+    /// when the key group does not exist (`panda_getEncryptedMessages`).
+    pub async fn get_encrypted_messages(
+        &self,
+        request: &EncryptedMessagesRequest,
+    ) -> Result<Option<EncryptedMessagesResponse>, ClientError> {
+        self.call("panda_getEncryptedMessages", request).await
+    }
+
+    /// Return entries of an author within a range of sequence numbers
+    /// (`panda_getEntriesByAuthor`).
+    pub async fn get_entries_by_author(
+        &self,
+        request: &EntriesByAuthorRequest,
+    ) -> Result<EntriesByAuthorResponse, ClientError> {
+        self.call("panda_getEntriesByAuthor", request).await
+    }
+
+    /// Return the arguments to create the next entry of an author (`panda_getEntryArguments`).
+    pub async fn get_entry_args(
+        &self,
+        request: &EntryArgsRequest,
+    ) -> Result<EntryArgsResponse, ClientError> {
+        self.call("panda_getEntryArguments", request).await
+    }
+
+    /// Return entry arguments for several authors and schemas (`panda_getEntryArgumentsBatch`).
+    pub async fn get_entry_args_batch(
+        &self,
+        request: &EntryArgsBatchRequest,
+    ) -> Result<EntryArgsBatchResponse, ClientError> {
+        self.call("panda_getEntryArgumentsBatch", request).await
+    }
+
+    /// Return an entry and its message by the entry hash, `None` when it does not exist
+    /// (`panda_getEntryByHash`).
+    pub async fn get_entry_by_hash(
+        &self,
+        request: &EntryByHashRequest,
+    ) -> Result<Option<EntryByHashResponse>, ClientError> {
+        self.call("panda_getEntryByHash", request).await
+    }
+
+    /// Return refused entries of forked logs of an author (`panda_getForks`).
+    pub async fn get_forks(&self, request: &ForksRequest) -> Result<ForksResponse, ClientError> {
+        self.call("panda_getForks", request).await
+    }
+
+    /// Return a key group and its members, `None` when it does not exist (`panda_getKeyGroup`).
+    pub async fn get_key_group(
+        &self,
+        request: &KeyGroupRequest,
+    ) -> Result<Option<KeyGroupResponse>, ClientError> {
+        self.call("panda_getKeyGroup", request).await
+    }
+
+    /// Return the logs of an author with their latest sequence number
+    /// (`panda_getLogsByAuthor`).
+    pub async fn get_logs_by_author(
+        &self,
+        request: &LogsByAuthorRequest,
+    ) -> Result<LogsByAuthorResponse, ClientError> {
+        self.call("panda_getLogsByAuthor", request).await
+    }
+
+    /// Return details about the node (`panda_getNodeInfo`).
+    pub async fn get_node_info(&self) -> Result<NodeInfoResponse, ClientError> {
+        self.call("panda_getNodeInfo", ()).await
+    }
+
+    /// Return entry, log and author counts of the node (`panda_getStats`).
+    pub async fn get_stats(&self) -> Result<StatsResponse, ClientError> {
+        self.call("panda_getStats", ()).await
+    }
+
+    /// Remove payloads of a schema's entries while keeping the entries, requires the admin token
+    /// (`panda_prunePayloads`).
+    pub async fn prune_payloads(
+        &self,
+        request: &PrunePayloadsRequest,
+    ) -> Result<PrunePayloadsResponse, ClientError> {
+        self.call("panda_prunePayloads", request).await
+    }
+
+    /// Store a batch of entries of one author (`panda_publishEntries`).
+    pub async fn publish_entries(
+        &self,
+        request: &PublishEntriesRequest,
+    ) -> Result<PublishEntriesResponse, ClientError> {
+        self.call("panda_publishEntries", request).await
+    }
+
+    /// Store an entry with its message (`panda_publishEntry`).
+    pub async fn publish_entry(
+        &self,
+        request: &PublishEntryRequest,
+    ) -> Result<PublishEntryResponse, ClientError> {
+        self.call("panda_publishEntry", request).await
+    }
+
+    /// Remove all data of an author, requires the admin token (`panda_purgeAuthor`).
+    pub async fn purge_author(
+        &self,
+        request: &PurgeAuthorRequest,
+    ) -> Result<PurgeResponse, ClientError> {
+        self.call("panda_purgeAuthor", request).await
+    }
+
+    /// Remove a log of an author, requires the admin token (`panda_purgeLog`).
+    pub async fn purge_log(&self, request: &PurgeLogRequest) -> Result<PurgeResponse, ClientError> {
+        self.call("panda_purgeLog", request).await
+    }
+
+    /// Filter, sort and paginate documents of a schema (`panda_queryDocuments`).
+    pub async fn query_documents(
+        &self,
+        request: &QueryDocumentsRequest,
+    ) -> Result<QueryDocumentsResponse, ClientError> {
+        self.call("panda_queryDocuments", request).await
+    }
+
+    /// Return entries filtered by author and schema (`panda_queryEntries`).
+    pub async fn query_entries(
+        &self,
+        request: &QueryEntriesRequest,
+    ) -> Result<QueryEntriesResponse, ClientError> {
+        self.call("panda_queryEntries", request).await
+    }
+
+    /// Validate an entry with its message and links without storing it
+    /// (`panda_validateEntry`).
+    pub async fn validate_entry(
+        &self,
+        request: &ValidateEntryRequest,
+    ) -> Result<ValidateEntryResponse, ClientError> {
+        self.call("panda_validateEntry", request).await
+    }
+
+    /// Return the OpenRPC document describing the API of the node (`rpc.discover`).
+    pub async fn discover(&self) -> Result<Value, ClientError> {
+        self.call("rpc.discover", ()).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use rand::Rng;
+
+    use super::{
+        ClientError, EntryArgsRequest, EntryByHashRequest, PublishEntryRequest, RpcClient,
+    };
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::{build_rpc_api_service, build_rpc_server, RpcApiOptions};
+    use crate::test_helpers::create_test_entry;
+
+    #[async_std::test]
+    async fn call_rpc_methods() {
+        // Start node with in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+        let port = rand::thread_rng().gen_range(20000..30000);
+        async_std::task::spawn(app.listen(format!("127.0.0.1:{}", port)));
+        async_std::task::sleep(Duration::from_millis(100)).await;
+
+        let client = RpcClient::new(&format!("http://127.0.0.1:{}", port)).unwrap();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        // Request arguments for the first entry of a new log
+        let args = client
+            .get_entry_args(&EntryArgsRequest {
+                author: entry.author(),
+                schema,
+            })
+            .await
+            .unwrap();
+        assert_eq!(args.log_id, LogId::new(1));
+        assert_eq!(args.seq_num, SeqNum::new(1).unwrap());
+        assert!(args.entry_hash_backlink.is_none());
+
+        // Publish the entry
+        let response = client
+            .publish_entry(&PublishEntryRequest {
+                entry_encoded: entry.clone(),
+                message_encoded: message.clone(),
+                dry_run: false,
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.entry_hash, entry.hash());
+        assert_eq!(response.seq_num, SeqNum::new(2).unwrap());
+        assert_eq!(response.log_id, LogId::new(1));
+
+        let stored = client
+            .get_entry_by_hash(&EntryByHashRequest { hash: entry.hash() })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.entry_encoded, entry.as_str());
+        assert_eq!(stored.message_encoded.unwrap(), message.as_str());
+
+        // Results of unknown entries are empty
+        let unknown = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+        let stored = client
+            .get_entry_by_hash(&EntryByHashRequest { hash: unknown })
+            .await
+            .unwrap();
+        assert!(stored.is_none());
+
+        let node_info = client.get_node_info().await.unwrap();
+        assert_eq!(node_info.entry_count, 1);
+
+        // Admin methods are refused when no admin token is configured
+        let result = client.with_token("secret").create_backup().await;
+        assert!(matches!(result, Err(ClientError::Rpc { .. })));
+    }
+}
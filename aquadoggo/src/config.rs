@@ -1,25 +1,42 @@
+use std::collections::HashMap;
+use std::env;
 use std::fs;
+use std::net::{IpAddr, Ipv4Addr};
 use std::path::PathBuf;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use directories::ProjectDirs;
 use serde::Deserialize;
+use toml::Value;
 
 /// Data directory name.
 const DATA_DIR_NAME: &str = "aquadoggo";
 
+/// Filename of configuration file in data directory.
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Name of default backup directory in data directory.
+const BACKUP_DIR_NAME: &str = "backups";
+
 /// Filename of default sqlite database.
 const DEFAULT_SQLITE_NAME: &str = "aquadoggo-node.sqlite3";
 
+/// Database url schemes supported by the node.
+const SUPPORTED_DATABASE_SCHEMES: [&str; 4] = ["sqlite:", "postgres:", "postgresql:", "mysql:"];
+
 /// Configuration object holding all important variables throughout the application.
 ///
 /// Each configuration also assures that a data directory exists on the host machine where database
 /// files or private keys get persisted.
 ///
+/// Settings are read from a TOML configuration file and environment variables, environment
+/// variables take precedence. Keys in the configuration file are the lowercase names of the
+/// environment variables, lists can be given as arrays.
+///
 /// When no custom directory path is set it reads the process environment $XDG_DATA_HOME variable
 /// to determine the XDG data directory path which is $HOME/.local/share/aquadoggo on Linux by
 /// default.
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct Configuration {
     /// Path to data directory.
@@ -31,11 +48,148 @@ pub struct Configuration {
     /// Maximum number of database connections in pool.
     pub database_max_connections: u32,
 
+    /// Maximum time in seconds to wait for a free database connection.
+    pub database_acquire_timeout: u64,
+
+    /// Time in seconds after which idle database connections are closed, never when 0.
+    pub database_idle_timeout: u64,
+
+    /// Journal mode of SQLite databases, `WAL` allows reads while writing.
+    pub database_sqlite_journal_mode: String,
+
+    /// Time in milliseconds SQLite waits for a locked database before failing.
+    pub database_sqlite_busy_timeout: u64,
+
+    /// Synchronous level of SQLite databases, `NORMAL` is safe in `WAL` mode.
+    pub database_sqlite_synchronous: String,
+
+    /// Database url for document queries, entry listings and statistics, for example of a read
+    /// replica. A separate pool to `database_url` is used when not set.
+    pub database_read_url: Option<String>,
+
+    /// Maximum number of database connections in the pool for read-only queries.
+    pub database_read_max_connections: u32,
+
+    /// Directory for database backups, <data-dir>/backups by default.
+    pub backup_path: Option<PathBuf>,
+
+    /// Interval between scheduled database backups in seconds, disabled when 0.
+    pub backup_interval: u64,
+
+    /// Number of database backups to keep, all backups are kept when 0.
+    pub backup_retention: usize,
+
+    /// Interval between database maintenance runs reclaiming unused space in seconds, disabled
+    /// when 0.
+    pub database_maintenance_interval: u64,
+
+    /// Number of latest entries of every log of a schema which keep their payloads, given as
+    /// `<schema hash>=<number>`. Older payloads get pruned during database maintenance runs.
+    pub payload_retention: Vec<String>,
+
+    /// Number of latest entries of every log of a schema which are kept next to the lipmaa
+    /// certificate pool, given as `<schema hash>=<number>`. Older entries get removed during
+    /// database maintenance runs.
+    pub log_retention: Vec<String>,
+
+    /// RPC API HTTP server address to bind to.
+    pub http_address: IpAddr,
+
     /// RPC API HTTP server port.
     pub http_port: u16,
 
     /// RPC API WebSocket server port.
     pub ws_port: u16,
+
+    /// Maximum number of RPC requests per minute from a single IP address, unlimited when 0.
+    pub rpc_rate_limit: u32,
+
+    /// Path of a unix domain socket to serve the RPC API on, next to the HTTP server port.
+    pub rpc_socket_path: Option<PathBuf>,
+
+    /// Sign HTTP RPC responses with the key pair of this node.
+    pub rpc_sign_responses: bool,
+
+    /// Maximum time in seconds to handle an RPC request, unlimited when 0.
+    pub rpc_request_timeout: u64,
+
+    /// Maximum number of RPC requests handled at the same time, unlimited when 0.
+    pub rpc_max_in_flight_requests: usize,
+
+    /// Origins allowed to send cross-origin requests to the HTTP server, `*` allows all origins.
+    pub cors_allowed_origins: Vec<String>,
+
+    /// HTTP methods allowed in cross-origin requests.
+    pub cors_allowed_methods: Vec<String>,
+
+    /// Request headers allowed in cross-origin requests, `*` allows all headers.
+    pub cors_allowed_headers: Vec<String>,
+
+    /// gRPC API server port, only available when built with the `grpc` feature.
+    pub grpc_port: Option<u16>,
+
+    /// Secret token required to call admin RPC methods, they are disabled when not set.
+    pub admin_token: Option<String>,
+
+    /// Bearer tokens of clients allowed to publish entries, everyone can publish when empty.
+    pub write_tokens: Vec<String>,
+
+    /// Port for peer-to-peer connections with other nodes.
+    pub network_port: u16,
+
+    /// Discover other nodes on the local network via mDNS.
+    pub network_mdns: bool,
+
+    /// Addresses of nodes to join the DHT with, including their peer ids.
+    pub network_bootstrap_peers: Vec<String>,
+
+    /// Addresses of nodes to always stay connected to, including their peer ids.
+    pub network_peers: Vec<String>,
+
+    /// Forward traffic between nodes which can not connect to each other directly.
+    pub network_relay: bool,
+
+    /// Address of a relay node including its peer id, used to be reachable when other nodes can
+    /// not connect to this node directly.
+    pub network_relay_address: Option<String>,
+
+    /// Address of the SOCKS5 proxy of a Tor daemon, used to connect to `.onion` addresses.
+    pub network_tor_proxy: Option<String>,
+
+    /// Onion service address of this node announced to other nodes, for example
+    /// `/onion3/<address>:2024`.
+    pub network_onion_address: Option<String>,
+
+    /// Only accept entries of these author public keys, all authors are accepted when empty.
+    pub allowed_authors: Vec<String>,
+
+    /// Never accept entries of these author public keys.
+    pub blocked_authors: Vec<String>,
+
+    /// Only accept entries of these schema hashes, entries of all schemas are accepted when empty.
+    pub accepted_schemas: Vec<String>,
+
+    /// Maximum size of encoded messages in bytes, unlimited when 0.
+    pub max_message_size: usize,
+
+    /// Only replicate entries of these schema hashes, all schemas are replicated when empty.
+    pub replication_schemas: Vec<String>,
+
+    /// Never replicate entries of these schema hashes.
+    pub replication_ignored_schemas: Vec<String>,
+
+    /// Record every change of a document in the history table of its schema.
+    pub document_history: bool,
+
+    /// Schemas whose entries are removed once they are older than a time to live in seconds,
+    /// given as `<schema hash>=<seconds>`.
+    pub ephemeral_schemas: Vec<String>,
+
+    /// URLs to send created and updated documents to, given as `<schema hash>=<url>`.
+    pub webhooks: Vec<String>,
+
+    /// Secret to sign webhook payloads with, required when webhooks are configured.
+    pub webhook_secret: Option<String>,
 }
 
 impl Default for Configuration {
@@ -44,12 +198,83 @@ impl Default for Configuration {
             base_path: None,
             database_url: None,
             database_max_connections: 32,
+            database_acquire_timeout: 30,
+            database_idle_timeout: 600,
+            database_sqlite_journal_mode: "WAL".to_owned(),
+            database_sqlite_busy_timeout: 5000,
+            database_sqlite_synchronous: "NORMAL".to_owned(),
+            database_read_url: None,
+            database_read_max_connections: 32,
+            backup_path: None,
+            backup_interval: 0,
+            backup_retention: 7,
+            database_maintenance_interval: 0,
+            payload_retention: Vec::new(),
+            log_retention: Vec::new(),
+            http_address: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
             http_port: 2020,
             ws_port: 2022,
+            rpc_rate_limit: 600,
+            rpc_socket_path: None,
+            rpc_sign_responses: false,
+            rpc_request_timeout: 30,
+            rpc_max_in_flight_requests: 256,
+            cors_allowed_origins: vec!["*".to_owned()],
+            cors_allowed_methods: vec!["GET".to_owned(), "POST".to_owned(), "OPTIONS".to_owned()],
+            cors_allowed_headers: vec!["*".to_owned()],
+            grpc_port: None,
+            admin_token: None,
+            write_tokens: Vec::new(),
+            network_port: 2024,
+            network_mdns: true,
+            network_bootstrap_peers: Vec::new(),
+            network_peers: Vec::new(),
+            network_relay: false,
+            network_relay_address: None,
+            network_tor_proxy: None,
+            network_onion_address: None,
+            allowed_authors: Vec::new(),
+            blocked_authors: Vec::new(),
+            accepted_schemas: Vec::new(),
+            max_message_size: 256 * 1024,
+            replication_schemas: Vec::new(),
+            replication_ignored_schemas: Vec::new(),
+            document_history: false,
+            ephemeral_schemas: Vec::new(),
+            webhooks: Vec::new(),
+            webhook_secret: None,
         }
     }
 }
 
+/// Returns the string representation of a configuration file value as used in environment
+/// variables.
+fn config_value(key: &str, value: Value) -> Result<String> {
+    match value {
+        Value::String(value) => Ok(value),
+        Value::Integer(value) => Ok(value.to_string()),
+        Value::Float(value) => Ok(value.to_string()),
+        Value::Boolean(value) => Ok(value.to_string()),
+        Value::Array(values) => Ok(values
+            .into_iter()
+            .map(|value| config_value(key, value))
+            .collect::<Result<Vec<String>>>()?
+            .join(",")),
+        _ => bail!("Unsupported value for {} in configuration file", key),
+    }
+}
+
+/// Parses the contents of a TOML configuration file into variables keyed like environment
+/// variables.
+fn parse_config_file(contents: &str) -> Result<HashMap<String, String>> {
+    let table: toml::value::Table = toml::from_str(contents)?;
+
+    table
+        .into_iter()
+        .map(|(key, value)| Ok((key.to_uppercase(), config_value(&key, value)?)))
+        .collect()
+}
+
 impl Configuration {
     /// Returns the data directory path and creates the folders when not existing.
     fn create_data_directory(path: Option<PathBuf>) -> Result<PathBuf> {
@@ -68,21 +293,50 @@ impl Configuration {
         Ok(base_path)
     }
 
-    /// Create a new configuration object pulling in the variables from the process environment.
-    /// This method also assures a data directory exists on the host machine.
-    pub fn new(path: Option<PathBuf>) -> Result<Self> {
+    /// Create a new configuration object pulling in the variables from the configuration file and
+    /// process environment. This method also assures a data directory exists on the host machine.
+    ///
+    /// When no configuration file path is given, `config.toml` in the data directory is used when
+    /// it exists.
+    pub fn new(path: Option<PathBuf>, config_file: Option<PathBuf>) -> Result<Self> {
         // Make sure data directory exists
         let base_path = Self::create_data_directory(path)?;
 
-        // Create configuration based on defaults and populate with environment variables
-        let mut config = envy::from_env::<Self>()?;
+        // Read variables from configuration file
+        let config_file = config_file.or_else(|| {
+            let path = base_path.join(CONFIG_FILE_NAME);
+            if path.exists() {
+                Some(path)
+            } else {
+                None
+            }
+        });
+
+        let mut vars = match config_file {
+            Some(path) => parse_config_file(&fs::read_to_string(path)?)?,
+            None => HashMap::new(),
+        };
+
+        // Environment variables take precedence over the configuration file
+        vars.extend(env::vars());
+
+        // Create configuration based on defaults and populate with variables
+        let mut config = envy::from_iter::<_, Self>(vars)?;
+
+        // Store backups in data directory when no other path is given
+        config.backup_path = config
+            .backup_path
+            .or_else(|| Some(base_path.join(BACKUP_DIR_NAME)));
 
         // Store data directory path in object
         config.base_path = Some(base_path);
 
         // Set default database url (sqlite) when not given
         config.database_url = match config.database_url {
-            Some(url) => Some(url),
+            Some(url) => {
+                check_database_url(&url)?;
+                Some(url)
+            }
             None => {
                 let mut path = config.base_path.clone().unwrap();
                 path.push(DEFAULT_SQLITE_NAME);
@@ -90,6 +344,47 @@ impl Configuration {
             }
         };
 
+        if let Some(url) = &config.database_read_url {
+            check_database_url(url)?;
+        }
+
         Ok(config)
     }
 }
+
+/// Returns an error when the database backend of the url is not supported.
+fn check_database_url(url: &str) -> Result<()> {
+    if !SUPPORTED_DATABASE_SCHEMES
+        .iter()
+        .any(|scheme| url.starts_with(scheme))
+    {
+        bail!("Unsupported database url {}", url);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_config_file, Configuration};
+
+    #[test]
+    fn config_file() {
+        let vars = parse_config_file(
+            r#"
+            database_max_connections = 8
+            network_mdns = false
+            network_peers = ["/ip4/1.2.3.4/tcp/2024/p2p/a", "/ip4/5.6.7.8/tcp/2024/p2p/b"]
+            "#,
+        )
+        .unwrap();
+
+        let config = envy::from_iter::<_, Configuration>(vars).unwrap();
+        assert_eq!(config.database_max_connections, 8);
+        assert!(!config.network_mdns);
+        assert_eq!(config.network_peers.len(), 2);
+        assert_eq!(config.http_port, 2020);
+
+        assert!(parse_config_file("network_peers = [{ address = 1 }]").is_err());
+    }
+}
@@ -0,0 +1,179 @@
+use std::sync::{Arc, Mutex};
+
+use lru::LruCache;
+use p2panda_rs::entry::LogId;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use crate::db::models::Entry;
+
+/// Maximum number of log ids kept in memory.
+const LOG_ID_CACHE_SIZE: usize = 10_000;
+
+/// Maximum number of latest log entries kept in memory.
+const LOG_HEAD_CACHE_SIZE: usize = 10_000;
+
+/// In-memory cache of the registered log ids of author's schemas.
+///
+/// Log ids never change once registered, entries only need to be removed when logs get deleted.
+/// The cache can be cheaply cloned as all clones share the same entries.
+#[derive(Debug, Clone)]
+pub struct LogIdCache {
+    log_ids: Arc<Mutex<LruCache<(String, String), LogId>>>,
+}
+
+impl Default for LogIdCache {
+    fn default() -> Self {
+        Self {
+            log_ids: Arc::new(Mutex::new(LruCache::new(LOG_ID_CACHE_SIZE))),
+        }
+    }
+}
+
+impl LogIdCache {
+    /// Returns an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached log id of an author's schema.
+    pub fn get(&self, author: &Author, schema: &Hash) -> Option<LogId> {
+        self.log_ids
+            .lock()
+            .unwrap()
+            .get(&(author.as_str().to_owned(), schema.as_str().to_owned()))
+            .cloned()
+    }
+
+    /// Remember the registered log id of an author's schema.
+    pub fn insert(&self, author: &Author, schema: &Hash, log_id: &LogId) {
+        self.log_ids.lock().unwrap().put(
+            (author.as_str().to_owned(), schema.as_str().to_owned()),
+            log_id.clone(),
+        );
+    }
+
+    /// Forget the log id of an author's schema.
+    pub fn remove(&self, author: &Author, schema: &Hash) {
+        self.log_ids
+            .lock()
+            .unwrap()
+            .pop(&(author.as_str().to_owned(), schema.as_str().to_owned()));
+    }
+
+    /// Forget all log ids of an author.
+    pub fn remove_author(&self, author: &Author) {
+        let mut log_ids = self.log_ids.lock().unwrap();
+
+        let keys: Vec<(String, String)> = log_ids
+            .iter()
+            .filter(|((cached_author, _), _)| cached_author == author.as_str())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in keys {
+            log_ids.pop(&key);
+        }
+    }
+}
+
+/// In-memory cache of the latest entry of author's logs.
+///
+/// The cache can be cheaply cloned as all clones share the same entries.
+#[derive(Debug, Clone)]
+pub struct LogHeadCache {
+    heads: Arc<Mutex<LruCache<(String, i64), Entry>>>,
+}
+
+impl Default for LogHeadCache {
+    fn default() -> Self {
+        Self {
+            heads: Arc::new(Mutex::new(LruCache::new(LOG_HEAD_CACHE_SIZE))),
+        }
+    }
+}
+
+impl LogHeadCache {
+    /// Returns an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached latest entry of an author's log.
+    pub fn get(&self, author: &Author, log_id: &LogId) -> Option<Entry> {
+        self.heads
+            .lock()
+            .unwrap()
+            .get(&(author.as_str().to_owned(), log_id.as_i64()))
+            .cloned()
+    }
+
+    /// Remember a stored entry as latest entry of its log, unless a later one is known already.
+    pub fn update(&self, entry: Entry) {
+        let mut heads = self.heads.lock().unwrap();
+        let key = (entry.author.as_str().to_owned(), entry.log_id.as_i64());
+
+        if let Some(head) = heads.peek(&key) {
+            if head.seq_num.as_i64() >= entry.seq_num.as_i64() {
+                return;
+            }
+        }
+
+        heads.put(key, entry);
+    }
+
+    /// Forget the latest entries of all logs of an author.
+    pub fn remove_author(&self, author: &Author) {
+        let mut heads = self.heads.lock().unwrap();
+
+        let keys: Vec<(String, i64)> = heads
+            .iter()
+            .filter(|((cached_author, _), _)| cached_author == author.as_str())
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        for key in keys {
+            heads.pop(&key);
+        }
+    }
+
+    /// Forget the latest entries of all logs.
+    pub fn clear(&self) {
+        self.heads.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::LogId;
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::LogIdCache;
+
+    const TEST_AUTHOR: &str = "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a";
+
+    const OTHER_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[test]
+    fn cache_log_ids() {
+        let cache = LogIdCache::new();
+        let author = Author::new(TEST_AUTHOR).unwrap();
+        let other_author = Author::new(OTHER_AUTHOR).unwrap();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        assert!(cache.get(&author, &schema).is_none());
+        cache.insert(&author, &schema, &LogId::new(1));
+        cache.insert(&other_author, &schema, &LogId::new(3));
+        assert_eq!(cache.get(&author, &schema), Some(LogId::new(1)));
+
+        cache.remove(&author, &schema);
+        assert!(cache.get(&author, &schema).is_none());
+
+        // Only log ids of the given author are removed
+        cache.insert(&author, &schema, &LogId::new(1));
+        cache.remove_author(&author);
+        assert!(cache.get(&author, &schema).is_none());
+        assert_eq!(cache.get(&other_author, &schema), Some(LogId::new(3)));
+    }
+}
@@ -0,0 +1,1145 @@
+use std::cmp::Ordering;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::{Message, MessageAction, MessageEncoded};
+
+use crate::db::models::{
+    certificate_pool, signed_payload_hash, AuthorProfile, EncryptedMessage, Entry, EntryPosition,
+    Fork, KeyGroup, Log, LogSummary, SchemaField, SchemaSummary,
+};
+use crate::db::query_builder::{Aggregate, Operator, Order, Value};
+use crate::db::traits::{
+    DocumentStore, EntryStore, ForkStore, IdentityStore, LogStore, PurgeStore, SchemaStore,
+    StatusStore, StorageTransaction, TransactionStore,
+};
+use crate::errors::Result;
+use crate::materializer::{
+    Document, DocumentAggregation, DocumentFields, DocumentGroup, DocumentQuery, DocumentVersion,
+    FieldFilter, MaterializerError,
+};
+
+/// Registered log_id of an author's schema.
+#[derive(Debug, Clone)]
+struct LogRecord {
+    author: Author,
+    log_id: LogId,
+    schema: Hash,
+}
+
+#[derive(Debug, Default)]
+struct MemoryStorageInner {
+    entries: Vec<Entry>,
+    forks: Vec<Fork>,
+    logs: Vec<LogRecord>,
+}
+
+/// Storage provider keeping all entries and logs in memory.
+///
+/// Nothing gets persisted, all data is lost as soon as the last clone of this storage got dropped.
+/// This allows running tests without setting up a database.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage {
+    inner: Arc<Mutex<MemoryStorageInner>>,
+}
+
+impl MemoryStorage {
+    /// Returns a new and empty in-memory storage provider.
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// Returns the sort key of an entry in the ordering of all entries.
+fn position(entry: &Entry) -> (String, i64, i64) {
+    (
+        entry.author.as_str().to_owned(),
+        entry.log_id.as_i64(),
+        entry.seq_num.as_i64(),
+    )
+}
+
+#[async_trait]
+impl EntryStore for MemoryStorage {
+    /// Insert an entry with its message payload.
+    ///
+    /// Returns false and does not store the entry when an entry with the same hash or at the same
+    /// position in the author's log already exists.
+    async fn insert_entry(
+        &self,
+        entry_encoded: &EntrySigned,
+        message_encoded: Option<&MessageEncoded>,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<bool> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let author = entry_encoded.author();
+        let entry_hash = entry_encoded.hash();
+
+        let exists = inner.entries.iter().any(|entry| {
+            entry.entry_hash == entry_hash
+                || (entry.author == author && &entry.log_id == log_id && &entry.seq_num == seq_num)
+        });
+
+        if exists {
+            return Ok(false);
+        }
+
+        inner.entries.push(Entry {
+            author,
+            entry_bytes: entry_encoded.as_str().to_owned(),
+            entry_hash,
+            log_id: log_id.clone(),
+            payload_bytes: message_encoded.map(|message| message.as_str().to_owned()),
+            payload_hash: message_encoded.map_or_else(
+                || signed_payload_hash(entry_encoded),
+                |message| message.hash(),
+            ),
+            seq_num: seq_num.clone(),
+        });
+
+        Ok(true)
+    }
+
+    async fn latest_entry(&self, author: &Author, log_id: &LogId) -> Result<Option<Entry>> {
+        let inner = self.inner.lock().unwrap();
+
+        let entry = inner
+            .entries
+            .iter()
+            .filter(|entry| &entry.author == author && &entry.log_id == log_id)
+            .max_by_key(|entry| entry.seq_num.as_i64())
+            .cloned();
+
+        Ok(entry)
+    }
+
+    async fn entry_at_seq_num(
+        &self,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<Entry>> {
+        let inner = self.inner.lock().unwrap();
+
+        let entry = inner
+            .entries
+            .iter()
+            .find(|entry| {
+                &entry.author == author && &entry.log_id == log_id && &entry.seq_num == seq_num
+            })
+            .cloned();
+
+        Ok(entry)
+    }
+
+    async fn entry_by_hash(&self, entry_hash: &Hash) -> Result<Option<Entry>> {
+        let inner = self.inner.lock().unwrap();
+
+        let entry = inner
+            .entries
+            .iter()
+            .find(|entry| &entry.entry_hash == entry_hash)
+            .cloned();
+
+        Ok(entry)
+    }
+
+    async fn query_entries(
+        &self,
+        author: Option<&Author>,
+        schema: Option<&Hash>,
+        after: Option<&EntryPosition>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Entry>> {
+        let inner = self.inner.lock().unwrap();
+
+        let after = after.map(|(author, log_id, seq_num)| {
+            (
+                author.as_str().to_owned(),
+                log_id.as_i64(),
+                seq_num.as_i64(),
+            )
+        });
+
+        let mut entries: Vec<Entry> = inner
+            .entries
+            .iter()
+            .filter(|entry| author.map_or(true, |author| &entry.author == author))
+            .filter(|entry| {
+                schema.map_or(true, |schema| {
+                    inner.logs.iter().any(|log| {
+                        &log.schema == schema
+                            && log.author == entry.author
+                            && log.log_id == entry.log_id
+                    })
+                })
+            })
+            .filter(|entry| {
+                after
+                    .as_ref()
+                    .map_or(true, |after| &position(entry) > after)
+            })
+            .cloned()
+            .collect();
+
+        // Use same ordering as the SQL storage to allow stable pagination
+        entries.sort_by_key(position);
+
+        Ok(entries
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect())
+    }
+
+    async fn entries_by_author(
+        &self,
+        author: &Author,
+        log_id: Option<&LogId>,
+        from: &SeqNum,
+        to: Option<&SeqNum>,
+        after: Option<&(LogId, SeqNum)>,
+        limit: i64,
+    ) -> Result<Vec<Entry>> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut entries: Vec<Entry> = inner
+            .entries
+            .iter()
+            .filter(|entry| &entry.author == author)
+            .filter(|entry| log_id.map_or(true, |log_id| &entry.log_id == log_id))
+            .filter(|entry| entry.seq_num.as_i64() >= from.as_i64())
+            .filter(|entry| to.map_or(true, |to| entry.seq_num.as_i64() <= to.as_i64()))
+            .filter(|entry| {
+                after.map_or(true, |(log_id, seq_num)| {
+                    (entry.log_id.as_i64(), entry.seq_num.as_i64())
+                        > (log_id.as_i64(), seq_num.as_i64())
+                })
+            })
+            .cloned()
+            .collect();
+
+        // Use same ordering as the SQL storage
+        entries.sort_by_key(|entry| (entry.log_id.as_i64(), entry.seq_num.as_i64()));
+
+        Ok(entries.into_iter().take(limit as usize).collect())
+    }
+}
+
+#[async_trait]
+impl LogStore for MemoryStorage {
+    /// Register any new log_id for an author's schema.
+    ///
+    /// Returns false and does not store the log when it was already registered.
+    async fn insert_log(&self, author: &Author, schema: &Hash, log_id: &LogId) -> Result<bool> {
+        assert!(log_id.is_user_log());
+        let mut inner = self.inner.lock().unwrap();
+
+        let exists = inner
+            .logs
+            .iter()
+            .any(|log| &log.author == author && &log.schema == schema && &log.log_id == log_id);
+
+        if exists {
+            return Ok(false);
+        }
+
+        inner.logs.push(LogRecord {
+            author: author.clone(),
+            log_id: log_id.clone(),
+            schema: schema.clone(),
+        });
+
+        Ok(true)
+    }
+
+    async fn get_log_id(&self, author: &Author, schema: &Hash) -> Result<Option<LogId>> {
+        let inner = self.inner.lock().unwrap();
+
+        let log_id = inner
+            .logs
+            .iter()
+            .find(|log| &log.author == author && &log.schema == schema)
+            .map(|log| log.log_id.clone());
+
+        Ok(log_id)
+    }
+
+    async fn logs_by_author(&self, author: &Author) -> Result<Vec<LogSummary>> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut logs: Vec<LogSummary> = inner
+            .logs
+            .iter()
+            .filter(|log| &log.author == author)
+            .filter_map(|log| {
+                // Ignore logs without entries like the SQL storage does
+                let seq_num = inner
+                    .entries
+                    .iter()
+                    .filter(|entry| entry.author == log.author && entry.log_id == log.log_id)
+                    .map(|entry| entry.seq_num.clone())
+                    .max_by_key(|seq_num| seq_num.as_i64())?;
+
+                Some(LogSummary {
+                    log_id: log.log_id.clone(),
+                    schema: log.schema.clone(),
+                    seq_num,
+                })
+            })
+            .collect();
+
+        logs.sort_by_key(|log| log.log_id.as_i64());
+
+        Ok(logs)
+    }
+
+    async fn next_user_schema_log_id(&self, author: &Author) -> Result<LogId> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut log_ids: Vec<LogId> = inner
+            .logs
+            .iter()
+            .filter(|log| &log.author == author)
+            .map(|log| log.log_id.clone())
+            .collect();
+
+        log_ids.sort_by_key(|log_id| log_id.as_i64());
+
+        Ok(Log::next_unused_user_log_id(&log_ids))
+    }
+}
+
+/// Returns all documents of a schema by applying the messages of the given entries, including
+/// deleted ones.
+///
+/// Messages are applied in the order of their sequence numbers and entry hashes like the
+/// materializer does.
+fn reduce_documents(entries: &[Entry], schema: &Hash) -> Result<Vec<Document>> {
+    let mut changes = Vec::new();
+    for entry in entries.iter() {
+        if let Some(payload_bytes) = &entry.payload_bytes {
+            let message = Message::from(&MessageEncoded::new(payload_bytes)?);
+
+            if message.schema() == schema {
+                changes.push((entry, message));
+            }
+        }
+    }
+
+    changes
+        .sort_by_key(|(entry, _)| (entry.seq_num.as_i64(), entry.entry_hash.as_str().to_owned()));
+
+    let mut documents: Vec<Document> = Vec::new();
+
+    for (entry, message) in changes {
+        let document_id = match message.action() {
+            MessageAction::Create => {
+                documents.push(Document {
+                    document_id: entry.entry_hash.as_str().to_owned(),
+                    author: entry.author.as_str().to_owned(),
+                    entry_hash: entry.entry_hash.as_str().to_owned(),
+                    seq_num: entry.seq_num.as_i64(),
+                    fields: DocumentFields::new(),
+                    deleted_at: None,
+                    deleted_by: None,
+                });
+                entry.entry_hash.clone()
+            }
+            _ => match message.id() {
+                Some(document_id) => document_id.clone(),
+                None => continue,
+            },
+        };
+
+        // Changes of unknown or deleted documents are ignored
+        let document = match documents
+            .iter_mut()
+            .find(|document| document.document_id == document_id.as_str() && !document.is_deleted())
+        {
+            Some(document) => document,
+            None => continue,
+        };
+
+        document.entry_hash = entry.entry_hash.as_str().to_owned();
+        document.seq_num = entry.seq_num.as_i64();
+
+        // Entries are not stored with the time they were received, so deleted documents have no
+        // deletion time
+        if let MessageAction::Delete = message.action() {
+            document.deleted_by = Some(entry.author.as_str().to_owned());
+            continue;
+        }
+
+        if let Some(fields) = message.fields() {
+            for (name, value) in fields.iter() {
+                document.fields.insert(name.to_owned(), value.clone());
+            }
+        }
+    }
+
+    Ok(documents)
+}
+
+/// Returns true when the document matches all filters.
+fn matches_filters(document: &Document, filters: &[FieldFilter]) -> bool {
+    filters.iter().all(|filter| {
+        let value = match document.value(&filter.field) {
+            Some(value) => value,
+            None => return false,
+        };
+
+        match filter.operator {
+            Operator::Eq => value == filter.value,
+            Operator::Ne => value != filter.value,
+            Operator::Lt => value < filter.value,
+            Operator::Lte => value <= filter.value,
+            Operator::Gt => value > filter.value,
+            Operator::Gte => value >= filter.value,
+        }
+    })
+}
+
+/// Returns the result of an aggregate function over the given values.
+fn aggregate_values(function: Aggregate, values: Vec<Value>) -> Option<Value> {
+    match function {
+        Aggregate::Count => Some(Value::Integer(values.len() as i64)),
+        Aggregate::Min => values.into_iter().fold(None, |min, value| match min {
+            Some(min) if min <= value => Some(min),
+            _ => Some(value),
+        }),
+        Aggregate::Max => values.into_iter().fold(None, |max, value| match max {
+            Some(max) if max >= value => Some(max),
+            _ => Some(value),
+        }),
+    }
+}
+
+#[async_trait]
+impl DocumentStore for MemoryStorage {
+    /// Documents are not materialized in memory, instead they are reduced from all stored
+    /// messages of their schema.
+    async fn document(&self, schema: &Hash, document_id: &Hash) -> Result<Option<Document>> {
+        let inner = self.inner.lock().unwrap();
+
+        Ok(reduce_documents(&inner.entries, schema)?
+            .into_iter()
+            .find(|document| document.document_id == document_id.as_str())
+            .filter(|document| !document.is_deleted()))
+    }
+
+    /// Documents are reduced from all stored messages of the schemas of their CREATE messages.
+    async fn documents_by_id(&self, document_ids: &[Hash]) -> Result<Vec<(Hash, Document)>> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut documents = Vec::new();
+
+        for document_id in document_ids {
+            // Find schema of document in the message which created it
+            let payload_bytes = match inner
+                .entries
+                .iter()
+                .find(|entry| &entry.entry_hash == document_id)
+                .and_then(|entry| entry.payload_bytes.as_ref())
+            {
+                Some(payload_bytes) => payload_bytes,
+                None => continue,
+            };
+
+            let message = Message::from(&MessageEncoded::new(payload_bytes)?);
+            if !matches!(message.action(), MessageAction::Create) {
+                continue;
+            }
+
+            let document = reduce_documents(&inner.entries, message.schema())?
+                .into_iter()
+                .find(|document| document.document_id == document_id.as_str())
+                .filter(|document| !document.is_deleted());
+
+            if let Some(document) = document {
+                documents.push((message.schema().clone(), document));
+            }
+        }
+
+        Ok(documents)
+    }
+
+    /// Documents are reduced from the stored messages up to the given sequence number. Entries
+    /// are not stored with the time they were received, so versions by timestamp are not
+    /// available.
+    async fn document_version(
+        &self,
+        schema: &Hash,
+        document_id: &Hash,
+        version: DocumentVersion,
+    ) -> Result<Option<Document>> {
+        let seq_num = match version {
+            DocumentVersion::SeqNum(seq_num) => seq_num,
+            DocumentVersion::Timestamp(_) => return Err(MaterializerError::HistoryDisabled.into()),
+        };
+
+        let inner = self.inner.lock().unwrap();
+
+        let entries: Vec<Entry> = inner
+            .entries
+            .iter()
+            .filter(|entry| entry.seq_num.as_i64() <= seq_num)
+            .cloned()
+            .collect();
+
+        Ok(reduce_documents(&entries, schema)?
+            .into_iter()
+            .find(|document| document.document_id == document_id.as_str())
+            .filter(|document| !document.is_deleted()))
+    }
+
+    /// Documents are not materialized in memory, instead they are reduced from all stored
+    /// messages of their schema. Filters on unknown fields never match.
+    async fn query_documents(&self, schema: &Hash, query: &DocumentQuery) -> Result<Vec<Document>> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut documents: Vec<Document> = reduce_documents(&inner.entries, schema)?
+            .into_iter()
+            .filter(|document| query.include_deleted || !document.is_deleted())
+            .filter(|document| matches_filters(document, &query.filters))
+            .filter(|document| {
+                let after = match &query.after {
+                    Some(after) => after,
+                    None => return true,
+                };
+
+                let position = match document.position(query.order_by.as_deref()) {
+                    Some(position) => position,
+                    None => return false,
+                };
+
+                let ordering = position
+                    .value
+                    .partial_cmp(&after.value)
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| position.document_id.cmp(&after.document_id));
+
+                match query.order {
+                    Order::Asc => ordering == Ordering::Greater,
+                    Order::Desc => ordering == Ordering::Less,
+                }
+            })
+            .collect();
+
+        // Use same ordering as the SQL storage
+        documents.sort_by(|a, b| {
+            let ordering = query
+                .order_by
+                .as_ref()
+                .and_then(|name| a.value(name).partial_cmp(&b.value(name)))
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| a.document_id.cmp(&b.document_id));
+
+            match query.order {
+                Order::Asc => ordering,
+                Order::Desc => ordering.reverse(),
+            }
+        });
+
+        Ok(documents
+            .into_iter()
+            .skip(query.offset as usize)
+            .take(query.limit as usize)
+            .collect())
+    }
+
+    /// Documents are reduced from all stored messages of their schema, unknown fields have no
+    /// values.
+    async fn aggregate_documents(
+        &self,
+        schema: &Hash,
+        aggregation: &DocumentAggregation,
+    ) -> Result<Vec<DocumentGroup>> {
+        if aggregation
+            .aggregates
+            .iter()
+            .any(|aggregate| aggregate.field.is_none() && aggregate.function != Aggregate::Count)
+        {
+            return Err(MaterializerError::MissingAggregateField.into());
+        }
+
+        let inner = self.inner.lock().unwrap();
+
+        let documents = reduce_documents(&inner.entries, schema)?;
+
+        // Documents of unknown schemas can not exist
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // All documents form one group when they are not grouped, even when none of them match
+        let mut groups: Vec<(Option<Value>, Vec<&Document>)> = Vec::new();
+        if aggregation.group_by.is_none() {
+            groups.push((None, Vec::new()));
+        }
+
+        for document in documents
+            .iter()
+            .filter(|document| !document.is_deleted())
+            .filter(|document| matches_filters(document, &aggregation.filters))
+        {
+            let group = aggregation
+                .group_by
+                .as_ref()
+                .and_then(|name| document.value(name));
+
+            match groups.iter_mut().find(|(value, _)| value == &group) {
+                Some((_, members)) => members.push(document),
+                None => groups.push((group, vec![document])),
+            }
+        }
+
+        // Use same ordering as the SQL storage
+        groups.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+
+        Ok(groups
+            .into_iter()
+            .map(|(group, members)| {
+                let values = aggregation
+                    .aggregates
+                    .iter()
+                    .map(|aggregate| {
+                        let values = members
+                            .iter()
+                            .filter_map(|document| match &aggregate.field {
+                                Some(name) => document.value(name),
+                                None => Some(Value::Boolean(true)),
+                            })
+                            .collect();
+
+                        aggregate_values(aggregate.function, values)
+                    })
+                    .collect();
+
+                DocumentGroup { group, values }
+            })
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ForkStore for MemoryStorage {
+    /// Register a refused entry conflicting with a stored entry.
+    ///
+    /// Returns false and does not store the fork when it was already registered.
+    async fn insert_fork(
+        &self,
+        fork_entry: &EntrySigned,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+        entry_hash: &Hash,
+    ) -> Result<bool> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let fork_entry_hash = fork_entry.hash();
+        if inner
+            .forks
+            .iter()
+            .any(|fork| fork.fork_entry_hash == fork_entry_hash)
+        {
+            return Ok(false);
+        }
+
+        inner.forks.push(Fork {
+            author: fork_entry.author(),
+            log_id: log_id.clone(),
+            seq_num: seq_num.clone(),
+            entry_hash: entry_hash.clone(),
+            fork_entry_bytes: fork_entry.as_str().to_owned(),
+            fork_entry_hash,
+        });
+
+        Ok(true)
+    }
+
+    async fn forks(&self, author: &Author, log_id: Option<&LogId>) -> Result<Vec<Fork>> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut forks: Vec<Fork> = inner
+            .forks
+            .iter()
+            .filter(|fork| &fork.author == author)
+            .filter(|fork| log_id.map_or(true, |log_id| &fork.log_id == log_id))
+            .cloned()
+            .collect();
+
+        forks.sort_by_key(|fork| (fork.log_id.as_i64(), fork.seq_num.as_i64()));
+
+        Ok(forks)
+    }
+}
+
+#[async_trait]
+impl PurgeStore for MemoryStorage {
+    async fn purge_author(&self, author: &Author) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let count = inner.entries.len();
+        inner.entries.retain(|entry| &entry.author != author);
+        inner.logs.retain(|log| &log.author != author);
+
+        Ok((count - inner.entries.len()) as u64)
+    }
+
+    async fn purge_log(&self, author: &Author, log_id: &LogId) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+
+        let count = inner.entries.len();
+        inner
+            .entries
+            .retain(|entry| &entry.author != author || &entry.log_id != log_id);
+        inner
+            .logs
+            .retain(|log| &log.author != author || &log.log_id != log_id);
+
+        Ok((count - inner.entries.len()) as u64)
+    }
+
+    async fn prune_entries(&self, schema: &Hash, keep: u64) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let count = inner.entries.len();
+
+        for log in inner.logs.iter().filter(|log| &log.schema == schema) {
+            let latest = inner
+                .entries
+                .iter()
+                .filter(|entry| entry.author == log.author && entry.log_id == log.log_id)
+                .map(|entry| entry.seq_num.clone())
+                .max_by_key(|seq_num| seq_num.as_i64());
+
+            let latest = match latest {
+                Some(latest) => latest,
+                None => continue,
+            };
+            let retained = certificate_pool(&latest);
+
+            inner.entries.retain(|entry| {
+                entry.author != log.author
+                    || entry.log_id != log.log_id
+                    || entry.seq_num.as_i64() > latest.as_i64() - keep as i64
+                    || retained.contains(&entry.seq_num.as_i64())
+            });
+        }
+
+        Ok((count - inner.entries.len()) as u64)
+    }
+
+    async fn prune_payloads(&self, schema: &Hash, keep: u64) -> Result<u64> {
+        let mut inner = self.inner.lock().unwrap();
+        let inner = &mut *inner;
+
+        let mut pruned = 0;
+
+        for log in inner.logs.iter().filter(|log| &log.schema == schema) {
+            let latest = inner
+                .entries
+                .iter()
+                .filter(|entry| entry.author == log.author && entry.log_id == log.log_id)
+                .map(|entry| entry.seq_num.as_i64())
+                .max()
+                .unwrap_or(0);
+
+            for entry in inner.entries.iter_mut() {
+                if entry.author == log.author
+                    && entry.log_id == log.log_id
+                    && entry.seq_num.as_i64() <= latest - keep as i64
+                    && entry.payload_bytes.take().is_some()
+                {
+                    pruned += 1;
+                }
+            }
+        }
+
+        Ok(pruned)
+    }
+
+    /// Entries are not stored with the time they were received at, no logs expire.
+    async fn expire_logs(&self, _schema: &Hash, _ttl: u64) -> Result<u64> {
+        Ok(0)
+    }
+}
+
+#[async_trait]
+impl SchemaStore for MemoryStorage {
+    /// Returns all schemas with their number of entries.
+    ///
+    /// Documents are not materialized in memory, instead all created documents are counted which
+    /// were not deleted again.
+    async fn schemas(&self) -> Result<Vec<SchemaSummary>> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut schemas: Vec<SchemaSummary> = Vec::new();
+
+        for log in inner.logs.iter() {
+            let entries: Vec<&Entry> = inner
+                .entries
+                .iter()
+                .filter(|entry| entry.author == log.author && entry.log_id == log.log_id)
+                .collect();
+
+            if entries.is_empty() {
+                continue;
+            }
+
+            let mut document_count = 0;
+            for entry in entries.iter() {
+                if let Some(payload_bytes) = &entry.payload_bytes {
+                    let message = Message::from(&MessageEncoded::new(payload_bytes)?);
+
+                    match message.action() {
+                        MessageAction::Create => document_count += 1,
+                        MessageAction::Delete => document_count -= 1,
+                        MessageAction::Update => (),
+                    }
+                }
+            }
+
+            match schemas
+                .iter_mut()
+                .find(|summary| summary.schema == log.schema)
+            {
+                Some(summary) => {
+                    summary.entry_count += entries.len() as i64;
+                    summary.document_count += document_count;
+                }
+                None => schemas.push(SchemaSummary {
+                    schema: log.schema.clone(),
+                    entry_count: entries.len() as i64,
+                    document_count,
+                }),
+            }
+        }
+
+        // Use same ordering as the SQL storage
+        schemas.sort_by_key(|summary| summary.schema.as_str().to_owned());
+
+        Ok(schemas)
+    }
+
+    /// Schema definitions are not registered in memory, messages of all schemas are accepted.
+    async fn schema_definition(&self, _schema: &Hash) -> Result<Option<Vec<SchemaField>>> {
+        Ok(None)
+    }
+}
+
+/// System schema messages are not materialized in memory, no author has a profile, key group or
+/// encrypted messages.
+#[async_trait]
+impl IdentityStore for MemoryStorage {
+    async fn author_profile(&self, _author: &Author) -> Result<Option<AuthorProfile>> {
+        Ok(None)
+    }
+
+    async fn key_group(&self, _key_group: &Hash) -> Result<Option<KeyGroup>> {
+        Ok(None)
+    }
+
+    async fn key_group_members(&self, _key_group: &Hash) -> Result<Vec<Author>> {
+        Ok(Vec::new())
+    }
+
+    async fn key_groups_by_member(&self, _author: &Author) -> Result<Vec<Hash>> {
+        Ok(Vec::new())
+    }
+
+    async fn encrypted_messages(&self, _key_group: &Hash) -> Result<Vec<EncryptedMessage>> {
+        Ok(Vec::new())
+    }
+}
+
+#[async_trait]
+impl StatusStore for MemoryStorage {
+    /// In-memory storage is always ready to be used.
+    async fn check_health(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn entry_count(&self) -> Result<i64> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.entries.len() as i64)
+    }
+
+    async fn author_count(&self) -> Result<i64> {
+        let inner = self.inner.lock().unwrap();
+
+        let mut authors: Vec<&str> = inner
+            .entries
+            .iter()
+            .map(|entry| entry.author.as_str())
+            .collect();
+        authors.sort_unstable();
+        authors.dedup();
+
+        Ok(authors.len() as i64)
+    }
+
+    async fn log_count(&self) -> Result<i64> {
+        let inner = self.inner.lock().unwrap();
+        Ok(inner.logs.len() as i64)
+    }
+
+    /// In-memory storage does not use any disk space.
+    async fn storage_size(&self) -> Result<Option<i64>> {
+        Ok(None)
+    }
+
+    /// Entries are not materialized in memory.
+    async fn materializer_backlog(&self) -> Result<i64> {
+        Ok(0)
+    }
+}
+
+/// Entry written within a transaction of the in-memory storage.
+#[derive(Debug)]
+struct PendingEntry {
+    entry_encoded: EntrySigned,
+    message_encoded: Option<MessageEncoded>,
+    log_id: LogId,
+    seq_num: SeqNum,
+}
+
+impl PendingEntry {
+    /// Returns the entry like it gets stored.
+    fn to_entry(&self) -> Entry {
+        Entry {
+            author: self.entry_encoded.author(),
+            entry_bytes: self.entry_encoded.as_str().to_owned(),
+            entry_hash: self.entry_encoded.hash(),
+            log_id: self.log_id.clone(),
+            payload_bytes: self
+                .message_encoded
+                .as_ref()
+                .map(|message| message.as_str().to_owned()),
+            payload_hash: self.message_encoded.as_ref().map_or_else(
+                || signed_payload_hash(&self.entry_encoded),
+                |message| message.hash(),
+            ),
+            seq_num: self.seq_num.clone(),
+        }
+    }
+}
+
+/// Transaction of the in-memory storage provider.
+///
+/// Writes are kept aside until the transaction gets committed, reads within the transaction see
+/// them before.
+#[derive(Debug)]
+pub struct MemoryTransaction {
+    storage: MemoryStorage,
+    logs: Vec<LogRecord>,
+    entries: Vec<PendingEntry>,
+}
+
+#[async_trait]
+impl StorageTransaction for MemoryTransaction {
+    async fn get_log_id(&mut self, author: &Author, schema: &Hash) -> Result<Option<LogId>> {
+        let pending = self
+            .logs
+            .iter()
+            .find(|log| &log.author == author && &log.schema == schema)
+            .map(|log| log.log_id.clone());
+
+        match pending {
+            Some(log_id) => Ok(Some(log_id)),
+            None => self.storage.get_log_id(author, schema).await,
+        }
+    }
+
+    async fn entry_by_hash(&mut self, entry_hash: &Hash) -> Result<Option<Entry>> {
+        let pending = self
+            .entries
+            .iter()
+            .find(|entry| &entry.entry_encoded.hash() == entry_hash)
+            .map(|entry| entry.to_entry());
+
+        match pending {
+            Some(entry) => Ok(Some(entry)),
+            None => self.storage.entry_by_hash(entry_hash).await,
+        }
+    }
+
+    async fn entry_at_seq_num(
+        &mut self,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<Entry>> {
+        let pending = self
+            .entries
+            .iter()
+            .find(|entry| {
+                &entry.entry_encoded.author() == author
+                    && &entry.log_id == log_id
+                    && &entry.seq_num == seq_num
+            })
+            .map(|entry| entry.to_entry());
+
+        match pending {
+            Some(entry) => Ok(Some(entry)),
+            None => self.storage.entry_at_seq_num(author, log_id, seq_num).await,
+        }
+    }
+
+    async fn insert_log(&mut self, author: &Author, schema: &Hash, log_id: &LogId) -> Result<bool> {
+        self.logs.push(LogRecord {
+            author: author.clone(),
+            log_id: log_id.clone(),
+            schema: schema.clone(),
+        });
+
+        Ok(true)
+    }
+
+    async fn insert_entry(
+        &mut self,
+        entry_encoded: &EntrySigned,
+        message_encoded: Option<&MessageEncoded>,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<bool> {
+        self.entries.push(PendingEntry {
+            entry_encoded: entry_encoded.clone(),
+            message_encoded: message_encoded.cloned(),
+            log_id: log_id.clone(),
+            seq_num: seq_num.clone(),
+        });
+
+        Ok(true)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        for log in self.logs.iter() {
+            self.storage
+                .insert_log(&log.author, &log.schema, &log.log_id)
+                .await?;
+        }
+
+        for entry in self.entries.iter() {
+            self.storage
+                .insert_entry(
+                    &entry.entry_encoded,
+                    entry.message_encoded.as_ref(),
+                    &entry.log_id,
+                    &entry.seq_num,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TransactionStore for MemoryStorage {
+    async fn begin(&self) -> Result<Box<dyn StorageTransaction>> {
+        Ok(Box::new(MemoryTransaction {
+            storage: self.clone(),
+            logs: Vec::new(),
+            entries: Vec::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::{Author, KeyPair};
+
+    use super::MemoryStorage;
+
+    use crate::db::{EntryStore, LogStore};
+    use crate::test_helpers::{create_test_entry, random_entry_hash};
+
+    const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[async_std::test]
+    async fn insert_and_find_entries() {
+        let storage = MemoryStorage::new();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        let seq_num = SeqNum::new(1).unwrap();
+
+        let (entry, message) = create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
+        let author = entry.author();
+
+        assert!(storage.insert_log(&author, &schema, &log_id).await.unwrap());
+        assert!(storage
+            .insert_entry(&entry, Some(&message), &log_id, &seq_num)
+            .await
+            .unwrap());
+
+        // Duplicates get rejected
+        assert!(!storage
+            .insert_entry(&entry, Some(&message), &log_id, &seq_num)
+            .await
+            .unwrap());
+
+        let latest = storage.latest_entry(&author, &log_id).await.unwrap();
+        assert_eq!(latest.unwrap().entry_hash, entry.hash());
+
+        let by_hash = storage.entry_by_hash(&entry.hash()).await.unwrap();
+        assert!(by_hash.is_some());
+
+        let by_schema = storage
+            .query_entries(None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(by_schema.len(), 1);
+
+        let next_page = storage
+            .query_entries(Some(&author), None, None, 10, 1)
+            .await
+            .unwrap();
+        assert!(next_page.is_empty());
+    }
+
+    #[async_std::test]
+    async fn user_log_ids() {
+        let storage = MemoryStorage::new();
+
+        let author = Author::new(TEST_AUTHOR).unwrap();
+        let schema_first = Hash::new(&random_entry_hash()).unwrap();
+        let schema_second = Hash::new(&random_entry_hash()).unwrap();
+
+        // Register log id in the middle
+        storage
+            .insert_log(&author, &schema_first, &LogId::new(3))
+            .await
+            .unwrap();
+
+        // Find next free user log id and register it
+        let log_id = storage.next_user_schema_log_id(&author).await.unwrap();
+        assert_eq!(log_id, LogId::new(1));
+        storage
+            .insert_log(&author, &schema_second, &log_id)
+            .await
+            .unwrap();
+
+        // Find next free user log id
+        let log_id = storage.next_user_schema_log_id(&author).await.unwrap();
+        assert_eq!(log_id, LogId::new(5));
+
+        // Registered log ids are found again
+        let log_id = storage
+            .find_schema_log_id(&author, &schema_first)
+            .await
+            .unwrap();
+        assert_eq!(log_id, LogId::new(3));
+    }
+}
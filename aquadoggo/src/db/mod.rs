@@ -1,9 +1,28 @@
-use anyhow::{Error, Result};
-use sqlx::any::{Any, AnyPool, AnyPoolOptions};
-use sqlx::migrate;
-use sqlx::migrate::MigrateDatabase;
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use anyhow::{bail, Error, Result};
+use sqlx::any::{Any, AnyConnection, AnyKind, AnyPool, AnyPoolOptions};
+use sqlx::migrate::{MigrateDatabase, Migrator};
+use sqlx::{migrate, query_scalar, Executor};
+
+use crate::config::Configuration;
+
+mod cache;
+#[cfg(test)]
+mod memory;
 pub mod models;
+mod provider;
+pub mod query_builder;
+mod traits;
+
+#[cfg(test)]
+pub use memory::MemoryStorage;
+pub use provider::SqlStorage;
+pub use traits::{
+    DocumentStore, EntryStore, ForkStore, IdentityStore, LogStore, PurgeStore, SchemaStore,
+    StatusStore, StorageProvider, StorageTransaction, TransactionStore,
+};
 
 /// Re-export of generic connection pool type.
 pub type Pool = AnyPool;
@@ -14,23 +33,315 @@ pub async fn create_database(url: &str) -> Result<()> {
         Any::create_database(url).await?;
     }
 
-    Any::drop_database(&url);
-
     Ok(())
 }
 
+/// SQLite journal modes which can be configured.
+const SQLITE_JOURNAL_MODES: [&str; 6] = ["DELETE", "TRUNCATE", "PERSIST", "MEMORY", "WAL", "OFF"];
+
+/// SQLite synchronous levels which can be configured.
+const SQLITE_SYNCHRONOUS_LEVELS: [&str; 4] = ["OFF", "NORMAL", "FULL", "EXTRA"];
+
+/// Settings of the connection pool and the connections it opens.
+#[derive(Debug, Clone)]
+pub struct PoolOptions {
+    /// Maximum number of connections in the pool.
+    pub max_connections: u32,
+
+    /// Maximum time to wait for a connection of the pool, `DatabaseBusy` errors are returned
+    /// afterwards.
+    pub acquire_timeout: Duration,
+
+    /// Time after which idle connections are closed, they are kept open when not set.
+    pub idle_timeout: Option<Duration>,
+
+    /// Journal mode of SQLite databases.
+    pub sqlite_journal_mode: String,
+
+    /// Time in milliseconds SQLite waits for locked databases before failing.
+    pub sqlite_busy_timeout: u64,
+
+    /// Synchronous level of SQLite databases.
+    pub sqlite_synchronous: String,
+}
+
+impl Default for PoolOptions {
+    fn default() -> Self {
+        Self {
+            max_connections: 32,
+            acquire_timeout: Duration::from_secs(30),
+            idle_timeout: Some(Duration::from_secs(600)),
+            sqlite_journal_mode: "WAL".to_owned(),
+            sqlite_busy_timeout: 5000,
+            sqlite_synchronous: "NORMAL".to_owned(),
+        }
+    }
+}
+
+impl PoolOptions {
+    /// Returns the pool settings configured for this node.
+    pub fn new(config: &Configuration) -> Result<Self> {
+        let sqlite_journal_mode = config.database_sqlite_journal_mode.to_uppercase();
+        if !SQLITE_JOURNAL_MODES.contains(&sqlite_journal_mode.as_str()) {
+            bail!("Unknown SQLite journal mode {}", sqlite_journal_mode);
+        }
+
+        let sqlite_synchronous = config.database_sqlite_synchronous.to_uppercase();
+        if !SQLITE_SYNCHRONOUS_LEVELS.contains(&sqlite_synchronous.as_str()) {
+            bail!("Unknown SQLite synchronous level {}", sqlite_synchronous);
+        }
+
+        Ok(Self {
+            max_connections: config.database_max_connections,
+            acquire_timeout: Duration::from_secs(config.database_acquire_timeout),
+            idle_timeout: match config.database_idle_timeout {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            },
+            sqlite_journal_mode,
+            sqlite_busy_timeout: config.database_sqlite_busy_timeout,
+            sqlite_synchronous,
+        })
+    }
+}
+
 /// Create a database agnostic connection pool.
-pub async fn connection_pool(url: &str, max_connections: u32) -> Result<Pool, Error> {
+///
+/// SQLite connections are set up with the configured journal mode, busy timeout and synchronous
+/// level, the write-ahead log allows reading while another connection writes.
+pub async fn connection_pool(url: &str, options: &PoolOptions) -> Result<Pool, Error> {
+    let pragmas = format!(
+        "PRAGMA journal_mode = {}; PRAGMA busy_timeout = {}; PRAGMA synchronous = {};",
+        options.sqlite_journal_mode, options.sqlite_busy_timeout, options.sqlite_synchronous
+    );
+
     let pool: Pool = AnyPoolOptions::new()
-        .max_connections(max_connections)
+        .max_connections(options.max_connections)
+        .connect_timeout(options.acquire_timeout)
+        .idle_timeout(options.idle_timeout)
+        .after_connect(move |conn| {
+            let pragmas = pragmas.clone();
+
+            Box::pin(async move {
+                match conn.kind() {
+                    // Treat double quotes as identifier quotes in MySQL like the other databases
+                    // do, this allows us to use the same queries for all of them
+                    AnyKind::MySql => {
+                        conn.execute(
+                            "SET SESSION sql_mode = CONCAT_WS(',', NULLIF(@@sql_mode, ''), 'ANSI_QUOTES')",
+                        )
+                        .await?;
+                    }
+                    AnyKind::Sqlite => {
+                        conn.execute(pragmas.as_str()).await?;
+                    }
+                    _ => (),
+                }
+
+                Ok(())
+            })
+        })
         .connect(url)
         .await?;
 
     Ok(pool)
 }
 
+/// Returns the migrations for the database used by this connection pool.
+fn migrator(pool: &Pool) -> Migrator {
+    match pool.any_kind() {
+        AnyKind::MySql => migrate!("./migrations/mysql"),
+        _ => migrate!(),
+    }
+}
+
 /// Run any pending database migrations from inside the application.
 pub async fn run_pending_migrations(pool: &Pool) -> Result<()> {
-    migrate!().run(pool).await?;
+    migrator(pool).run(pool).await?;
     Ok(())
 }
+
+/// Returns the number of migrations which have not been applied to the database yet.
+pub async fn pending_migrations(pool: &Pool) -> Result<usize, sqlx::Error> {
+    let applied: Vec<i64> =
+        query_scalar("SELECT version FROM _sqlx_migrations WHERE success = TRUE")
+            .fetch_all(pool)
+            .await?;
+
+    let pending = migrator(pool)
+        .iter()
+        .filter(|migration| !applied.contains(&migration.version))
+        .count();
+
+    Ok(pending)
+}
+
+/// Returns the size of the database on disk in bytes.
+pub async fn database_size(pool: &Pool) -> Result<i64, sqlx::Error> {
+    let statement = match pool.any_kind() {
+        AnyKind::Postgres => "SELECT pg_database_size(current_database())",
+        AnyKind::MySql => {
+            "SELECT CAST(COALESCE(SUM(data_length + index_length), 0) AS SIGNED)
+            FROM information_schema.tables WHERE table_schema = DATABASE()"
+        }
+        _ => "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+    };
+
+    query_scalar(statement).fetch_one(pool).await
+}
+
+/// Returns the current time in seconds since the Unix epoch.
+pub fn unix_timestamp() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// Connection pools, connections and transactions which know the database they are talking to.
+pub trait SqlDialect {
+    /// Returns the kind of database.
+    fn dialect(&self) -> AnyKind;
+}
+
+impl SqlDialect for AnyPool {
+    fn dialect(&self) -> AnyKind {
+        self.any_kind()
+    }
+}
+
+impl SqlDialect for AnyConnection {
+    fn dialect(&self) -> AnyKind {
+        self.kind()
+    }
+}
+
+impl<T: SqlDialect + ?Sized> SqlDialect for &T {
+    fn dialect(&self) -> AnyKind {
+        (**self).dialect()
+    }
+}
+
+impl<T: SqlDialect + ?Sized> SqlDialect for &mut T {
+    fn dialect(&self) -> AnyKind {
+        (**self).dialect()
+    }
+}
+
+/// Prepare SQL query for the database used by this connection pool, connection or transaction.
+///
+/// All queries in the node are written with numbered `$1` parameters as understood by SQLite and
+/// PostgreSQL. MySQL only supports `?` placeholders which is why they get rewritten here.
+pub fn sql<'a>(db: &impl SqlDialect, query: &'a str) -> Cow<'a, str> {
+    match db.dialect() {
+        AnyKind::MySql => Cow::Owned(numbered_to_positional_params(query)),
+        _ => Cow::Borrowed(query),
+    }
+}
+
+/// Replace all numbered `$1` parameters with positional `?` placeholders.
+///
+/// This expects the numbered parameters to occur in ascending order and exactly once.
+fn numbered_to_positional_params(query: &str) -> String {
+    let mut result = String::with_capacity(query.len());
+    let mut chars = query.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '$' && chars.peek().map_or(false, |next| next.is_ascii_digit()) {
+            // Skip all digits of the parameter number
+            while chars.peek().map_or(false, |next| next.is_ascii_digit()) {
+                chars.next();
+            }
+
+            result.push('?');
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use sqlx::query_scalar;
+
+    use crate::db::models::Log;
+    use crate::errors::Error;
+    use crate::test_helpers::initialize_db;
+
+    use super::{
+        connection_pool, database_size, numbered_to_positional_params, pending_migrations,
+        PoolOptions,
+    };
+
+    #[async_std::test]
+    async fn no_pending_migrations() {
+        let pool = initialize_db().await;
+        assert_eq!(pending_migrations(&pool).await.unwrap(), 0);
+    }
+
+    #[async_std::test]
+    async fn apply_sqlite_pragmas() {
+        let options = PoolOptions {
+            max_connections: 1,
+            sqlite_busy_timeout: 1234,
+            sqlite_synchronous: "FULL".to_owned(),
+            ..PoolOptions::default()
+        };
+        let pool = connection_pool("sqlite::memory:", &options).await.unwrap();
+
+        let busy_timeout: i64 = query_scalar("PRAGMA busy_timeout")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(busy_timeout, 1234);
+
+        // Level FULL is reported as 2
+        let synchronous: i64 = query_scalar("PRAGMA synchronous")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(synchronous, 2);
+    }
+
+    #[async_std::test]
+    async fn report_exhausted_pool() {
+        let options = PoolOptions {
+            max_connections: 1,
+            acquire_timeout: Duration::from_millis(50),
+            ..PoolOptions::default()
+        };
+        let pool = connection_pool("sqlite::memory:", &options).await.unwrap();
+
+        // Hold the only connection of the pool
+        let _connection = pool.acquire().await.unwrap();
+
+        assert!(matches!(Log::count(&pool).await, Err(Error::DatabaseBusy)));
+    }
+
+    #[async_std::test]
+    async fn report_database_size() {
+        let pool = initialize_db().await;
+        assert!(database_size(&pool).await.unwrap() > 0);
+    }
+
+    #[test]
+    fn rewrite_numbered_params() {
+        assert_eq!(
+            numbered_to_positional_params("SELECT * FROM logs WHERE author = $1 AND log_id = $2"),
+            "SELECT * FROM logs WHERE author = ? AND log_id = ?"
+        );
+
+        assert_eq!(
+            numbered_to_positional_params("INSERT INTO logs VALUES ($1, $2, $10)"),
+            "INSERT INTO logs VALUES (?, ?, ?)"
+        );
+
+        assert_eq!(
+            numbered_to_positional_params("SELECT COUNT(*) FROM entries"),
+            "SELECT COUNT(*) FROM entries"
+        );
+    }
+}
@@ -0,0 +1,186 @@
+use p2panda_rs::entry::SeqNum;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Public profile of an author, published as messages of the author profile system schema.
+///
+/// Every author has at most one profile, it holds the values of the latest message in the author's
+/// profile log.
+#[derive(FromRow, Debug, Clone)]
+pub struct AuthorProfile {
+    /// Public key of the author.
+    pub author: Author,
+
+    /// Name of the author to display in applications.
+    pub display_name: String,
+
+    /// Hash of the entry which set the current profile.
+    pub entry_hash: Hash,
+
+    /// Sequence number of the entry which set the current profile.
+    pub seq_num: SeqNum,
+}
+
+impl AuthorProfile {
+    /// Set the profile of an author.
+    ///
+    /// Returns false and does not change anything when the profile was already set by a later
+    /// entry.
+    pub async fn upsert(
+        pool: &Pool,
+        author: &Author,
+        display_name: &str,
+        entry_hash: &Hash,
+        seq_num: &SeqNum,
+    ) -> Result<bool> {
+        let statement = match Self::by_author(pool, author).await? {
+            Some(profile) if profile.seq_num.as_i64() >= seq_num.as_i64() => return Ok(false),
+            Some(_) => {
+                "
+                UPDATE
+                    author_profiles
+                SET
+                    display_name = $1,
+                    entry_hash = $2,
+                    seq_num = $3
+                WHERE
+                    author = $4
+                "
+            }
+            None => {
+                "
+                INSERT INTO
+                    author_profiles (display_name, entry_hash, seq_num, author)
+                VALUES
+                    ($1, $2, $3, $4)
+                "
+            }
+        };
+
+        let rows_affected = query(&sql(pool, statement))
+            .bind(display_name)
+            .bind(entry_hash)
+            .bind(seq_num)
+            .bind(author)
+            .execute(pool)
+            .await?
+            .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns the profile of an author.
+    pub async fn by_author(pool: &Pool, author: &Author) -> Result<Option<AuthorProfile>> {
+        let profile = query_as::<_, AuthorProfile>(&sql(
+            pool,
+            "
+            SELECT
+                author,
+                display_name,
+                entry_hash,
+                seq_num
+            FROM
+                author_profiles
+            WHERE
+                author = $1
+            ",
+        ))
+        .bind(author)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(profile)
+    }
+
+    /// Remove the profile of an author.
+    pub async fn delete(pool: &Pool, author: &Author) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                author_profiles
+            WHERE
+                author = $1
+            ",
+        ))
+        .bind(author)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove the profile which was set by the given entry.
+    pub async fn delete_by_entry_hash(pool: &Pool, entry_hash: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                author_profiles
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all author profiles.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM author_profiles").execute(pool).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::SeqNum;
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::AuthorProfile;
+
+    use crate::test_helpers::{initialize_db, random_entry_hash};
+
+    const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[async_std::test]
+    async fn latest_profile_wins() {
+        let pool = initialize_db().await;
+        let author = Author::new(TEST_AUTHOR).unwrap();
+
+        for (display_name, seq_num) in [("Panda", 2), ("Bear", 1)].iter() {
+            AuthorProfile::upsert(
+                &pool,
+                &author,
+                display_name,
+                &Hash::new(&random_entry_hash()).unwrap(),
+                &SeqNum::new(*seq_num).unwrap(),
+            )
+            .await
+            .unwrap();
+        }
+
+        // Older entries do not overwrite the profile
+        let profile = AuthorProfile::by_author(&pool, &author)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(profile.display_name, "Panda");
+        assert_eq!(profile.seq_num, SeqNum::new(2).unwrap());
+
+        AuthorProfile::delete(&pool, &author).await.unwrap();
+        assert!(AuthorProfile::by_author(&pool, &author)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,300 @@
+use p2panda_rs::entry::{LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Materialized entry changing a document, it is a CREATE, UPDATE or DELETE message referencing
+/// the document.
+///
+/// The materializer keeps the operations of every document to apply them in the same order on
+/// every node, independent of the order in which entries of different logs arrived.
+#[derive(FromRow, Debug, Clone)]
+pub struct DocumentOperation {
+    /// Hash of the entry holding the message.
+    pub entry_hash: Hash,
+
+    /// Schema of the document.
+    pub schema: Hash,
+
+    /// Id of the document, the hash of the entry which created it.
+    pub document_id: Hash,
+
+    /// Public key of the author.
+    pub author: Author,
+
+    /// Log of the entry.
+    pub log_id: LogId,
+
+    /// Sequence number of the entry.
+    pub seq_num: SeqNum,
+}
+
+impl DocumentOperation {
+    /// Store the operation of an entry.
+    ///
+    /// Returns false and does not change anything when the operation is already stored.
+    pub async fn insert(pool: &Pool, operation: &DocumentOperation) -> Result<bool> {
+        if Self::by_entry_hash(pool, &operation.entry_hash)
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                document_operations (entry_hash, \"schema\", document_id, author, log_id, seq_num)
+            VALUES
+                ($1, $2, $3, $4, $5, $6)
+            ",
+        ))
+        .bind(&operation.entry_hash)
+        .bind(&operation.schema)
+        .bind(&operation.document_id)
+        .bind(&operation.author)
+        .bind(&operation.log_id)
+        .bind(&operation.seq_num)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns the operation of an entry.
+    pub async fn by_entry_hash(
+        pool: &Pool,
+        entry_hash: &Hash,
+    ) -> Result<Option<DocumentOperation>> {
+        let operation = query_as::<_, DocumentOperation>(&sql(
+            pool,
+            "
+            SELECT
+                entry_hash,
+                \"schema\",
+                document_id,
+                author,
+                log_id,
+                seq_num
+            FROM
+                document_operations
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(operation)
+    }
+
+    /// Returns all operations of a document.
+    pub async fn by_document(pool: &Pool, document_id: &Hash) -> Result<Vec<DocumentOperation>> {
+        let operations = query_as::<_, DocumentOperation>(&sql(
+            pool,
+            "
+            SELECT
+                entry_hash,
+                \"schema\",
+                document_id,
+                author,
+                log_id,
+                seq_num
+            FROM
+                document_operations
+            WHERE
+                document_id = $1
+            ",
+        ))
+        .bind(document_id)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(operations)
+    }
+
+    /// Returns the CREATE operations of the given documents, telling the schema of every known
+    /// document.
+    pub async fn creations(pool: &Pool, document_ids: &[Hash]) -> Result<Vec<DocumentOperation>> {
+        if document_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // The id of a document is the hash of the entry which created it
+        let parameters: Vec<String> = (1..=document_ids.len())
+            .map(|index| format!("${}", index))
+            .collect();
+
+        let statement = format!(
+            "
+            SELECT
+                entry_hash,
+                \"schema\",
+                document_id,
+                author,
+                log_id,
+                seq_num
+            FROM
+                document_operations
+            WHERE
+                entry_hash IN ({})
+            ",
+            parameters.join(", ")
+        );
+
+        let statement = sql(pool, &statement);
+        let mut query = query_as::<_, DocumentOperation>(&statement);
+
+        for document_id in document_ids {
+            query = query.bind(document_id);
+        }
+
+        let operations = query.fetch_all(pool).await?;
+
+        Ok(operations)
+    }
+
+    /// Returns the schemas of documents with operations whose entry or payload is not stored
+    /// anymore, their documents can not be materialized again from the stored entries.
+    pub async fn pruned_schemas(pool: &Pool) -> Result<Vec<Hash>> {
+        let schemas = query_as::<_, (Hash,)>(&sql(
+            pool,
+            "
+            SELECT DISTINCT
+                document_operations.\"schema\"
+            FROM
+                document_operations
+            LEFT JOIN entries
+                ON entries.entry_hash = document_operations.entry_hash
+            WHERE
+                entries.payload_bytes IS NULL
+            ",
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(schemas.into_iter().map(|(schema,)| schema).collect())
+    }
+
+    /// Remove the operation of an entry.
+    pub async fn delete(pool: &Pool, entry_hash: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                document_operations
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all operations of documents of a schema.
+    pub async fn delete_by_schema(pool: &Pool, schema: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                document_operations
+            WHERE
+                \"schema\" = $1
+            ",
+        ))
+        .bind(schema)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all operations.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM document_operations")
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::DocumentOperation;
+
+    use crate::test_helpers::{initialize_db, random_entry_hash};
+
+    const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[async_std::test]
+    async fn operations_by_document() {
+        let pool = initialize_db().await;
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let document_id = Hash::new(&random_entry_hash()).unwrap();
+
+        for seq_num in 1..3 {
+            // The first operation creates the document
+            let entry_hash = match seq_num {
+                1 => document_id.clone(),
+                _ => Hash::new(&random_entry_hash()).unwrap(),
+            };
+
+            let operation = DocumentOperation {
+                entry_hash,
+                schema: schema.clone(),
+                document_id: document_id.clone(),
+                author: Author::new(TEST_AUTHOR).unwrap(),
+                log_id: LogId::new(1),
+                seq_num: SeqNum::new(seq_num).unwrap(),
+            };
+
+            assert!(DocumentOperation::insert(&pool, &operation).await.unwrap());
+
+            // Operations are only stored once
+            assert!(!DocumentOperation::insert(&pool, &operation).await.unwrap());
+        }
+
+        assert_eq!(
+            DocumentOperation::by_document(&pool, &document_id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let creations = DocumentOperation::creations(
+            &pool,
+            &[
+                document_id.clone(),
+                Hash::new(&random_entry_hash()).unwrap(),
+            ],
+        )
+        .await
+        .unwrap();
+        assert_eq!(creations.len(), 1);
+        assert_eq!(creations[0].schema, schema);
+
+        DocumentOperation::delete_by_schema(&pool, &schema)
+            .await
+            .unwrap();
+        assert!(DocumentOperation::by_document(&pool, &document_id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use p2panda_rs::entry::SeqNum;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Message encrypted for the members of a key group, published with the encrypted message system
+/// schema.
+///
+/// The node can not read the plaintext, it only keeps the ciphertext next to the key group it was
+/// encrypted for.
+#[derive(FromRow, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedMessage {
+    /// Hash of the entry holding the message.
+    #[schemars(with = "String")]
+    pub entry_hash: Hash,
+
+    /// Id of the key group the message was encrypted for.
+    #[schemars(with = "String")]
+    pub key_group: Hash,
+
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Sequence number of the entry holding the message.
+    #[schemars(with = "u64")]
+    pub seq_num: SeqNum,
+
+    /// Encrypted message fields as given by the author.
+    pub ciphertext: String,
+}
+
+impl EncryptedMessage {
+    /// Store an encrypted message.
+    ///
+    /// Returns false and does not change anything when the message is already stored.
+    pub async fn insert(pool: &Pool, message: &EncryptedMessage) -> Result<bool> {
+        if Self::by_entry_hash(pool, &message.entry_hash)
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                encrypted_messages (entry_hash, key_group, author, seq_num, ciphertext)
+            VALUES
+                ($1, $2, $3, $4, $5)
+            ",
+        ))
+        .bind(&message.entry_hash)
+        .bind(&message.key_group)
+        .bind(&message.author)
+        .bind(&message.seq_num)
+        .bind(&message.ciphertext)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns the encrypted message of an entry.
+    pub async fn by_entry_hash(pool: &Pool, entry_hash: &Hash) -> Result<Option<EncryptedMessage>> {
+        let message = query_as::<_, EncryptedMessage>(&sql(
+            pool,
+            "
+            SELECT
+                entry_hash,
+                key_group,
+                author,
+                seq_num,
+                ciphertext
+            FROM
+                encrypted_messages
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(message)
+    }
+
+    /// Returns all messages encrypted for a key group, ordered by author and sequence number.
+    pub async fn by_key_group(pool: &Pool, key_group: &Hash) -> Result<Vec<EncryptedMessage>> {
+        let messages = query_as::<_, EncryptedMessage>(&sql(
+            pool,
+            "
+            SELECT
+                entry_hash,
+                key_group,
+                author,
+                seq_num,
+                ciphertext
+            FROM
+                encrypted_messages
+            WHERE
+                key_group = $1
+            ORDER BY
+                author, seq_num
+            ",
+        ))
+        .bind(key_group)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(messages)
+    }
+
+    /// Remove an encrypted message.
+    pub async fn delete(pool: &Pool, entry_hash: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                encrypted_messages
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all encrypted messages.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM encrypted_messages")
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::SeqNum;
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::EncryptedMessage;
+
+    use crate::test_helpers::{initialize_db, random_entry_hash};
+
+    const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[async_std::test]
+    async fn messages_by_key_group() {
+        let pool = initialize_db().await;
+        let key_group = Hash::new(&random_entry_hash()).unwrap();
+
+        for seq_num in [2, 1].iter() {
+            let message = EncryptedMessage {
+                entry_hash: Hash::new(&random_entry_hash()).unwrap(),
+                key_group: key_group.clone(),
+                author: Author::new(TEST_AUTHOR).unwrap(),
+                seq_num: SeqNum::new(*seq_num).unwrap(),
+                ciphertext: format!("secret-{}", seq_num),
+            };
+
+            assert!(EncryptedMessage::insert(&pool, &message).await.unwrap());
+
+            // Messages are only stored once
+            assert!(!EncryptedMessage::insert(&pool, &message).await.unwrap());
+        }
+
+        let messages = EncryptedMessage::by_key_group(&pool, &key_group)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].ciphertext, "secret-1");
+
+        EncryptedMessage::delete(&pool, &messages[0].entry_hash)
+            .await
+            .unwrap();
+        EncryptedMessage::delete_all(&pool).await.unwrap();
+        assert!(EncryptedMessage::by_key_group(&pool, &key_group)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -3,12 +3,18 @@ use p2panda_rs::message::MessageEncoded;
 use p2panda_rs::hash::Hash;
 use p2panda_rs::identity::Author;
 
-use serde::Serialize;
-use sqlx::{query, query_as, FromRow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::any::Any;
+use sqlx::{query, query_as, Executor, FromRow};
 
-use crate::db::Pool;
+use crate::db::models::materializer_task::TASK_PENDING;
+use crate::db::{sql, unix_timestamp, Pool, SqlDialect};
 use crate::errors::Result;
 
+/// Position of an entry in the ordering of all entries by author, log_id and sequence number.
+pub type EntryPosition = (Author, LogId, SeqNum);
+
 /// Entry of an append-only log based on Bamboo specification. It describes the actual data in the
 /// p2p network and is shared between nodes.
 ///
@@ -20,43 +26,54 @@ use crate::errors::Result;
 /// payload can be deleted without affecting the data structures integrity. All other fields like
 /// `author`, `payload_hash` etc. can be retrieved from `entry_bytes` but are separately stored in
 /// the database for faster querying.
-#[derive(FromRow, Debug, Serialize)]
+#[derive(FromRow, Debug, Clone, Deserialize, Serialize, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct Entry {
     /// Public key of the author.
+    #[schemars(with = "String")]
     pub author: Author,
 
     /// Actual Bamboo entry data.
     pub entry_bytes: String,
 
     /// Hash of Bamboo entry data.
+    #[schemars(with = "String")]
     pub entry_hash: Hash,
 
     /// Used log for this entry.
+    #[schemars(with = "u64")]
     pub log_id: LogId,
 
     /// Payload of entry, can be deleted.
     pub payload_bytes: Option<String>,
 
     /// Hash of payload data.
+    #[schemars(with = "String")]
     pub payload_hash: Hash,
 
     /// Sequence number of this entry.
+    #[schemars(with = "u64")]
     pub seq_num: SeqNum,
 }
 
 impl Entry {
-    pub async fn insert(
-        pool: &Pool,
+    /// Store an entry with its encoded message and the time it was received at, the message is
+    /// missing when its payload got pruned on the node we received the entry from.
+    pub async fn insert<'e, E>(
+        executor: E,
         author: &Author,
         entry_bytes: &EntrySigned,
         entry_hash: &Hash,
         log_id: &LogId,
-        payload_bytes: &MessageEncoded,
+        payload_bytes: Option<&MessageEncoded>,
         payload_hash: &Hash,
         seq_num: &SeqNum,
-    ) -> Result<bool> {
-        let rows_affected = query(
+    ) -> Result<bool>
+    where
+        E: Executor<'e, Database = Any> + SqlDialect,
+    {
+        let rows_affected = query(&sql(
+            &executor,
             "
             INSERT INTO
                 entries (
@@ -66,12 +83,13 @@ impl Entry {
                     log_id,
                     payload_bytes,
                     payload_hash,
-                    seq_num
+                    seq_num,
+                    received_at
                 )
             VALUES
-                ($1, $2, $3, $4, $5, $6, $7)
+                ($1, $2, $3, $4, $5, $6, $7, $8)
             ",
-        )
+        ))
         .bind(author)
         .bind(entry_bytes)
         .bind(entry_hash)
@@ -79,7 +97,8 @@ impl Entry {
         .bind(payload_bytes)
         .bind(payload_hash)
         .bind(seq_num)
-        .execute(pool)
+        .bind(unix_timestamp())
+        .execute(executor)
         .await?
         .rows_affected();
 
@@ -88,7 +107,8 @@ impl Entry {
 
     /// Returns the latest Bamboo entry of an author's log.
     pub async fn latest(pool: &Pool, author: &Author, log_id: &LogId) -> Result<Option<Entry>> {
-        let latest_entry = query_as::<_, Entry>(
+        let latest_entry = query_as::<_, Entry>(&sql(
+            pool,
             "
             SELECT
                 author,
@@ -108,7 +128,7 @@ impl Entry {
             LIMIT
                 1
             ",
-        )
+        ))
         .bind(author)
         .bind(log_id)
         .fetch_optional(pool)
@@ -117,9 +137,56 @@ impl Entry {
         Ok(latest_entry)
     }
 
-    /// Return vector of all entries of a given schema
-    pub async fn by_schema(pool: &Pool, schema: &Hash) -> Result<Vec<Entry>> {
-        let entries = query_as::<_, Entry>(
+    /// Returns a page of entries, optionally filtered by author and / or schema.
+    ///
+    /// Entries are ordered by author, log_id and sequence number to guarantee a stable ordering
+    /// when paginating through them. When a position is given only entries following it are
+    /// returned.
+    pub async fn query(
+        pool: &Pool,
+        author: Option<&Author>,
+        schema: Option<&Hash>,
+        after: Option<&EntryPosition>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Entry>> {
+        // Build WHERE clause based on the given filters
+        let mut conditions = Vec::new();
+        let mut parameters = 0;
+
+        if author.is_some() {
+            parameters += 1;
+            conditions.push(format!("entries.author = ${}", parameters));
+        }
+
+        if schema.is_some() {
+            parameters += 1;
+            conditions.push(format!("logs.schema = ${}", parameters));
+        }
+
+        // Every parameter is only used once as MySQL does not support numbered parameters
+        if after.is_some() {
+            conditions.push(format!(
+                "(entries.author > ${}
+                    OR (entries.author = ${}
+                        AND (entries.log_id > ${}
+                            OR (entries.log_id = ${} AND entries.seq_num > ${}))))",
+                parameters + 1,
+                parameters + 2,
+                parameters + 3,
+                parameters + 4,
+                parameters + 5,
+            ));
+            parameters += 5;
+        }
+
+        let where_clause = if conditions.is_empty() {
+            "".to_owned()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let statement = format!(
             "
             SELECT
                 entries.author,
@@ -134,25 +201,143 @@ impl Entry {
             INNER JOIN logs
                 ON (entries.log_id = logs.log_id
                     AND entries.author = logs.author)
+            {}
+            ORDER BY
+                entries.author ASC,
+                entries.log_id ASC,
+                entries.seq_num ASC
+            LIMIT
+                ${}
+            OFFSET
+                ${}
+            ",
+            where_clause,
+            parameters + 1,
+            parameters + 2,
+        );
+
+        // Bind filter values in the same order as they appear in the WHERE clause
+        let statement = sql(pool, &statement);
+        let mut query = query_as::<_, Entry>(&statement);
+
+        if let Some(author) = author {
+            query = query.bind(author);
+        }
+
+        if let Some(schema) = schema {
+            query = query.bind(schema);
+        }
+
+        if let Some((author, log_id, seq_num)) = after {
+            query = query
+                .bind(author)
+                .bind(author)
+                .bind(log_id)
+                .bind(log_id)
+                .bind(seq_num);
+        }
+
+        let entries = query.bind(limit).bind(offset).fetch_all(pool).await?;
+
+        Ok(entries)
+    }
+
+    /// Returns the entry with the given entry hash.
+    pub async fn by_hash<'e, E>(executor: E, entry_hash: &Hash) -> Result<Option<Entry>>
+    where
+        E: Executor<'e, Database = Any> + SqlDialect,
+    {
+        let entry = query_as::<_, Entry>(&sql(
+            &executor,
+            "
+            SELECT
+                author,
+                entry_bytes,
+                entry_hash,
+                log_id,
+                payload_bytes,
+                payload_hash,
+                seq_num
+            FROM
+                entries
             WHERE
-                logs.schema = $1
+                entry_hash = $1
             ",
-        )
-        .bind(schema)
+        ))
+        .bind(entry_hash)
+        .fetch_optional(executor)
+        .await?;
+
+        Ok(entry)
+    }
+
+    /// Returns the time in seconds since the Unix epoch when an entry was received, `None` when
+    /// the entry does not exist or was stored before its receive time was recorded.
+    pub async fn received_at(pool: &Pool, entry_hash: &Hash) -> Result<Option<i64>> {
+        let received_at = query_as::<_, (Option<i64>,)>(&sql(
+            pool,
+            "
+            SELECT
+                received_at
+            FROM
+                entries
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(received_at.and_then(|(received_at,)| received_at))
+    }
+
+    /// Returns the schemas of logs which contain entries without payload or which miss entries
+    /// before their latest one.
+    ///
+    /// Entries of these logs were pruned or they were received without the entries and payloads
+    /// before them.
+    pub async fn pruned_schemas(pool: &Pool) -> Result<Vec<Hash>> {
+        let schemas = query_as::<_, (Hash,)>(&sql(
+            pool,
+            "
+            SELECT
+                logs.\"schema\"
+            FROM
+                entries
+            INNER JOIN logs
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            GROUP BY
+                entries.author,
+                entries.log_id,
+                logs.\"schema\"
+            HAVING
+                COUNT(entries.payload_bytes) < MAX(entries.seq_num)
+            ",
+        ))
         .fetch_all(pool)
         .await?;
 
-        Ok(entries)
+        let mut schemas: Vec<Hash> = schemas.into_iter().map(|(schema,)| schema).collect();
+        schemas.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        schemas.dedup();
+
+        Ok(schemas)
     }
 
     /// Returns entry at sequence position within an author's log.
-    pub async fn at_seq_num(
-        pool: &Pool,
+    pub async fn at_seq_num<'e, E>(
+        executor: E,
         author: &Author,
         log_id: &LogId,
         seq_num: &SeqNum,
-    ) -> Result<Option<Entry>> {
-        let entry = query_as::<_, Entry>(
+    ) -> Result<Option<Entry>>
+    where
+        E: Executor<'e, Database = Any> + SqlDialect,
+    {
+        let entry = query_as::<_, Entry>(&sql(
+            &executor,
             "
             SELECT
                 author,
@@ -169,29 +354,622 @@ impl Entry {
                 AND log_id = $2
                 AND seq_num = $3
             ",
-        )
+        ))
         .bind(author)
         .bind(log_id)
         .bind(seq_num)
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
 
         Ok(entry)
     }
+
+    /// Returns the latest sequence number of every log stored on this node with its schema.
+    pub async fn log_heights(pool: &Pool) -> Result<Vec<(Author, LogId, SeqNum, Hash)>> {
+        let log_heights = query_as::<_, (Author, LogId, SeqNum, Hash)>(&sql(
+            pool,
+            "
+            SELECT
+                entries.author,
+                entries.log_id,
+                MAX(entries.seq_num),
+                logs.\"schema\"
+            FROM
+                entries
+            INNER JOIN logs
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            GROUP BY
+                entries.author,
+                entries.log_id,
+                logs.\"schema\"
+            ORDER BY
+                entries.author ASC,
+                entries.log_id ASC
+            ",
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(log_heights)
+    }
+
+    /// Returns the sequence number of the latest entry of every log of a schema.
+    pub async fn schema_log_heights(
+        pool: &Pool,
+        schema: &Hash,
+    ) -> Result<Vec<(Author, LogId, SeqNum)>> {
+        let log_heights = query_as::<_, (Author, LogId, SeqNum)>(&sql(
+            pool,
+            "
+            SELECT
+                entries.author,
+                entries.log_id,
+                MAX(entries.seq_num)
+            FROM
+                entries
+            INNER JOIN logs
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            WHERE
+                logs.\"schema\" = $1
+            GROUP BY
+                entries.author,
+                entries.log_id
+            ORDER BY
+                entries.author ASC,
+                entries.log_id ASC
+            ",
+        ))
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(log_heights)
+    }
+
+    /// Returns entries of an author's log following the given sequence number, ordered by
+    /// sequence number.
+    pub async fn after_seq_num(
+        pool: &Pool,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: i64,
+        limit: i64,
+    ) -> Result<Vec<Entry>> {
+        let entries = query_as::<_, Entry>(&sql(
+            pool,
+            "
+            SELECT
+                author,
+                entry_bytes,
+                entry_hash,
+                log_id,
+                payload_bytes,
+                payload_hash,
+                seq_num
+            FROM
+                entries
+            WHERE
+                author = $1
+                AND log_id = $2
+                AND seq_num > $3
+            ORDER BY
+                seq_num ASC
+            LIMIT
+                $4
+            ",
+        ))
+        .bind(author)
+        .bind(log_id)
+        .bind(seq_num)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(entries)
+    }
+
+    /// Returns entries of an author within a range of sequence numbers, optionally only of one
+    /// log, ordered by log_id and sequence number.
+    ///
+    /// Both ends of the range are inclusive, all entries following `from` are returned when `to`
+    /// is not set. When a log_id and sequence number are given as position only entries following
+    /// it are returned.
+    pub async fn by_author(
+        pool: &Pool,
+        author: &Author,
+        log_id: Option<&LogId>,
+        from: &SeqNum,
+        to: Option<&SeqNum>,
+        after: Option<&(LogId, SeqNum)>,
+        limit: i64,
+    ) -> Result<Vec<Entry>> {
+        // Build WHERE clause based on the given filters
+        let mut conditions = vec!["author = $1".to_owned(), "seq_num >= $2".to_owned()];
+        let mut parameters = 2;
+
+        if log_id.is_some() {
+            parameters += 1;
+            conditions.push(format!("log_id = ${}", parameters));
+        }
+
+        if to.is_some() {
+            parameters += 1;
+            conditions.push(format!("seq_num <= ${}", parameters));
+        }
+
+        // Every parameter is only used once as MySQL does not support numbered parameters
+        if after.is_some() {
+            conditions.push(format!(
+                "(log_id > ${} OR (log_id = ${} AND seq_num > ${}))",
+                parameters + 1,
+                parameters + 2,
+                parameters + 3,
+            ));
+            parameters += 3;
+        }
+
+        let statement = format!(
+            "
+            SELECT
+                author,
+                entry_bytes,
+                entry_hash,
+                log_id,
+                payload_bytes,
+                payload_hash,
+                seq_num
+            FROM
+                entries
+            WHERE
+                {}
+            ORDER BY
+                log_id ASC,
+                seq_num ASC
+            LIMIT
+                ${}
+            ",
+            conditions.join(" AND "),
+            parameters + 1,
+        );
+
+        // Bind filter values in the same order as they appear in the WHERE clause
+        let statement = sql(pool, &statement);
+        let mut query = query_as::<_, Entry>(&statement).bind(author).bind(from);
+
+        if let Some(log_id) = log_id {
+            query = query.bind(log_id);
+        }
+
+        if let Some(to) = to {
+            query = query.bind(to);
+        }
+
+        if let Some((log_id, seq_num)) = after {
+            query = query.bind(log_id).bind(log_id).bind(seq_num);
+        }
+
+        let entries = query.bind(limit).fetch_all(pool).await?;
+
+        Ok(entries)
+    }
+
+    /// Returns the number of entries stored on this node.
+    pub async fn count(pool: &Pool) -> Result<i64> {
+        let (count,) = query_as::<_, (i64,)>(&sql(
+            pool,
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                entries
+            ",
+        ))
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Remove the entries of an author, optionally limited to a single log.
+    ///
+    /// Returns the number of removed entries.
+    pub async fn delete(pool: &Pool, author: &Author, log_id: Option<&LogId>) -> Result<u64> {
+        let mut statement = "
+            DELETE FROM
+                entries
+            WHERE
+                author = $1
+            "
+        .to_owned();
+
+        if log_id.is_some() {
+            statement.push_str("AND log_id = $2");
+        }
+
+        let statement = sql(pool, &statement);
+        let mut query = query(&statement).bind(author);
+
+        if let Some(log_id) = log_id {
+            query = query.bind(log_id);
+        }
+
+        Ok(query.execute(pool).await?.rows_affected())
+    }
+
+    /// Returns the hashes of all entries of an author, optionally limited to a single log, together
+    /// with the schema of their log.
+    pub async fn hashes_by_author(
+        pool: &Pool,
+        author: &Author,
+        log_id: Option<&LogId>,
+    ) -> Result<Vec<(Hash, Hash)>> {
+        let mut statement = "
+            SELECT
+                entries.entry_hash,
+                logs.\"schema\"
+            FROM
+                entries
+            INNER JOIN logs
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            WHERE
+                entries.author = $1
+            "
+        .to_owned();
+
+        if log_id.is_some() {
+            statement.push_str("AND entries.log_id = $2");
+        }
+
+        let statement = sql(pool, &statement);
+        let mut query = query_as::<_, (Hash, Hash)>(&statement).bind(author);
+
+        if let Some(log_id) = log_id {
+            query = query.bind(log_id);
+        }
+
+        Ok(query.fetch_all(pool).await?)
+    }
+
+    /// Returns the logs of a schema whose latest entry was received before the given time in
+    /// seconds since the Unix epoch.
+    ///
+    /// Entries stored before their receive time was recorded count as received at the epoch.
+    pub async fn stale_logs(
+        pool: &Pool,
+        schema: &Hash,
+        received_before: i64,
+    ) -> Result<Vec<(Author, LogId)>> {
+        let logs = query_as::<_, (Author, LogId)>(&sql(
+            pool,
+            "
+            SELECT
+                entries.author,
+                entries.log_id
+            FROM
+                entries
+            INNER JOIN logs
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            WHERE
+                logs.\"schema\" = $1
+            GROUP BY
+                entries.author,
+                entries.log_id
+            HAVING
+                MAX(COALESCE(entries.received_at, 0)) < $2
+            ORDER BY
+                entries.author ASC,
+                entries.log_id ASC
+            ",
+        ))
+        .bind(schema)
+        .bind(received_before)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(logs)
+    }
+
+    /// Remove all but the latest `keep` entries of every log of a schema, entries of the lipmaa
+    /// certificate pool of the latest entry are kept to link following entries to.
+    ///
+    /// Entries waiting to be materialized are kept. Returns the number of removed entries.
+    pub async fn prune_entries(pool: &Pool, schema: &Hash, keep: i64) -> Result<u64> {
+        let mut pruned = 0;
+
+        for (author, log_id, seq_num) in Self::schema_log_heights(pool, schema).await? {
+            if seq_num.as_i64() <= keep {
+                continue;
+            }
+
+            // Sequence numbers are no user input, they can be part of the statement
+            let certificate_pool: Vec<String> = certificate_pool(&seq_num)
+                .iter()
+                .map(|seq_num| seq_num.to_string())
+                .collect();
+
+            let statement = format!(
+                "
+                DELETE FROM
+                    entries
+                WHERE
+                    author = $1
+                    AND log_id = $2
+                    AND seq_num <= $3
+                    AND seq_num NOT IN ({})
+                    AND entry_hash NOT IN (
+                        SELECT
+                            entry_hash
+                        FROM
+                            materializer_tasks
+                        WHERE
+                            status = $4
+                    )
+                ",
+                certificate_pool.join(", ")
+            );
+
+            pruned += query(&sql(pool, &statement))
+                .bind(&author)
+                .bind(&log_id)
+                .bind(seq_num.as_i64() - keep)
+                .bind(TASK_PENDING)
+                .execute(pool)
+                .await?
+                .rows_affected();
+        }
+
+        Ok(pruned)
+    }
+
+    /// Remove all entries of every log of a schema which were received before the given time in
+    /// seconds since the Unix epoch, entries of the lipmaa certificate pool of the latest entry
+    /// are kept to link following entries to while their payloads are removed.
+    ///
+    /// Entries waiting to be materialized are kept. Returns the number of removed entries and the
+    /// hashes of all entries which were removed or lost their payload.
+    pub async fn expire_entries(
+        pool: &Pool,
+        schema: &Hash,
+        received_before: i64,
+    ) -> Result<(u64, Vec<Hash>)> {
+        let mut expired = 0;
+        let mut entry_hashes = Vec::new();
+
+        for (author, log_id, seq_num) in Self::schema_log_heights(pool, schema).await? {
+            // Sequence numbers are no user input, they can be part of the statement
+            let certificate_pool: Vec<String> = certificate_pool(&seq_num)
+                .iter()
+                .map(|seq_num| seq_num.to_string())
+                .collect();
+            let certificate_pool = certificate_pool.join(", ");
+
+            let statement = format!(
+                "
+                SELECT
+                    entry_hash
+                FROM
+                    entries
+                WHERE
+                    author = $1
+                    AND log_id = $2
+                    AND COALESCE(received_at, 0) < $3
+                    AND (seq_num NOT IN ({}) OR payload_bytes IS NOT NULL)
+                    AND entry_hash NOT IN (
+                        SELECT
+                            entry_hash
+                        FROM
+                            materializer_tasks
+                        WHERE
+                            status = $4
+                    )
+                ",
+                certificate_pool
+            );
+
+            let hashes = query_as::<_, (Hash,)>(&sql(pool, &statement))
+                .bind(&author)
+                .bind(&log_id)
+                .bind(received_before)
+                .bind(TASK_PENDING)
+                .fetch_all(pool)
+                .await?;
+
+            if hashes.is_empty() {
+                continue;
+            }
+
+            let statement = format!(
+                "
+                DELETE FROM
+                    entries
+                WHERE
+                    author = $1
+                    AND log_id = $2
+                    AND COALESCE(received_at, 0) < $3
+                    AND seq_num NOT IN ({})
+                    AND entry_hash NOT IN (
+                        SELECT
+                            entry_hash
+                        FROM
+                            materializer_tasks
+                        WHERE
+                            status = $4
+                    )
+                ",
+                certificate_pool
+            );
+
+            expired += query(&sql(pool, &statement))
+                .bind(&author)
+                .bind(&log_id)
+                .bind(received_before)
+                .bind(TASK_PENDING)
+                .execute(pool)
+                .await?
+                .rows_affected();
+
+            // Expired entries of the certificate pool do not keep their payloads either
+            let statement = format!(
+                "
+                UPDATE
+                    entries
+                SET
+                    payload_bytes = NULL
+                WHERE
+                    author = $1
+                    AND log_id = $2
+                    AND COALESCE(received_at, 0) < $3
+                    AND seq_num IN ({})
+                    AND payload_bytes IS NOT NULL
+                    AND entry_hash NOT IN (
+                        SELECT
+                            entry_hash
+                        FROM
+                            materializer_tasks
+                        WHERE
+                            status = $4
+                    )
+                ",
+                certificate_pool
+            );
+
+            query(&sql(pool, &statement))
+                .bind(&author)
+                .bind(&log_id)
+                .bind(received_before)
+                .bind(TASK_PENDING)
+                .execute(pool)
+                .await?;
+
+            entry_hashes.extend(hashes.into_iter().map(|(entry_hash,)| entry_hash));
+        }
+
+        Ok((expired, entry_hashes))
+    }
+
+    /// Remove the payloads of all but the latest `keep` entries of every log of a schema, the
+    /// entries themselves stay stored.
+    ///
+    /// Payloads of entries waiting to be materialized are kept. Returns the number of pruned
+    /// payloads.
+    pub async fn prune_payloads(pool: &Pool, schema: &Hash, keep: i64) -> Result<u64> {
+        let mut pruned = 0;
+
+        for (author, log_id, seq_num) in Self::schema_log_heights(pool, schema).await? {
+            if seq_num.as_i64() <= keep {
+                continue;
+            }
+
+            pruned += query(&sql(
+                pool,
+                "
+                UPDATE
+                    entries
+                SET
+                    payload_bytes = NULL
+                WHERE
+                    author = $1
+                    AND log_id = $2
+                    AND seq_num <= $3
+                    AND payload_bytes IS NOT NULL
+                    AND entry_hash NOT IN (
+                        SELECT
+                            entry_hash
+                        FROM
+                            materializer_tasks
+                        WHERE
+                            status = $4
+                    )
+                ",
+            ))
+            .bind(&author)
+            .bind(&log_id)
+            .bind(seq_num.as_i64() - keep)
+            .bind(TASK_PENDING)
+            .execute(pool)
+            .await?
+            .rows_affected();
+        }
+
+        Ok(pruned)
+    }
+
+    /// Returns the number of authors with entries stored on this node.
+    pub async fn author_count(pool: &Pool) -> Result<i64> {
+        let (count,) = query_as::<_, (i64,)>(&sql(
+            pool,
+            "
+            SELECT
+                COUNT(DISTINCT author)
+            FROM
+                entries
+            ",
+        ))
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+}
+
+/// Returns the sequence numbers of the lipmaa certificate pool of an entry, the entries on the
+/// path of skiplinks from it back to the first entry of its log.
+///
+/// Together with the entry itself they are enough to verify it, skiplinks of all following
+/// entries point into this pool as well.
+pub fn certificate_pool(seq_num: &SeqNum) -> Vec<i64> {
+    let mut certificate_pool = Vec::new();
+    let mut next = Some(seq_num.clone());
+
+    while let Some(seq_num) = next {
+        certificate_pool.push(seq_num.as_i64());
+        next = seq_num.skiplink_seq_num();
+    }
+
+    certificate_pool
+}
+
+/// Returns the payload hash signed within a Bamboo entry, it is needed to store entries which are
+/// received without their payload.
+pub fn signed_payload_hash(entry_encoded: &EntrySigned) -> Hash {
+    let entry_bytes = entry_encoded.to_bytes();
+    let entry = bamboo_rs_core::decode(&entry_bytes).expect("Signed entry could not be decoded");
+
+    let mut payload_hash = Vec::new();
+    entry
+        .payload_hash
+        .encode_write(&mut payload_hash)
+        .expect("Payload hash could not be encoded");
+
+    Hash::new(&hex::encode(payload_hash)).expect("Signed entry contains invalid payload hash")
 }
 
 #[cfg(test)]
 mod tests {
-    use p2panda_rs::entry::LogId;
+    use p2panda_rs::entry::{LogId, SeqNum};
     use p2panda_rs::identity::Author;
     use p2panda_rs::hash::Hash;
 
-    use super::Entry;
+    use super::{certificate_pool, Entry};
 
-    use crate::test_helpers::initialize_db;
+    use crate::test_helpers::{initialize_db, random_entry_hash};
 
     const TEST_AUTHOR: &str = "1a8a62c5f64eed987326513ea15a6ea2682c256ac57a418c1c92d96787c8b36e";
 
+    #[test]
+    fn lipmaa_certificate_pool() {
+        assert_eq!(certificate_pool(&SeqNum::new(1).unwrap()), vec![1]);
+        assert_eq!(certificate_pool(&SeqNum::new(13).unwrap()), vec![13, 4, 1]);
+        assert_eq!(
+            certificate_pool(&SeqNum::new(20).unwrap()),
+            vec![20, 19, 18, 17, 13, 4, 1]
+        );
+    }
+
     #[async_std::test]
     async fn latest_entry() {
         let pool = initialize_db().await;
@@ -203,13 +981,43 @@ mod tests {
         assert!(latest_entry.is_none());
     }
 
+    #[async_std::test]
+    async fn entry_by_hash() {
+        let pool = initialize_db().await;
+
+        let entry_hash = Hash::new(&random_entry_hash()).unwrap();
+
+        let entry = Entry::by_hash(&pool, &entry_hash).await.unwrap();
+        assert!(entry.is_none());
+    }
+
     #[async_std::test]
     async fn entries_by_schema() {
         let pool = initialize_db().await;
 
         let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
 
-        let entries = Entry::by_schema(&pool, &schema).await.unwrap();
+        let entries = Entry::query(&pool, None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        assert!(entries.len() == 0);
+    }
+
+    #[async_std::test]
+    async fn entries_by_author_and_schema() {
+        let pool = initialize_db().await;
+
+        let author = Author::new(TEST_AUTHOR).unwrap();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        let entries = Entry::query(&pool, Some(&author), Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        assert!(entries.len() == 0);
+
+        let entries = Entry::query(&pool, Some(&author), None, None, 10, 0)
+            .await
+            .unwrap();
         assert!(entries.len() == 0);
     }
 }
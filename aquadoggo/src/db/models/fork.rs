@@ -0,0 +1,209 @@
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Record of an entry which conflicts with a stored entry at the same position of a log.
+///
+/// Authors must never create two different entries with the same sequence number in one log. When
+/// this happens the log is forked, the node keeps the entry it stored first and refuses the other
+/// one. The refused entry is kept as proof of the fork.
+#[derive(FromRow, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Fork {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Log id of the forked log.
+    #[schemars(with = "u64")]
+    pub log_id: LogId,
+
+    /// Sequence number of both conflicting entries.
+    #[schemars(with = "u64")]
+    pub seq_num: SeqNum,
+
+    /// Hash of the entry stored on this node.
+    #[schemars(with = "String")]
+    pub entry_hash: Hash,
+
+    /// Encoded entry which was refused.
+    pub fork_entry_bytes: String,
+
+    /// Hash of the entry which was refused.
+    #[schemars(with = "String")]
+    pub fork_entry_hash: Hash,
+}
+
+impl Fork {
+    /// Register a refused entry conflicting with the stored entry with the given hash.
+    ///
+    /// Returns false and does not change anything when this entry was already registered.
+    pub async fn insert(
+        pool: &Pool,
+        fork_entry: &EntrySigned,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+        entry_hash: &Hash,
+    ) -> Result<bool> {
+        let exists = query_as::<_, Fork>(&sql(
+            pool,
+            "
+            SELECT
+                author,
+                log_id,
+                seq_num,
+                entry_hash,
+                fork_entry_bytes,
+                fork_entry_hash
+            FROM
+                forks
+            WHERE
+                fork_entry_hash = $1
+            ",
+        ))
+        .bind(fork_entry.hash())
+        .fetch_optional(pool)
+        .await?
+        .is_some();
+
+        if exists {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                forks (
+                    author,
+                    log_id,
+                    seq_num,
+                    entry_hash,
+                    fork_entry_bytes,
+                    fork_entry_hash
+                )
+            VALUES
+                ($1, $2, $3, $4, $5, $6)
+            ",
+        ))
+        .bind(fork_entry.author())
+        .bind(log_id)
+        .bind(seq_num)
+        .bind(entry_hash)
+        .bind(fork_entry)
+        .bind(fork_entry.hash())
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns all forks of an author, optionally only of one log, ordered by log id and sequence
+    /// number.
+    pub async fn by_author(
+        pool: &Pool,
+        author: &Author,
+        log_id: Option<&LogId>,
+    ) -> Result<Vec<Fork>> {
+        let mut statement = "
+            SELECT
+                author,
+                log_id,
+                seq_num,
+                entry_hash,
+                fork_entry_bytes,
+                fork_entry_hash
+            FROM
+                forks
+            WHERE
+                author = $1
+            "
+        .to_owned();
+
+        if log_id.is_some() {
+            statement.push_str("AND log_id = $2");
+        }
+
+        statement.push_str(
+            "
+            ORDER BY
+                log_id, seq_num
+            ",
+        );
+
+        let statement = sql(pool, &statement);
+        let mut query = query_as::<_, Fork>(&statement).bind(author);
+
+        if let Some(log_id) = log_id {
+            query = query.bind(log_id);
+        }
+
+        let forks = query.fetch_all(pool).await?;
+
+        Ok(forks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use super::Fork;
+
+    use crate::test_helpers::{create_test_entry, initialize_db};
+
+    #[async_std::test]
+    async fn insert_and_find_forks() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        let seq_num = SeqNum::new(1).unwrap();
+
+        let (entry, _) = create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
+        let (fork_entry, _) = create_test_entry(
+            &key_pair,
+            &Hash::new_from_bytes(vec![4, 5, 6]).unwrap(),
+            &log_id,
+            None,
+            None,
+            &seq_num,
+        );
+
+        assert!(
+            Fork::insert(&pool, &fork_entry, &log_id, &seq_num, &entry.hash())
+                .await
+                .unwrap()
+        );
+
+        // Duplicates get ignored
+        assert!(
+            !Fork::insert(&pool, &fork_entry, &log_id, &seq_num, &entry.hash())
+                .await
+                .unwrap()
+        );
+
+        let forks = Fork::by_author(&pool, &entry.author(), Some(&log_id))
+            .await
+            .unwrap();
+        assert_eq!(forks.len(), 1);
+        assert_eq!(forks[0].entry_hash, entry.hash());
+        assert_eq!(forks[0].fork_entry_hash, fork_entry.hash());
+
+        let forks = Fork::by_author(&pool, &entry.author(), Some(&LogId::new(2)))
+            .await
+            .unwrap();
+        assert!(forks.is_empty());
+    }
+}
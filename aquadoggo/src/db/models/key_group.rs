@@ -0,0 +1,386 @@
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Group of authors created by a message of the key group system schema.
+///
+/// The owner of a key group is always one of its members, only the owner can add other members.
+#[derive(FromRow, Debug, Clone)]
+pub struct KeyGroup {
+    /// Id of the key group, the hash of the entry which created it.
+    pub key_group: Hash,
+
+    /// Public key of the author who created the key group.
+    pub owner: Author,
+
+    /// Name of the key group.
+    pub name: String,
+}
+
+impl KeyGroup {
+    /// Register a new key group, its owner becomes the first member.
+    ///
+    /// Returns false and does not change anything when the key group already exists.
+    pub async fn insert(pool: &Pool, key_group: &Hash, owner: &Author, name: &str) -> Result<bool> {
+        if Self::by_key_group(pool, key_group).await?.is_some() {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                key_groups (key_group, owner, name)
+            VALUES
+                ($1, $2, $3)
+            ",
+        ))
+        .bind(key_group)
+        .bind(owner)
+        .bind(name)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Change the name of a key group.
+    pub async fn update_name(pool: &Pool, key_group: &Hash, name: &str) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            UPDATE
+                key_groups
+            SET
+                name = $1
+            WHERE
+                key_group = $2
+            ",
+        ))
+        .bind(name)
+        .bind(key_group)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Returns the key group with this id.
+    pub async fn by_key_group(pool: &Pool, key_group: &Hash) -> Result<Option<KeyGroup>> {
+        let group = query_as::<_, KeyGroup>(&sql(
+            pool,
+            "
+            SELECT
+                key_group,
+                owner,
+                name
+            FROM
+                key_groups
+            WHERE
+                key_group = $1
+            ",
+        ))
+        .bind(key_group)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(group)
+    }
+
+    /// Returns the ids of all key groups an author is a member of, ordered by id.
+    pub async fn by_member(pool: &Pool, member: &Author) -> Result<Vec<Hash>> {
+        let groups = query_as::<_, (Hash,)>(&sql(
+            pool,
+            "
+            SELECT
+                key_group
+            FROM
+                key_groups
+            WHERE
+                owner = $1
+            UNION
+            SELECT
+                key_group_members.key_group
+            FROM
+                key_group_members
+            INNER JOIN key_groups
+                ON (key_groups.key_group = key_group_members.key_group
+                    AND key_groups.owner = key_group_members.added_by)
+            WHERE
+                key_group_members.member = $2
+            ORDER BY
+                key_group
+            ",
+        ))
+        .bind(member)
+        .bind(member)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(key_group,)| key_group)
+        .collect();
+
+        Ok(groups)
+    }
+
+    /// Returns the public keys of all members of a key group including its owner, ordered by
+    /// public key.
+    ///
+    /// Only memberships added by the owner of the key group count.
+    pub async fn members(pool: &Pool, key_group: &Hash) -> Result<Vec<Author>> {
+        let members = query_as::<_, (Author,)>(&sql(
+            pool,
+            "
+            SELECT
+                owner AS member
+            FROM
+                key_groups
+            WHERE
+                key_group = $1
+            UNION
+            SELECT
+                key_group_members.member
+            FROM
+                key_group_members
+            INNER JOIN key_groups
+                ON (key_groups.key_group = key_group_members.key_group
+                    AND key_groups.owner = key_group_members.added_by)
+            WHERE
+                key_group_members.key_group = $2
+            ORDER BY
+                member
+            ",
+        ))
+        .bind(key_group)
+        .bind(key_group)
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(member,)| member)
+        .collect();
+
+        Ok(members)
+    }
+
+    /// Remove a key group with all its memberships.
+    pub async fn delete(pool: &Pool, key_group: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                key_group_members
+            WHERE
+                key_group = $1
+            ",
+        ))
+        .bind(key_group)
+        .execute(pool)
+        .await?;
+
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                key_groups
+            WHERE
+                key_group = $1
+            ",
+        ))
+        .bind(key_group)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all key groups and memberships.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM key_group_members").execute(pool).await?;
+        query("DELETE FROM key_groups").execute(pool).await?;
+        Ok(())
+    }
+}
+
+/// Membership of an author in a key group created by a message of the key group membership system
+/// schema.
+///
+/// Memberships are stored even when the key group is not known yet, they only count when they were
+/// added by the owner of the key group.
+#[derive(FromRow, Debug, Clone)]
+pub struct KeyGroupMembership {
+    /// Id of the membership, the hash of the entry which created it.
+    pub membership: Hash,
+
+    /// Id of the key group.
+    pub key_group: Hash,
+
+    /// Public key of the member.
+    pub member: Author,
+
+    /// Public key of the author who added the member.
+    pub added_by: Author,
+}
+
+impl KeyGroupMembership {
+    /// Register a new membership.
+    ///
+    /// Returns false and does not change anything when the membership already exists.
+    pub async fn insert(
+        pool: &Pool,
+        membership: &Hash,
+        key_group: &Hash,
+        member: &Author,
+        added_by: &Author,
+    ) -> Result<bool> {
+        if Self::by_membership(pool, membership).await?.is_some() {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                key_group_members (membership, key_group, member, added_by)
+            VALUES
+                ($1, $2, $3, $4)
+            ",
+        ))
+        .bind(membership)
+        .bind(key_group)
+        .bind(member)
+        .bind(added_by)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns the membership with this id.
+    pub async fn by_membership(
+        pool: &Pool,
+        membership: &Hash,
+    ) -> Result<Option<KeyGroupMembership>> {
+        let membership = query_as::<_, KeyGroupMembership>(&sql(
+            pool,
+            "
+            SELECT
+                membership,
+                key_group,
+                member,
+                added_by
+            FROM
+                key_group_members
+            WHERE
+                membership = $1
+            ",
+        ))
+        .bind(membership)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(membership)
+    }
+
+    /// Remove a membership.
+    pub async fn delete(pool: &Pool, membership: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                key_group_members
+            WHERE
+                membership = $1
+            ",
+        ))
+        .bind(membership)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::{KeyGroup, KeyGroupMembership};
+
+    use crate::test_helpers::{initialize_db, random_entry_hash};
+
+    const OWNER: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+    const MEMBER: &str = "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a";
+
+    #[async_std::test]
+    async fn key_group_members() {
+        let pool = initialize_db().await;
+
+        let key_group = Hash::new(&random_entry_hash()).unwrap();
+        let owner = Author::new(OWNER).unwrap();
+        let member = Author::new(MEMBER).unwrap();
+
+        // Memberships can arrive before their key group
+        let membership = Hash::new(&random_entry_hash()).unwrap();
+        assert!(
+            KeyGroupMembership::insert(&pool, &membership, &key_group, &member, &owner)
+                .await
+                .unwrap()
+        );
+        assert!(KeyGroup::members(&pool, &key_group)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(KeyGroup::insert(&pool, &key_group, &owner, "Pandas")
+            .await
+            .unwrap());
+        assert_eq!(
+            KeyGroup::members(&pool, &key_group).await.unwrap(),
+            vec![owner.clone(), member.clone()]
+        );
+        assert_eq!(
+            KeyGroup::by_member(&pool, &member).await.unwrap(),
+            vec![key_group.clone()]
+        );
+
+        // Memberships added by other authors do not count
+        let other_group = Hash::new(&random_entry_hash()).unwrap();
+        KeyGroup::insert(&pool, &other_group, &member, "Bears")
+            .await
+            .unwrap();
+        KeyGroupMembership::insert(
+            &pool,
+            &Hash::new(&random_entry_hash()).unwrap(),
+            &other_group,
+            &owner,
+            &owner,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            KeyGroup::by_member(&pool, &owner).await.unwrap(),
+            vec![key_group.clone()]
+        );
+
+        KeyGroupMembership::delete(&pool, &membership)
+            .await
+            .unwrap();
+        assert_eq!(
+            KeyGroup::members(&pool, &key_group).await.unwrap(),
+            vec![owner.clone()]
+        );
+
+        KeyGroup::delete(&pool, &key_group).await.unwrap();
+        assert!(KeyGroup::by_key_group(&pool, &key_group)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,10 +1,13 @@
-use p2panda_rs::entry::LogId;
+use p2panda_rs::entry::{LogId, SeqNum};
 use p2panda_rs::identity::Author;
 use p2panda_rs::hash::Hash;
 
-use sqlx::{query, query_as, FromRow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::any::Any;
+use sqlx::{query, query_as, Executor, FromRow};
 
-use crate::db::Pool;
+use crate::db::{sql, Pool, SqlDialect};
 use crate::errors::Result;
 
 /// Keeps track of which log_id has been used for which schema per author.
@@ -23,30 +26,66 @@ pub struct Log {
     schema: Hash,
 }
 
+/// Log of an author with the sequence number of its latest entry.
+#[derive(FromRow, Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSummary {
+    /// Log id used for the author's schema.
+    #[schemars(with = "u64")]
+    pub log_id: LogId,
+
+    /// Schema hash used by author.
+    #[schemars(with = "String")]
+    pub schema: Hash,
+
+    /// Sequence number of the latest entry in this log.
+    #[schemars(with = "u64")]
+    pub seq_num: SeqNum,
+}
+
+/// Schema this node stores entries of with the number of its entries and materialized documents.
+#[derive(Deserialize, Serialize, Clone, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaSummary {
+    /// Schema hash.
+    #[schemars(with = "String")]
+    pub schema: Hash,
+
+    /// Number of stored entries of this schema.
+    pub entry_count: i64,
+
+    /// Number of materialized documents of this schema.
+    pub document_count: i64,
+}
+
 impl Log {
     /// Register any new log_id for an author's schema.
     ///
     /// The database will reject duplicate entries.
     #[allow(dead_code)]
-    pub async fn insert(
-        pool: &Pool,
+    pub async fn insert<'e, E>(
+        executor: E,
         author: &Author,
         schema: &Hash,
         log_id: &LogId,
-    ) -> Result<bool> {
+    ) -> Result<bool>
+    where
+        E: Executor<'e, Database = Any> + SqlDialect,
+    {
         assert!(log_id.is_user_log());
-        let rows_affected = query(
+        let rows_affected = query(&sql(
+            &executor,
             "
             INSERT INTO
-                logs (author, log_id, schema)
+                logs (author, log_id, \"schema\")
             VALUES
                 ($1, $2, $3)
             ",
-        )
+        ))
         .bind(author)
         .bind(log_id)
         .bind(schema)
-        .execute(pool)
+        .execute(executor)
         .await?
         .rows_affected();
 
@@ -56,7 +95,8 @@ impl Log {
     /// Determines the next unused user schema log_id of an author.
     pub async fn next_user_schema_log_id(pool: &Pool, author: &Author) -> Result<LogId> {
         // Get all log ids from this author
-        let log_ids: Vec<LogId> = query_as::<_, LogId>(
+        let log_ids: Vec<LogId> = query_as::<_, LogId>(&sql(
+            pool,
             "
             SELECT
                 log_id
@@ -67,11 +107,16 @@ impl Log {
             ORDER BY
                 log_id ASC
             ",
-        )
+        ))
         .bind(author)
         .fetch_all(pool)
         .await?;
 
+        Ok(Log::next_unused_user_log_id(&log_ids))
+    }
+
+    /// Finds the next unused user schema log_id from a list of log ids sorted in ascending order.
+    pub fn next_unused_user_log_id(log_ids: &[LogId]) -> LogId {
         // Find next unused user schema log_id by comparing the sequence of known log ids with an
         // ideal sequence of subsequent log ids until we find a gap.
         let mut next_log_id = LogId::default();
@@ -91,16 +136,20 @@ impl Log {
             next_log_id = next_log_id.next().unwrap();
         }
 
-        Ok(next_log_id)
+        next_log_id
     }
 
     /// Returns the registered log_id of an author's schema.
     ///
     /// Messages are separated in different logs per schema and author. This method checks if a log
     /// has already been registered for a certain schema and returns its id.
-    pub async fn get(pool: &Pool, author: &Author, schema: &Hash) -> Result<Option<LogId>> {
+    pub async fn get<'e, E>(executor: E, author: &Author, schema: &Hash) -> Result<Option<LogId>>
+    where
+        E: Executor<'e, Database = Any> + SqlDialect,
+    {
         // @TODO: Look up if system schema was used and return regarding log id
-        let result = query_as::<_, LogId>(
+        let result = query_as::<_, LogId>(&sql(
+            &executor,
             "
             SELECT
                 log_id
@@ -108,32 +157,133 @@ impl Log {
                 logs
             WHERE
                 author = $1
-                AND schema = $2
+                AND \"schema\" = $2
             ",
-        )
+        ))
         .bind(author)
         .bind(schema)
-        .fetch_optional(pool)
+        .fetch_optional(executor)
         .await?;
 
         Ok(result)
     }
 
-    /// Returns registered or possible log_id of an author's schema.
-    ///
-    /// If no log has been found for a USER schema it automatically returns the next unused log_id.
-    /// SYSTEM schema log ids are pre-defined by the protocol specification.
-    pub async fn find_schema_log_id(pool: &Pool, author: &Author, schema: &Hash) -> Result<LogId> {
-        // Determine log_id for author's schema
-        let schema_log_id = Log::get(pool, author, schema).await?;
-
-        // Use result or find next possible log_id automatically
-        let log_id = match schema_log_id {
-            Some(value) => value,
-            None => Log::next_user_schema_log_id(pool, author).await?,
-        };
-
-        Ok(log_id)
+    /// Returns all schemas this node stores entries of.
+    pub async fn schemas(pool: &Pool) -> Result<Vec<Hash>> {
+        let schemas = query_as::<_, (Hash,)>(&sql(
+            pool,
+            "
+            SELECT DISTINCT
+                \"schema\"
+            FROM
+                logs
+            ",
+        ))
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(schema,)| schema)
+        .collect();
+
+        Ok(schemas)
+    }
+
+    /// Returns all schemas this node stores entries of with their number of entries, ordered by
+    /// schema hash.
+    pub async fn schema_entry_counts(pool: &Pool) -> Result<Vec<(Hash, i64)>> {
+        let counts = query_as::<_, (Hash, i64)>(&sql(
+            pool,
+            "
+            SELECT
+                logs.\"schema\",
+                COUNT(entries.entry_hash)
+            FROM
+                logs
+            INNER JOIN entries
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            GROUP BY
+                logs.\"schema\"
+            ORDER BY
+                logs.\"schema\" ASC
+            ",
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        Ok(counts)
+    }
+
+    /// Returns the number of logs registered on this node.
+    pub async fn count(pool: &Pool) -> Result<i64> {
+        let (count,) = query_as::<_, (i64,)>(&sql(
+            pool,
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                logs
+            ",
+        ))
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Returns all logs of an author with the sequence number of their latest entry, ordered by
+    /// log_id.
+    pub async fn by_author(pool: &Pool, author: &Author) -> Result<Vec<LogSummary>> {
+        let logs = query_as::<_, LogSummary>(&sql(
+            pool,
+            "
+            SELECT
+                logs.log_id,
+                logs.\"schema\",
+                MAX(entries.seq_num) AS seq_num
+            FROM
+                logs
+            INNER JOIN entries
+                ON (entries.log_id = logs.log_id
+                    AND entries.author = logs.author)
+            WHERE
+                logs.author = $1
+            GROUP BY
+                logs.log_id,
+                logs.\"schema\"
+            ORDER BY
+                logs.log_id ASC
+            ",
+        ))
+        .bind(author)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(logs)
+    }
+
+    /// Remove the logs of an author, optionally limited to a single log.
+    pub async fn delete(pool: &Pool, author: &Author, log_id: Option<&LogId>) -> Result<u64> {
+        let mut statement = "
+            DELETE FROM
+                logs
+            WHERE
+                author = $1
+            "
+        .to_owned();
+
+        if log_id.is_some() {
+            statement.push_str("AND log_id = $2");
+        }
+
+        let statement = sql(pool, &statement);
+        let mut query = query(&statement).bind(author);
+
+        if let Some(log_id) = log_id {
+            query = query.bind(log_id);
+        }
+
+        Ok(query.execute(pool).await?.rows_affected())
     }
 }
 
@@ -145,6 +295,7 @@ mod tests {
     
     use super::Log;
 
+    use crate::db::{LogStore, SqlStorage};
     use crate::test_helpers::{initialize_db, random_entry_hash};
 
     const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
@@ -156,7 +307,8 @@ mod tests {
         let author = Author::new(TEST_AUTHOR).unwrap();
         let schema = Hash::new(&random_entry_hash()).unwrap();
 
-        let log_id = Log::find_schema_log_id(&pool, &author, &schema)
+        let log_id = SqlStorage::new(pool)
+            .find_schema_log_id(&author, &schema)
             .await
             .unwrap();
 
@@ -218,4 +370,20 @@ mod tests {
         let log_id = Log::next_user_schema_log_id(&pool, &author).await.unwrap();
         assert_eq!(log_id, LogId::new(7));
     }
+
+    #[async_std::test]
+    async fn stored_schemas() {
+        let pool = initialize_db().await;
+
+        let author = Author::new(TEST_AUTHOR).unwrap();
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+
+        assert!(Log::schemas(&pool).await.unwrap().is_empty());
+
+        Log::insert(&pool, &author, &schema, &LogId::new(1))
+            .await
+            .unwrap();
+
+        assert_eq!(Log::schemas(&pool).await.unwrap(), vec![schema]);
+    }
 }
@@ -0,0 +1,310 @@
+use p2panda_rs::hash::Hash;
+
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Status of a task which still needs to be materialized.
+pub const TASK_PENDING: &str = "pending";
+
+/// Status of a task which got materialized successfully.
+pub const TASK_DONE: &str = "done";
+
+/// Status of a task which failed too often and will not be retried anymore.
+pub const TASK_FAILED: &str = "failed";
+
+/// Persisted task to materialize an entry.
+///
+/// Every stored entry gets a task which is processed by the materializer in the background. Keeping
+/// them in the database allows retrying failed tasks and continuing after the node restarted.
+#[derive(FromRow, Debug)]
+pub struct MaterializerTask {
+    /// Hash of the entry to materialize.
+    pub entry_hash: Hash,
+
+    /// Current status of this task.
+    pub status: String,
+
+    /// Number of failed attempts to materialize this entry.
+    pub retries: i64,
+}
+
+impl MaterializerTask {
+    /// Register a new pending task for an entry.
+    ///
+    /// Returns false and does not change anything when a task for this entry already exists.
+    pub async fn insert(pool: &Pool, entry_hash: &Hash) -> Result<bool> {
+        let exists = query_as::<_, MaterializerTask>(&sql(
+            pool,
+            "
+            SELECT
+                entry_hash,
+                status,
+                retries
+            FROM
+                materializer_tasks
+            WHERE
+                entry_hash = $1
+            ",
+        ))
+        .bind(entry_hash)
+        .fetch_optional(pool)
+        .await?
+        .is_some();
+
+        if exists {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                materializer_tasks (entry_hash, status, retries)
+            VALUES
+                ($1, $2, 0)
+            ",
+        ))
+        .bind(entry_hash)
+        .bind(TASK_PENDING)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Register pending tasks for all stored entries which do not have a task yet.
+    ///
+    /// This catches up with entries which were stored while the materializer was not running.
+    pub async fn insert_missing(pool: &Pool) -> Result<u64> {
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                materializer_tasks (entry_hash, status, retries)
+            SELECT
+                entries.entry_hash,
+                $1,
+                0
+            FROM
+                entries
+            WHERE
+                entries.entry_hash NOT IN (
+                    SELECT entry_hash FROM materializer_tasks
+                )
+            ",
+        ))
+        .bind(TASK_PENDING)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected)
+    }
+
+    /// Returns pending tasks in the order of their entries' sequence numbers.
+    pub async fn pending(pool: &Pool, limit: i64) -> Result<Vec<MaterializerTask>> {
+        let tasks = query_as::<_, MaterializerTask>(&sql(
+            pool,
+            "
+            SELECT
+                materializer_tasks.entry_hash,
+                materializer_tasks.status,
+                materializer_tasks.retries
+            FROM
+                materializer_tasks
+            INNER JOIN entries
+                ON entries.entry_hash = materializer_tasks.entry_hash
+            WHERE
+                materializer_tasks.status = $1
+            ORDER BY
+                entries.seq_num, entries.entry_hash
+            LIMIT
+                $2
+            ",
+        ))
+        .bind(TASK_PENDING)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(tasks)
+    }
+
+    /// Returns the number of pending tasks.
+    pub async fn pending_count(pool: &Pool) -> Result<i64> {
+        let (count,) = query_as::<_, (i64,)>(&sql(
+            pool,
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                materializer_tasks
+            WHERE
+                status = $1
+            ",
+        ))
+        .bind(TASK_PENDING)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Returns the number of pending tasks which were not attempted yet.
+    pub async fn unattempted_count(pool: &Pool) -> Result<i64> {
+        let (count,) = query_as::<_, (i64,)>(&sql(
+            pool,
+            "
+            SELECT
+                COUNT(*)
+            FROM
+                materializer_tasks
+            WHERE
+                status = $1
+                AND retries = 0
+            ",
+        ))
+        .bind(TASK_PENDING)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(count)
+    }
+
+    /// Remove all tasks.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM materializer_tasks")
+            .execute(pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Remove all tasks of entries of a schema, they get registered again by `insert_missing`.
+    pub async fn delete_by_schema(pool: &Pool, schema: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                materializer_tasks
+            WHERE
+                entry_hash IN (
+                    SELECT
+                        entries.entry_hash
+                    FROM
+                        entries
+                    INNER JOIN logs
+                        ON (entries.log_id = logs.log_id
+                            AND entries.author = logs.author)
+                    WHERE
+                        logs.\"schema\" = $1
+                )
+            ",
+        ))
+        .bind(schema)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all tasks of entries which do not exist anymore.
+    pub async fn delete_orphaned(pool: &Pool) -> Result<()> {
+        query(
+            "
+            DELETE FROM
+                materializer_tasks
+            WHERE
+                entry_hash NOT IN (
+                    SELECT entry_hash FROM entries
+                )
+            ",
+        )
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Update status and number of retries of a task.
+    pub async fn update(pool: &Pool, entry_hash: &Hash, status: &str, retries: i64) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            UPDATE
+                materializer_tasks
+            SET
+                status = $1,
+                retries = $2
+            WHERE
+                entry_hash = $3
+            ",
+        ))
+        .bind(status)
+        .bind(retries)
+        .bind(entry_hash)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use super::{MaterializerTask, TASK_DONE};
+
+    use crate::db::models::Entry;
+    use crate::test_helpers::{create_test_entry, initialize_db};
+
+    #[async_std::test]
+    async fn pending_tasks() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        let seq_num = SeqNum::new(1).unwrap();
+
+        let (entry, message) = create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
+
+        Entry::insert(
+            &pool,
+            &entry.author(),
+            &entry,
+            &entry.hash(),
+            &log_id,
+            Some(&message),
+            &message.hash(),
+            &seq_num,
+        )
+        .await
+        .unwrap();
+
+        // Missing tasks are registered for stored entries
+        assert_eq!(MaterializerTask::insert_missing(&pool).await.unwrap(), 1);
+        assert_eq!(MaterializerTask::insert_missing(&pool).await.unwrap(), 0);
+        assert!(!MaterializerTask::insert(&pool, &entry.hash())
+            .await
+            .unwrap());
+
+        let tasks = MaterializerTask::pending(&pool, 10).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].retries, 0);
+
+        // Finished tasks are not pending anymore
+        MaterializerTask::update(&pool, &entry.hash(), TASK_DONE, 0)
+            .await
+            .unwrap();
+        assert!(MaterializerTask::pending(&pool, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
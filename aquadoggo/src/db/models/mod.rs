@@ -1,5 +1,21 @@
+mod author_profile;
+mod document_operation;
+mod encrypted_message;
 mod entry;
+mod fork;
+mod key_group;
 mod log;
+mod materializer_task;
+mod schema;
+mod schema_field;
 
-pub use self::log::Log;
-pub use entry::Entry;
+pub use self::log::{Log, LogSummary, SchemaSummary};
+pub use author_profile::AuthorProfile;
+pub use document_operation::DocumentOperation;
+pub use encrypted_message::EncryptedMessage;
+pub use entry::{certificate_pool, signed_payload_hash, Entry, EntryPosition};
+pub use fork::Fork;
+pub use key_group::{KeyGroup, KeyGroupMembership};
+pub use materializer_task::{MaterializerTask, TASK_DONE, TASK_FAILED};
+pub use schema::SchemaDefinition;
+pub use schema_field::SchemaField;
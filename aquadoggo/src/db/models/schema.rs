@@ -0,0 +1,139 @@
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Schema registered by a published schema definition message.
+///
+/// The fields of the schema are stored as `SchemaField` rows, only the author of the definition
+/// is allowed to add fields to it later.
+#[derive(FromRow, Debug, Clone)]
+pub struct SchemaDefinition {
+    /// Hash of the schema, this is the entry hash of the message creating the definition.
+    pub schema: Hash,
+
+    /// Public key of the author who published the definition.
+    pub author: Author,
+}
+
+impl SchemaDefinition {
+    /// Register a new schema.
+    ///
+    /// Returns false and does not change anything when the schema is already registered.
+    pub async fn insert(pool: &Pool, schema: &Hash, author: &Author) -> Result<bool> {
+        if Self::by_schema(pool, schema).await?.is_some() {
+            return Ok(false);
+        }
+
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                schemas (\"schema\", author)
+            VALUES
+                ($1, $2)
+            ",
+        ))
+        .bind(schema)
+        .bind(author)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns the registered schema with this hash.
+    pub async fn by_schema(pool: &Pool, schema: &Hash) -> Result<Option<SchemaDefinition>> {
+        let definition = query_as::<_, SchemaDefinition>(&sql(
+            pool,
+            "
+            SELECT
+                \"schema\",
+                author
+            FROM
+                schemas
+            WHERE
+                \"schema\" = $1
+            ",
+        ))
+        .bind(schema)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(definition)
+    }
+
+    /// Remove a registered schema.
+    pub async fn delete(pool: &Pool, schema: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                schemas
+            WHERE
+                \"schema\" = $1
+            ",
+        ))
+        .bind(schema)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all registered schemas.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM schemas").execute(pool).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::SchemaDefinition;
+
+    use crate::test_helpers::{initialize_db, random_entry_hash};
+
+    const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[async_std::test]
+    async fn register_schema() {
+        let pool = initialize_db().await;
+
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+        let author = Author::new(TEST_AUTHOR).unwrap();
+
+        assert!(SchemaDefinition::by_schema(&pool, &schema)
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(SchemaDefinition::insert(&pool, &schema, &author)
+            .await
+            .unwrap());
+
+        // Schemas are only registered once
+        assert!(!SchemaDefinition::insert(&pool, &schema, &author)
+            .await
+            .unwrap());
+
+        let definition = SchemaDefinition::by_schema(&pool, &schema)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(definition.author, author);
+
+        SchemaDefinition::delete_all(&pool).await.unwrap();
+        assert!(SchemaDefinition::by_schema(&pool, &schema)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,159 @@
+use p2panda_rs::hash::Hash;
+
+use sqlx::{query, query_as, FromRow};
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Field of a schema definition describing the name and type of a document field.
+///
+/// The materializer uses schema definitions to create a table with one column per field for every
+/// schema.
+#[derive(FromRow, Debug, Clone)]
+pub struct SchemaField {
+    /// Schema hash this field belongs to.
+    pub schema: Hash,
+
+    /// Name of the field.
+    pub name: String,
+
+    /// Type of the field, for example `str`.
+    pub field_type: String,
+}
+
+impl SchemaField {
+    /// Register a new field of a schema definition.
+    ///
+    /// The database will reject duplicate fields.
+    pub async fn insert(pool: &Pool, schema: &Hash, name: &str, field_type: &str) -> Result<bool> {
+        let rows_affected = query(&sql(
+            pool,
+            "
+            INSERT INTO
+                schema_fields (\"schema\", name, field_type)
+            VALUES
+                ($1, $2, $3)
+            ",
+        ))
+        .bind(schema)
+        .bind(name)
+        .bind(field_type)
+        .execute(pool)
+        .await?
+        .rows_affected();
+
+        Ok(rows_affected == 1)
+    }
+
+    /// Returns all fields of a schema definition, ordered by name.
+    ///
+    /// An empty list is returned when no definition is known for this schema.
+    pub async fn by_schema(pool: &Pool, schema: &Hash) -> Result<Vec<SchemaField>> {
+        let fields = query_as::<_, SchemaField>(&sql(
+            pool,
+            "
+            SELECT
+                \"schema\",
+                name,
+                field_type
+            FROM
+                schema_fields
+            WHERE
+                \"schema\" = $1
+            ORDER BY
+                name
+            ",
+        ))
+        .bind(schema)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(fields)
+    }
+
+    /// Returns the hashes of all schemas with a known definition.
+    pub async fn schemas(pool: &Pool) -> Result<Vec<Hash>> {
+        let schemas = query_as::<_, (Hash,)>(&sql(
+            pool,
+            "
+            SELECT DISTINCT
+                \"schema\"
+            FROM
+                schema_fields
+            ",
+        ))
+        .fetch_all(pool)
+        .await?
+        .into_iter()
+        .map(|(schema,)| schema)
+        .collect();
+
+        Ok(schemas)
+    }
+
+    /// Remove all fields of a schema definition.
+    pub async fn delete_by_schema(pool: &Pool, schema: &Hash) -> Result<()> {
+        query(&sql(
+            pool,
+            "
+            DELETE FROM
+                schema_fields
+            WHERE
+                \"schema\" = $1
+            ",
+        ))
+        .bind(schema)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove all schema definitions.
+    pub async fn delete_all(pool: &Pool) -> Result<()> {
+        query("DELETE FROM schema_fields").execute(pool).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::hash::Hash;
+
+    use super::SchemaField;
+
+    use crate::test_helpers::{initialize_db, random_entry_hash};
+
+    #[async_std::test]
+    async fn schema_definition() {
+        let pool = initialize_db().await;
+
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+        let schema_other = Hash::new(&random_entry_hash()).unwrap();
+
+        assert!(SchemaField::insert(&pool, &schema, "title", "str")
+            .await
+            .unwrap());
+        assert!(SchemaField::insert(&pool, &schema, "message", "str")
+            .await
+            .unwrap());
+
+        // Duplicate fields get rejected
+        assert!(SchemaField::insert(&pool, &schema, "title", "str")
+            .await
+            .is_err());
+
+        let fields = SchemaField::by_schema(&pool, &schema).await.unwrap();
+        assert_eq!(fields.len(), 2);
+        assert_eq!(fields[0].name, "message");
+
+        let fields = SchemaField::by_schema(&pool, &schema_other).await.unwrap();
+        assert!(fields.is_empty());
+
+        let schemas = SchemaField::schemas(&pool).await.unwrap();
+        assert_eq!(schemas, vec![schema.clone()]);
+
+        SchemaField::delete_all(&pool).await.unwrap();
+        assert!(SchemaField::schemas(&pool).await.unwrap().is_empty());
+    }
+}
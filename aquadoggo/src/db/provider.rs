@@ -0,0 +1,906 @@
+use async_trait::async_trait;
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::MessageEncoded;
+use sqlx::any::Any;
+use sqlx::Transaction;
+use tracing::instrument;
+
+use crate::db::cache::{LogHeadCache, LogIdCache};
+use crate::db::models::{
+    signed_payload_hash, AuthorProfile, EncryptedMessage, Entry, EntryPosition, Fork, KeyGroup,
+    Log, LogSummary, MaterializerTask, SchemaField, SchemaSummary,
+};
+use crate::db::traits::{
+    DocumentStore, EntryStore, ForkStore, IdentityStore, LogStore, PurgeStore, SchemaStore,
+    StatusStore, StorageTransaction, TransactionStore,
+};
+use crate::db::{database_size, pending_migrations, unix_timestamp, Pool};
+use crate::errors::{Error, Result};
+use crate::materializer::{
+    aggregate_documents, document_count, get_document, get_document_version, get_documents,
+    query_documents, schema_definition, unmaterialize_entries, Document, DocumentAggregation,
+    DocumentGroup, DocumentQuery, DocumentVersion, MaterializerError,
+};
+
+/// Storage provider persisting entries and logs in a SQL database.
+///
+/// Document queries, entry listings and statistics run on a separate read pool, so heavy queries
+/// can not take all connections needed to publish entries.
+///
+/// Registered log ids and the latest entry of each log are cached in memory as they are looked up
+/// for every published entry and entry arguments request.
+#[derive(Debug, Clone)]
+pub struct SqlStorage {
+    pub pool: Pool,
+    pub read_pool: Pool,
+    log_ids: LogIdCache,
+    log_heads: LogHeadCache,
+    history: bool,
+}
+
+impl SqlStorage {
+    /// Returns a new SQL storage provider using the given connection pool for all queries.
+    pub fn new(pool: Pool) -> Self {
+        Self::with_read_pool(pool.clone(), pool)
+    }
+
+    /// Returns a new SQL storage provider using the given read pool for read-only queries which
+    /// do not need to see the latest writes, for example on a read replica.
+    pub fn with_read_pool(pool: Pool, read_pool: Pool) -> Self {
+        Self {
+            pool,
+            read_pool,
+            log_ids: LogIdCache::new(),
+            log_heads: LogHeadCache::new(),
+            history: false,
+        }
+    }
+
+    /// Record the version history of documents when they get materialized again after entries
+    /// were removed.
+    pub fn with_history(mut self, history: bool) -> Self {
+        self.history = history;
+        self
+    }
+}
+
+/// Returns the hash of an entry's message, taken from the entry itself when its payload got
+/// pruned.
+fn payload_hash(entry_encoded: &EntrySigned, message_encoded: Option<&MessageEncoded>) -> Hash {
+    match message_encoded {
+        Some(message_encoded) => message_encoded.hash(),
+        None => signed_payload_hash(entry_encoded),
+    }
+}
+
+/// Returns the database model of an entry with its message.
+fn entry_model(
+    entry_encoded: &EntrySigned,
+    message_encoded: Option<&MessageEncoded>,
+    log_id: &LogId,
+    seq_num: &SeqNum,
+) -> Entry {
+    Entry {
+        author: entry_encoded.author(),
+        entry_bytes: entry_encoded.as_str().to_owned(),
+        entry_hash: entry_encoded.hash(),
+        log_id: log_id.clone(),
+        payload_bytes: message_encoded.map(|message| message.as_str().to_owned()),
+        payload_hash: payload_hash(entry_encoded, message_encoded),
+        seq_num: seq_num.clone(),
+    }
+}
+
+#[async_trait]
+impl EntryStore for SqlStorage {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(
+            entry_hash = entry_encoded.hash().as_str(),
+            log_id = log_id.as_i64(),
+            seq_num = seq_num.as_i64()
+        )
+    )]
+    async fn insert_entry(
+        &self,
+        entry_encoded: &EntrySigned,
+        message_encoded: Option<&MessageEncoded>,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<bool> {
+        let inserted = Entry::insert(
+            &self.pool,
+            &entry_encoded.author(),
+            entry_encoded,
+            &entry_encoded.hash(),
+            log_id,
+            message_encoded,
+            &payload_hash(entry_encoded, message_encoded),
+            seq_num,
+        )
+        .await?;
+
+        if inserted {
+            self.log_heads
+                .update(entry_model(entry_encoded, message_encoded, log_id, seq_num));
+        }
+
+        Ok(inserted)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(author = author.as_str(), log_id = log_id.as_i64())
+    )]
+    async fn latest_entry(&self, author: &Author, log_id: &LogId) -> Result<Option<Entry>> {
+        if let Some(entry) = self.log_heads.get(author, log_id) {
+            return Ok(Some(entry));
+        }
+
+        let entry = Entry::latest(&self.pool, author, log_id).await?;
+        if let Some(entry) = &entry {
+            self.log_heads.update(entry.clone());
+        }
+
+        Ok(entry)
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(author = author.as_str(), log_id = log_id.as_i64(), seq_num = seq_num.as_i64())
+    )]
+    async fn entry_at_seq_num(
+        &self,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<Entry>> {
+        Entry::at_seq_num(&self.pool, author, log_id, seq_num).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(entry_hash = entry_hash.as_str()))]
+    async fn entry_by_hash(&self, entry_hash: &Hash) -> Result<Option<Entry>> {
+        Entry::by_hash(&self.pool, entry_hash).await
+    }
+
+    #[instrument(level = "debug", skip(self, author, schema, after))]
+    async fn query_entries(
+        &self,
+        author: Option<&Author>,
+        schema: Option<&Hash>,
+        after: Option<&EntryPosition>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Entry>> {
+        Entry::query(&self.read_pool, author, schema, after, limit, offset).await
+    }
+
+    #[instrument(level = "debug", skip(self, author, log_id, from, to, after))]
+    async fn entries_by_author(
+        &self,
+        author: &Author,
+        log_id: Option<&LogId>,
+        from: &SeqNum,
+        to: Option<&SeqNum>,
+        after: Option<&(LogId, SeqNum)>,
+        limit: i64,
+    ) -> Result<Vec<Entry>> {
+        Entry::by_author(&self.read_pool, author, log_id, from, to, after, limit).await
+    }
+}
+
+#[async_trait]
+impl LogStore for SqlStorage {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(author = author.as_str(), schema = schema.as_str(), log_id = log_id.as_i64())
+    )]
+    async fn insert_log(&self, author: &Author, schema: &Hash, log_id: &LogId) -> Result<bool> {
+        self.log_ids.remove(author, schema);
+        Log::insert(&self.pool, author, schema, log_id).await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(author = author.as_str(), schema = schema.as_str())
+    )]
+    async fn get_log_id(&self, author: &Author, schema: &Hash) -> Result<Option<LogId>> {
+        if let Some(log_id) = self.log_ids.get(author, schema) {
+            return Ok(Some(log_id));
+        }
+
+        let log_id = Log::get(&self.pool, author, schema).await?;
+        if let Some(log_id) = &log_id {
+            self.log_ids.insert(author, schema, log_id);
+        }
+
+        Ok(log_id)
+    }
+
+    #[instrument(level = "debug", skip_all, fields(author = author.as_str()))]
+    async fn logs_by_author(&self, author: &Author) -> Result<Vec<LogSummary>> {
+        Log::by_author(&self.pool, author).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(author = author.as_str()))]
+    async fn next_user_schema_log_id(&self, author: &Author) -> Result<LogId> {
+        Log::next_user_schema_log_id(&self.pool, author).await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(author = author.as_str(), schema = schema.as_str())
+    )]
+    async fn find_schema_log_id(&self, author: &Author, schema: &Hash) -> Result<LogId> {
+        // Only registered log ids are cached, the next unused log id can change with every entry
+        match self.get_log_id(author, schema).await? {
+            Some(log_id) => Ok(log_id),
+            None => self.next_user_schema_log_id(author).await,
+        }
+    }
+}
+
+#[async_trait]
+impl DocumentStore for SqlStorage {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(schema = schema.as_str(), document_id = document_id.as_str())
+    )]
+    async fn document(&self, schema: &Hash, document_id: &Hash) -> Result<Option<Document>> {
+        get_document(&self.read_pool, schema, document_id.as_str()).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(documents = document_ids.len()))]
+    async fn documents_by_id(&self, document_ids: &[Hash]) -> Result<Vec<(Hash, Document)>> {
+        get_documents(&self.read_pool, document_ids).await
+    }
+
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(schema = schema.as_str(), document_id = document_id.as_str())
+    )]
+    async fn document_version(
+        &self,
+        schema: &Hash,
+        document_id: &Hash,
+        version: DocumentVersion,
+    ) -> Result<Option<Document>> {
+        if !self.history {
+            return Err(MaterializerError::HistoryDisabled.into());
+        }
+
+        get_document_version(&self.read_pool, schema, document_id.as_str(), version).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(schema = schema.as_str()))]
+    async fn query_documents(&self, schema: &Hash, query: &DocumentQuery) -> Result<Vec<Document>> {
+        query_documents(&self.read_pool, schema, query).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(schema = schema.as_str()))]
+    async fn aggregate_documents(
+        &self,
+        schema: &Hash,
+        aggregation: &DocumentAggregation,
+    ) -> Result<Vec<DocumentGroup>> {
+        aggregate_documents(&self.read_pool, schema, aggregation).await
+    }
+}
+
+#[async_trait]
+impl ForkStore for SqlStorage {
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(fork_entry_hash = fork_entry.hash().as_str(), entry_hash = entry_hash.as_str())
+    )]
+    async fn insert_fork(
+        &self,
+        fork_entry: &EntrySigned,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+        entry_hash: &Hash,
+    ) -> Result<bool> {
+        Fork::insert(&self.pool, fork_entry, log_id, seq_num, entry_hash).await
+    }
+
+    #[instrument(level = "debug", skip_all, fields(author = author.as_str()))]
+    async fn forks(&self, author: &Author, log_id: Option<&LogId>) -> Result<Vec<Fork>> {
+        Fork::by_author(&self.pool, author, log_id).await
+    }
+}
+
+impl SqlStorage {
+    /// Remove entries and logs of an author and materialize the affected schemas again.
+    async fn purge(&self, author: &Author, log_id: Option<&LogId>) -> Result<u64> {
+        let entries = Entry::hashes_by_author(&self.pool, author, log_id).await?;
+
+        let purged = Entry::delete(&self.pool, author, log_id).await?;
+        Log::delete(&self.pool, author, log_id).await?;
+        self.log_ids.remove_author(author);
+        self.log_heads.remove_author(author);
+        MaterializerTask::delete_orphaned(&self.pool).await?;
+
+        // Documents might contain changes of the removed entries
+        unmaterialize_entries(&self.pool, &entries, self.history).await?;
+
+        Ok(purged)
+    }
+}
+
+#[async_trait]
+impl PurgeStore for SqlStorage {
+    #[instrument(skip_all, fields(author = author.as_str()))]
+    async fn purge_author(&self, author: &Author) -> Result<u64> {
+        self.purge(author, None).await
+    }
+
+    #[instrument(skip_all, fields(author = author.as_str(), log_id = log_id.as_i64()))]
+    async fn purge_log(&self, author: &Author, log_id: &LogId) -> Result<u64> {
+        self.purge(author, Some(log_id)).await
+    }
+
+    #[instrument(skip_all, fields(schema = schema.as_str(), keep))]
+    async fn prune_entries(&self, schema: &Hash, keep: u64) -> Result<u64> {
+        let pruned = Entry::prune_entries(&self.pool, schema, keep as i64).await?;
+
+        // Logs and their latest entries are kept, cached entries might still link to removed ones
+        self.log_heads.clear();
+
+        // Tasks of removed entries which already ran are not needed anymore
+        MaterializerTask::delete_orphaned(&self.pool).await?;
+
+        Ok(pruned)
+    }
+
+    #[instrument(skip_all, fields(schema = schema.as_str(), keep))]
+    async fn prune_payloads(&self, schema: &Hash, keep: u64) -> Result<u64> {
+        let pruned = Entry::prune_payloads(&self.pool, schema, keep as i64).await?;
+
+        // Cached latest entries might still contain their pruned payloads
+        self.log_heads.clear();
+
+        Ok(pruned)
+    }
+
+    #[instrument(skip_all, fields(schema = schema.as_str(), ttl))]
+    async fn expire_logs(&self, schema: &Hash, ttl: u64) -> Result<u64> {
+        let received_before = unix_timestamp() - ttl as i64;
+
+        // Logs without new entries are removed as a whole
+        let mut expired = 0;
+        let mut entries = Vec::new();
+        for (author, log_id) in Entry::stale_logs(&self.pool, schema, received_before).await? {
+            entries.extend(Entry::hashes_by_author(&self.pool, &author, Some(&log_id)).await?);
+            expired += Entry::delete(&self.pool, &author, Some(&log_id)).await?;
+            Log::delete(&self.pool, &author, Some(&log_id)).await?;
+            self.log_ids.remove_author(&author);
+            self.log_heads.remove_author(&author);
+        }
+
+        // Active logs keep the certificate pool of their latest entry without payloads, so new
+        // entries can still link to them
+        let (removed, entry_hashes) =
+            Entry::expire_entries(&self.pool, schema, received_before).await?;
+        expired += removed;
+        entries.extend(
+            entry_hashes
+                .into_iter()
+                .map(|entry_hash| (entry_hash, schema.clone())),
+        );
+
+        if !entries.is_empty() {
+            // Cached latest entries might still contain their removed payloads
+            self.log_heads.clear();
+            MaterializerTask::delete_orphaned(&self.pool).await?;
+            unmaterialize_entries(&self.pool, &entries, self.history).await?;
+        }
+
+        Ok(expired)
+    }
+}
+
+#[async_trait]
+impl SchemaStore for SqlStorage {
+    #[instrument(level = "debug", skip_all)]
+    async fn schemas(&self) -> Result<Vec<SchemaSummary>> {
+        let mut schemas = Vec::new();
+
+        for (schema, entry_count) in Log::schema_entry_counts(&self.read_pool).await? {
+            schemas.push(SchemaSummary {
+                document_count: document_count(&self.read_pool, &schema).await?,
+                entry_count,
+                schema,
+            });
+        }
+
+        Ok(schemas)
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn schema_definition(&self, schema: &Hash) -> Result<Option<Vec<SchemaField>>> {
+        schema_definition(&self.read_pool, schema).await
+    }
+}
+
+#[async_trait]
+impl IdentityStore for SqlStorage {
+    #[instrument(level = "debug", skip_all)]
+    async fn author_profile(&self, author: &Author) -> Result<Option<AuthorProfile>> {
+        AuthorProfile::by_author(&self.read_pool, author).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn key_group(&self, key_group: &Hash) -> Result<Option<KeyGroup>> {
+        KeyGroup::by_key_group(&self.read_pool, key_group).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn key_group_members(&self, key_group: &Hash) -> Result<Vec<Author>> {
+        KeyGroup::members(&self.read_pool, key_group).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn key_groups_by_member(&self, author: &Author) -> Result<Vec<Hash>> {
+        KeyGroup::by_member(&self.read_pool, author).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn encrypted_messages(&self, key_group: &Hash) -> Result<Vec<EncryptedMessage>> {
+        EncryptedMessage::by_key_group(&self.read_pool, key_group).await
+    }
+}
+
+#[async_trait]
+impl StatusStore for SqlStorage {
+    #[instrument(level = "debug", skip_all)]
+    async fn check_health(&self) -> Result<()> {
+        // This query fails when the database can not be reached
+        match pending_migrations(&self.pool).await? {
+            0 => Ok(()),
+            pending => Err(Error::PendingMigrations(pending)),
+        }
+    }
+
+    async fn entry_count(&self) -> Result<i64> {
+        Entry::count(&self.read_pool).await
+    }
+
+    async fn author_count(&self) -> Result<i64> {
+        Entry::author_count(&self.read_pool).await
+    }
+
+    async fn log_count(&self) -> Result<i64> {
+        Log::count(&self.read_pool).await
+    }
+
+    #[instrument(level = "debug", skip_all)]
+    async fn storage_size(&self) -> Result<Option<i64>> {
+        Ok(Some(database_size(&self.pool).await?))
+    }
+
+    async fn materializer_backlog(&self) -> Result<i64> {
+        MaterializerTask::pending_count(&self.pool).await
+    }
+}
+
+/// Database transaction of the SQL storage provider.
+#[derive(Debug)]
+pub struct SqlTransaction {
+    tx: Transaction<'static, Any>,
+    log_ids: LogIdCache,
+    log_heads: LogHeadCache,
+
+    /// Entries inserted within this transaction, they become log heads when it got committed.
+    inserted: Vec<Entry>,
+}
+
+#[async_trait]
+impl StorageTransaction for SqlTransaction {
+    async fn get_log_id(&mut self, author: &Author, schema: &Hash) -> Result<Option<LogId>> {
+        // Only committed log ids are cached, they can be shared with all transactions
+        if let Some(log_id) = self.log_ids.get(author, schema) {
+            return Ok(Some(log_id));
+        }
+
+        Log::get(&mut *self.tx, author, schema).await
+    }
+
+    async fn entry_by_hash(&mut self, entry_hash: &Hash) -> Result<Option<Entry>> {
+        Entry::by_hash(&mut *self.tx, entry_hash).await
+    }
+
+    async fn entry_at_seq_num(
+        &mut self,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<Entry>> {
+        Entry::at_seq_num(&mut *self.tx, author, log_id, seq_num).await
+    }
+
+    async fn insert_log(&mut self, author: &Author, schema: &Hash, log_id: &LogId) -> Result<bool> {
+        Log::insert(&mut *self.tx, author, schema, log_id).await
+    }
+
+    async fn insert_entry(
+        &mut self,
+        entry_encoded: &EntrySigned,
+        message_encoded: Option<&MessageEncoded>,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<bool> {
+        let inserted = Entry::insert(
+            &mut *self.tx,
+            &entry_encoded.author(),
+            entry_encoded,
+            &entry_encoded.hash(),
+            log_id,
+            message_encoded,
+            &payload_hash(entry_encoded, message_encoded),
+            seq_num,
+        )
+        .await?;
+
+        if inserted {
+            self.inserted
+                .push(entry_model(entry_encoded, message_encoded, log_id, seq_num));
+        }
+
+        Ok(inserted)
+    }
+
+    async fn commit(self: Box<Self>) -> Result<()> {
+        self.tx.commit().await?;
+
+        for entry in self.inserted {
+            self.log_heads.update(entry);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TransactionStore for SqlStorage {
+    async fn begin(&self) -> Result<Box<dyn StorageTransaction>> {
+        let tx = self.pool.begin().await?;
+        Ok(Box::new(SqlTransaction {
+            tx,
+            log_ids: self.log_ids.clone(),
+            log_heads: self.log_heads.clone(),
+            inserted: Vec::new(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::message::{Message, MessageFields, MessageValue};
+
+    use super::SqlStorage;
+
+    use crate::db::models::{Entry, Log, MaterializerTask};
+    use crate::db::{EntryStore, LogStore, PurgeStore, SchemaStore, StatusStore, TransactionStore};
+    use crate::materializer::{get_document, process_pending_tasks};
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry, create_test_entry_with_message, define_test_schema, initialize_db,
+    };
+
+    #[async_std::test]
+    async fn purge_author() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        define_test_schema(&pool, &schema, &[("test", "str")]).await;
+
+        // Two authors create a document each
+        let key_pairs = [KeyPair::new(), KeyPair::new()];
+        let mut entries = Vec::new();
+        for key_pair in key_pairs.iter() {
+            let (entry, message) = create_test_entry(
+                key_pair,
+                &schema,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        // The second author also updates the document of the first one
+        let mut fields = MessageFields::new();
+        fields
+            .add("test", MessageValue::Text("Panda".to_owned()))
+            .unwrap();
+        let message = Message::new_update(schema.clone(), entries[0].hash(), fields).unwrap();
+        let (update, message) = create_test_entry_with_message(
+            &key_pairs[1],
+            &message,
+            &LogId::new(1),
+            None,
+            Some(&entries[1]),
+            &SeqNum::new(2).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &update, &message)
+            .await
+            .unwrap();
+
+        MaterializerTask::insert_missing(&pool).await.unwrap();
+        process_pending_tasks(&pool, false).await.unwrap();
+
+        let document = get_document(&pool, &schema, entries[0].hash().as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.entry_hash, update.hash().as_str());
+
+        assert_eq!(storage.purge_author(&entries[1].author()).await.unwrap(), 2);
+        assert_eq!(Entry::count(&pool).await.unwrap(), 1);
+
+        // The document of the purged author got removed
+        let document_id = entries[1].hash();
+        assert!(get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .is_none());
+
+        // The other document lost the changes of the purged author
+        let document = get_document(&pool, &schema, entries[0].hash().as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.entry_hash, entries[0].hash().as_str());
+        assert_eq!(
+            document.fields.get("test"),
+            Some(&MessageValue::Text("Hello".to_owned()))
+        );
+    }
+
+    #[async_std::test]
+    async fn discard_uncommitted_transactions() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        let (entry, message) = create_test_entry(
+            &KeyPair::new(),
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let author = entry.author();
+
+        // Log registration gets discarded when the transaction is dropped
+        let mut tx = storage.begin().await.unwrap();
+        assert!(tx
+            .insert_log(&author, &schema, &LogId::new(1))
+            .await
+            .unwrap());
+        assert!(tx.get_log_id(&author, &schema).await.unwrap().is_some());
+        drop(tx);
+        assert!(Log::get(&pool, &author, &schema).await.unwrap().is_none());
+
+        // Log and entry are stored together on commit
+        let mut tx = storage.begin().await.unwrap();
+        tx.insert_log(&author, &schema, &LogId::new(1))
+            .await
+            .unwrap();
+        tx.insert_entry(
+            &entry,
+            Some(&message),
+            &LogId::new(1),
+            &SeqNum::new(1).unwrap(),
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+        assert!(Log::get(&pool, &author, &schema).await.unwrap().is_some());
+        assert_eq!(Entry::count(&pool).await.unwrap(), 1);
+    }
+
+    #[async_std::test]
+    async fn query_read_pool() {
+        let pool = initialize_db().await;
+        let read_pool = initialize_db().await;
+        let storage = SqlStorage::with_read_pool(pool.clone(), read_pool);
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        let (entry, message) = create_test_entry(
+            &KeyPair::new(),
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+
+        // Entry is written to the main database, listings and statistics only see the other one
+        assert!(storage
+            .entry_by_hash(&entry.hash())
+            .await
+            .unwrap()
+            .is_some());
+        assert_eq!(storage.entry_count().await.unwrap(), 0);
+        assert!(storage
+            .query_entries(None, None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[async_std::test]
+    async fn cache_log_ids() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        let (entry, message) = create_test_entry(
+            &KeyPair::new(),
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+        let author = entry.author();
+
+        // Log id is served from memory after the first lookup
+        let log_id = storage.get_log_id(&author, &schema).await.unwrap();
+        assert_eq!(log_id, Some(LogId::new(1)));
+        Log::delete(&pool, &author, None).await.unwrap();
+        assert_eq!(storage.get_log_id(&author, &schema).await.unwrap(), log_id);
+        assert_eq!(
+            storage.find_schema_log_id(&author, &schema).await.ok(),
+            log_id
+        );
+
+        // Purging an author removes its cached log ids
+        storage.purge_author(&author).await.unwrap();
+        assert!(storage
+            .get_log_id(&author, &schema)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[async_std::test]
+    async fn cache_log_heads() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let key_pair = KeyPair::new();
+
+        // [1] <-- [2]
+        let mut entries = Vec::new();
+        for seq_num in 1..3 {
+            let (entry, message) = create_test_entry(
+                &key_pair,
+                &schema,
+                &LogId::new(1),
+                None,
+                entries.last(),
+                &SeqNum::new(seq_num).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+
+            // Latest entry is known right after it got committed
+            let latest = storage
+                .latest_entry(&entry.author(), &LogId::new(1))
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(latest.seq_num, SeqNum::new(seq_num).unwrap());
+
+            entries.push(entry);
+        }
+
+        // Latest entry is served from memory until the author gets purged
+        let author = entries[0].author();
+        Entry::delete(&pool, &author, None).await.unwrap();
+        assert!(storage
+            .latest_entry(&author, &LogId::new(1))
+            .await
+            .unwrap()
+            .is_some());
+
+        storage.purge_author(&author).await.unwrap();
+        assert!(storage
+            .latest_entry(&author, &LogId::new(1))
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[async_std::test]
+    async fn logs_by_author() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let key_pair = KeyPair::new();
+
+        // [1] <-- [2]
+        let mut entries = Vec::new();
+        for seq_num in 1..3 {
+            let (entry, message) = create_test_entry(
+                &key_pair,
+                &schema,
+                &LogId::new(1),
+                None,
+                entries.last(),
+                &SeqNum::new(seq_num).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        let logs = storage.logs_by_author(&entries[0].author()).await.unwrap();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(logs[0].log_id, LogId::new(1));
+        assert_eq!(logs[0].schema, schema);
+        assert_eq!(logs[0].seq_num, SeqNum::new(2).unwrap());
+    }
+
+    #[async_std::test]
+    async fn schemas() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        define_test_schema(&pool, &schema, &[("test", "str")]).await;
+
+        let (entry, message) = create_test_entry(
+            &KeyPair::new(),
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+
+        // Documents are counted after they got materialized
+        let schemas = storage.schemas().await.unwrap();
+        assert_eq!(schemas.len(), 1);
+        assert_eq!(schemas[0].schema, schema);
+        assert_eq!(schemas[0].entry_count, 1);
+        assert_eq!(schemas[0].document_count, 0);
+
+        MaterializerTask::insert_missing(&pool).await.unwrap();
+        process_pending_tasks(&pool, false).await.unwrap();
+
+        let schemas = storage.schemas().await.unwrap();
+        assert_eq!(schemas[0].document_count, 1);
+    }
+}
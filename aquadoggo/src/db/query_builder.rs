@@ -0,0 +1,983 @@
+use p2panda_rs::hash::Hash;
+use p2panda_rs::message::MessageValue;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::any::{Any, AnyArguments, AnyRow};
+use sqlx::query::Query;
+
+use crate::db::{sql, Pool};
+use crate::errors::Result;
+
+/// Quote an identifier like a table or column name to safely use it in a SQL statement.
+///
+/// Double quotes inside of the identifier are escaped by doubling them.
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// Value which gets bound to a statement.
+///
+/// Values can be deserialized from their JSON representation, for example to use them as filters
+/// given by a client.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, PartialOrd, JsonSchema)]
+#[serde(untagged)]
+pub enum Value {
+    /// Boolean value.
+    Boolean(bool),
+
+    /// Signed integer value.
+    Integer(i64),
+
+    /// Floating point value.
+    Float(f64),
+
+    /// Text value.
+    Text(String),
+}
+
+impl Value {
+    fn bind<'q>(
+        &'q self,
+        query: Query<'q, Any, AnyArguments<'q>>,
+    ) -> Query<'q, Any, AnyArguments<'q>> {
+        match self {
+            Value::Boolean(value) => query.bind(*value),
+            Value::Integer(value) => query.bind(*value),
+            Value::Float(value) => query.bind(*value),
+            Value::Text(value) => query.bind(value.as_str()),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Boolean(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Integer(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_owned())
+    }
+}
+
+impl From<&Hash> for Value {
+    fn from(hash: &Hash) -> Self {
+        Value::Text(hash.as_str().to_owned())
+    }
+}
+
+impl From<&MessageValue> for Value {
+    fn from(value: &MessageValue) -> Self {
+        match value {
+            MessageValue::Boolean(value) => Value::Boolean(*value),
+            MessageValue::Integer(value) => Value::Integer(*value),
+            MessageValue::Float(value) => Value::Float(*value),
+            MessageValue::Text(value) => Value::Text(value.to_owned()),
+            // Relations are stored as hash strings
+            MessageValue::Relation(hash) => Value::Text(hash.as_str().to_owned()),
+        }
+    }
+}
+
+/// Comparison of a column with a value in a `WHERE` clause.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Operator {
+    /// Column equals the value.
+    Eq,
+
+    /// Column does not equal the value.
+    Ne,
+
+    /// Column is lower than the value.
+    Lt,
+
+    /// Column is lower than or equals the value.
+    Lte,
+
+    /// Column is greater than the value.
+    Gt,
+
+    /// Column is greater than or equals the value.
+    Gte,
+}
+
+impl Operator {
+    /// Returns the SQL comparison operator.
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Operator::Eq => "=",
+            Operator::Ne => "<>",
+            Operator::Lt => "<",
+            Operator::Lte => "<=",
+            Operator::Gt => ">",
+            Operator::Gte => ">=",
+        }
+    }
+}
+
+/// Sort direction of an `ORDER BY` clause.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Order {
+    /// Lowest values first.
+    Asc,
+
+    /// Highest values first.
+    Desc,
+}
+
+impl Default for Order {
+    fn default() -> Self {
+        Order::Asc
+    }
+}
+
+impl Order {
+    /// Returns the SQL sort direction.
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        }
+    }
+}
+
+/// Aggregate function of a `SELECT` statement.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub enum Aggregate {
+    /// Number of rows, or of rows with a value in the column when one is given.
+    Count,
+
+    /// Lowest value of the column.
+    Min,
+
+    /// Highest value of the column.
+    Max,
+}
+
+impl Aggregate {
+    /// Returns the SQL aggregate function.
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Aggregate::Count => "COUNT",
+            Aggregate::Min => "MIN",
+            Aggregate::Max => "MAX",
+        }
+    }
+}
+
+/// Condition of a `WHERE` clause comparing a column with a value.
+type Filter = (String, Operator, Value);
+
+/// SQL statement with its values, built by one of the query builders.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    sql: String,
+    values: Vec<Value>,
+}
+
+impl Statement {
+    /// Returns the SQL statement using numbered parameters.
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    /// Returns the values bound to the parameters of this statement in order.
+    pub fn values(&self) -> &[Value] {
+        &self.values
+    }
+
+    /// Execute statement and return the number of affected rows.
+    pub async fn execute(&self, pool: &Pool) -> Result<u64> {
+        let statement = sql(pool, &self.sql);
+        let mut query = sqlx::query(&statement);
+
+        for value in self.values.iter() {
+            query = value.bind(query);
+        }
+
+        Ok(query.execute(pool).await?.rows_affected())
+    }
+
+    /// Execute statement and return at most one row.
+    pub async fn fetch_optional(&self, pool: &Pool) -> Result<Option<AnyRow>> {
+        let statement = sql(pool, &self.sql);
+        let mut query = sqlx::query(&statement);
+
+        for value in self.values.iter() {
+            query = value.bind(query);
+        }
+
+        Ok(query.fetch_optional(pool).await?)
+    }
+
+    /// Execute statement and return all rows.
+    pub async fn fetch_all(&self, pool: &Pool) -> Result<Vec<AnyRow>> {
+        let statement = sql(pool, &self.sql);
+        let mut query = sqlx::query(&statement);
+
+        for value in self.values.iter() {
+            query = value.bind(query);
+        }
+
+        Ok(query.fetch_all(pool).await?)
+    }
+}
+
+/// Returns a `WHERE` clause matching all filters, rows with one of the given values in a column
+/// and rows without values in the given columns, adding the values of the filters to the list of
+/// values.
+fn where_clause(
+    filters: Vec<Filter>,
+    in_filters: Vec<(String, Vec<Value>)>,
+    null_columns: Vec<String>,
+    values: &mut Vec<Value>,
+) -> String {
+    if filters.is_empty() && in_filters.is_empty() && null_columns.is_empty() {
+        return String::new();
+    }
+
+    let mut conditions: Vec<String> = filters
+        .into_iter()
+        .map(|(column, operator, value)| {
+            values.push(value);
+            format!(
+                "{} {} ${}",
+                quote_identifier(&column),
+                operator.as_sql(),
+                values.len()
+            )
+        })
+        .collect();
+
+    for (column, column_values) in in_filters {
+        // An empty list matches no rows, `IN ()` is not valid SQL
+        if column_values.is_empty() {
+            conditions.push("1 = 0".to_owned());
+            continue;
+        }
+
+        let parameters: Vec<String> = column_values
+            .into_iter()
+            .map(|value| {
+                values.push(value);
+                format!("${}", values.len())
+            })
+            .collect();
+
+        conditions.push(format!(
+            "{} IN ({})",
+            quote_identifier(&column),
+            parameters.join(", ")
+        ));
+    }
+
+    for column in null_columns {
+        conditions.push(format!("{} IS NULL", quote_identifier(&column)));
+    }
+
+    format!(" WHERE {}", conditions.join(" AND "))
+}
+
+/// Returns a condition matching all rows sorted after the given column values, adding the values to
+/// the list of values.
+///
+/// For ascending columns `a` and `b` this is `(a > $1 OR (a = $2 AND b > $3))`, every value is
+/// bound once per parameter as some databases do not support reusing numbered parameters.
+fn keyset_condition(
+    after: &[(String, Value)],
+    order_by: &[(String, Order)],
+    values: &mut Vec<Value>,
+) -> String {
+    let (column, value) = &after[0];
+
+    let operator = match order_by.iter().find(|(name, _)| name == column) {
+        Some((_, Order::Desc)) => Operator::Lt,
+        _ => Operator::Gt,
+    };
+
+    values.push(value.clone());
+    let comparison = format!(
+        "{} {} ${}",
+        quote_identifier(column),
+        operator.as_sql(),
+        values.len()
+    );
+
+    if after.len() == 1 {
+        return comparison;
+    }
+
+    values.push(value.clone());
+    let equality = format!("{} = ${}", quote_identifier(column), values.len());
+
+    format!(
+        "({} OR ({} AND {}))",
+        comparison,
+        equality,
+        keyset_condition(&after[1..], order_by, values)
+    )
+}
+
+/// Builds a `CREATE TABLE IF NOT EXISTS` statement.
+#[derive(Debug)]
+pub struct CreateTable {
+    table: String,
+    columns: Vec<(String, String)>,
+}
+
+impl CreateTable {
+    /// Start building statement for a table.
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+            columns: Vec::new(),
+        }
+    }
+
+    /// Add a column with its type and constraints, for example `BIGINT NOT NULL`.
+    ///
+    /// The column definition is used as it is and must not contain any user input.
+    pub fn column(mut self, name: &str, definition: &str) -> Self {
+        self.columns.push((name.to_owned(), definition.to_owned()));
+        self
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|(name, definition)| format!("{} {}", quote_identifier(name), definition))
+            .collect();
+
+        Statement {
+            sql: format!(
+                "CREATE TABLE IF NOT EXISTS {} ({})",
+                quote_identifier(&self.table),
+                columns.join(", ")
+            ),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Builds an `ALTER TABLE` statement adding a column to an existing table.
+///
+/// Only one column is added per statement as SQLite does not support more.
+#[derive(Debug)]
+pub struct AddColumn {
+    table: String,
+    column: String,
+    definition: String,
+}
+
+impl AddColumn {
+    /// Start building statement adding a column with its type and constraints to a table.
+    ///
+    /// The column definition is used as it is and must not contain any user input.
+    pub fn new(table: &str, column: &str, definition: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+            column: column.to_owned(),
+            definition: definition.to_owned(),
+        }
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        Statement {
+            sql: format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                quote_identifier(&self.table),
+                quote_identifier(&self.column),
+                self.definition
+            ),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Builds a `DROP TABLE IF EXISTS` statement.
+#[derive(Debug)]
+pub struct DropTable {
+    table: String,
+}
+
+impl DropTable {
+    /// Start building statement for a table.
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+        }
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        Statement {
+            sql: format!("DROP TABLE IF EXISTS {}", quote_identifier(&self.table)),
+            values: Vec::new(),
+        }
+    }
+}
+
+/// Builds a `SELECT` statement.
+#[derive(Debug)]
+pub struct Select {
+    table: String,
+    columns: Vec<String>,
+    aggregates: Vec<(String, Aggregate, Option<String>)>,
+    filters: Vec<Filter>,
+    in_filters: Vec<(String, Vec<Value>)>,
+    null_columns: Vec<String>,
+    group_by: Vec<String>,
+    order_by: Vec<(String, Order)>,
+    after: Vec<(String, Value)>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl Select {
+    /// Start building statement for a table.
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+            columns: Vec::new(),
+            aggregates: Vec::new(),
+            filters: Vec::new(),
+            in_filters: Vec::new(),
+            null_columns: Vec::new(),
+            group_by: Vec::new(),
+            order_by: Vec::new(),
+            after: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// Add a column to the selection.
+    pub fn column(mut self, name: &str) -> Self {
+        self.columns.push(name.to_owned());
+        self
+    }
+
+    /// Add the result of an aggregate function to the selection, named by the given alias.
+    ///
+    /// The function is applied to all rows when no column is given, this is only supported by
+    /// `COUNT`.
+    pub fn aggregate(mut self, alias: &str, function: Aggregate, column: Option<&str>) -> Self {
+        self.aggregates
+            .push((alias.to_owned(), function, column.map(str::to_owned)));
+        self
+    }
+
+    /// Group rows with equal values of the column, it needs to be part of the selection.
+    pub fn group_by(mut self, column: &str) -> Self {
+        self.group_by.push(column.to_owned());
+        self
+    }
+
+    /// Only select rows where the column equals the value.
+    pub fn filter(self, column: &str, value: impl Into<Value>) -> Self {
+        self.compare(column, Operator::Eq, value)
+    }
+
+    /// Only select rows where the comparison of the column with the value is true.
+    pub fn compare(mut self, column: &str, operator: Operator, value: impl Into<Value>) -> Self {
+        self.filters
+            .push((column.to_owned(), operator, value.into()));
+        self
+    }
+
+    /// Only select rows where the column equals one of the values.
+    pub fn filter_in<V: Into<Value>>(
+        mut self,
+        column: &str,
+        values: impl IntoIterator<Item = V>,
+    ) -> Self {
+        self.in_filters.push((
+            column.to_owned(),
+            values.into_iter().map(Into::into).collect(),
+        ));
+        self
+    }
+
+    /// Only select rows without a value in the column.
+    pub fn is_null(mut self, column: &str) -> Self {
+        self.null_columns.push(column.to_owned());
+        self
+    }
+
+    /// Sort rows by a column, rows with equal values are sorted by the next given column.
+    pub fn order_by(mut self, column: &str, order: Order) -> Self {
+        self.order_by.push((column.to_owned(), order));
+        self
+    }
+
+    /// Only select rows which are sorted after the row with the given values of the ordered
+    /// columns.
+    ///
+    /// Values need to be given in the same order as the columns were added with `order_by`, this
+    /// allows paginating through results without an offset (keyset pagination).
+    pub fn after(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.after.push((column.to_owned(), value.into()));
+        self
+    }
+
+    /// Select at most this number of rows.
+    pub fn limit(mut self, limit: i64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Skip this number of rows.
+    ///
+    /// Some databases only support an offset together with a limit, so set both.
+    pub fn offset(mut self, offset: i64) -> Self {
+        self.offset = Some(offset);
+        self
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        let mut values = Vec::new();
+
+        let mut columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect();
+
+        for (alias, function, column) in self.aggregates.iter() {
+            let argument = column
+                .as_ref()
+                .map_or_else(|| "*".to_owned(), |column| quote_identifier(column));
+
+            columns.push(format!(
+                "{}({}) AS {}",
+                function.as_sql(),
+                argument,
+                quote_identifier(alias)
+            ));
+        }
+
+        let has_filters = !self.filters.is_empty()
+            || !self.in_filters.is_empty()
+            || !self.null_columns.is_empty();
+
+        let mut sql = format!(
+            "SELECT {} FROM {}{}",
+            columns.join(", "),
+            quote_identifier(&self.table),
+            where_clause(
+                self.filters,
+                self.in_filters,
+                self.null_columns,
+                &mut values
+            )
+        );
+
+        if !self.after.is_empty() {
+            let condition = keyset_condition(&self.after, &self.order_by, &mut values);
+            let keyword = if has_filters { "AND" } else { "WHERE" };
+            sql.push_str(&format!(" {} {}", keyword, condition));
+        }
+
+        if !self.group_by.is_empty() {
+            let group_by: Vec<String> = self
+                .group_by
+                .iter()
+                .map(|column| quote_identifier(column))
+                .collect();
+
+            sql.push_str(&format!(" GROUP BY {}", group_by.join(", ")));
+        }
+
+        if !self.order_by.is_empty() {
+            let order_by: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|(column, order)| format!("{} {}", quote_identifier(column), order.as_sql()))
+                .collect();
+
+            sql.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+        }
+
+        if let Some(limit) = self.limit {
+            values.push(Value::Integer(limit));
+            sql.push_str(&format!(" LIMIT ${}", values.len()));
+        }
+
+        if let Some(offset) = self.offset {
+            values.push(Value::Integer(offset));
+            sql.push_str(&format!(" OFFSET ${}", values.len()));
+        }
+
+        Statement { sql, values }
+    }
+}
+
+/// Builds an `INSERT` statement.
+#[derive(Debug)]
+pub struct Insert {
+    table: String,
+    columns: Vec<String>,
+    values: Vec<Value>,
+}
+
+impl Insert {
+    /// Start building statement for a table.
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+            columns: Vec::new(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Set the value of a column.
+    pub fn value(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.columns.push(column.to_owned());
+        self.values.push(value.into());
+        self
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        let columns: Vec<String> = self
+            .columns
+            .iter()
+            .map(|column| quote_identifier(column))
+            .collect();
+
+        let params: Vec<String> = (1..=self.values.len())
+            .map(|index| format!("${}", index))
+            .collect();
+
+        Statement {
+            sql: format!(
+                "INSERT INTO {} ({}) VALUES ({})",
+                quote_identifier(&self.table),
+                columns.join(", "),
+                params.join(", ")
+            ),
+            values: self.values,
+        }
+    }
+}
+
+/// Builds an `UPDATE` statement.
+#[derive(Debug)]
+pub struct Update {
+    table: String,
+    assignments: Vec<(String, Value)>,
+    filters: Vec<Filter>,
+}
+
+impl Update {
+    /// Start building statement for a table.
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+            assignments: Vec::new(),
+            filters: Vec::new(),
+        }
+    }
+
+    /// Set a column to a new value.
+    pub fn set(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.assignments.push((column.to_owned(), value.into()));
+        self
+    }
+
+    /// Only update rows where the column equals the value.
+    pub fn filter(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.filters
+            .push((column.to_owned(), Operator::Eq, value.into()));
+        self
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        let mut values = Vec::new();
+
+        let assignments: Vec<String> = self
+            .assignments
+            .into_iter()
+            .map(|(column, value)| {
+                values.push(value);
+                format!("{} = ${}", quote_identifier(&column), values.len())
+            })
+            .collect();
+
+        let sql = format!(
+            "UPDATE {} SET {}{}",
+            quote_identifier(&self.table),
+            assignments.join(", "),
+            where_clause(self.filters, Vec::new(), Vec::new(), &mut values)
+        );
+
+        Statement { sql, values }
+    }
+}
+
+/// Builds a `DELETE` statement.
+#[derive(Debug)]
+pub struct Delete {
+    table: String,
+    filters: Vec<Filter>,
+}
+
+impl Delete {
+    /// Start building statement for a table.
+    pub fn new(table: &str) -> Self {
+        Self {
+            table: table.to_owned(),
+            filters: Vec::new(),
+        }
+    }
+
+    /// Only delete rows where the column equals the value.
+    pub fn filter(mut self, column: &str, value: impl Into<Value>) -> Self {
+        self.filters
+            .push((column.to_owned(), Operator::Eq, value.into()));
+        self
+    }
+
+    /// Returns the built statement.
+    pub fn build(self) -> Statement {
+        let mut values = Vec::new();
+
+        let sql = format!(
+            "DELETE FROM {}{}",
+            quote_identifier(&self.table),
+            where_clause(self.filters, Vec::new(), Vec::new(), &mut values)
+        );
+
+        Statement { sql, values }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::Row;
+
+    use super::{
+        quote_identifier, AddColumn, Aggregate, CreateTable, Delete, Insert, Operator, Order,
+        Select, Update, Value,
+    };
+
+    use crate::test_helpers::initialize_db;
+
+    #[test]
+    fn quote_identifiers() {
+        assert_eq!(quote_identifier("message"), "\"message\"");
+        assert_eq!(quote_identifier("a\"; DROP"), "\"a\"\"; DROP\"");
+    }
+
+    #[test]
+    fn build_statements() {
+        let statement = Update::new("documents")
+            .set("title", "Panda")
+            .set("views", 12_i64)
+            .filter("document_id", "abc")
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "UPDATE \"documents\" SET \"title\" = $1, \"views\" = $2 WHERE \"document_id\" = $3"
+        );
+        assert_eq!(
+            statement.values(),
+            &[
+                Value::Text("Panda".to_owned()),
+                Value::Integer(12),
+                Value::Text("abc".to_owned())
+            ]
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .filter("document_id", "abc")
+            .filter("views", 12_i64)
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\" FROM \"documents\" WHERE \"document_id\" = $1 AND \"views\" = $2"
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .filter("views", 12_i64)
+            .is_null("deleted_at")
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\" FROM \"documents\" WHERE \"views\" = $1 AND \"deleted_at\" IS NULL"
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .filter("views", 12_i64)
+            .filter_in("document_id", vec!["abc", "def"])
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\" FROM \"documents\" WHERE \"views\" = $1 \
+            AND \"document_id\" IN ($2, $3)"
+        );
+        assert_eq!(
+            statement.values(),
+            &[
+                Value::Integer(12),
+                Value::Text("abc".to_owned()),
+                Value::Text("def".to_owned())
+            ]
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .filter_in("document_id", Vec::<&str>::new())
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\" FROM \"documents\" WHERE 1 = 0"
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .compare("views", Operator::Gte, 10_i64)
+            .order_by("views", Order::Desc)
+            .order_by("title", Order::Asc)
+            .limit(20)
+            .offset(40)
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\" FROM \"documents\" WHERE \"views\" >= $1 \
+            ORDER BY \"views\" DESC, \"title\" ASC LIMIT $2 OFFSET $3"
+        );
+        assert_eq!(
+            statement.values(),
+            &[Value::Integer(10), Value::Integer(20), Value::Integer(40)]
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .order_by("views", Order::Desc)
+            .order_by("title", Order::Asc)
+            .after("views", 12_i64)
+            .after("title", "Panda")
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\" FROM \"documents\" WHERE (\"views\" < $1 OR \
+            (\"views\" = $2 AND \"title\" > $3)) ORDER BY \"views\" DESC, \"title\" ASC"
+        );
+        assert_eq!(
+            statement.values(),
+            &[
+                Value::Integer(12),
+                Value::Integer(12),
+                Value::Text("Panda".to_owned())
+            ]
+        );
+
+        let statement = Select::new("documents")
+            .column("title")
+            .aggregate("count", Aggregate::Count, None)
+            .aggregate("views", Aggregate::Max, Some("views"))
+            .compare("views", Operator::Gt, 0_i64)
+            .group_by("title")
+            .build();
+
+        assert_eq!(
+            statement.sql(),
+            "SELECT \"title\", COUNT(*) AS \"count\", MAX(\"views\") AS \"views\" \
+            FROM \"documents\" WHERE \"views\" > $1 GROUP BY \"title\""
+        );
+
+        let statement = AddColumn::new("documents", "views", "BIGINT").build();
+
+        assert_eq!(
+            statement.sql(),
+            "ALTER TABLE \"documents\" ADD COLUMN \"views\" BIGINT"
+        );
+    }
+
+    #[async_std::test]
+    async fn execute_statements() {
+        let pool = initialize_db().await;
+
+        CreateTable::new("documents")
+            .column("title", "TEXT NOT NULL")
+            .column("views", "BIGINT")
+            .build()
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let rows_affected = Insert::new("documents")
+            .value("title", "Panda")
+            .value("views", 12_i64)
+            .build()
+            .execute(&pool)
+            .await
+            .unwrap();
+        assert_eq!(rows_affected, 1);
+
+        Update::new("documents")
+            .set("views", 13_i64)
+            .filter("title", "Panda")
+            .build()
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let row = Select::new("documents")
+            .column("views")
+            .filter("title", "Panda")
+            .build()
+            .fetch_optional(&pool)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(row.try_get::<i64, _>("views").unwrap(), 13);
+
+        Delete::new("documents")
+            .filter("title", "Panda")
+            .build()
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let rows = Select::new("documents")
+            .column("views")
+            .build()
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert!(rows.is_empty());
+    }
+}
@@ -0,0 +1,304 @@
+use std::fmt::Debug;
+
+use async_trait::async_trait;
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::MessageEncoded;
+
+use crate::db::models::{
+    AuthorProfile, EncryptedMessage, Entry, EntryPosition, Fork, KeyGroup, LogSummary, SchemaField,
+    SchemaSummary,
+};
+use crate::errors::Result;
+use crate::materializer::{
+    Document, DocumentAggregation, DocumentGroup, DocumentQuery, DocumentVersion,
+};
+
+/// Storage interface for Bamboo entries.
+#[async_trait]
+pub trait EntryStore {
+    /// Insert an entry with its message payload, returns true when it was stored.
+    ///
+    /// The message is missing for entries whose payload got pruned on the node we received them
+    /// from.
+    async fn insert_entry(
+        &self,
+        entry_encoded: &EntrySigned,
+        message_encoded: Option<&MessageEncoded>,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<bool>;
+
+    /// Returns the latest Bamboo entry of an author's log.
+    async fn latest_entry(&self, author: &Author, log_id: &LogId) -> Result<Option<Entry>>;
+
+    /// Returns entry at sequence position within an author's log.
+    async fn entry_at_seq_num(
+        &self,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<Entry>>;
+
+    /// Returns the entry with the given entry hash.
+    async fn entry_by_hash(&self, entry_hash: &Hash) -> Result<Option<Entry>>;
+
+    /// Returns a page of entries, optionally filtered by author and / or schema, ordered by
+    /// author, log_id and sequence number. Only entries following the given position are returned.
+    async fn query_entries(
+        &self,
+        author: Option<&Author>,
+        schema: Option<&Hash>,
+        after: Option<&EntryPosition>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Entry>>;
+
+    /// Returns entries of an author within an inclusive range of sequence numbers, optionally
+    /// only of one log, ordered by log_id and sequence number. Only entries following the given
+    /// log_id and sequence number are returned.
+    async fn entries_by_author(
+        &self,
+        author: &Author,
+        log_id: Option<&LogId>,
+        from: &SeqNum,
+        to: Option<&SeqNum>,
+        after: Option<&(LogId, SeqNum)>,
+        limit: i64,
+    ) -> Result<Vec<Entry>>;
+}
+
+/// Storage interface for the log_id registry of authors and their schemas.
+#[async_trait]
+pub trait LogStore {
+    /// Register any new log_id for an author's schema, returns true when it was stored.
+    async fn insert_log(&self, author: &Author, schema: &Hash, log_id: &LogId) -> Result<bool>;
+
+    /// Returns the registered log_id of an author's schema.
+    async fn get_log_id(&self, author: &Author, schema: &Hash) -> Result<Option<LogId>>;
+
+    /// Returns all logs of an author with the sequence number of their latest entry, ordered by
+    /// log_id.
+    async fn logs_by_author(&self, author: &Author) -> Result<Vec<LogSummary>>;
+
+    /// Determines the next unused user schema log_id of an author.
+    async fn next_user_schema_log_id(&self, author: &Author) -> Result<LogId>;
+
+    /// Returns registered or possible log_id of an author's schema.
+    ///
+    /// If no log has been found for a USER schema it automatically returns the next unused log_id.
+    async fn find_schema_log_id(&self, author: &Author, schema: &Hash) -> Result<LogId> {
+        match self.get_log_id(author, schema).await? {
+            Some(log_id) => Ok(log_id),
+            None => self.next_user_schema_log_id(author).await,
+        }
+    }
+}
+
+/// Storage interface for materialized documents.
+#[async_trait]
+pub trait DocumentStore {
+    /// Returns the current state of a document of a schema or `None` when it does not exist or
+    /// was deleted.
+    async fn document(&self, schema: &Hash, document_id: &Hash) -> Result<Option<Document>>;
+
+    /// Returns the current state of all documents with the given ids together with their schema,
+    /// independent of the schema they belong to. Unknown and deleted documents are skipped.
+    async fn documents_by_id(&self, document_ids: &[Hash]) -> Result<Vec<(Hash, Document)>>;
+
+    /// Returns the state of a document of a schema at the given version or `None` when it did not
+    /// exist yet or was deleted at this version.
+    async fn document_version(
+        &self,
+        schema: &Hash,
+        document_id: &Hash,
+        version: DocumentVersion,
+    ) -> Result<Option<Document>>;
+
+    /// Returns the current state of all documents of a schema matching the query.
+    async fn query_documents(&self, schema: &Hash, query: &DocumentQuery) -> Result<Vec<Document>>;
+
+    /// Returns the results of aggregate functions over the documents of a schema, optionally
+    /// grouped by a field.
+    async fn aggregate_documents(
+        &self,
+        schema: &Hash,
+        aggregation: &DocumentAggregation,
+    ) -> Result<Vec<DocumentGroup>>;
+}
+
+/// Storage interface for forks, entries refused as they conflict with a stored entry at the same
+/// position of a log.
+#[async_trait]
+pub trait ForkStore {
+    /// Register a refused entry conflicting with the stored entry with the given hash, returns
+    /// true when it was stored.
+    async fn insert_fork(
+        &self,
+        fork_entry: &EntrySigned,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+        entry_hash: &Hash,
+    ) -> Result<bool>;
+
+    /// Returns all forks of an author, optionally only of one log.
+    async fn forks(&self, author: &Author, log_id: Option<&LogId>) -> Result<Vec<Fork>>;
+}
+
+/// Storage interface to remove data from the node.
+#[async_trait]
+pub trait PurgeStore {
+    /// Remove all entries and logs of an author, returns the number of removed entries.
+    async fn purge_author(&self, author: &Author) -> Result<u64>;
+
+    /// Remove all entries of an author's log and the log itself, returns the number of removed
+    /// entries.
+    async fn purge_log(&self, author: &Author, log_id: &LogId) -> Result<u64>;
+
+    /// Remove all but the latest `keep` entries of every log of a schema besides the lipmaa
+    /// certificate pool of their latest entries, returns the number of removed entries.
+    async fn prune_entries(&self, schema: &Hash, keep: u64) -> Result<u64>;
+
+    /// Remove the payloads of all but the latest `keep` entries of every log of a schema while
+    /// keeping the entries, returns the number of pruned payloads.
+    async fn prune_payloads(&self, schema: &Hash, keep: u64) -> Result<u64>;
+
+    /// Remove all logs of a schema whose latest entry was received longer than `ttl` seconds ago
+    /// and the expired entries of its other logs, the ones needed to verify their latest entries
+    /// only lose their payloads. Returns the number of removed entries.
+    async fn expire_logs(&self, schema: &Hash, ttl: u64) -> Result<u64>;
+}
+
+/// Storage interface for the schemas of stored entries.
+#[async_trait]
+pub trait SchemaStore {
+    /// Returns all schemas this node stores entries of with their number of entries and
+    /// materialized documents, ordered by schema hash.
+    async fn schemas(&self) -> Result<Vec<SchemaSummary>>;
+
+    /// Returns the fields of the registered definition of a schema, `None` when no definition was
+    /// published for it.
+    async fn schema_definition(&self, schema: &Hash) -> Result<Option<Vec<SchemaField>>>;
+}
+
+/// Storage interface for key groups, author profiles and encrypted messages materialized from
+/// system schema messages.
+#[async_trait]
+pub trait IdentityStore {
+    /// Returns the profile of an author, `None` when the author did not publish one.
+    async fn author_profile(&self, author: &Author) -> Result<Option<AuthorProfile>>;
+
+    /// Returns the key group with this id.
+    async fn key_group(&self, key_group: &Hash) -> Result<Option<KeyGroup>>;
+
+    /// Returns the public keys of all members of a key group including its owner, ordered by
+    /// public key.
+    async fn key_group_members(&self, key_group: &Hash) -> Result<Vec<Author>>;
+
+    /// Returns the ids of all key groups an author is a member of, ordered by id.
+    async fn key_groups_by_member(&self, author: &Author) -> Result<Vec<Hash>>;
+
+    /// Returns all messages encrypted for a key group, ordered by author and sequence number.
+    async fn encrypted_messages(&self, key_group: &Hash) -> Result<Vec<EncryptedMessage>>;
+}
+
+/// Reads and writes within a storage transaction.
+///
+/// Nothing gets persisted before the transaction got committed, dropping it discards all writes.
+#[async_trait]
+pub trait StorageTransaction: Send {
+    /// Returns the registered log_id of an author's schema.
+    async fn get_log_id(&mut self, author: &Author, schema: &Hash) -> Result<Option<LogId>>;
+
+    /// Returns the entry with the given entry hash.
+    async fn entry_by_hash(&mut self, entry_hash: &Hash) -> Result<Option<Entry>>;
+
+    /// Returns entry at sequence position within an author's log.
+    async fn entry_at_seq_num(
+        &mut self,
+        author: &Author,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<Option<Entry>>;
+
+    /// Register any new log_id for an author's schema, returns true when it was stored.
+    async fn insert_log(&mut self, author: &Author, schema: &Hash, log_id: &LogId) -> Result<bool>;
+
+    /// Insert an entry with its message payload, returns true when it was stored.
+    async fn insert_entry(
+        &mut self,
+        entry_encoded: &EntrySigned,
+        message_encoded: Option<&MessageEncoded>,
+        log_id: &LogId,
+        seq_num: &SeqNum,
+    ) -> Result<bool>;
+
+    /// Persist all writes of this transaction.
+    async fn commit(self: Box<Self>) -> Result<()>;
+}
+
+/// Storage interface to read and write data atomically.
+#[async_trait]
+pub trait TransactionStore {
+    /// Start a new transaction.
+    async fn begin(&self) -> Result<Box<dyn StorageTransaction>>;
+}
+
+/// Storage interface reporting the state of the storage backend itself.
+#[async_trait]
+pub trait StatusStore {
+    /// Returns an error when the storage backend can not be reached or is not ready to be used.
+    async fn check_health(&self) -> Result<()>;
+
+    /// Returns the number of stored entries.
+    async fn entry_count(&self) -> Result<i64>;
+
+    /// Returns the number of authors with stored entries.
+    async fn author_count(&self) -> Result<i64>;
+
+    /// Returns the number of registered logs.
+    async fn log_count(&self) -> Result<i64>;
+
+    /// Returns the size of the storage on disk in bytes, `None` when nothing is stored on disk.
+    async fn storage_size(&self) -> Result<Option<i64>>;
+
+    /// Returns the number of stored entries which still need to be materialized.
+    async fn materializer_backlog(&self) -> Result<i64>;
+}
+
+/// Storage backend of the node holding all entries and logs.
+///
+/// Every type implementing all store traits is automatically a storage provider. Use this trait
+/// to plug in alternative storage backends.
+pub trait StorageProvider:
+    DocumentStore
+    + EntryStore
+    + ForkStore
+    + IdentityStore
+    + LogStore
+    + PurgeStore
+    + SchemaStore
+    + StatusStore
+    + TransactionStore
+    + Debug
+    + Send
+    + Sync
+{
+}
+
+impl<T> StorageProvider for T where
+    T: DocumentStore
+        + EntryStore
+        + ForkStore
+        + IdentityStore
+        + LogStore
+        + PurgeStore
+        + SchemaStore
+        + StatusStore
+        + TransactionStore
+        + Debug
+        + Send
+        + Sync
+{
+}
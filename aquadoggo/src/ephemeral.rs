@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_std::future::Future;
+use async_std::task;
+use futures::{pin_mut, select, FutureExt};
+use p2panda_rs::hash::Hash;
+use tracing::{error, info};
+
+use crate::config::Configuration;
+use crate::db::{PurgeStore, SqlStorage};
+
+/// Schemas whose entries are only kept for a limited time, for example presence or typing
+/// indicators, with their time to live in seconds.
+#[derive(Debug, Clone, Default)]
+pub struct EphemeralSchemas(Vec<(Hash, u64)>);
+
+impl EphemeralSchemas {
+    /// Returns the ephemeral schemas configured for this node.
+    pub fn new(config: &Configuration) -> Result<Self> {
+        let schemas = config
+            .ephemeral_schemas
+            .iter()
+            .map(|schema| {
+                let mut parts = schema.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(schema), Some(ttl)) => match ttl.parse::<u64>()? {
+                        0 => bail!("Time to live of ephemeral schema {} can not be 0", schema),
+                        ttl => Ok((Hash::new(schema)?, ttl)),
+                    },
+                    _ => bail!(
+                        "Invalid ephemeral schema {}, expected <schema hash>=<seconds>",
+                        schema
+                    ),
+                }
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self(schemas))
+    }
+
+    /// Returns true when no schemas are ephemeral.
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the interval to look for expired logs in, the shortest time to live of all
+    /// schemas.
+    pub fn interval(&self) -> Duration {
+        let ttl = self.0.iter().map(|(_, ttl)| *ttl).min().unwrap_or(0);
+        Duration::from_secs(ttl)
+    }
+
+    /// Remove the expired entries of all ephemeral schemas including their materialized
+    /// documents, returns the number of removed entries.
+    pub async fn expire(&self, storage: &SqlStorage) -> Result<u64> {
+        let mut expired = 0;
+
+        for (schema, ttl) in self.0.iter() {
+            expired += storage.expire_logs(schema, *ttl).await?;
+        }
+
+        if expired > 0 {
+            info!("Removed {} expired entries of ephemeral schemas", expired);
+        }
+
+        Ok(expired)
+    }
+}
+
+/// Remove expired entries of ephemeral schemas in every interval until the shutdown future resolves.
+///
+/// Failed runs are logged and retried in the next interval.
+pub async fn start_expiry(
+    storage: &SqlStorage,
+    schemas: &EphemeralSchemas,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let shutdown = shutdown.fuse();
+    pin_mut!(shutdown);
+
+    loop {
+        select! {
+            _ = task::sleep(schemas.interval()).fuse() => {
+                if let Err(err) = schemas.expire(storage).await {
+                    error!("Could not remove expired entries: {}", err);
+                }
+            },
+            _ = shutdown => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use sqlx::query;
+
+    use super::EphemeralSchemas;
+
+    use crate::config::Configuration;
+    use crate::db::models::MaterializerTask;
+    use crate::db::{sql, EntryStore, LogStore, SqlStorage};
+    use crate::materializer::{get_document, process_pending_tasks};
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, define_test_schema, initialize_db};
+
+    #[async_std::test]
+    async fn expire_logs() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let other_schema = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        let mut entries = Vec::new();
+        for schema in [&schema, &schema, &other_schema].iter() {
+            let (entry, message) = create_test_entry(
+                &KeyPair::new(),
+                schema,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        let config = Configuration {
+            ephemeral_schemas: vec![
+                format!("{}=60", schema.as_str()),
+                format!("{}=3600", other_schema.as_str()),
+            ],
+            ..Configuration::default()
+        };
+        let schemas = EphemeralSchemas::new(&config).unwrap();
+        assert_eq!(schemas.interval(), Duration::from_secs(60));
+
+        // Nothing expired yet
+        assert_eq!(schemas.expire(&storage).await.unwrap(), 0);
+
+        // Entries received two minutes ago expire unless their schema has a longer time to live
+        query("UPDATE entries SET received_at = received_at - 120")
+            .execute(&pool)
+            .await
+            .unwrap();
+        query(&sql(
+            &pool,
+            "UPDATE entries SET received_at = received_at + 120 WHERE entry_hash = $1",
+        ))
+        .bind(entries[1].hash().as_str())
+        .execute(&pool)
+        .await
+        .unwrap();
+        assert_eq!(schemas.expire(&storage).await.unwrap(), 1);
+
+        let remaining = storage
+            .query_entries(None, None, None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(remaining.len(), 2);
+        assert!(storage
+            .get_log_id(&entries[0].author(), &schema)
+            .await
+            .unwrap()
+            .is_none());
+
+        // Time to live is required
+        let config = Configuration {
+            ephemeral_schemas: vec![format!("{}=0", schema.as_str())],
+            ..Configuration::default()
+        };
+        assert!(EphemeralSchemas::new(&config).is_err());
+    }
+
+    #[async_std::test]
+    async fn expire_entries_of_active_log() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        define_test_schema(&pool, &schema, &[("test", "str")]).await;
+
+        let mut entries: Vec<EntrySigned> = Vec::new();
+        let publish = |seq_num: i64, entries: &[EntrySigned]| {
+            let seq_num = SeqNum::new(seq_num).unwrap();
+            let skiplink = seq_num
+                .skiplink_seq_num()
+                .map(|skiplink| entries[skiplink.as_i64() as usize - 1].clone());
+            create_test_entry(
+                &key_pair,
+                &schema,
+                &log_id,
+                skiplink.as_ref(),
+                entries.last(),
+                &seq_num,
+            )
+        };
+
+        // Entries 1 to 8 were received two minutes ago
+        for seq_num in 1..9 {
+            let (entry, message) = publish(seq_num, &entries);
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+        query("UPDATE entries SET received_at = received_at - 120")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // The log is still receiving entries
+        let (entry, message) = publish(9, &entries);
+        store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+        entries.push(entry);
+
+        MaterializerTask::insert_missing(&pool).await.unwrap();
+        process_pending_tasks(&pool, false).await.unwrap();
+
+        let config = Configuration {
+            ephemeral_schemas: vec![format!("{}=60", schema.as_str())],
+            ..Configuration::default()
+        };
+        let schemas = EphemeralSchemas::new(&config).unwrap();
+
+        // Expired entries are removed besides the certificate pool 9, 8, 4 and 1
+        assert_eq!(schemas.expire(&storage).await.unwrap(), 5);
+        assert_eq!(schemas.expire(&storage).await.unwrap(), 0);
+
+        let remaining = storage
+            .query_entries(None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        let seq_nums: Vec<i64> = remaining
+            .iter()
+            .map(|entry| entry.seq_num.as_i64())
+            .collect();
+        assert_eq!(seq_nums, vec![1, 4, 8, 9]);
+
+        // Only the latest entry keeps its payload and its document
+        let payloads: Vec<bool> = remaining
+            .iter()
+            .map(|entry| entry.payload_bytes.is_some())
+            .collect();
+        assert_eq!(payloads, vec![false, false, false, true]);
+
+        for (index, entry) in entries.iter().enumerate() {
+            let document = get_document(&pool, &schema, entry.hash().as_str())
+                .await
+                .unwrap();
+            assert_eq!(document.is_some(), index == 8);
+        }
+
+        // The log can be continued
+        let (entry, message) = publish(10, &entries);
+        assert!(
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .is_ok()
+        );
+        assert!(storage
+            .get_log_id(&entries[0].author(), &schema)
+            .await
+            .unwrap()
+            .is_some());
+    }
+}
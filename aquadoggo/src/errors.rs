@@ -41,7 +41,48 @@ pub enum Error {
     #[error(transparent)]
     PublishEntryValidation(#[from] crate::rpc::PublishEntryError),
 
+    /// Error returned from admin RPC methods.
+    #[error(transparent)]
+    Admin(#[from] crate::rpc::AdminError),
+
+    /// Error returned from `panda_getEncryptedMessages` RPC method.
+    #[error(transparent)]
+    EncryptedMessages(#[from] crate::rpc::EncryptedMessagesError),
+
+    /// Error returned when an entry is not accepted by the node's policy.
+    #[error(transparent)]
+    EntryPolicyViolation(#[from] crate::policy::EntryPolicyError),
+
+    /// Error returned from `panda_queryEntries` RPC method.
+    #[error(transparent)]
+    QueryEntriesValidation(#[from] crate::rpc::QueryEntriesError),
+
+    /// Error returned from materializing documents.
+    #[error(transparent)]
+    Materialization(#[from] crate::materializer::MaterializerError),
+
     /// Error returned from the database.
     #[error(transparent)]
-    Database(#[from] sqlx::Error),
+    Database(sqlx::Error),
+
+    /// Error returned when no database connection became available in time.
+    #[error("Database is busy, no connection available")]
+    DatabaseBusy,
+
+    /// Error returned when a database backup failed.
+    #[error("Could not create database backup: {0}")]
+    Backup(anyhow::Error),
+
+    /// Error returned when database migrations have not been applied yet.
+    #[error("Database has {0} pending migrations")]
+    PendingMigrations(usize),
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::PoolTimedOut => Error::DatabaseBusy,
+            err => Error::Database(err),
+        }
+    }
 }
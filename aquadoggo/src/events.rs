@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+
+use async_std::channel::{unbounded, Receiver, Sender};
+use serde_json::{json, Value};
+
+use crate::db::models::EncryptedMessage;
+use crate::materializer::DocumentChange;
+
+/// Activity of the node, next to new entries which are announced via `Subscriptions`.
+#[derive(Debug, Clone)]
+pub enum NodeEvent {
+    /// A document got created or updated by the materializer.
+    DocumentMaterialized(Arc<DocumentChange>),
+
+    /// A message encrypted for a key group got stored, its plaintext is unknown to the node.
+    EncryptedMessageStored(Arc<EncryptedMessage>),
+
+    /// A connection to another node got established.
+    PeerConnected(String),
+
+    /// The connection to another node got closed.
+    PeerDisconnected(String),
+}
+
+impl NodeEvent {
+    /// Returns the name of this kind of event.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NodeEvent::DocumentMaterialized(_) => "documentMaterialized",
+            NodeEvent::EncryptedMessageStored(_) => "encryptedMessageStored",
+            NodeEvent::PeerConnected(_) => "peerConnected",
+            NodeEvent::PeerDisconnected(_) => "peerDisconnected",
+        }
+    }
+
+    /// Returns the JSON representation of the event details.
+    pub fn to_json(&self) -> Value {
+        match self {
+            NodeEvent::DocumentMaterialized(change) => json!({
+                "action": if change.created { "create" } else { "update" },
+                "schema": change.schema,
+                "documentId": change.document.document_id,
+                "entryHash": change.document.entry_hash,
+                "seqNum": change.document.seq_num,
+            }),
+            NodeEvent::EncryptedMessageStored(message) => json!({
+                "keyGroup": message.key_group.as_str(),
+                "author": message.author.as_str(),
+                "entryHash": message.entry_hash.as_str(),
+                "seqNum": message.seq_num.as_i64(),
+            }),
+            NodeEvent::PeerConnected(peer_id) | NodeEvent::PeerDisconnected(peer_id) => json!({
+                "peerId": peer_id,
+            }),
+        }
+    }
+}
+
+/// Broadcasts node events to all listeners.
+///
+/// Events can be cheaply cloned as they share the same listeners.
+#[derive(Clone, Debug, Default)]
+pub struct Events {
+    listeners: Arc<Mutex<Vec<Sender<NodeEvent>>>>,
+}
+
+impl Events {
+    /// Returns a new event broadcast without listeners.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns a receiver for all following events.
+    pub fn subscribe(&self) -> Receiver<NodeEvent> {
+        let (sender, receiver) = unbounded();
+        self.listeners.lock().unwrap().push(sender);
+        receiver
+    }
+
+    /// Send event to all listeners.
+    pub fn emit(&self, event: NodeEvent) {
+        // Remove listeners whose receiving end got dropped in the meantime
+        self.listeners
+            .lock()
+            .unwrap()
+            .retain(|sender| sender.try_send(event.clone()).is_ok());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Events, NodeEvent};
+
+    #[async_std::test]
+    async fn broadcast_events() {
+        let events = Events::new();
+
+        let receiver_1 = events.subscribe();
+        let receiver_2 = events.subscribe();
+        drop(receiver_2);
+
+        events.emit(NodeEvent::PeerConnected("peer".to_owned()));
+
+        let event = receiver_1.recv().await.unwrap();
+        assert_eq!(event.name(), "peerConnected");
+        assert_eq!(event.to_json().to_string(), r#"{"peerId":"peer"}"#);
+
+        // Dropped listeners got removed
+        assert_eq!(events.listeners.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,446 @@
+//! gRPC API mirroring the `panda_*` methods of the JSON RPC API.
+//!
+//! Every gRPC call is converted into a JSON RPC request and handled by the same router as HTTP
+//! and WebSocket requests, so both APIs share their validation, errors and behaviour.
+use std::net::SocketAddr;
+use std::pin::Pin;
+
+use futures::{Stream, StreamExt};
+use http_types::StatusCode;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+use tonic::transport::Server;
+use tonic::{Code, Request, Response, Status};
+
+use crate::rpc::{
+    handle_rpc_request, RpcApiService, RATE_LIMIT_CODE, REQUEST_TIMEOUT_CODE, SERVER_BUSY_CODE,
+};
+
+#[allow(
+    missing_docs,
+    missing_debug_implementations,
+    unused_qualifications,
+    clippy::all
+)]
+mod proto {
+    tonic::include_proto!("aquadoggo");
+}
+
+use proto::panda_server::{Panda, PandaServer};
+use proto::*;
+
+/// JSON RPC error code for unknown methods.
+const METHOD_NOT_FOUND_CODE: i64 = -32601;
+
+/// JSON RPC error code for internal errors.
+const INTERNAL_ERROR_CODE: i64 = -32603;
+
+/// (De)serialize strings holding JSON as regular JSON values.
+///
+/// Used for fields with dynamic values, like document fields, which are JSON encoded strings in
+/// gRPC messages.
+pub(crate) mod json {
+    use serde::de::Error as _;
+    use serde::ser::Error as _;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use serde_json::Value;
+
+    pub fn serialize<S: Serializer>(value: &str, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_json::from_str::<Value>(value)
+            .map_err(S::Error::custom)?
+            .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+        Value::deserialize(deserializer).map(|value| value.to_string())
+    }
+}
+
+/// Returns the gRPC status of a JSON RPC error object.
+///
+/// Errors returned by the methods themselves are mostly caused by invalid requests, like entries
+/// which do not fit into their log, they are treated as invalid arguments.
+fn error_status(error: &Value) -> Status {
+    let message = error["message"].as_str().unwrap_or_default();
+
+    let code = match error["code"].as_i64() {
+        Some(METHOD_NOT_FOUND_CODE) => Code::Unimplemented,
+        Some(INTERNAL_ERROR_CODE) => Code::Internal,
+        Some(SERVER_BUSY_CODE) | Some(RATE_LIMIT_CODE) => Code::ResourceExhausted,
+        Some(REQUEST_TIMEOUT_CODE) => Code::DeadlineExceeded,
+        _ => Code::InvalidArgument,
+    };
+
+    Status::new(code, message)
+}
+
+/// gRPC service handling calls via the JSON RPC API.
+#[derive(Clone)]
+pub struct GrpcService {
+    api: RpcApiService,
+}
+
+impl std::fmt::Debug for GrpcService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GrpcService").finish()
+    }
+}
+
+impl GrpcService {
+    /// Returns a gRPC service for the given JSON RPC API.
+    pub fn new(api: RpcApiService) -> Self {
+        Self { api }
+    }
+
+    /// Handle gRPC call with the JSON RPC method of the same name.
+    ///
+    /// Bearer tokens for write and admin methods are given in the `authorization` metadata of the
+    /// call. Every call counts towards the rate limit of the remote address.
+    async fn call<P, R>(&self, method: &str, request: Request<P>) -> Result<Response<R>, Status>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let authorization = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_owned());
+        let peer = request.remote_addr().map(|address| address.ip());
+
+        let params = serde_json::to_value(request.into_inner())
+            .map_err(|err| Status::invalid_argument(err.to_string()))?;
+
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+        });
+
+        let response = handle_rpc_request(&self.api, request, authorization.as_deref(), peer)
+            .await
+            .map_err(|err| match err.status() {
+                StatusCode::Unauthorized => Status::unauthenticated(err.to_string()),
+                _ => Status::invalid_argument(err.to_string()),
+            })?;
+
+        if response["error"].is_object() {
+            return Err(error_status(&response["error"]));
+        }
+
+        // Methods returning a single item respond with null when it does not exist
+        if response["result"].is_null() {
+            return Err(Status::not_found(format!("Nothing found by {}", method)));
+        }
+
+        serde_json::from_value(response["result"].clone())
+            .map(Response::new)
+            .map_err(|err| Status::internal(err.to_string()))
+    }
+}
+
+#[tonic::async_trait]
+impl Panda for GrpcService {
+    async fn aggregate_documents(
+        &self,
+        request: Request<AggregateDocumentsRequest>,
+    ) -> Result<Response<AggregateDocumentsResponse>, Status> {
+        self.call("panda_aggregateDocuments", request).await
+    }
+
+    async fn create_backup(
+        &self,
+        request: Request<CreateBackupRequest>,
+    ) -> Result<Response<CreateBackupResponse>, Status> {
+        self.call("panda_createBackup", request).await
+    }
+
+    async fn get_all_schemas(
+        &self,
+        request: Request<SchemasRequest>,
+    ) -> Result<Response<SchemasResponse>, Status> {
+        self.call("panda_getAllSchemas", request).await
+    }
+
+    async fn get_author_profile(
+        &self,
+        request: Request<AuthorProfileRequest>,
+    ) -> Result<Response<AuthorProfileResponse>, Status> {
+        self.call("panda_getAuthorProfile", request).await
+    }
+
+    async fn get_document(
+        &self,
+        request: Request<DocumentRequest>,
+    ) -> Result<Response<Document>, Status> {
+        self.call("panda_getDocument", request).await
+    }
+
+    async fn get_encrypted_messages(
+        &self,
+        request: Request<EncryptedMessagesRequest>,
+    ) -> Result<Response<EncryptedMessagesResponse>, Status> {
+        self.call("panda_getEncryptedMessages", request).await
+    }
+
+    async fn get_entries_by_author(
+        &self,
+        request: Request<EntriesByAuthorRequest>,
+    ) -> Result<Response<EntriesByAuthorResponse>, Status> {
+        self.call("panda_getEntriesByAuthor", request).await
+    }
+
+    async fn get_entry_arguments(
+        &self,
+        request: Request<EntryArgsRequest>,
+    ) -> Result<Response<EntryArgsResponse>, Status> {
+        self.call("panda_getEntryArguments", request).await
+    }
+
+    async fn get_entry_arguments_batch(
+        &self,
+        request: Request<EntryArgsBatchRequest>,
+    ) -> Result<Response<EntryArgsBatchResponse>, Status> {
+        self.call("panda_getEntryArgumentsBatch", request).await
+    }
+
+    async fn get_entry_by_hash(
+        &self,
+        request: Request<EntryByHashRequest>,
+    ) -> Result<Response<EntryByHashResponse>, Status> {
+        self.call("panda_getEntryByHash", request).await
+    }
+
+    async fn get_forks(
+        &self,
+        request: Request<ForksRequest>,
+    ) -> Result<Response<ForksResponse>, Status> {
+        self.call("panda_getForks", request).await
+    }
+
+    async fn get_key_group(
+        &self,
+        request: Request<KeyGroupRequest>,
+    ) -> Result<Response<KeyGroupResponse>, Status> {
+        self.call("panda_getKeyGroup", request).await
+    }
+
+    async fn get_logs_by_author(
+        &self,
+        request: Request<LogsByAuthorRequest>,
+    ) -> Result<Response<LogsByAuthorResponse>, Status> {
+        self.call("panda_getLogsByAuthor", request).await
+    }
+
+    async fn get_node_info(
+        &self,
+        request: Request<NodeInfoRequest>,
+    ) -> Result<Response<NodeInfoResponse>, Status> {
+        self.call("panda_getNodeInfo", request).await
+    }
+
+    async fn get_stats(
+        &self,
+        request: Request<StatsRequest>,
+    ) -> Result<Response<StatsResponse>, Status> {
+        self.call("panda_getStats", request).await
+    }
+
+    async fn prune_payloads(
+        &self,
+        request: Request<PrunePayloadsRequest>,
+    ) -> Result<Response<PrunePayloadsResponse>, Status> {
+        self.call("panda_prunePayloads", request).await
+    }
+
+    async fn publish_entries(
+        &self,
+        request: Request<PublishEntriesRequest>,
+    ) -> Result<Response<PublishEntriesResponse>, Status> {
+        self.call("panda_publishEntries", request).await
+    }
+
+    async fn publish_entry(
+        &self,
+        request: Request<PublishEntryRequest>,
+    ) -> Result<Response<PublishEntryResponse>, Status> {
+        self.call("panda_publishEntry", request).await
+    }
+
+    async fn purge_author(
+        &self,
+        request: Request<PurgeAuthorRequest>,
+    ) -> Result<Response<PurgeResponse>, Status> {
+        self.call("panda_purgeAuthor", request).await
+    }
+
+    async fn purge_log(
+        &self,
+        request: Request<PurgeLogRequest>,
+    ) -> Result<Response<PurgeResponse>, Status> {
+        self.call("panda_purgeLog", request).await
+    }
+
+    async fn query_documents(
+        &self,
+        request: Request<QueryDocumentsRequest>,
+    ) -> Result<Response<QueryDocumentsResponse>, Status> {
+        self.call("panda_queryDocuments", request).await
+    }
+
+    async fn query_entries(
+        &self,
+        request: Request<QueryEntriesRequest>,
+    ) -> Result<Response<QueryEntriesResponse>, Status> {
+        self.call("panda_queryEntries", request).await
+    }
+
+    type SubscribeEntriesStream =
+        Pin<Box<dyn Stream<Item = Result<EntryNotification, Status>> + Send + 'static>>;
+
+    async fn subscribe_entries(
+        &self,
+        request: Request<SubscribeEntriesRequest>,
+    ) -> Result<Response<Self::SubscribeEntriesStream>, Status> {
+        let request = request.into_inner();
+
+        let author = request
+            .author
+            .map(|author| Author::new(&author))
+            .transpose()
+            .map_err(|_| Status::invalid_argument("Invalid author in subscription request"))?;
+        let schema = request
+            .schema
+            .map(|schema| Hash::new(&schema))
+            .transpose()
+            .map_err(|_| Status::invalid_argument("Invalid schema in subscription request"))?;
+
+        // The subscription gets removed with the next notification after the client disconnected
+        let (_, receiver) = self.api.subscriptions.subscribe(author, schema);
+        let stream = receiver.map(|notification| {
+            serde_json::to_value(&notification)
+                .and_then(serde_json::from_value)
+                .map_err(|err| Status::internal(err.to_string()))
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn validate_entry(
+        &self,
+        request: Request<ValidateEntryRequest>,
+    ) -> Result<Response<ValidateEntryResponse>, Status> {
+        self.call("panda_validateEntry", request).await
+    }
+}
+
+/// Start gRPC server on its own tokio runtime.
+pub async fn start_grpc_server(address: SocketAddr, api: RpcApiService) -> anyhow::Result<()> {
+    let service = PandaServer::new(GrpcService::new(api));
+
+    // tonic runs on tokio while the rest of the node runs on async-std
+    async_std::task::spawn_blocking(move || -> anyhow::Result<()> {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?
+            .block_on(Server::builder().add_service(service).serve(address))?;
+
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use tonic::{Code, Request};
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::{build_rpc_api_service, RpcApiOptions};
+    use crate::test_helpers::create_test_entry;
+
+    use super::proto::panda_server::Panda;
+    use super::proto::{EntryArgsRequest, EntryByHashRequest, PublishEntryRequest};
+    use super::GrpcService;
+
+    #[async_std::test]
+    async fn publish_and_get_entries() {
+        let service = GrpcService::new(build_rpc_api_service(
+            MemoryStorage::new(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        let response = service
+            .publish_entry(Request::new(PublishEntryRequest {
+                entry_encoded: entry.as_str().to_owned(),
+                message_encoded: message.as_str().to_owned(),
+                dry_run: false,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.entry_hash, entry.hash().as_str());
+        assert_eq!(response.seq_num, 2);
+        assert_eq!(
+            response.entry_hash_backlink,
+            Some(entry.hash().as_str().to_owned())
+        );
+
+        let response = service
+            .get_entry_arguments(Request::new(EntryArgsRequest {
+                author: entry.author().as_str().to_owned(),
+                schema: schema.as_str().to_owned(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.seq_num, 2);
+        assert_eq!(response.log_id, 1);
+
+        let response = service
+            .get_entry_by_hash(Request::new(EntryByHashRequest {
+                hash: entry.hash().as_str().to_owned(),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(response.entry_encoded, entry.as_str());
+
+        // Unknown entries are not found
+        let status = service
+            .get_entry_by_hash(Request::new(EntryByHashRequest {
+                hash: schema.as_str().to_owned(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::NotFound);
+
+        // Invalid entries are rejected by the JSON RPC API
+        let status = service
+            .publish_entry(Request::new(PublishEntryRequest {
+                entry_encoded: entry.as_str().to_owned(),
+                message_encoded: message.as_str().to_owned(),
+                dry_run: false,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(status.code(), Code::InvalidArgument);
+    }
+}
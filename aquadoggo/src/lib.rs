@@ -11,15 +11,27 @@
     unused_qualifications
 )]
 
+mod archive;
+mod backup;
+pub mod client;
 mod config;
 mod db;
+mod ephemeral;
 mod errors;
+mod events;
+#[cfg(feature = "grpc")]
+mod grpc;
+mod maintenance;
+mod materializer;
+mod network;
+mod policy;
 mod rpc;
 mod runtime;
 mod task;
+mod webhooks;
 
 #[cfg(test)]
 mod test_helpers;
 
 pub use config::Configuration;
-pub use runtime::Runtime;
+pub use runtime::{export_entries, import_entries, keygen, rebuild_views, Node};
@@ -0,0 +1,351 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_std::future::Future;
+use async_std::task;
+use futures::{pin_mut, select, FutureExt};
+use p2panda_rs::hash::Hash;
+use sqlx::any::AnyKind;
+use sqlx::Executor;
+use tracing::{error, info, instrument};
+
+use crate::config::Configuration;
+use crate::db::models::SchemaField;
+use crate::db::{database_size, Pool, PurgeStore, SqlStorage};
+use crate::materializer::{history_table_name, schema_table_name};
+
+/// Tables created by the migrations of the node, MySQL needs to optimize them one by one.
+const TABLES: [&str; 11] = [
+    "author_profiles",
+    "document_operations",
+    "encrypted_messages",
+    "entries",
+    "forks",
+    "key_group_members",
+    "key_groups",
+    "logs",
+    "materializer_tasks",
+    "schema_fields",
+    "schemas",
+];
+
+/// Number of latest entries of every log which are retained, configured per schema.
+#[derive(Debug, Clone, Default)]
+pub struct Retention {
+    /// Number of latest entries which keep their payloads.
+    payloads: Vec<(Hash, u64)>,
+
+    /// Number of latest entries which are kept next to the lipmaa certificate pool.
+    entries: Vec<(Hash, u64)>,
+}
+
+impl Retention {
+    /// Returns the retention configured for this node.
+    pub fn new(config: &Configuration) -> Result<Self> {
+        Ok(Self {
+            payloads: parse_retention(&config.payload_retention)?,
+            entries: parse_retention(&config.log_retention)?,
+        })
+    }
+
+    /// Returns true when neither entries nor payloads get pruned.
+    pub fn is_empty(&self) -> bool {
+        self.payloads.is_empty() && self.entries.is_empty()
+    }
+
+    /// Remove all entries which are not retained anymore and are not part of the lipmaa
+    /// certificate pool of their log, returns the number of removed entries.
+    ///
+    /// Entries are removed through the storage, so its cached latest entries are cleared as well.
+    #[instrument(skip_all)]
+    pub async fn prune_entries(&self, storage: &SqlStorage) -> Result<u64> {
+        let mut pruned = 0;
+
+        for (schema, keep) in self.entries.iter() {
+            pruned += storage.prune_entries(schema, *keep).await?;
+        }
+
+        info!("Pruned {} entries", pruned);
+
+        Ok(pruned)
+    }
+
+    /// Prune the payloads of all entries which are not retained anymore, returns the number of
+    /// pruned payloads.
+    ///
+    /// Payloads are pruned through the storage, so its cached latest entries are cleared as well.
+    #[instrument(skip_all)]
+    pub async fn prune_payloads(&self, storage: &SqlStorage) -> Result<u64> {
+        let mut pruned = 0;
+
+        for (schema, keep) in self.payloads.iter() {
+            pruned += storage.prune_payloads(schema, *keep).await?;
+        }
+
+        info!("Pruned {} payloads", pruned);
+
+        Ok(pruned)
+    }
+}
+
+/// Returns the retention of every schema given as `<schema hash>=<number>`.
+fn parse_retention(retention: &[String]) -> Result<Vec<(Hash, u64)>> {
+    retention
+        .iter()
+        .map(|retention| {
+            let mut parts = retention.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(schema), Some(keep)) => Ok((Hash::new(schema)?, keep.parse()?)),
+                _ => bail!(
+                    "Invalid retention {}, expected <schema hash>=<number>",
+                    retention
+                ),
+            }
+        })
+        .collect()
+}
+
+/// Returns the names of all tables of the node, including the documents and history tables of every
+/// schema definition.
+///
+/// Tables of a schema which were not created yet are listed as well, MySQL reports them in the
+/// result of `OPTIMIZE TABLE` without failing.
+async fn table_names(pool: &Pool) -> Result<Vec<String>> {
+    let mut tables: Vec<String> = TABLES.iter().map(|table| table.to_string()).collect();
+
+    for schema in SchemaField::schemas(pool).await? {
+        tables.push(schema_table_name(&schema));
+        tables.push(history_table_name(&schema));
+    }
+
+    Ok(tables)
+}
+
+/// Reclaim unused space and update the statistics of the query planner, returns the number of
+/// bytes the database got smaller.
+///
+/// SQLite databases are rebuilt with `VACUUM`, PostgreSQL runs `VACUUM ANALYZE` and MySQL
+/// optimizes all tables of the node.
+#[instrument(skip(pool))]
+pub async fn run_maintenance(pool: &Pool) -> Result<i64> {
+    let size_before = database_size(pool).await?;
+
+    match pool.any_kind() {
+        AnyKind::Sqlite => {
+            pool.execute("VACUUM").await?;
+            pool.execute("ANALYZE").await?;
+        }
+        AnyKind::MySql => {
+            for table in table_names(pool).await? {
+                pool.execute(format!("OPTIMIZE TABLE {}", table).as_str())
+                    .await?;
+            }
+        }
+        _ => {
+            pool.execute("VACUUM ANALYZE").await?;
+        }
+    }
+
+    let reclaimed = size_before - database_size(pool).await?;
+    info!(
+        "Finished database maintenance, reclaimed {} bytes",
+        reclaimed
+    );
+
+    Ok(reclaimed)
+}
+
+/// Run database maintenance in every interval until the shutdown future resolves.
+///
+/// Entries and payloads which are not retained anymore get pruned first, so their space gets
+/// reclaimed in the same run. Failed runs are logged and retried in the next interval.
+pub async fn start_maintenance(
+    storage: &SqlStorage,
+    retention: &Retention,
+    interval: Duration,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let shutdown = shutdown.fuse();
+    pin_mut!(shutdown);
+
+    loop {
+        select! {
+            _ = task::sleep(interval).fuse() => {
+                if !retention.is_empty() {
+                    if let Err(err) = retention.prune_entries(storage).await {
+                        error!("Could not prune entries: {}", err);
+                    }
+
+                    if let Err(err) = retention.prune_payloads(storage).await {
+                        error!("Could not prune payloads: {}", err);
+                    }
+                }
+
+                if let Err(err) = run_maintenance(&storage.pool).await {
+                    error!("Could not run database maintenance: {}", err);
+                }
+            },
+            _ = shutdown => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use super::{run_maintenance, Retention};
+
+    use crate::config::Configuration;
+    use crate::db::{EntryStore, SqlStorage};
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, initialize_db};
+
+    #[async_std::test]
+    async fn run_database_maintenance() {
+        let pool = initialize_db().await;
+        assert!(run_maintenance(&pool).await.unwrap() >= 0);
+    }
+
+    #[async_std::test]
+    async fn prune_payloads() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2] <-- [3]
+        let mut backlink = None;
+        for seq_num in 1..4 {
+            let (entry, message) = create_test_entry(
+                &key_pair,
+                &schema,
+                &log_id,
+                None,
+                backlink.as_ref(),
+                &SeqNum::new(seq_num).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            backlink = Some(entry);
+        }
+
+        let config = Configuration {
+            payload_retention: vec![format!("{}=1", schema.as_str())],
+            ..Configuration::default()
+        };
+        let retention = Retention::new(&config).unwrap();
+
+        // Only the latest entry keeps its payload
+        assert_eq!(retention.prune_payloads(&storage).await.unwrap(), 2);
+        assert_eq!(retention.prune_payloads(&storage).await.unwrap(), 0);
+
+        let entries = storage
+            .query_entries(None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 3);
+        assert!(entries[0].payload_bytes.is_none());
+        assert!(entries[1].payload_bytes.is_none());
+        assert!(entries[2].payload_bytes.is_some());
+
+        // The latest entry keeps its payload
+        assert!(storage
+            .latest_entry(&backlink.as_ref().unwrap().author(), &log_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .payload_bytes
+            .is_some());
+
+        let config = Configuration {
+            payload_retention: vec![format!("{}=0", schema.as_str())],
+            ..Configuration::default()
+        };
+        let retention = Retention::new(&config).unwrap();
+        assert_eq!(retention.prune_payloads(&storage).await.unwrap(), 1);
+
+        // Cached latest entries do not keep pruned payloads
+        assert!(storage
+            .latest_entry(&backlink.as_ref().unwrap().author(), &log_id)
+            .await
+            .unwrap()
+            .unwrap()
+            .payload_bytes
+            .is_none());
+
+        // Retention needs schema hash and number of entries
+        let config = Configuration {
+            payload_retention: vec![schema.as_str().to_owned()],
+            ..Configuration::default()
+        };
+        assert!(Retention::new(&config).is_err());
+    }
+
+    #[async_std::test]
+    async fn prune_entries() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // Publish entries with sequence numbers 1 to 8 and 9 after pruning
+        let mut entries: Vec<EntrySigned> = Vec::new();
+        let publish = |seq_num: i64, entries: &[EntrySigned]| {
+            let seq_num = SeqNum::new(seq_num).unwrap();
+            let skiplink = seq_num
+                .skiplink_seq_num()
+                .map(|skiplink| entries[skiplink.as_i64() as usize - 1].clone());
+            create_test_entry(
+                &key_pair,
+                &schema,
+                &log_id,
+                skiplink.as_ref(),
+                entries.last(),
+                &seq_num,
+            )
+        };
+
+        for seq_num in 1..9 {
+            let (entry, message) = publish(seq_num, &entries);
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        let config = Configuration {
+            log_retention: vec![format!("{}=2", schema.as_str())],
+            ..Configuration::default()
+        };
+        let retention = Retention::new(&config).unwrap();
+
+        // Latest two entries and the certificate pool 8, 4 and 1 are kept
+        assert_eq!(retention.prune_entries(&storage).await.unwrap(), 4);
+        assert_eq!(retention.prune_entries(&storage).await.unwrap(), 0);
+
+        let seq_nums: Vec<i64> = storage
+            .query_entries(None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap()
+            .iter()
+            .map(|entry| entry.seq_num.as_i64())
+            .collect();
+        assert_eq!(seq_nums, vec![1, 4, 7, 8]);
+
+        // The pruned log can be continued
+        let (entry, message) = publish(9, &entries);
+        assert!(
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .is_ok()
+        );
+    }
+}
@@ -0,0 +1,3840 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_std::channel::Receiver;
+use async_std::future::{timeout, Future};
+use futures::{pin_mut, select, FutureExt};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::message::{Message, MessageAction, MessageEncoded, MessageValue};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sqlx::any::{AnyKind, AnyRow};
+use sqlx::{query_as, Row};
+use tracing::{debug, error, info, instrument, warn};
+
+use crate::db::models::{
+    AuthorProfile, DocumentOperation, EncryptedMessage, Entry, KeyGroup, MaterializerTask,
+    SchemaDefinition, SchemaField, TASK_DONE, TASK_FAILED,
+};
+use crate::db::query_builder::{
+    quote_identifier, AddColumn, Aggregate, CreateTable, Delete, DropTable, Insert, Operator,
+    Order, Select, Statement, Update, Value,
+};
+use crate::db::{sql, unix_timestamp, Pool};
+use crate::errors::{Error, Result};
+use crate::events::{Events, NodeEvent};
+use crate::rpc::EntryNotification;
+
+mod system;
+
+pub use system::{
+    is_system_schema, system_schema_definition, AUTHOR_PROFILE_SCHEMA, ENCRYPTED_MESSAGE_SCHEMA,
+    KEY_GROUP_MEMBERSHIP_SCHEMA, KEY_GROUP_SCHEMA,
+};
+
+/// Maximum number of attempts to materialize an entry before giving up.
+const MAX_RETRIES: i64 = 5;
+
+/// Interval in which pending tasks get retried when no new entries arrive.
+const RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Number of pending tasks loaded from the database at once.
+const TASK_BATCH_SIZE: i64 = 100;
+
+/// Hash of the reserved schema of schema definition messages.
+///
+/// Every CREATE message of this schema registers a new schema identified by the hash of its entry.
+/// The message fields map the field names of the new schema to their types, for example
+/// `title: "str"`. UPDATE messages of the same author add fields to an existing definition.
+pub const SCHEMA_DEFINITION_SCHEMA: &str = "00408697268bc2f59327bc70c03c7b7b39c907fa182cd5be5e559cf69851c0c4f4356d77d5e8abc6d50b50f33d33cdcd05c30cac5931dcb4eb0ca45fea6fb0cbc26a";
+
+/// Columns every documents table has next to the fields of its schema.
+const DOCUMENT_COLUMNS: [&str; 4] = ["document_id", "author", "entry_hash", "seq_num"];
+
+/// Columns of documents tables marking deleted documents with their types, they are empty for
+/// documents which were not deleted.
+const TOMBSTONE_COLUMNS: [(&str, &str); 2] =
+    [("deleted_at", "BIGINT"), ("deleted_by", "VARCHAR(64)")];
+
+/// Field names and values of a materialized document.
+pub type DocumentFields = BTreeMap<String, MessageValue>;
+
+/// Condition on a field or document column of a document query.
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldFilter {
+    /// Name of the field or document column.
+    pub field: String,
+
+    /// Comparison of the field with the value.
+    pub operator: Operator,
+
+    /// Value to compare the field with.
+    pub value: Value,
+}
+
+/// Filters, ordering and pagination of a document query.
+#[derive(Debug, Clone)]
+pub struct DocumentQuery {
+    /// Only documents matching all filters are returned.
+    pub filters: Vec<FieldFilter>,
+
+    /// Field or document column to sort documents by, documents with equal values are sorted by
+    /// their id.
+    pub order_by: Option<String>,
+
+    /// Sort direction.
+    pub order: Order,
+
+    /// Maximum number of returned documents.
+    pub limit: i64,
+
+    /// Number of documents to skip.
+    pub offset: i64,
+
+    /// Only documents sorted after this position are returned.
+    ///
+    /// Documents without a value for the sorted field are never sorted after a position.
+    pub after: Option<DocumentPosition>,
+
+    /// Return deleted documents as well.
+    pub include_deleted: bool,
+}
+
+/// Aggregate function applied to a field or document column of all documents of a group.
+#[derive(Serialize, Deserialize, Debug, Clone, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct FieldAggregate {
+    /// Aggregate function.
+    pub function: Aggregate,
+
+    /// Name of the field or document column, can only be omitted to count all documents.
+    pub field: Option<String>,
+}
+
+/// Filters, grouping and aggregate functions of a document aggregation.
+#[derive(Debug, Clone)]
+pub struct DocumentAggregation {
+    /// Only documents matching all filters are aggregated.
+    pub filters: Vec<FieldFilter>,
+
+    /// Field or document column to group documents by, all documents form one group when it is
+    /// not given.
+    pub group_by: Option<String>,
+
+    /// Aggregate functions applied to every group.
+    pub aggregates: Vec<FieldAggregate>,
+}
+
+/// Results of the aggregate functions of a group of documents.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentGroup {
+    /// Value of the grouped field, `None` for documents without this field or when documents are
+    /// not grouped.
+    pub group: Option<Value>,
+
+    /// Results in the order of the aggregate functions, `None` when no document of the group has
+    /// a value for the aggregated field.
+    pub values: Vec<Option<Value>>,
+}
+
+/// Document which got created or updated by a materialized entry.
+#[derive(Debug)]
+pub struct DocumentChange {
+    /// True when the document got created, false when it got updated.
+    pub created: bool,
+
+    /// Schema of the document.
+    pub schema: Hash,
+
+    /// State of the document after the change.
+    pub document: Document,
+}
+
+/// Position of a document in the ordering of a document query.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentPosition {
+    /// Value of the field the documents are sorted by, `None` when they are sorted by id.
+    pub value: Option<Value>,
+
+    /// Id of the document.
+    pub document_id: String,
+}
+
+/// Point in the history of a document.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DocumentVersion {
+    /// State after all changes with this or a lower sequence number.
+    SeqNum(i64),
+
+    /// State at this time, given in seconds since the Unix epoch.
+    Timestamp(i64),
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum MaterializerError {
+    #[error("Unimplemented type of message field \"{0}\"")]
+    UnsupportedValue(String),
+
+    #[error("Message is missing document id")]
+    MissingDocumentId,
+
+    #[error("Field \"{0}\" is not part of the schema definition")]
+    UnknownField(String),
+
+    #[error("Invalid field name \"{0}\"")]
+    InvalidFieldName(String),
+
+    #[error("Field \"{0}\" does not match the type of the schema definition")]
+    InvalidFieldType(String),
+
+    #[error("Field \"{0}\" of schema definition needs to be given as type name")]
+    InvalidFieldDefinition(String),
+
+    #[error("Field \"{0}\" needs to be of type {1}")]
+    FieldTypeMismatch(String, String),
+
+    #[error("Message is missing field \"{0}\" of the schema definition")]
+    MissingField(String),
+
+    #[error("Aggregate function needs a field, only documents can be counted without one")]
+    MissingAggregateField,
+
+    #[error("Document history is not recorded by this node")]
+    HistoryDisabled,
+
+    #[error("Document version can either be given by sequence number or timestamp")]
+    AmbiguousVersion,
+
+    #[error("Documents of schema {0} can not be materialized again, its entries were pruned")]
+    PrunedSchema(String),
+}
+
+/// Current materialized state of a document.
+#[derive(Debug, Clone)]
+pub struct Document {
+    /// Hash of the entry which created this document.
+    pub document_id: String,
+
+    /// Public key of the document's author.
+    pub author: String,
+
+    /// Hash of the latest entry which was applied to this document.
+    pub entry_hash: String,
+
+    /// Sequence number of the latest entry which was applied to this document.
+    pub seq_num: i64,
+
+    /// Field names and current values, fields without a value are omitted.
+    pub fields: DocumentFields,
+
+    /// Time in seconds since the Unix epoch when the document got deleted, when it is known.
+    pub deleted_at: Option<i64>,
+
+    /// Public key of the author who deleted the document.
+    pub deleted_by: Option<String>,
+}
+
+impl Document {
+    /// Returns true when the document got deleted.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_by.is_some()
+    }
+
+    /// Returns the value of a field or document column.
+    pub fn value(&self, name: &str) -> Option<Value> {
+        match name {
+            "document_id" => Some(Value::Text(self.document_id.clone())),
+            "author" => Some(Value::Text(self.author.clone())),
+            "entry_hash" => Some(Value::Text(self.entry_hash.clone())),
+            "seq_num" => Some(Value::Integer(self.seq_num)),
+            name => self.fields.get(name).map(Value::from),
+        }
+    }
+
+    /// Returns the position of this document when sorted by the given field or by id.
+    ///
+    /// Returns `None` when the document has no value for the sorted field.
+    pub fn position(&self, order_by: Option<&str>) -> Option<DocumentPosition> {
+        let value = match order_by {
+            Some(name) => Some(self.value(name)?),
+            None => None,
+        };
+
+        Some(DocumentPosition {
+            value,
+            document_id: self.document_id.clone(),
+        })
+    }
+
+    /// Read document from a database row of its schema table.
+    fn from_row(row: &AnyRow, definition: &[SchemaField]) -> Result<Self> {
+        let mut document = Self::from_columns(row, definition)?;
+        document.deleted_at = row.try_get("deleted_at")?;
+        document.deleted_by = row.try_get("deleted_by")?;
+        Ok(document)
+    }
+
+    /// Read document from the document and field columns of a database row, ignoring tombstones.
+    fn from_columns(row: &AnyRow, definition: &[SchemaField]) -> Result<Self> {
+        let mut fields = DocumentFields::new();
+
+        for field in definition {
+            let name = field.name.as_str();
+
+            let value = match field.field_type.as_str() {
+                "bool" => row
+                    .try_get::<Option<bool>, _>(name)?
+                    .map(MessageValue::Boolean),
+                "int" => row
+                    .try_get::<Option<i64>, _>(name)?
+                    .map(MessageValue::Integer),
+                "float" => row
+                    .try_get::<Option<f64>, _>(name)?
+                    .map(MessageValue::Float),
+                "str" => row
+                    .try_get::<Option<String>, _>(name)?
+                    .map(MessageValue::Text),
+                "relation" => match row.try_get::<Option<String>, _>(name)? {
+                    Some(hash) => Some(MessageValue::Relation(Hash::new(&hash)?)),
+                    None => None,
+                },
+                field_type => {
+                    return Err(MaterializerError::UnsupportedValue(field_type.to_owned()).into())
+                }
+            };
+
+            if let Some(value) = value {
+                fields.insert(field.name.clone(), value);
+            }
+        }
+
+        Ok(Self {
+            document_id: row.try_get("document_id")?,
+            author: row.try_get("author")?,
+            entry_hash: row.try_get("entry_hash")?,
+            seq_num: row.try_get("seq_num")?,
+            fields,
+            deleted_at: None,
+            deleted_by: None,
+        })
+    }
+}
+
+/// Returns the name of the table holding all materialized documents of a schema.
+///
+/// Identifiers are limited to 63 characters in PostgreSQL and 64 characters in MySQL, this is why
+/// only a part of the schema hash digest is used, skipping the hash header.
+pub fn schema_table_name(schema: &Hash) -> String {
+    format!("schema_{}", &schema.as_str()[4..60])
+}
+
+/// Returns the name of the table holding the version history of all documents of a schema.
+///
+/// The hash digest is cut shorter than in the documents table name to stay within the identifier
+/// limits.
+pub fn history_table_name(schema: &Hash) -> String {
+    format!("history_{}", &schema.as_str()[4..59])
+}
+
+/// Returns true when a field name can be used as a column name.
+///
+/// Column names are quoted by the query builder, still field names need to start with a letter,
+/// can only contain alphanumeric characters and underscores and must not collide with the document
+/// and tombstone columns to keep the tables easy to query.
+fn is_valid_field_name(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    let starts_with_letter = chars
+        .next()
+        .map_or(false, |first| first.is_ascii_alphabetic());
+
+    starts_with_letter
+        && name.len() <= 63
+        && chars.all(|char| char.is_ascii_alphanumeric() || char == '_')
+        && !DOCUMENT_COLUMNS.contains(&name)
+        && !TOMBSTONE_COLUMNS.iter().any(|(column, _)| column == &name)
+}
+
+/// Returns the schema field type of a message value.
+fn field_type(value: &MessageValue) -> &'static str {
+    match value {
+        MessageValue::Boolean(_) => "bool",
+        MessageValue::Integer(_) => "int",
+        MessageValue::Float(_) => "float",
+        MessageValue::Text(_) => "str",
+        MessageValue::Relation(_) => "relation",
+    }
+}
+
+/// Returns the SQL column type of a schema field type.
+fn column_type(field_type: &str) -> Result<&'static str> {
+    match field_type {
+        "bool" => Ok("BOOLEAN"),
+        "int" => Ok("BIGINT"),
+        "float" => Ok("DOUBLE PRECISION"),
+        "str" => Ok("TEXT"),
+        "relation" => Ok("VARCHAR(132)"),
+        _ => Err(MaterializerError::UnsupportedValue(field_type.to_owned()).into()),
+    }
+}
+
+/// Returns true when this is the reserved schema of schema definition messages.
+pub fn is_schema_definition(schema: &Hash) -> bool {
+    schema.as_str() == SCHEMA_DEFINITION_SCHEMA
+}
+
+/// Returns the fields of a registered schema, `None` when no definition was published for it.
+///
+/// System schemas always have their built-in definition.
+pub async fn schema_definition(pool: &Pool, schema: &Hash) -> Result<Option<Vec<SchemaField>>> {
+    if let Some(definition) = system_schema_definition(schema) {
+        return Ok(Some(definition));
+    }
+
+    if SchemaDefinition::by_schema(pool, schema).await?.is_none() {
+        return Ok(None);
+    }
+
+    Ok(Some(SchemaField::by_schema(pool, schema).await?))
+}
+
+/// Register the schema definition published by a message of the schema definition schema.
+///
+/// Existing fields of a definition can not be changed, only its author can add new fields to it.
+/// The documents table of the schema is created or migrated right away. Afterwards all entries of
+/// the schema are materialized again, as they were ignored or failed while the definition was not
+/// known yet.
+async fn register_schema(pool: &Pool, entry: &EntryNotification, message: &Message) -> Result<()> {
+    let schema = match message.action() {
+        MessageAction::Create => entry.entry_hash.clone(),
+        MessageAction::Update => {
+            let schema = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+            match SchemaDefinition::by_schema(pool, schema).await? {
+                Some(definition) if definition.author == entry.author => schema.clone(),
+                _ => {
+                    warn!(
+                        "Ignore update of unknown schema definition {}",
+                        schema.as_str()
+                    );
+                    return Ok(());
+                }
+            }
+        }
+        MessageAction::Delete => {
+            warn!("Ignore deletion of schema definition");
+            return Ok(());
+        }
+    };
+
+    // Check all fields before anything gets registered
+    let definition = SchemaField::by_schema(pool, &schema).await?;
+    let mut new_fields = Vec::new();
+
+    if let Some(message_fields) = message.fields() {
+        for (name, value) in message_fields.iter() {
+            let field_type = match value {
+                MessageValue::Text(field_type) => field_type,
+                _ => return Err(MaterializerError::InvalidFieldDefinition(name.to_owned()).into()),
+            };
+
+            if !is_valid_field_name(name) {
+                return Err(MaterializerError::InvalidFieldName(name.to_owned()).into());
+            }
+
+            column_type(field_type)?;
+
+            match definition.iter().find(|field| &field.name == name) {
+                Some(field) if &field.field_type != field_type => {
+                    return Err(MaterializerError::FieldTypeMismatch(
+                        name.to_owned(),
+                        field.field_type.clone(),
+                    )
+                    .into());
+                }
+                Some(_) => (),
+                None => new_fields.push((name, field_type)),
+            }
+        }
+    }
+
+    // Tables are created and migrated before the new fields get visible to readers
+    let mut definition = definition;
+    for (name, field_type) in new_fields.iter() {
+        definition.push(SchemaField {
+            schema: schema.clone(),
+            name: name.to_string(),
+            field_type: field_type.to_string(),
+        });
+    }
+
+    create_schema_table(pool, &schema_table_name(&schema), &definition).await?;
+
+    // History tables only exist when history is enabled
+    let history_table = history_table_name(&schema);
+    if !table_columns(pool, &history_table).await?.is_empty() {
+        create_history_table(pool, &history_table, &definition).await?;
+    }
+
+    SchemaDefinition::insert(pool, &schema, &entry.author).await?;
+
+    for (name, field_type) in new_fields {
+        SchemaField::insert(pool, &schema, name, field_type).await?;
+    }
+
+    info!("Registered schema definition {}", schema.as_str());
+
+    MaterializerTask::delete_by_schema(pool, &schema).await?;
+    MaterializerTask::insert_missing(pool).await?;
+
+    Ok(())
+}
+
+/// Returns the names of all columns of a table, the list is empty when the table does not exist.
+async fn table_columns(pool: &Pool, table_name: &str) -> Result<Vec<String>> {
+    let statement = match pool.any_kind() {
+        AnyKind::Sqlite => "SELECT name FROM pragma_table_info($1)",
+        AnyKind::MySql => {
+            "SELECT CAST(column_name AS CHAR) FROM information_schema.columns
+            WHERE table_schema = DATABASE() AND table_name = $1"
+        }
+        _ => {
+            "SELECT CAST(column_name AS TEXT) FROM information_schema.columns
+            WHERE table_schema = current_schema() AND table_name = $1"
+        }
+    };
+
+    let columns = query_as::<_, (String,)>(&sql(pool, statement))
+        .bind(table_name)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(columns.into_iter().map(|(column,)| column).collect())
+}
+
+/// Returns the names and SQL column types of the fields of a schema definition.
+fn field_columns(definition: &[SchemaField]) -> Result<Vec<(String, &'static str)>> {
+    definition
+        .iter()
+        .map(|field| Ok((field.name.clone(), column_type(&field.field_type)?)))
+        .collect()
+}
+
+/// Returns the statements migrating an existing table to the given nullable columns.
+///
+/// Columns which are missing in the table are added, existing rows have no value for them.
+/// Columns of fields which are not part of the definition anymore are kept and ignored.
+fn plan_migration(
+    table_name: &str,
+    columns: &[String],
+    nullable_columns: &[(String, &str)],
+) -> Vec<Statement> {
+    nullable_columns
+        .iter()
+        .filter(|(name, _)| !columns.contains(name))
+        .map(|(name, column_type)| AddColumn::new(table_name, name, column_type).build())
+        .collect()
+}
+
+/// Create a table with the given columns and nullable columns when it does not exist yet.
+///
+/// Existing tables get migrated when nullable columns were added, for example when the schema
+/// definition gained new fields.
+async fn create_table(
+    pool: &Pool,
+    table_name: &str,
+    mut statement: CreateTable,
+    nullable_columns: &[(String, &str)],
+) -> Result<()> {
+    for (name, column_type) in nullable_columns {
+        statement = statement.column(name, column_type);
+    }
+
+    statement.build().execute(pool).await?;
+
+    let columns = table_columns(pool, table_name).await?;
+
+    for statement in plan_migration(table_name, &columns, nullable_columns) {
+        info!("Migrate table: {}", statement.sql());
+        statement.execute(pool).await?;
+    }
+
+    Ok(())
+}
+
+/// Create documents table of a schema with one column per field when it does not exist yet.
+///
+/// Deleted documents are kept as tombstones, they have values in the tombstone columns.
+async fn create_schema_table(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+) -> Result<()> {
+    let statement = CreateTable::new(table_name)
+        .column("document_id", "VARCHAR(132) NOT NULL PRIMARY KEY")
+        .column("author", "VARCHAR(64) NOT NULL")
+        .column("entry_hash", "VARCHAR(132) NOT NULL")
+        .column("seq_num", "BIGINT NOT NULL");
+
+    let mut columns = field_columns(definition)?;
+    for (name, column_type) in TOMBSTONE_COLUMNS.iter() {
+        columns.push((name.to_string(), column_type));
+    }
+
+    create_table(pool, table_name, statement, &columns).await
+}
+
+/// Create history table of a schema with one column per field when it does not exist yet.
+///
+/// Every row holds the state of a document after it got changed by an entry. The author is the
+/// author of this entry and `received_at` the time in seconds since the Unix epoch when the node
+/// received it.
+async fn create_history_table(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+) -> Result<()> {
+    let statement = CreateTable::new(table_name)
+        .column("entry_hash", "VARCHAR(132) NOT NULL PRIMARY KEY")
+        .column("document_id", "VARCHAR(132) NOT NULL")
+        .column("author", "VARCHAR(64) NOT NULL")
+        .column("seq_num", "BIGINT NOT NULL")
+        .column("deleted", "BOOLEAN NOT NULL")
+        .column("received_at", "BIGINT NOT NULL");
+
+    create_table(pool, table_name, statement, &field_columns(definition)?).await
+}
+
+/// Record the state of a document after it got changed by an operation in the history table of
+/// its schema.
+///
+/// Deleted documents are recorded without fields.
+async fn insert_history(
+    pool: &Pool,
+    definition: &[SchemaField],
+    operation: &DocumentOperation,
+    fields: Option<&DocumentFields>,
+    received_at: i64,
+) -> Result<()> {
+    let table_name = history_table_name(&operation.schema);
+    create_history_table(pool, &table_name, definition).await?;
+
+    let mut statement = Insert::new(&table_name)
+        .value("entry_hash", &operation.entry_hash)
+        .value("document_id", &operation.document_id)
+        .value("author", operation.author.as_str())
+        .value("seq_num", operation.seq_num.as_i64())
+        .value("deleted", fields.is_none())
+        .value("received_at", received_at);
+
+    for (name, value) in fields.into_iter().flatten() {
+        statement = statement.value(name, value);
+    }
+
+    statement.build().execute(pool).await?;
+
+    Ok(())
+}
+
+/// Returns a statement selecting all columns of documents from the documents table of a schema.
+fn select_documents(table_name: &str, definition: &[SchemaField]) -> Select {
+    let mut statement = Select::new(table_name);
+
+    for column in DOCUMENT_COLUMNS.iter() {
+        statement = statement.column(column);
+    }
+
+    for (column, _) in TOMBSTONE_COLUMNS.iter() {
+        statement = statement.column(column);
+    }
+
+    for field in definition {
+        statement = statement.column(&field.name);
+    }
+
+    statement
+}
+
+/// Returns the document with the given id from the documents table of a schema, including
+/// deleted documents.
+async fn fetch_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    document_id: &str,
+) -> Result<Option<Document>> {
+    let row = select_documents(table_name, definition)
+        .filter("document_id", document_id)
+        .build()
+        .fetch_optional(pool)
+        .await?;
+
+    match row {
+        Some(row) => Ok(Some(Document::from_row(&row, definition)?)),
+        None => Ok(None),
+    }
+}
+
+/// Returns true when a query failed because the queried table does not exist.
+fn is_missing_table(err: &Error) -> bool {
+    match err {
+        Error::Database(sqlx::Error::Database(err)) => {
+            // PostgreSQL and MySQL report a SQLSTATE code, SQLite only a message
+            matches!(err.code().as_deref(), Some("42P01") | Some("42S02"))
+                || err.message().starts_with("no such table")
+        }
+        _ => false,
+    }
+}
+
+/// Returns the default value instead of an error when the queried table does not exist.
+///
+/// Tables are only created and migrated when documents get materialized, readers treat missing
+/// tables as empty.
+fn or_missing_table<T: Default>(result: Result<T>) -> Result<T> {
+    match result {
+        Err(err) if is_missing_table(&err) => Ok(T::default()),
+        result => result,
+    }
+}
+
+/// Returns the materialized document with the given id, `None` when it got deleted.
+pub async fn get_document(
+    pool: &Pool,
+    schema: &Hash,
+    document_id: &str,
+) -> Result<Option<Document>> {
+    let definition = SchemaField::by_schema(pool, schema).await?;
+
+    // Documents of unknown schemas can not exist
+    if definition.is_empty() {
+        return Ok(None);
+    }
+
+    let table_name = schema_table_name(schema);
+    let document =
+        or_missing_table(fetch_document(pool, &table_name, &definition, document_id).await)?;
+    Ok(document.filter(|document| !document.is_deleted()))
+}
+
+/// Returns the materialized documents with the given ids together with their schema, independent
+/// of the schema they belong to.
+///
+/// Documents are selected with one query per schema. Unknown and deleted documents are skipped.
+pub async fn get_documents(pool: &Pool, document_ids: &[Hash]) -> Result<Vec<(Hash, Document)>> {
+    // Group the documents by schema, known from the operation which created them
+    let mut schemas: BTreeMap<String, Vec<Hash>> = BTreeMap::new();
+    for operation in DocumentOperation::creations(pool, document_ids).await? {
+        schemas
+            .entry(operation.schema.as_str().to_owned())
+            .or_default()
+            .push(operation.document_id);
+    }
+
+    let mut documents = Vec::new();
+
+    for (schema, document_ids) in schemas {
+        let schema = Hash::new(&schema)?;
+        let definition = SchemaField::by_schema(pool, &schema).await?;
+
+        if definition.is_empty() {
+            continue;
+        }
+
+        let table_name = schema_table_name(&schema);
+        let rows = or_missing_table(
+            select_documents(&table_name, &definition)
+                .filter_in("document_id", document_ids.iter())
+                .is_null("deleted_by")
+                .build()
+                .fetch_all(pool)
+                .await,
+        )?;
+
+        for row in rows.iter() {
+            documents.push((schema.clone(), Document::from_row(row, &definition)?));
+        }
+    }
+
+    Ok(documents)
+}
+
+/// Returns the state of a document at the given version, reconstructed from the history table of
+/// its schema.
+///
+/// Returns `None` when the document did not exist yet or was deleted at this version.
+pub async fn get_document_version(
+    pool: &Pool,
+    schema: &Hash,
+    document_id: &str,
+    version: DocumentVersion,
+) -> Result<Option<Document>> {
+    let definition = SchemaField::by_schema(pool, schema).await?;
+
+    // Documents of unknown schemas can not exist
+    if definition.is_empty() {
+        return Ok(None);
+    }
+
+    let records = document_history(pool, &definition, schema, document_id).await?;
+
+    // History rows hold the author of the change, documents the author who created them
+    let author = match records
+        .iter()
+        .find(|record| record.document.entry_hash == document_id)
+    {
+        Some(creation) => creation.document.author.clone(),
+        None => return Ok(None),
+    };
+
+    // Use the change ordered last by the materializer, sequence numbers of different logs can
+    // not be compared
+    let document_hash = Hash::new(document_id)?;
+    let operations = causal_order(
+        DocumentOperation::by_document(pool, &document_hash).await?,
+        &document_hash,
+    );
+
+    let record = operations.iter().rev().find_map(|operation| {
+        records.iter().find(|record| {
+            record.document.entry_hash == operation.entry_hash.as_str()
+                && match version {
+                    DocumentVersion::SeqNum(seq_num) => record.document.seq_num <= seq_num,
+                    DocumentVersion::Timestamp(timestamp) => record.received_at <= timestamp,
+                }
+        })
+    });
+
+    match record {
+        Some(record) if !record.document.is_deleted() => Ok(Some(Document {
+            author,
+            ..record.document.clone()
+        })),
+        _ => Ok(None),
+    }
+}
+
+/// Returns the number of materialized documents of a schema which were not deleted.
+pub async fn document_count(pool: &Pool, schema: &Hash) -> Result<i64> {
+    let definition = SchemaField::by_schema(pool, schema).await?;
+
+    // Documents of unknown schemas can not exist
+    if definition.is_empty() {
+        return Ok(0);
+    }
+
+    let table_name = schema_table_name(schema);
+    let (count,) = or_missing_table(
+        query_as::<_, (i64,)>(&format!(
+            "SELECT COUNT(*) FROM {} WHERE deleted_by IS NULL",
+            quote_identifier(&table_name)
+        ))
+        .fetch_one(pool)
+        .await
+        .map_err(Error::from),
+    )?;
+
+    Ok(count)
+}
+
+/// Returns the type of a field or document column, `None` when it is unknown.
+fn column_field_type<'a>(definition: &'a [SchemaField], name: &str) -> Option<&'a str> {
+    match name {
+        "seq_num" => Some("int"),
+        name if DOCUMENT_COLUMNS.contains(&name) => Some("str"),
+        name => definition
+            .iter()
+            .find(|field| field.name == name)
+            .map(|field| field.field_type.as_str()),
+    }
+}
+
+/// Returns the filter value converted to the type of the filtered column.
+///
+/// Integers can be used to filter float fields, all other values need to match the type exactly.
+fn filter_value(definition: &[SchemaField], filter: &FieldFilter) -> Result<Value> {
+    let field_type = column_field_type(definition, &filter.field)
+        .ok_or_else(|| MaterializerError::UnknownField(filter.field.clone()))?;
+
+    match (field_type, &filter.value) {
+        ("bool", Value::Boolean(_))
+        | ("int", Value::Integer(_))
+        | ("float", Value::Float(_))
+        | ("str", Value::Text(_))
+        | ("relation", Value::Text(_)) => Ok(filter.value.clone()),
+        ("float", Value::Integer(value)) => Ok(Value::Float(*value as f64)),
+        _ => Err(MaterializerError::InvalidFieldType(filter.field.clone()).into()),
+    }
+}
+
+/// Returns the materialized documents of a schema matching the query.
+///
+/// Documents are sorted by their id when no other ordering was given to allow stable pagination.
+/// Deleted documents are only returned when the query includes them.
+pub async fn query_documents(
+    pool: &Pool,
+    schema: &Hash,
+    query: &DocumentQuery,
+) -> Result<Vec<Document>> {
+    let definition = SchemaField::by_schema(pool, schema).await?;
+
+    // Documents of unknown schemas can not exist
+    if definition.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let table_name = schema_table_name(schema);
+    let mut statement = select_documents(&table_name, &definition);
+
+    if !query.include_deleted {
+        statement = statement.is_null("deleted_by");
+    }
+
+    for filter in query.filters.iter() {
+        let value = filter_value(&definition, filter)?;
+        statement = statement.compare(&filter.field, filter.operator, value);
+    }
+
+    if let Some(order_by) = &query.order_by {
+        if column_field_type(&definition, order_by).is_none() {
+            return Err(MaterializerError::UnknownField(order_by.to_owned()).into());
+        }
+
+        statement = statement.order_by(order_by, query.order);
+    }
+
+    statement = statement.order_by("document_id", query.order);
+
+    if let Some(after) = &query.after {
+        if let Some(order_by) = &query.order_by {
+            // Validate the type of the position like a filter value
+            let value = filter_value(
+                &definition,
+                &FieldFilter {
+                    field: order_by.to_owned(),
+                    operator: Operator::Gt,
+                    value: after
+                        .value
+                        .clone()
+                        .ok_or_else(|| MaterializerError::InvalidFieldType(order_by.to_owned()))?,
+                },
+            )?;
+
+            statement = statement.after(order_by, value);
+        }
+
+        statement = statement.after("document_id", after.document_id.as_str());
+    }
+
+    let rows = or_missing_table(
+        statement
+            .limit(query.limit)
+            .offset(query.offset)
+            .build()
+            .fetch_all(pool)
+            .await,
+    )?;
+
+    rows.iter()
+        .map(|row| Document::from_row(row, &definition))
+        .collect()
+}
+
+/// Returns the type of the field an aggregate function gets applied to, `None` when all documents
+/// are counted.
+///
+/// Booleans can only be counted as not all databases support comparing them.
+fn aggregate_field_type<'a>(
+    definition: &'a [SchemaField],
+    aggregate: &FieldAggregate,
+) -> Result<Option<&'a str>> {
+    let name = match (&aggregate.field, aggregate.function) {
+        (Some(name), _) => name,
+        (None, Aggregate::Count) => return Ok(None),
+        (None, _) => return Err(MaterializerError::MissingAggregateField.into()),
+    };
+
+    let field_type = column_field_type(definition, name)
+        .ok_or_else(|| MaterializerError::UnknownField(name.to_owned()))?;
+
+    if field_type == "bool" && aggregate.function != Aggregate::Count {
+        return Err(MaterializerError::InvalidFieldType(name.to_owned()).into());
+    }
+
+    Ok(Some(field_type))
+}
+
+/// Read a value of the given field type from a column of a database row.
+fn row_value(row: &AnyRow, column: &str, field_type: &str) -> Result<Option<Value>> {
+    let value = match field_type {
+        "bool" => row.try_get::<Option<bool>, _>(column)?.map(Value::Boolean),
+        "int" => row.try_get::<Option<i64>, _>(column)?.map(Value::Integer),
+        "float" => row.try_get::<Option<f64>, _>(column)?.map(Value::Float),
+        "str" | "relation" => row.try_get::<Option<String>, _>(column)?.map(Value::Text),
+        field_type => return Err(MaterializerError::UnsupportedValue(field_type.to_owned()).into()),
+    };
+
+    Ok(value)
+}
+
+/// Returns the results of aggregate functions over the materialized documents of a schema,
+/// grouped by the values of a field.
+///
+/// Groups are sorted by their value, starting with the group of documents without a value.
+/// Deleted documents are not aggregated. Returns no groups for unknown schemas.
+pub async fn aggregate_documents(
+    pool: &Pool,
+    schema: &Hash,
+    aggregation: &DocumentAggregation,
+) -> Result<Vec<DocumentGroup>> {
+    let definition = SchemaField::by_schema(pool, schema).await?;
+
+    // Documents of unknown schemas can not exist
+    if definition.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let table_name = schema_table_name(schema);
+    let mut statement = Select::new(&table_name).is_null("deleted_by");
+
+    let group_type = match &aggregation.group_by {
+        Some(group_by) => {
+            let group_type = column_field_type(&definition, group_by)
+                .ok_or_else(|| MaterializerError::UnknownField(group_by.to_owned()))?;
+
+            statement = statement.column(group_by).group_by(group_by);
+            Some((group_by.as_str(), group_type))
+        }
+        None => None,
+    };
+
+    let mut aggregate_types = Vec::new();
+
+    for (index, aggregate) in aggregation.aggregates.iter().enumerate() {
+        aggregate_types.push(aggregate_field_type(&definition, aggregate)?);
+
+        statement = statement.aggregate(
+            &format!("aggregate_{}", index),
+            aggregate.function,
+            aggregate.field.as_deref(),
+        );
+    }
+
+    for filter in aggregation.filters.iter() {
+        let value = filter_value(&definition, filter)?;
+        statement = statement.compare(&filter.field, filter.operator, value);
+    }
+
+    let rows = or_missing_table(statement.build().fetch_all(pool).await)?;
+
+    let mut groups = Vec::new();
+
+    for row in rows.iter() {
+        let group = match group_type {
+            Some((group_by, group_type)) => row_value(row, group_by, group_type)?,
+            None => None,
+        };
+
+        let mut values = Vec::new();
+
+        for (index, aggregate) in aggregation.aggregates.iter().enumerate() {
+            let alias = format!("aggregate_{}", index);
+
+            let value = match (aggregate.function, aggregate_types[index]) {
+                (Aggregate::Count, _) => Some(Value::Integer(row.try_get(alias.as_str())?)),
+                (_, Some(field_type)) => row_value(row, &alias, field_type)?,
+                (_, None) => None,
+            };
+
+            values.push(value);
+        }
+
+        groups.push(DocumentGroup { group, values });
+    }
+
+    // Sort groups the same way independent of the database
+    groups.sort_by(|a, b| a.group.partial_cmp(&b.group).unwrap_or(Ordering::Equal));
+
+    Ok(groups)
+}
+
+/// Returns all fields of a message, making sure they match the schema definition.
+fn message_fields(message: &Message, definition: &[SchemaField]) -> Result<DocumentFields> {
+    let mut fields = DocumentFields::new();
+
+    if let Some(message_fields) = message.fields() {
+        for (name, value) in message_fields.iter() {
+            let field = definition
+                .iter()
+                .find(|field| &field.name == name)
+                .ok_or_else(|| MaterializerError::UnknownField(name.to_owned()))?;
+
+            if field.field_type != field_type(value) {
+                return Err(MaterializerError::FieldTypeMismatch(
+                    name.to_owned(),
+                    field.field_type.clone(),
+                )
+                .into());
+            }
+
+            fields.insert(name.to_owned(), value.clone());
+        }
+    }
+
+    Ok(fields)
+}
+
+/// Returns an error naming the offending field when a message does not match the schema
+/// definition.
+///
+/// CREATE messages need to contain all fields of the definition, UPDATE messages any of them.
+pub fn validate_message(message: &Message, definition: &[SchemaField]) -> Result<()> {
+    message_fields(message, definition)?;
+
+    if matches!(message.action(), MessageAction::Create) {
+        for field in definition {
+            let is_given = message.fields().map_or(false, |message_fields| {
+                message_fields.iter().any(|(name, _)| name == &field.name)
+            });
+
+            if !is_given {
+                return Err(MaterializerError::MissingField(field.name.clone()).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Insert a new document created by a CREATE message.
+///
+/// Returns the id of the document when it got inserted.
+async fn create_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<Option<String>> {
+    let document_id = entry.entry_hash.as_str();
+
+    // Ignore documents we already know about
+    if fetch_document(pool, table_name, definition, document_id)
+        .await?
+        .is_some()
+    {
+        debug!("Document {} already materialized", document_id);
+        return Ok(None);
+    }
+
+    let fields = message_fields(message, definition)?;
+
+    let mut statement = Insert::new(table_name)
+        .value("document_id", document_id)
+        .value("author", entry.author.as_str())
+        .value("entry_hash", entry.entry_hash.as_str())
+        .value("seq_num", entry.seq_num.as_i64());
+
+    for (name, value) in fields.iter() {
+        statement = statement.value(name, value);
+    }
+
+    statement.build().execute(pool).await?;
+
+    Ok(Some(document_id.to_owned()))
+}
+
+/// Apply an UPDATE message to the fields of an existing document.
+///
+/// The message needs to be the latest operation of the document in causal order. Returns the id
+/// of the document when it got updated.
+async fn update_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<Option<String>> {
+    let document_id = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+    let document = match fetch_document(pool, table_name, definition, document_id.as_str()).await? {
+        Some(document) => document,
+        None => {
+            warn!("Ignore update of unknown document {}", document_id.as_str());
+            return Ok(None);
+        }
+    };
+
+    if document.is_deleted() {
+        debug!("Ignore update of deleted document {}", document_id.as_str());
+        return Ok(None);
+    }
+
+    // Only overwrite the fields which were given in the update
+    let fields = message_fields(message, definition)?;
+
+    let mut statement = Update::new(table_name)
+        .set("entry_hash", entry.entry_hash.as_str())
+        .set("seq_num", entry.seq_num.as_i64());
+
+    for (name, value) in fields.iter() {
+        statement = statement.set(name, value);
+    }
+
+    statement
+        .filter("document_id", document_id.as_str())
+        .build()
+        .execute(pool)
+        .await?;
+
+    Ok(Some(document_id.as_str().to_owned()))
+}
+
+/// Mark a document as deleted after a DELETE message.
+///
+/// The document is kept as a tombstone holding the time of the deletion and its author, its fields
+/// keep the values they had before. The message needs to be the latest operation of the document
+/// in causal order. Returns the id of the document when it got deleted.
+async fn delete_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<Option<String>> {
+    let document_id = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+    let document = match fetch_document(pool, table_name, definition, document_id.as_str()).await? {
+        Some(document) => document,
+        None => {
+            warn!(
+                "Ignore deletion of unknown document {}",
+                document_id.as_str()
+            );
+            return Ok(None);
+        }
+    };
+
+    if document.is_deleted() {
+        debug!(
+            "Ignore deletion of deleted document {}",
+            document_id.as_str()
+        );
+        return Ok(None);
+    }
+
+    Update::new(table_name)
+        .set("entry_hash", entry.entry_hash.as_str())
+        .set("seq_num", entry.seq_num.as_i64())
+        .set("deleted_at", unix_timestamp())
+        .set("deleted_by", entry.author.as_str())
+        .filter("document_id", document_id.as_str())
+        .build()
+        .execute(pool)
+        .await?;
+
+    Ok(Some(document_id.as_str().to_owned()))
+}
+
+/// Returns the operations of a document in causal order.
+///
+/// Every operation references the CREATE operation through the document id and the previous entry
+/// of its log through its backlink, it is always ordered after them. Operations of different logs
+/// are ordered by their entry hash. This gives every node the same order, independent of the order
+/// in which it received the entries.
+///
+/// Messages do not reference operations of other logs, so all operations of different logs are
+/// treated as concurrent. An UPDATE which was published after seeing an UPDATE of another author
+/// can still be ordered before it and lose a change of the same field.
+fn causal_order(operations: Vec<DocumentOperation>, document_id: &Hash) -> Vec<DocumentOperation> {
+    let mut ordered = Vec::with_capacity(operations.len());
+    let mut logs: BTreeMap<(String, i64), VecDeque<DocumentOperation>> = BTreeMap::new();
+
+    let mut operations = operations;
+    operations.sort_by_key(|operation| operation.seq_num.as_i64());
+
+    for operation in operations {
+        if &operation.entry_hash == document_id {
+            ordered.insert(0, operation);
+        } else {
+            logs.entry((
+                operation.author.as_str().to_owned(),
+                operation.log_id.as_i64(),
+            ))
+            .or_default()
+            .push_back(operation);
+        }
+    }
+
+    // Take the operation with the lowest hash of all operations whose predecessors are ordered
+    loop {
+        let next = logs
+            .iter()
+            .filter_map(|(log, operations)| Some((log, operations.front()?)))
+            .min_by(|(_, a), (_, b)| a.entry_hash.as_str().cmp(b.entry_hash.as_str()))
+            .map(|(log, _)| log.clone());
+
+        match next.and_then(|log| logs.get_mut(&log)?.pop_front()) {
+            Some(operation) => ordered.push(operation),
+            None => return ordered,
+        }
+    }
+}
+
+/// State of a document after an operation, recorded in the history table of its schema.
+#[derive(Debug)]
+struct HistoryRecord {
+    /// Time in seconds since the Unix epoch when the entry of the change was received.
+    received_at: i64,
+
+    /// State of the document, the author is the author of the change. Deleted documents have no
+    /// fields and are deleted by the author of the change.
+    document: Document,
+}
+
+/// Returns all recorded states of a document from the history table of its schema.
+async fn document_history(
+    pool: &Pool,
+    definition: &[SchemaField],
+    schema: &Hash,
+    document_id: &str,
+) -> Result<Vec<HistoryRecord>> {
+    let table_name = history_table_name(schema);
+    let mut statement = Select::new(&table_name)
+        .column("deleted")
+        .column("received_at");
+
+    for column in DOCUMENT_COLUMNS.iter() {
+        statement = statement.column(column);
+    }
+
+    for field in definition.iter() {
+        statement = statement.column(&field.name);
+    }
+
+    let rows = or_missing_table(
+        statement
+            .filter("document_id", document_id)
+            .build()
+            .fetch_all(pool)
+            .await,
+    )?;
+
+    rows.iter()
+        .map(|row| {
+            let mut document = Document::from_columns(row, definition)?;
+
+            if row.try_get::<bool, _>("deleted")? {
+                document.deleted_by = Some(document.author.clone());
+            }
+
+            Ok(HistoryRecord {
+                received_at: row.try_get("received_at")?,
+                document,
+            })
+        })
+        .collect()
+}
+
+/// Returns the time in seconds since the Unix epoch when the entry of an operation was received.
+///
+/// Recorded history keeps this time when documents get materialized again. Entries stored before
+/// their receive time was recorded count as received now.
+async fn entry_received_at(pool: &Pool, entry_hash: &Hash) -> Result<i64> {
+    Ok(Entry::received_at(pool, entry_hash)
+        .await?
+        .unwrap_or_else(unix_timestamp))
+}
+
+/// Returns the message of an operation, `None` when its entry or payload is not stored anymore.
+async fn load_operation(
+    pool: &Pool,
+    entry: &EntryNotification,
+    operation: &DocumentOperation,
+) -> Result<Option<Message>> {
+    let message_encoded = if operation.entry_hash == entry.entry_hash {
+        entry.message_encoded.clone()
+    } else {
+        match load_entry(pool, &operation.entry_hash).await? {
+            Some(operation_entry) => operation_entry.message_encoded,
+            None => return Ok(None),
+        }
+    };
+
+    Ok(Some(Message::from(&MessageEncoded::new(&message_encoded)?)))
+}
+
+/// Apply the message of an operation to the state of a document.
+///
+/// Returns false when the operation got ignored, because it is invalid, the document does not
+/// exist yet or it was already deleted.
+fn apply_operation(
+    state: &mut Option<Document>,
+    operation: &DocumentOperation,
+    message: &Message,
+    definition: &[SchemaField],
+) -> bool {
+    let fields = match message_fields(message, definition) {
+        Ok(fields) => fields,
+        Err(err) => {
+            warn!(
+                "Skip invalid operation {}: {}",
+                operation.entry_hash.as_str(),
+                err
+            );
+            return false;
+        }
+    };
+
+    // The CREATE operation is always ordered first
+    if let MessageAction::Create = message.action() {
+        if state.is_some() {
+            return false;
+        }
+
+        *state = Some(Document {
+            document_id: operation.document_id.as_str().to_owned(),
+            author: operation.author.as_str().to_owned(),
+            entry_hash: String::new(),
+            seq_num: 0,
+            fields: DocumentFields::new(),
+            deleted_at: None,
+            deleted_by: None,
+        });
+    }
+
+    let document = match state.as_mut() {
+        Some(document) if !document.is_deleted() => document,
+        _ => return false,
+    };
+
+    document.fields.extend(fields);
+
+    if let MessageAction::Delete = message.action() {
+        document.deleted_by = Some(operation.author.as_str().to_owned());
+    }
+
+    document.entry_hash = operation.entry_hash.as_str().to_owned();
+    document.seq_num = operation.seq_num.as_i64();
+
+    true
+}
+
+/// Returns the state of a document after the given operations.
+///
+/// All operations are replayed when they are still stored. Otherwise the state is taken from the
+/// latest recorded history of the operations, `None` when there is none.
+async fn replay_state(
+    pool: &Pool,
+    entry: &EntryNotification,
+    definition: &[SchemaField],
+    operations: &[DocumentOperation],
+    records: &[HistoryRecord],
+) -> Result<Option<Document>> {
+    let mut state = None;
+    let mut complete = true;
+
+    for operation in operations {
+        match load_operation(pool, entry, operation).await? {
+            Some(message) => {
+                apply_operation(&mut state, operation, &message, definition);
+            }
+            None => {
+                complete = false;
+                break;
+            }
+        }
+    }
+
+    if complete {
+        return Ok(state);
+    }
+
+    let record = operations.iter().rev().find_map(|operation| {
+        records
+            .iter()
+            .find(|record| record.document.entry_hash == operation.entry_hash.as_str())
+    });
+
+    Ok(record.map(|record| Document {
+        // History rows hold the author of the change, documents the author who created them
+        author: operations[0].author.as_str().to_owned(),
+        ..record.document.clone()
+    }))
+}
+
+/// Apply an operation which is ordered before already applied operations on top of the current
+/// state of a document.
+///
+/// This is only used when the document can not be replayed as operations are not stored anymore.
+/// Fields which are changed by the following operations keep their current value. Returns the id
+/// of the document when it got changed.
+async fn merge_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    entry: &EntryNotification,
+    message: &Message,
+    following: &[Message],
+) -> Result<Option<String>> {
+    if let MessageAction::Create = message.action() {
+        return create_document(pool, table_name, definition, entry, message).await;
+    }
+
+    let document_id = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+    match fetch_document(pool, table_name, definition, document_id.as_str()).await? {
+        Some(document) if !document.is_deleted() => (),
+        _ => return Ok(None),
+    }
+
+    let mut fields = message_fields(message, definition)?;
+    for following_message in following {
+        if let Ok(following_fields) = message_fields(following_message, definition) {
+            fields.retain(|name, _| !following_fields.contains_key(name));
+        }
+    }
+
+    let mut statement = Update::new(table_name);
+    let mut changed = false;
+
+    for (name, value) in fields.iter() {
+        statement = statement.set(name, value);
+        changed = true;
+    }
+
+    if let MessageAction::Delete = message.action() {
+        statement = statement
+            .set("deleted_at", unix_timestamp())
+            .set("deleted_by", entry.author.as_str());
+        changed = true;
+    }
+
+    if changed {
+        statement
+            .filter("document_id", document_id.as_str())
+            .build()
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(Some(document_id.as_str().to_owned()))
+}
+
+/// Materialize a document again after an operation arrived which is ordered before already
+/// applied operations.
+///
+/// The state before the arriving operation is replayed from the earlier operations or, when some
+/// of them are not stored anymore, taken from the history of the document. The arriving and all
+/// following operations are applied on top of it and, when `history` is enabled, their recorded
+/// states are replaced by the new intermediate states. When this is not possible the arriving
+/// operation gets merged into the current state of the document. Returns the id of the document
+/// when it exists.
+async fn replay_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    entry: &EntryNotification,
+    message: &Message,
+    operations: &[DocumentOperation],
+    history: bool,
+) -> Result<Option<String>> {
+    let index = match operations
+        .iter()
+        .position(|operation| operation.entry_hash == entry.entry_hash)
+    {
+        Some(index) => index,
+        None => return Ok(None),
+    };
+
+    let document_id = operations[index].document_id.as_str();
+
+    let records = if history {
+        document_history(pool, definition, &entry.schema, document_id).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut loaded = Vec::new();
+    for operation in operations[index..].iter() {
+        loaded.push(load_operation(pool, entry, operation).await?);
+    }
+
+    let mut state = match index {
+        0 => None,
+        _ => replay_state(pool, entry, definition, &operations[..index], &records).await?,
+    };
+
+    if loaded.iter().any(Option::is_none) || (index > 0 && state.is_none()) {
+        warn!(
+            "Merge operation {} into document {} as not all its operations are stored",
+            entry.entry_hash.as_str(),
+            document_id
+        );
+
+        let following: Vec<Message> = loaded.into_iter().skip(1).flatten().collect();
+        return merge_document(pool, table_name, definition, entry, message, &following).await;
+    }
+
+    let mut applied = Vec::new();
+    for (operation, message) in operations[index..].iter().zip(loaded.into_iter().flatten()) {
+        if apply_operation(&mut state, operation, &message, definition) {
+            if let Some(document) = &state {
+                applied.push((operation, document.clone()));
+            }
+        }
+    }
+
+    let document = match state {
+        Some(document) => document,
+        None => return Ok(None),
+    };
+
+    write_document(pool, table_name, definition, &document).await?;
+
+    // Record the history of the arriving and all following operations again
+    if history {
+        record_history(pool, definition, &operations[index..], &applied, &records).await?;
+    }
+
+    Ok(Some(document.document_id))
+}
+
+/// Replace the row of a document in the documents table of its schema with the given state.
+///
+/// Documents which were already deleted keep the time of their deletion.
+async fn write_document(
+    pool: &Pool,
+    table_name: &str,
+    definition: &[SchemaField],
+    document: &Document,
+) -> Result<()> {
+    let current = fetch_document(pool, table_name, definition, &document.document_id).await?;
+    let deleted_at = match current.and_then(|current| current.deleted_at) {
+        Some(deleted_at) => deleted_at,
+        None => unix_timestamp(),
+    };
+
+    Delete::new(table_name)
+        .filter("document_id", document.document_id.as_str())
+        .build()
+        .execute(pool)
+        .await?;
+
+    let mut statement = Insert::new(table_name)
+        .value("document_id", document.document_id.as_str())
+        .value("author", document.author.as_str())
+        .value("entry_hash", document.entry_hash.as_str())
+        .value("seq_num", document.seq_num);
+
+    for (name, value) in document.fields.iter() {
+        statement = statement.value(name, value);
+    }
+
+    if let Some(deleted_by) = &document.deleted_by {
+        statement = statement
+            .value("deleted_at", deleted_at)
+            .value("deleted_by", deleted_by.as_str());
+    }
+
+    statement.build().execute(pool).await?;
+
+    Ok(())
+}
+
+/// Replace the recorded history of the given operations with the states of the applied ones.
+///
+/// Records keep the time they were received at when they were recorded before.
+async fn record_history(
+    pool: &Pool,
+    definition: &[SchemaField],
+    operations: &[DocumentOperation],
+    applied: &[(&DocumentOperation, Document)],
+    records: &[HistoryRecord],
+) -> Result<()> {
+    for operation in operations {
+        Delete::new(&history_table_name(&operation.schema))
+            .filter("entry_hash", operation.entry_hash.as_str())
+            .build()
+            .execute(pool)
+            .await?;
+    }
+
+    for (operation, state) in applied {
+        let received_at = match records
+            .iter()
+            .find(|record| record.document.entry_hash == operation.entry_hash.as_str())
+        {
+            Some(record) => record.received_at,
+            None => entry_received_at(pool, &operation.entry_hash).await?,
+        };
+
+        let fields = if state.is_deleted() {
+            None
+        } else {
+            Some(&state.fields)
+        };
+
+        insert_history(pool, definition, operation, fields, received_at).await?;
+    }
+
+    Ok(())
+}
+
+/// Materialize a document again from its stored operations after operations of removed entries
+/// got removed.
+///
+/// The document is removed when its CREATE operation is gone. When not all of its other
+/// operations are stored anymore, the document keeps its current state as it can not be
+/// replayed. Recorded history of the removed operations is removed when `history` is enabled.
+async fn rematerialize_document(
+    pool: &Pool,
+    schema: &Hash,
+    document_id: &Hash,
+    removed: &[Hash],
+    history: bool,
+) -> Result<()> {
+    let definition = match schema_definition(pool, schema).await? {
+        Some(definition) => definition,
+        None => return Ok(()),
+    };
+
+    let table_name = schema_table_name(schema);
+    let history_table = history_table_name(schema);
+
+    if history {
+        create_history_table(pool, &history_table, &definition).await?;
+
+        for entry_hash in removed {
+            Delete::new(&history_table)
+                .filter("entry_hash", entry_hash.as_str())
+                .build()
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    let operations = causal_order(
+        DocumentOperation::by_document(pool, document_id).await?,
+        document_id,
+    );
+
+    // Documents are removed together with the entry which created them
+    if operations
+        .first()
+        .map_or(true, |operation| &operation.entry_hash != document_id)
+    {
+        Delete::new(&table_name)
+            .filter("document_id", document_id.as_str())
+            .build()
+            .execute(pool)
+            .await?;
+
+        if history {
+            Delete::new(&history_table)
+                .filter("document_id", document_id.as_str())
+                .build()
+                .execute(pool)
+                .await?;
+        }
+
+        return Ok(());
+    }
+
+    let mut messages = Vec::new();
+    for operation in operations.iter() {
+        match load_entry(pool, &operation.entry_hash).await? {
+            Some(entry) => {
+                let message_encoded = MessageEncoded::new(&entry.message_encoded)?;
+                messages.push(Message::from(&message_encoded));
+            }
+            None => {
+                warn!(
+                    "Keep state of document {} as not all its operations are stored",
+                    document_id.as_str()
+                );
+                return Ok(());
+            }
+        }
+    }
+
+    let records = if history {
+        document_history(pool, &definition, schema, document_id.as_str()).await?
+    } else {
+        Vec::new()
+    };
+
+    let mut state = None;
+    let mut applied = Vec::new();
+    for (operation, message) in operations.iter().zip(messages.iter()) {
+        if apply_operation(&mut state, operation, message, &definition) {
+            if let Some(document) = &state {
+                applied.push((operation, document.clone()));
+            }
+        }
+    }
+
+    match &state {
+        Some(document) => write_document(pool, &table_name, &definition, document).await?,
+        None => {
+            Delete::new(&table_name)
+                .filter("document_id", document_id.as_str())
+                .build()
+                .execute(pool)
+                .await?;
+        }
+    }
+
+    if history {
+        record_history(pool, &definition, &operations, &applied, &records).await?;
+    }
+
+    Ok(())
+}
+
+/// Remove a schema definition registered by an entry together with all documents of the schema.
+async fn remove_schema_definition(pool: &Pool, schema: &Hash) -> Result<()> {
+    if SchemaDefinition::by_schema(pool, schema).await?.is_none() {
+        return Ok(());
+    }
+
+    // Documents of unknown schemas do not exist, remove the definition before its tables
+    SchemaDefinition::delete(pool, schema).await?;
+    SchemaField::delete_by_schema(pool, schema).await?;
+    drop_schema_tables(pool, schema).await?;
+    DocumentOperation::delete_by_schema(pool, schema).await?;
+
+    info!("Removed schema definition {}", schema.as_str());
+
+    Ok(())
+}
+
+/// Remove the changes of entries which got removed from the node from the materialized views.
+///
+/// Entries are given by their hash together with the schema of their log. Documents changed by
+/// the entries are materialized again from their remaining operations, documents created by them
+/// are removed. Schema definitions, key groups, memberships, encrypted messages and author
+/// profiles created by the entries are removed, changes they made to system schema rows of other
+/// entries are kept.
+///
+/// Only the affected rows are changed, so this can run while the materializer service is running.
+pub async fn unmaterialize_entries(
+    pool: &Pool,
+    entries: &[(Hash, Hash)],
+    history: bool,
+) -> Result<()> {
+    // Removed operations of every affected document with its schema
+    let mut documents: BTreeMap<String, (Hash, Vec<Hash>)> = BTreeMap::new();
+
+    for (entry_hash, schema) in entries {
+        if is_schema_definition(schema) {
+            remove_schema_definition(pool, entry_hash).await?;
+        } else if is_system_schema(schema) {
+            system::remove_system_message(pool, schema, entry_hash).await?;
+        } else if let Some(operation) = DocumentOperation::by_entry_hash(pool, entry_hash).await? {
+            DocumentOperation::delete(pool, entry_hash).await?;
+
+            documents
+                .entry(operation.document_id.as_str().to_owned())
+                .or_insert_with(|| (operation.schema.clone(), Vec::new()))
+                .1
+                .push(operation.entry_hash);
+        }
+    }
+
+    for (document_id, (schema, removed)) in documents {
+        let document_id = Hash::new(&document_id)?;
+        rematerialize_document(pool, &schema, &document_id, &removed, history).await?;
+    }
+
+    Ok(())
+}
+
+/// Materialize the message of a new entry into the documents table of its schema.
+///
+/// Every change is recorded in the history table of the schema when `history` is enabled.
+///
+/// Returns the new state of the document when it got created or updated.
+#[instrument(
+    skip_all,
+    fields(
+        entry_hash = entry.entry_hash.as_str(),
+        author = entry.author.as_str(),
+        schema = entry.schema.as_str(),
+        seq_num = entry.seq_num.as_i64()
+    )
+)]
+pub async fn materialize(
+    pool: &Pool,
+    entry: &EntryNotification,
+    history: bool,
+) -> Result<Option<DocumentChange>> {
+    let message_encoded = MessageEncoded::new(&entry.message_encoded)?;
+    let message = Message::from(&message_encoded);
+
+    // Schema definitions are registered instead of being materialized as documents
+    if is_schema_definition(&entry.schema) {
+        register_schema(pool, entry, &message).await?;
+        return Ok(None);
+    }
+
+    // System schemas are materialized into their dedicated tables instead
+    if is_system_schema(&entry.schema) {
+        system::materialize_system_message(pool, entry, &message).await?;
+        return Ok(None);
+    }
+
+    let definition = match schema_definition(pool, &entry.schema).await? {
+        Some(definition) => definition,
+        None => {
+            warn!(
+                "Ignore entry {} of unknown schema {}",
+                entry.entry_hash.as_str(),
+                entry.schema.as_str()
+            );
+            return Ok(None);
+        }
+    };
+
+    let table_name = schema_table_name(&entry.schema);
+    create_schema_table(pool, &table_name, &definition).await?;
+
+    let operation = DocumentOperation {
+        entry_hash: entry.entry_hash.clone(),
+        schema: entry.schema.clone(),
+        document_id: match message.action() {
+            MessageAction::Create => entry.entry_hash.clone(),
+            _ => message
+                .id()
+                .ok_or(MaterializerError::MissingDocumentId)?
+                .clone(),
+        },
+        author: entry.author.clone(),
+        log_id: entry.log_id.clone(),
+        seq_num: entry.seq_num.clone(),
+    };
+
+    let mut operations = DocumentOperation::by_document(pool, &operation.document_id).await?;
+
+    if operations
+        .iter()
+        .any(|applied| applied.entry_hash == operation.entry_hash)
+    {
+        debug!("Entry {} already materialized", entry.entry_hash.as_str());
+        return Ok(None);
+    }
+
+    operations.push(operation.clone());
+    let operations = causal_order(operations, &operation.document_id);
+
+    // Keep operations arriving before the CREATE operation until the document gets created
+    if operations[0].entry_hash != operation.document_id {
+        DocumentOperation::insert(pool, &operation).await?;
+        debug!(
+            "Wait for creation of document {}",
+            operation.document_id.as_str()
+        );
+        return Ok(None);
+    }
+
+    // Apply operations ordered after all known ones directly, otherwise replay the document
+    let is_latest = operations[operations.len() - 1].entry_hash == operation.entry_hash;
+
+    let document_id = match message.action() {
+        _ if !is_latest => {
+            replay_document(
+                pool,
+                &table_name,
+                &definition,
+                entry,
+                &message,
+                &operations,
+                history,
+            )
+            .await?
+        }
+        MessageAction::Create => {
+            create_document(pool, &table_name, &definition, entry, &message).await?
+        }
+        MessageAction::Update => {
+            update_document(pool, &table_name, &definition, entry, &message).await?
+        }
+        MessageAction::Delete => {
+            delete_document(pool, &table_name, &definition, entry, &message).await?
+        }
+    };
+
+    DocumentOperation::insert(pool, &operation).await?;
+
+    let document = match document_id {
+        Some(document_id) => fetch_document(pool, &table_name, &definition, &document_id).await?,
+        None => None,
+    };
+
+    // Replayed documents already recorded their history
+    let history = history && is_latest;
+
+    let document = match document {
+        Some(document) if document.is_deleted() => {
+            if history {
+                let received_at = entry_received_at(pool, &operation.entry_hash).await?;
+                insert_history(pool, &definition, &operation, None, received_at).await?;
+            }
+
+            return Ok(None);
+        }
+        Some(document) => document,
+        None => return Ok(None),
+    };
+
+    if history {
+        let received_at = entry_received_at(pool, &operation.entry_hash).await?;
+        insert_history(
+            pool,
+            &definition,
+            &operation,
+            Some(&document.fields),
+            received_at,
+        )
+        .await?;
+    }
+
+    Ok(Some(DocumentChange {
+        created: matches!(message.action(), MessageAction::Create),
+        schema: entry.schema.clone(),
+        document,
+    }))
+}
+
+/// Returns the data required to materialize a stored entry.
+///
+/// Returns `None` when the entry or its payload does not exist anymore.
+async fn load_entry(pool: &Pool, entry_hash: &Hash) -> Result<Option<EntryNotification>> {
+    let entry = match Entry::by_hash(pool, entry_hash).await? {
+        Some(entry) => entry,
+        None => return Ok(None),
+    };
+
+    let message_encoded = match entry.payload_bytes {
+        Some(payload_bytes) => MessageEncoded::new(&payload_bytes)?,
+        None => return Ok(None),
+    };
+
+    let message = Message::from(&message_encoded);
+
+    Ok(Some(EntryNotification {
+        author: entry.author,
+        entry_encoded: entry.entry_bytes,
+        entry_hash: entry.entry_hash,
+        log_id: entry.log_id,
+        message_encoded: message_encoded.as_str().to_owned(),
+        schema: message.schema().clone(),
+        seq_num: entry.seq_num,
+    }))
+}
+
+/// Materialize the entry of a task and update its status.
+///
+/// Created and updated documents are emitted as node events. Returns true when the entry was
+/// materialized successfully.
+#[instrument(
+    skip_all,
+    fields(entry_hash = task.entry_hash.as_str(), retries = task.retries)
+)]
+async fn process_task(
+    pool: &Pool,
+    task: &MaterializerTask,
+    events: Option<&Events>,
+    history: bool,
+) -> Result<bool> {
+    let entry = load_entry(pool, &task.entry_hash).await?;
+
+    let result = match &entry {
+        Some(entry) => materialize(pool, entry, history).await,
+        None => {
+            warn!("Ignore task of missing entry {}", task.entry_hash.as_str());
+            Ok(None)
+        }
+    };
+
+    match result {
+        Ok(change) => {
+            MaterializerTask::update(pool, &task.entry_hash, TASK_DONE, task.retries).await?;
+
+            if let (Some(events), Some(change)) = (events, change) {
+                events.emit(NodeEvent::DocumentMaterialized(Arc::new(change)));
+            }
+
+            // Announce encrypted messages so key management services can pick them up
+            if let (Some(events), Some(entry)) = (events, entry) {
+                if entry.schema.as_str() == ENCRYPTED_MESSAGE_SCHEMA {
+                    if let Some(message) =
+                        EncryptedMessage::by_entry_hash(pool, &entry.entry_hash).await?
+                    {
+                        events.emit(NodeEvent::EncryptedMessageStored(Arc::new(message)));
+                    }
+                }
+            }
+
+            Ok(true)
+        }
+        Err(err) => {
+            let retries = task.retries + 1;
+
+            // Give up when entry failed too often, otherwise keep it pending to retry it later
+            if retries >= MAX_RETRIES {
+                error!(
+                    "Could not materialize entry {}, giving up: {}",
+                    task.entry_hash.as_str(),
+                    err
+                );
+                MaterializerTask::update(pool, &task.entry_hash, TASK_FAILED, retries).await?;
+            } else {
+                warn!(
+                    "Could not materialize entry {}, retry later: {}",
+                    task.entry_hash.as_str(),
+                    err
+                );
+                MaterializerTask::update(pool, &task.entry_hash, &task.status, retries).await?;
+            }
+
+            Ok(false)
+        }
+    }
+}
+
+/// Process all pending materializer tasks.
+///
+/// Every failed task is only attempted once per call, so they can be retried later.
+pub async fn process_pending_tasks(pool: &Pool, history: bool) -> Result<()> {
+    process_tasks(pool, None, history).await
+}
+
+/// Process all pending materializer tasks, emitting created and updated documents as node events.
+async fn process_tasks(pool: &Pool, events: Option<&Events>, history: bool) -> Result<()> {
+    loop {
+        let tasks = MaterializerTask::pending(pool, TASK_BATCH_SIZE).await?;
+        let mut materialized = 0;
+
+        for task in tasks.iter() {
+            if process_task(pool, task, events, history).await? {
+                materialized += 1;
+            }
+        }
+
+        // Stop when only failed tasks remained
+        if materialized == 0 {
+            return Ok(());
+        }
+
+        // Stop when there are no more tasks, unless registered schema definitions added new ones
+        if tasks.len() < TASK_BATCH_SIZE as usize
+            && MaterializerTask::unattempted_count(pool).await? == 0
+        {
+            return Ok(());
+        }
+    }
+}
+
+/// Drop the documents and history tables of a schema.
+async fn drop_schema_tables(pool: &Pool, schema: &Hash) -> Result<()> {
+    DropTable::new(&schema_table_name(schema))
+        .build()
+        .execute(pool)
+        .await?;
+
+    DropTable::new(&history_table_name(schema))
+        .build()
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+/// Returns the schemas whose documents can not be materialized again from the stored entries,
+/// as entries or payloads of their applied operations were pruned.
+async fn pruned_schemas(pool: &Pool) -> Result<Vec<Hash>> {
+    let mut schemas = DocumentOperation::pruned_schemas(pool).await?;
+
+    // Operations are only kept for documents, other schemas can not tell which entries applied
+    for schema in Entry::pruned_schemas(pool).await? {
+        if (is_schema_definition(&schema) || is_system_schema(&schema))
+            && !schemas.contains(&schema)
+        {
+            schemas.push(schema);
+        }
+    }
+
+    Ok(schemas)
+}
+
+/// Drop all materialized documents and schema definitions and materialize all stored entries
+/// again.
+///
+/// Views are not rebuilt when entries or payloads of any schema were pruned, as their documents
+/// would be lost. This should only be used while the node is not running.
+pub async fn rebuild_views(pool: &Pool, history: bool) -> Result<()> {
+    if let Some(schema) = pruned_schemas(pool).await?.first() {
+        return Err(MaterializerError::PrunedSchema(schema.as_str().to_owned()).into());
+    }
+    // Drop all documents and history tables
+    for schema in SchemaField::schemas(pool).await? {
+        drop_schema_tables(pool, &schema).await?;
+    }
+
+    // Schema definitions and system schemas are materialized again from their messages
+    SchemaDefinition::delete_all(pool).await?;
+    SchemaField::delete_all(pool).await?;
+    KeyGroup::delete_all(pool).await?;
+    AuthorProfile::delete_all(pool).await?;
+    EncryptedMessage::delete_all(pool).await?;
+    DocumentOperation::delete_all(pool).await?;
+
+    // Register a new task for every entry and materialize them all
+    MaterializerTask::delete_all(pool).await?;
+    let count = MaterializerTask::insert_missing(pool).await?;
+    info!("Rebuild views from {} entries", count);
+    process_pending_tasks(pool, history).await?;
+
+    Ok(())
+}
+
+/// Run materializer service until the sending side of the channel got closed or the shutdown
+/// future resolves.
+///
+/// Every incoming entry is registered as a persisted task before it gets materialized, pending
+/// tasks of failed entries are retried in an interval. On shutdown all received entries are
+/// materialized before the service stops.
+///
+/// Created and updated documents are emitted as `DocumentMaterialized` node events, all changes
+/// are recorded in the history tables when `history` is enabled.
+pub async fn start_materializer(
+    pool: Pool,
+    receiver: Receiver<EntryNotification>,
+    events: Events,
+    history: bool,
+    shutdown: impl Future<Output = ()>,
+) -> Result<()> {
+    let events = Some(&events);
+
+    // Catch up with entries which were stored while the materializer was not running
+    MaterializerTask::insert_missing(&pool).await?;
+    process_tasks(&pool, events, history).await?;
+
+    let shutdown = shutdown.fuse();
+    pin_mut!(shutdown);
+
+    loop {
+        select! {
+            result = timeout(RETRY_INTERVAL, receiver.recv()).fuse() => match result {
+                // Register task for new entry
+                Ok(Ok(entry)) => {
+                    MaterializerTask::insert(&pool, &entry.entry_hash).await?;
+                }
+                // Channel got closed, stop service
+                Ok(Err(_)) => return Ok(()),
+                // No new entries arrived, retry pending tasks
+                Err(_) => (),
+            },
+            _ = shutdown => {
+                // Drain queue before stopping service
+                while let Ok(entry) = receiver.try_recv() {
+                    MaterializerTask::insert(&pool, &entry.entry_hash).await?;
+                }
+
+                process_tasks(&pool, events, history).await?;
+                return Ok(());
+            }
+        }
+
+        process_tasks(&pool, events, history).await?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::{Author, KeyPair};
+    use p2panda_rs::message::{Message, MessageEncoded, MessageFields, MessageValue};
+    use sqlx::{query, Row};
+
+    use super::{
+        aggregate_documents, causal_order, document_count, field_columns, get_document,
+        get_document_version, get_documents, history_table_name, is_valid_field_name, materialize,
+        plan_migration, process_pending_tasks, query_documents, rebuild_views, schema_table_name,
+        table_columns, DocumentAggregation, DocumentGroup, DocumentQuery, DocumentVersion,
+        FieldAggregate, FieldFilter, MAX_RETRIES, SCHEMA_DEFINITION_SCHEMA,
+    };
+
+    use crate::db::models::{DocumentOperation, Entry, MaterializerTask, SchemaField};
+    use crate::db::query_builder::{Aggregate, Operator, Order, Select, Value};
+    use crate::db::{Pool, SqlStorage};
+    use crate::rpc::{self, EntryNotification, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry_with_message, define_test_schema, initialize_db, random_entry_hash,
+    };
+
+    fn fields(value: &str) -> MessageFields {
+        let mut fields = MessageFields::new();
+        fields
+            .add("message", MessageValue::Text(value.to_owned()))
+            .unwrap();
+        fields
+    }
+
+    fn notification(
+        entry: &EntrySigned,
+        message: &MessageEncoded,
+        schema: &Hash,
+        seq_num: i64,
+    ) -> EntryNotification {
+        EntryNotification {
+            author: entry.author(),
+            entry_encoded: entry.as_str().to_owned(),
+            entry_hash: entry.hash(),
+            log_id: LogId::new(1),
+            message_encoded: message.as_str().to_owned(),
+            schema: schema.clone(),
+            seq_num: SeqNum::new(seq_num).unwrap(),
+        }
+    }
+
+    fn definition_fields(definition: &[(&str, &str)]) -> MessageFields {
+        let mut fields = MessageFields::new();
+        for (name, field_type) in definition {
+            fields
+                .add(name, MessageValue::Text((*field_type).to_owned()))
+                .unwrap();
+        }
+        fields
+    }
+
+    async fn store_entry(pool: &Pool, entry: &EntrySigned, message: &MessageEncoded, seq_num: i64) {
+        Entry::insert(
+            pool,
+            &entry.author(),
+            entry,
+            &entry.hash(),
+            &LogId::new(1),
+            Some(message),
+            &message.hash(),
+            &SeqNum::new(seq_num).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        MaterializerTask::insert(pool, &entry.hash()).await.unwrap();
+    }
+
+    #[async_std::test]
+    async fn create_update_and_delete() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        // Create document
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let change = materialize(
+            &pool,
+            &notification(&entry_1, &message_1, &schema, 1),
+            false,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(change.created);
+
+        let document_id = entry_1.hash();
+        let document = get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Hello".to_owned()))
+        );
+
+        // Update document
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Panda")).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        let change = materialize(
+            &pool,
+            &notification(&entry_2, &message_2, &schema, 2),
+            false,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(!change.created);
+        assert_eq!(change.document.seq_num, 2);
+
+        let document = get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.seq_num, 2);
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Panda".to_owned()))
+        );
+
+        // Outdated updates are ignored
+        let change = materialize(
+            &pool,
+            &notification(&entry_2, &message_2, &schema, 1),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(change.is_none());
+
+        let document = get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.seq_num, 2);
+
+        // Delete document
+        let message = Message::new_delete(schema.clone(), document_id.clone()).unwrap();
+        let (entry_3, message_3) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_2),
+            &SeqNum::new(3).unwrap(),
+        );
+        let change = materialize(
+            &pool,
+            &notification(&entry_3, &message_3, &schema, 3),
+            false,
+        )
+        .await
+        .unwrap();
+        assert!(change.is_none());
+
+        let document = get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap();
+        assert!(document.is_none());
+
+        // Deleted documents are kept as tombstones
+        let mut query = DocumentQuery {
+            filters: Vec::new(),
+            order_by: None,
+            order: Order::Asc,
+            limit: 10,
+            offset: 0,
+            after: None,
+            include_deleted: false,
+        };
+        assert!(query_documents(&pool, &schema, &query)
+            .await
+            .unwrap()
+            .is_empty());
+
+        query.include_deleted = true;
+        let documents = query_documents(&pool, &schema, &query).await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].seq_num, 3);
+        assert_eq!(
+            documents[0].deleted_by.as_deref(),
+            Some(entry_3.author().as_str())
+        );
+        assert!(documents[0].deleted_at.is_some());
+    }
+
+    #[async_std::test]
+    async fn record_document_history() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let document_id = entry_1.hash();
+
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Panda")).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        let message = Message::new_delete(schema.clone(), document_id.clone()).unwrap();
+        let (entry_3, message_3) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_2),
+            &SeqNum::new(3).unwrap(),
+        );
+
+        for (seq_num, (entry, message)) in [
+            (&entry_1, &message_1),
+            (&entry_2, &message_2),
+            (&entry_3, &message_3),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let notification = notification(entry, message, &schema, seq_num as i64 + 1);
+            materialize(&pool, &notification, true).await.unwrap();
+        }
+
+        // Every change is recorded with the state of the document after it
+        let rows = Select::new(&history_table_name(&schema))
+            .column("seq_num")
+            .column("deleted")
+            .column("message")
+            .filter("document_id", document_id.as_str())
+            .order_by("seq_num", Order::Asc)
+            .build()
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        let history: Vec<(i64, bool, Option<String>)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get("seq_num").unwrap(),
+                    row.try_get("deleted").unwrap(),
+                    row.try_get("message").unwrap(),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            history,
+            vec![
+                (1, false, Some("Hello".to_owned())),
+                (2, false, Some("Panda".to_owned())),
+                (3, true, None),
+            ]
+        );
+
+        // Earlier states are reconstructed from the history
+        let document = get_document_version(
+            &pool,
+            &schema,
+            document_id.as_str(),
+            DocumentVersion::SeqNum(1),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(document.entry_hash, entry_1.hash().as_str());
+        assert_eq!(document.author, entry_1.author().as_str());
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Hello".to_owned()))
+        );
+
+        let document = get_document_version(
+            &pool,
+            &schema,
+            document_id.as_str(),
+            DocumentVersion::SeqNum(2),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Panda".to_owned()))
+        );
+
+        // Deleted documents and documents which did not exist yet have no state
+        for version in [DocumentVersion::SeqNum(3), DocumentVersion::Timestamp(0)].iter() {
+            let document = get_document_version(&pool, &schema, document_id.as_str(), *version)
+                .await
+                .unwrap();
+            assert!(document.is_none());
+        }
+    }
+
+    #[async_std::test]
+    async fn ignore_unknown_documents() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let document_id = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        let message = Message::new_update(schema.clone(), document_id, fields("Panda")).unwrap();
+        let (entry, message) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        assert!(
+            materialize(&pool, &notification(&entry, &message, &schema, 1), false)
+                .await
+                .is_ok()
+        );
+    }
+
+    #[async_std::test]
+    async fn read_without_creating_tables() {
+        let pool = initialize_db().await;
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let document_id = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        // Nothing was materialized yet, readers treat the missing tables as empty
+        assert!(get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(get_document_version(
+            &pool,
+            &schema,
+            document_id.as_str(),
+            DocumentVersion::SeqNum(1)
+        )
+        .await
+        .unwrap()
+        .is_none());
+        assert_eq!(document_count(&pool, &schema).await.unwrap(), 0);
+
+        let query = DocumentQuery {
+            filters: Vec::new(),
+            order_by: None,
+            order: Order::Asc,
+            limit: 10,
+            offset: 0,
+            after: None,
+            include_deleted: true,
+        };
+        assert!(query_documents(&pool, &schema, &query)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let aggregation = DocumentAggregation {
+            filters: Vec::new(),
+            group_by: None,
+            aggregates: vec![FieldAggregate {
+                function: Aggregate::Count,
+                field: None,
+            }],
+        };
+        assert!(aggregate_documents(&pool, &schema, &aggregation)
+            .await
+            .unwrap()
+            .is_empty());
+
+        assert!(table_columns(&pool, &schema_table_name(&schema))
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(table_columns(&pool, &history_table_name(&schema))
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[async_std::test]
+    async fn register_schema_definitions() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let definition_schema = Hash::new(SCHEMA_DEFINITION_SCHEMA).unwrap();
+        let log_id = LogId::new(1);
+
+        // Definition message registers a new schema named after its entry hash
+        let message = Message::new_create(
+            definition_schema.clone(),
+            definition_fields(&[("message", "str")]),
+        )
+        .unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        assert!(materialize(
+            &pool,
+            &notification(&entry_1, &message_1, &definition_schema, 1),
+            false
+        )
+        .await
+        .unwrap()
+        .is_none());
+
+        let schema = entry_1.hash();
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (document_entry, document_message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        assert!(materialize(
+            &pool,
+            &notification(&document_entry, &document_message, &schema, 1),
+            false
+        )
+        .await
+        .unwrap()
+        .is_some());
+
+        // Author of the definition can add new fields
+        let message = Message::new_update(
+            definition_schema.clone(),
+            schema.clone(),
+            definition_fields(&[("title", "str")]),
+        )
+        .unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        materialize(
+            &pool,
+            &notification(&entry_2, &message_2, &definition_schema, 2),
+            false,
+        )
+        .await
+        .unwrap();
+
+        // Other authors can not change the definition
+        let message = Message::new_update(
+            definition_schema.clone(),
+            schema.clone(),
+            definition_fields(&[("views", "int")]),
+        )
+        .unwrap();
+        let (entry_3, message_3) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        materialize(
+            &pool,
+            &notification(&entry_3, &message_3, &definition_schema, 1),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let definition = SchemaField::by_schema(&pool, &schema).await.unwrap();
+        let names: Vec<&str> = definition.iter().map(|field| field.name.as_str()).collect();
+        assert_eq!(names, vec!["message", "title"]);
+
+        // Existing documents have no value for the new field
+        let document = get_document(&pool, &schema, document_entry.hash().as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(document.fields.get("title").is_none());
+
+        // Types of existing fields can not be changed and need to be known
+        for field_type in ["int", "panda"].iter() {
+            let message = Message::new_update(
+                definition_schema.clone(),
+                schema.clone(),
+                definition_fields(&[("title", *field_type)]),
+            )
+            .unwrap();
+            let (entry_4, message_4) = create_test_entry_with_message(
+                &key_pair,
+                &message,
+                &log_id,
+                None,
+                Some(&entry_2),
+                &SeqNum::new(3).unwrap(),
+            );
+            assert!(materialize(
+                &pool,
+                &notification(&entry_4, &message_4, &definition_schema, 3),
+                false
+            )
+            .await
+            .is_err());
+        }
+
+        // Documents of unknown schemas are ignored
+        let unknown_schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let message = Message::new_create(unknown_schema.clone(), fields("Hello")).unwrap();
+        let (entry_5, message_5) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &LogId::new(2),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        assert!(materialize(
+            &pool,
+            &notification(&entry_5, &message_5, &unknown_schema, 1),
+            false
+        )
+        .await
+        .unwrap()
+        .is_none());
+    }
+
+    #[async_std::test]
+    async fn materialize_documents_published_before_definition() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let definition_schema = Hash::new(SCHEMA_DEFINITION_SCHEMA).unwrap();
+        let message = Message::new_create(
+            definition_schema.clone(),
+            definition_fields(&[("message", "str")]),
+        )
+        .unwrap();
+        let (definition_entry, definition_message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        // Document arrives before the definition of its schema
+        let schema = definition_entry.hash();
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry, message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        rpc::store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+
+        MaterializerTask::insert_missing(&pool).await.unwrap();
+        process_pending_tasks(&pool, false).await.unwrap();
+        assert!(get_document(&pool, &schema, entry.hash().as_str())
+            .await
+            .unwrap()
+            .is_none());
+
+        // Document gets materialized as soon as the definition is known
+        rpc::store_entry(
+            &storage,
+            &Subscriptions::new(),
+            &definition_entry,
+            &definition_message,
+        )
+        .await
+        .unwrap();
+
+        MaterializerTask::insert_missing(&pool).await.unwrap();
+        process_pending_tasks(&pool, false).await.unwrap();
+
+        let document = get_document(&pool, &schema, entry.hash().as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Hello".to_owned()))
+        );
+    }
+
+    #[test]
+    fn plan_table_migration() {
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let definition: Vec<SchemaField> = [("message", "str"), ("views", "int")]
+            .iter()
+            .map(|(name, field_type)| SchemaField {
+                schema: schema.clone(),
+                name: name.to_string(),
+                field_type: field_type.to_string(),
+            })
+            .collect();
+
+        // Only missing fields are added, removed fields are kept
+        let columns = vec![
+            "document_id".to_owned(),
+            "message".to_owned(),
+            "old".to_owned(),
+        ];
+        let nullable_columns = field_columns(&definition).unwrap();
+        let statements = plan_migration("documents", &columns, &nullable_columns);
+        assert_eq!(statements.len(), 1);
+        assert_eq!(
+            statements[0].sql(),
+            "ALTER TABLE \"documents\" ADD COLUMN \"views\" BIGINT"
+        );
+
+        let columns = vec!["message".to_owned(), "views".to_owned()];
+        assert!(plan_migration("documents", &columns, &nullable_columns).is_empty());
+    }
+
+    #[async_std::test]
+    async fn all_value_types() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let relation = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        define_test_schema(
+            &pool,
+            &schema,
+            &[
+                ("active", "bool"),
+                ("age", "int"),
+                ("height", "float"),
+                ("name", "str"),
+                ("profile", "relation"),
+            ],
+        )
+        .await;
+
+        let mut fields = MessageFields::new();
+        fields.add("active", MessageValue::Boolean(true)).unwrap();
+        fields.add("age", MessageValue::Integer(28)).unwrap();
+        fields.add("height", MessageValue::Float(1.75)).unwrap();
+        fields
+            .add("name", MessageValue::Text("Panda".to_owned()))
+            .unwrap();
+        fields
+            .add("profile", MessageValue::Relation(relation.clone()))
+            .unwrap();
+
+        let message = Message::new_create(schema.clone(), fields).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        materialize(
+            &pool,
+            &notification(&entry_1, &message_1, &schema, 1),
+            false,
+        )
+        .await
+        .unwrap();
+
+        let document = get_document(&pool, &schema, entry_1.hash().as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.fields.len(), 5);
+        assert_eq!(
+            document.fields.get("active"),
+            Some(&MessageValue::Boolean(true))
+        );
+        assert_eq!(document.fields.get("age"), Some(&MessageValue::Integer(28)));
+        assert_eq!(
+            document.fields.get("height"),
+            Some(&MessageValue::Float(1.75))
+        );
+        assert_eq!(
+            document.fields.get("profile"),
+            Some(&MessageValue::Relation(relation))
+        );
+
+        // Values need to match the type of the schema definition
+        let mut fields = MessageFields::new();
+        fields
+            .add("age", MessageValue::Text("old".to_owned()))
+            .unwrap();
+
+        let message = Message::new_update(schema.clone(), entry_1.hash(), fields).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &LogId::new(1),
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        assert!(materialize(
+            &pool,
+            &notification(&entry_2, &message_2, &schema, 2),
+            false
+        )
+        .await
+        .is_err());
+    }
+
+    #[test]
+    fn causal_order_of_operations() {
+        let document_id = Hash::new(&random_entry_hash()).unwrap();
+        let authors = [
+            "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c",
+            "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a",
+        ];
+
+        let operation = |entry_hash: &Hash, author: &str, seq_num: i64| DocumentOperation {
+            entry_hash: entry_hash.clone(),
+            schema: Hash::new_from_bytes(vec![1, 2, 3]).unwrap(),
+            document_id: document_id.clone(),
+            author: Author::new(author).unwrap(),
+            log_id: LogId::new(1),
+            seq_num: SeqNum::new(seq_num).unwrap(),
+        };
+
+        let mut operations = vec![operation(&document_id, authors[0], 1)];
+        for seq_num in 2..5 {
+            operations.push(operation(
+                &Hash::new(&random_entry_hash()).unwrap(),
+                authors[0],
+                seq_num,
+            ));
+        }
+        for seq_num in 1..4 {
+            operations.push(operation(
+                &Hash::new(&random_entry_hash()).unwrap(),
+                authors[1],
+                seq_num,
+            ));
+        }
+
+        let expected: Vec<Hash> = causal_order(operations.clone(), &document_id)
+            .into_iter()
+            .map(|operation| operation.entry_hash)
+            .collect();
+
+        // The CREATE operation comes first and every log stays in order
+        assert_eq!(expected[0], document_id);
+        for author in authors.iter() {
+            let seq_nums: Vec<i64> = causal_order(operations.clone(), &document_id)
+                .iter()
+                .filter(|operation| operation.author.as_str() == *author)
+                .map(|operation| operation.seq_num.as_i64())
+                .collect();
+            assert!(seq_nums.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+
+        // The order does not depend on the order of the given operations
+        for _ in 0..10 {
+            operations.rotate_left(3);
+            operations.reverse();
+
+            let ordered: Vec<Hash> = causal_order(operations.clone(), &document_id)
+                .into_iter()
+                .map(|operation| operation.entry_hash)
+                .collect();
+            assert_eq!(ordered, expected);
+        }
+    }
+
+    #[async_std::test]
+    async fn concurrent_updates_in_deterministic_order() {
+        let key_pair_a = KeyPair::new();
+        let key_pair_b = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair_a,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let document_id = entry_1.hash();
+
+        // Both authors update the same field concurrently
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Panda")).unwrap();
+        let (entry_a, message_a) = create_test_entry_with_message(
+            &key_pair_a,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Bear")).unwrap();
+        let (entry_b, message_b) = create_test_entry_with_message(
+            &key_pair_b,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        // The update with the higher entry hash is ordered last
+        let expected = if entry_a.hash().as_str() > entry_b.hash().as_str() {
+            "Panda"
+        } else {
+            "Bear"
+        };
+
+        let entries = [
+            (&entry_1, &message_1, 1),
+            (&entry_a, &message_a, 2),
+            (&entry_b, &message_b, 1),
+        ];
+
+        for order in [[0, 1, 2], [2, 1, 0], [1, 0, 2]].iter() {
+            let pool = initialize_db().await;
+            define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+            for index in order.iter() {
+                let (entry, message, seq_num) = entries[*index];
+                store_entry(&pool, entry, message, seq_num).await;
+                materialize(
+                    &pool,
+                    &notification(entry, message, &schema, seq_num),
+                    false,
+                )
+                .await
+                .unwrap();
+            }
+
+            let document = get_document(&pool, &schema, document_id.as_str())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                document.fields.get("message"),
+                Some(&MessageValue::Text(expected.to_owned()))
+            );
+        }
+    }
+
+    /// Returns a concurrent update of another author which is ordered before the given entry.
+    fn concurrent_update(
+        schema: &Hash,
+        document_id: &Hash,
+        fields: MessageFields,
+        before: &EntrySigned,
+    ) -> (EntrySigned, MessageEncoded) {
+        let message = Message::new_update(schema.clone(), document_id.clone(), fields).unwrap();
+
+        loop {
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+
+            if entry.hash().as_str() < before.hash().as_str() {
+                return (entry, message);
+            }
+        }
+    }
+
+    #[async_std::test]
+    async fn replay_and_record_history_again() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        define_test_schema(&pool, &schema, &[("message", "str"), ("title", "str")]).await;
+
+        let mut create_fields = fields("Hello");
+        create_fields
+            .add("title", MessageValue::Text("Pandas".to_owned()))
+            .unwrap();
+        let message = Message::new_create(schema.clone(), create_fields).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let document_id = entry_1.hash();
+
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Panda")).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        let mut title = MessageFields::new();
+        title
+            .add("title", MessageValue::Text("Bears".to_owned()))
+            .unwrap();
+        let (entry_b, message_b) = concurrent_update(&schema, &document_id, title, &entry_2);
+
+        for (entry, message, seq_num) in [
+            (&entry_1, &message_1, 1),
+            (&entry_2, &message_2, 2),
+            (&entry_b, &message_b, 1),
+        ]
+        .iter()
+        {
+            store_entry(&pool, entry, message, *seq_num).await;
+            materialize(
+                &pool,
+                &notification(entry, message, &schema, *seq_num),
+                true,
+            )
+            .await
+            .unwrap();
+        }
+
+        // Every operation from the late one onwards is recorded with its new intermediate state
+        let rows = Select::new(&history_table_name(&schema))
+            .column("entry_hash")
+            .column("message")
+            .column("title")
+            .filter("document_id", document_id.as_str())
+            .build()
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+
+        let mut history: Vec<(String, String, String)> = rows
+            .iter()
+            .map(|row| {
+                (
+                    row.try_get("entry_hash").unwrap(),
+                    row.try_get("message").unwrap(),
+                    row.try_get("title").unwrap(),
+                )
+            })
+            .collect();
+        history.sort();
+
+        let mut expected = vec![
+            (entry_1.hash(), "Hello", "Pandas"),
+            (entry_b.hash(), "Hello", "Bears"),
+            (entry_2.hash(), "Panda", "Bears"),
+        ]
+        .into_iter()
+        .map(|(entry_hash, message, title)| {
+            (
+                entry_hash.as_str().to_owned(),
+                message.to_owned(),
+                title.to_owned(),
+            )
+        })
+        .collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(history, expected);
+    }
+
+    #[async_std::test]
+    async fn merge_late_update_after_pruning() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        define_test_schema(&pool, &schema, &[("message", "str"), ("title", "str")]).await;
+
+        let mut create_fields = fields("Hello");
+        create_fields
+            .add("title", MessageValue::Text("Pandas".to_owned()))
+            .unwrap();
+        let message = Message::new_create(schema.clone(), create_fields).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let document_id = entry_1.hash();
+
+        let mut title = MessageFields::new();
+        title
+            .add("title", MessageValue::Text("Bears".to_owned()))
+            .unwrap();
+        let message = Message::new_update(schema.clone(), document_id.clone(), title).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        for (entry, message, seq_num) in
+            [(&entry_1, &message_1, 1), (&entry_2, &message_2, 2)].iter()
+        {
+            store_entry(&pool, entry, message, *seq_num).await;
+            materialize(
+                &pool,
+                &notification(entry, message, &schema, *seq_num),
+                true,
+            )
+            .await
+            .unwrap();
+        }
+
+        // The payloads of all applied operations get pruned
+        MaterializerTask::delete_all(&pool).await.unwrap();
+        assert_eq!(Entry::prune_payloads(&pool, &schema, 0).await.unwrap(), 2);
+
+        // A concurrent update ordered before the pruned update arrives late
+        let (entry_b, message_b) =
+            concurrent_update(&schema, &document_id, fields("Panda"), &entry_2);
+        store_entry(&pool, &entry_b, &message_b, 1).await;
+        materialize(&pool, &notification(&entry_b, &message_b, &schema, 1), true)
+            .await
+            .unwrap();
+
+        // Fields of pruned operations are kept
+        let document = get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Panda".to_owned()))
+        );
+        assert_eq!(
+            document.fields.get("title"),
+            Some(&MessageValue::Text("Bears".to_owned()))
+        );
+        assert_eq!(document.entry_hash, entry_2.hash().as_str());
+    }
+
+    #[async_std::test]
+    async fn document_version_of_concurrent_updates() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let document_id = entry_1.hash();
+
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Panda")).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        // Another author updates the document concurrently, ordered after the second entry
+        let message =
+            Message::new_update(schema.clone(), document_id.clone(), fields("Bear")).unwrap();
+        let (entry_b, message_b) = loop {
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &log_id,
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+
+            if entry.hash().as_str() > entry_2.hash().as_str() {
+                break (entry, message);
+            }
+        };
+
+        for (entry, message, seq_num) in [
+            (&entry_1, &message_1, 1),
+            (&entry_b, &message_b, 1),
+            (&entry_2, &message_2, 2),
+        ]
+        .iter()
+        {
+            store_entry(&pool, entry, message, *seq_num).await;
+            materialize(
+                &pool,
+                &notification(entry, message, &schema, *seq_num),
+                true,
+            )
+            .await
+            .unwrap();
+        }
+
+        let document = get_document(&pool, &schema, document_id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(document.entry_hash, entry_b.hash().as_str());
+
+        // Versions resolve concurrent changes like the materializer does
+        let version = get_document_version(
+            &pool,
+            &schema,
+            document_id.as_str(),
+            DocumentVersion::SeqNum(2),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(version.entry_hash, entry_b.hash().as_str());
+        assert_eq!(version.fields, document.fields);
+        assert_eq!(version.author, entry_1.author().as_str());
+    }
+
+    #[async_std::test]
+    async fn documents_of_different_schemas() {
+        let pool = initialize_db().await;
+
+        let mut document_ids = Vec::new();
+        for schema in [vec![1, 2, 3], vec![4, 5, 6]].iter() {
+            let schema = Hash::new_from_bytes(schema.clone()).unwrap();
+            define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+            let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            materialize(&pool, &notification(&entry, &message, &schema, 1), false)
+                .await
+                .unwrap();
+
+            document_ids.push((schema, entry.hash()));
+        }
+
+        // Unknown documents are skipped
+        let mut ids: Vec<Hash> = document_ids.iter().map(|(_, id)| id.clone()).collect();
+        ids.push(Hash::new(&random_entry_hash()).unwrap());
+
+        let documents = get_documents(&pool, &ids).await.unwrap();
+        assert_eq!(documents.len(), 2);
+
+        for (schema, document_id) in document_ids.iter() {
+            assert!(documents.iter().any(|(document_schema, document)| {
+                document_schema == schema && document.document_id == document_id.as_str()
+            }));
+        }
+    }
+
+    #[async_std::test]
+    async fn retry_failed_tasks() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        // First document matches the schema definition
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&pool, &entry_1, &message_1, 1).await;
+
+        // Second document does not match the schema definition and fails
+        let mut invalid_fields = MessageFields::new();
+        invalid_fields
+            .add("message", MessageValue::Integer(12))
+            .unwrap();
+
+        let message = Message::new_create(schema.clone(), invalid_fields).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        store_entry(&pool, &entry_2, &message_2, 2).await;
+
+        process_pending_tasks(&pool, false).await.unwrap();
+
+        let document = get_document(&pool, &schema, entry_1.hash().as_str())
+            .await
+            .unwrap();
+        assert!(document.is_some());
+
+        // Failed task stays pending until it reached the maximum number of retries
+        let tasks = MaterializerTask::pending(&pool, 10).await.unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].retries, 1);
+
+        for _ in 1..MAX_RETRIES {
+            process_pending_tasks(&pool, false).await.unwrap();
+        }
+
+        assert!(MaterializerTask::pending(&pool, 10)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[async_std::test]
+    async fn rebuild_from_entries() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+
+        let definition_schema = Hash::new(SCHEMA_DEFINITION_SCHEMA).unwrap();
+        let message = Message::new_create(
+            definition_schema.clone(),
+            definition_fields(&[("message", "str")]),
+        )
+        .unwrap();
+        let (definition_entry, definition_message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        rpc::store_entry(
+            &storage,
+            &Subscriptions::new(),
+            &definition_entry,
+            &definition_message,
+        )
+        .await
+        .unwrap();
+
+        let key_pair = KeyPair::new();
+        let schema = definition_entry.hash();
+
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry, message) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        rpc::store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+
+        MaterializerTask::insert_missing(&pool).await.unwrap();
+        process_pending_tasks(&pool, false).await.unwrap();
+
+        query("UPDATE entries SET received_at = 1000")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Schema definitions and documents are materialized again after dropping all views
+        rebuild_views(&pool, true).await.unwrap();
+
+        let document = get_document(&pool, &schema, entry.hash().as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            document.fields.get("message"),
+            Some(&MessageValue::Text("Hello".to_owned()))
+        );
+        assert!(MaterializerTask::pending(&pool, 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // History is recorded with the time the entries were received
+        for (timestamp, exists) in [(999, false), (1000, true)].iter() {
+            let document = get_document_version(
+                &pool,
+                &schema,
+                entry.hash().as_str(),
+                DocumentVersion::Timestamp(*timestamp),
+            )
+            .await
+            .unwrap();
+            assert_eq!(document.is_some(), *exists);
+        }
+
+        // Views are not rebuilt once payloads of materialized entries were pruned
+        assert_eq!(Entry::prune_payloads(&pool, &schema, 0).await.unwrap(), 1);
+        assert!(rebuild_views(&pool, true).await.is_err());
+
+        let document = get_document(&pool, &schema, entry.hash().as_str())
+            .await
+            .unwrap();
+        assert!(document.is_some());
+    }
+
+    #[async_std::test]
+    async fn query_with_filters_and_ordering() {
+        let pool = initialize_db().await;
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        for value in ["a", "b", "c"].iter() {
+            let message = Message::new_create(schema.clone(), fields(value)).unwrap();
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            materialize(&pool, &notification(&entry, &message, &schema, 1), false)
+                .await
+                .unwrap();
+        }
+
+        let mut query = DocumentQuery {
+            filters: vec![FieldFilter {
+                field: "message".to_owned(),
+                operator: Operator::Gte,
+                value: Value::Text("b".to_owned()),
+            }],
+            order_by: Some("message".to_owned()),
+            order: Order::Desc,
+            limit: 10,
+            offset: 0,
+            after: None,
+            include_deleted: false,
+        };
+
+        let documents = query_documents(&pool, &schema, &query).await.unwrap();
+        let values: Vec<_> = documents
+            .iter()
+            .map(|document| document.fields.get("message").unwrap())
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                &MessageValue::Text("c".to_owned()),
+                &MessageValue::Text("b".to_owned())
+            ]
+        );
+
+        // Paginate through results
+        query.limit = 1;
+        query.offset = 1;
+        let documents = query_documents(&pool, &schema, &query).await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(
+            documents[0].fields.get("message"),
+            Some(&MessageValue::Text("b".to_owned()))
+        );
+
+        // Paginate with the position of the last document
+        query.offset = 0;
+        query.after = documents[0].position(Some("message"));
+        let documents = query_documents(&pool, &schema, &query).await.unwrap();
+        assert!(documents.is_empty());
+
+        query.filters.clear();
+        let documents = query_documents(&pool, &schema, &query).await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(
+            documents[0].fields.get("message"),
+            Some(&MessageValue::Text("a".to_owned()))
+        );
+
+        // Filters need to match the type of the field
+        query.after = None;
+        query.filters = vec![FieldFilter {
+            field: "message".to_owned(),
+            operator: Operator::Gte,
+            value: Value::Text("b".to_owned()),
+        }];
+        query.filters[0].value = Value::Integer(1);
+        assert!(query_documents(&pool, &schema, &query).await.is_err());
+
+        // Only known fields can be used
+        query.filters.clear();
+        query.order_by = Some("unknown".to_owned());
+        assert!(query_documents(&pool, &schema, &query).await.is_err());
+    }
+
+    #[async_std::test]
+    async fn aggregate_with_grouping() {
+        let pool = initialize_db().await;
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        define_test_schema(&pool, &schema, &[("message", "str")]).await;
+
+        for value in ["a", "b", "b"].iter() {
+            let message = Message::new_create(schema.clone(), fields(value)).unwrap();
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            materialize(&pool, &notification(&entry, &message, &schema, 1), false)
+                .await
+                .unwrap();
+        }
+
+        let mut aggregation = DocumentAggregation {
+            filters: Vec::new(),
+            group_by: Some("message".to_owned()),
+            aggregates: vec![
+                FieldAggregate {
+                    function: Aggregate::Count,
+                    field: None,
+                },
+                FieldAggregate {
+                    function: Aggregate::Max,
+                    field: Some("seq_num".to_owned()),
+                },
+            ],
+        };
+
+        let groups = aggregate_documents(&pool, &schema, &aggregation)
+            .await
+            .unwrap();
+        assert_eq!(
+            groups,
+            vec![
+                DocumentGroup {
+                    group: Some(Value::Text("a".to_owned())),
+                    values: vec![Some(Value::Integer(1)), Some(Value::Integer(1))],
+                },
+                DocumentGroup {
+                    group: Some(Value::Text("b".to_owned())),
+                    values: vec![Some(Value::Integer(2)), Some(Value::Integer(1))],
+                }
+            ]
+        );
+
+        // Aggregate all documents matching a filter
+        aggregation.group_by = None;
+        aggregation.filters = vec![FieldFilter {
+            field: "message".to_owned(),
+            operator: Operator::Ne,
+            value: Value::Text("a".to_owned()),
+        }];
+        aggregation.aggregates[1] = FieldAggregate {
+            function: Aggregate::Min,
+            field: Some("message".to_owned()),
+        };
+
+        let groups = aggregate_documents(&pool, &schema, &aggregation)
+            .await
+            .unwrap();
+        assert_eq!(
+            groups,
+            vec![DocumentGroup {
+                group: None,
+                values: vec![Some(Value::Integer(2)), Some(Value::Text("b".to_owned()))],
+            }]
+        );
+
+        // Only documents can be counted without a field
+        aggregation.aggregates[1].field = None;
+        assert!(aggregate_documents(&pool, &schema, &aggregation)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn field_names() {
+        assert!(is_valid_field_name("message"));
+        assert!(is_valid_field_name("created_at2"));
+        assert!(!is_valid_field_name(""));
+        assert!(!is_valid_field_name("2fast"));
+        assert!(!is_valid_field_name("drop\"; --"));
+        assert!(!is_valid_field_name("document_id"));
+    }
+}
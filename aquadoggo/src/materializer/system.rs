@@ -0,0 +1,488 @@
+//! Built-in system schemas for key groups, author profiles and encrypted group messages.
+//!
+//! Messages of system schemas are not materialized as documents, they are kept in dedicated tables
+//! which other parts of the node can query, for example to check permissions or to display the
+//! names of authors.
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::{Message, MessageAction, MessageValue};
+use tracing::{debug, warn};
+
+use crate::db::models::{
+    AuthorProfile, EncryptedMessage, KeyGroup, KeyGroupMembership, SchemaField,
+};
+use crate::db::Pool;
+use crate::errors::Result;
+use crate::materializer::{validate_message, MaterializerError};
+use crate::rpc::EntryNotification;
+
+/// Hash of the system schema of key groups.
+///
+/// CREATE messages create a new key group with a `name`, the hash of their entry is the id of the
+/// key group. Only the owner of a key group can rename it with UPDATE or remove it with DELETE
+/// messages.
+pub const KEY_GROUP_SCHEMA: &str = "00401036b6e4fc01564827bc673beea48aa3621a92c1a66debf2ffb0d3d93811928d9e2a2a2317943cd15d36795276ad51e3a74d2226750a3f492c2a66fe5156018d";
+
+/// Hash of the system schema of key group memberships.
+///
+/// CREATE messages of the owner of a key group add the public key given as `member` to the key
+/// group given as `key_group` relation. Memberships are removed with DELETE messages of the owner
+/// or the member.
+pub const KEY_GROUP_MEMBERSHIP_SCHEMA: &str = "004043c30009288452116d6a6541c4d90aa5c747fd7c0900c679b97bb41c31a525518b57aa8f0a119868ca7d8f0d2533042853ef34bca4d1394afb9acfd04d353e92";
+
+/// Hash of the system schema of author profiles.
+///
+/// CREATE and UPDATE messages set the `display_name` of their author, DELETE messages remove the
+/// profile.
+pub const AUTHOR_PROFILE_SCHEMA: &str = "00406dde2eaeccb78516bfa606abf0e20a914441e8f5a8af2efc7d6b776ea392c311e40681e2256d85a6704cebd623dd964f1ab2872686ba7821658ff0c5e5ae478d";
+
+/// Hash of the system schema of messages encrypted for a key group.
+///
+/// CREATE messages hold the `ciphertext` of the encrypted message fields for the key group given as
+/// `key_group` relation. Their plaintext is never materialized, DELETE messages of the author
+/// remove them.
+pub const ENCRYPTED_MESSAGE_SCHEMA: &str = "00401ff23c7bfede6c11ac54ee5d3e896ff0d3b01aec592819043b449b9945a902854363d959d3e6e70d86f8adc5905e2d2046b045c6a8aeb9ca150163843c8cf5d7";
+
+/// Returns the built-in definition of a system schema, `None` for all other schemas.
+pub fn system_schema_definition(schema: &Hash) -> Option<Vec<SchemaField>> {
+    let fields: &[(&str, &str)] = match schema.as_str() {
+        KEY_GROUP_SCHEMA => &[("name", "str")],
+        KEY_GROUP_MEMBERSHIP_SCHEMA => &[("key_group", "relation"), ("member", "str")],
+        AUTHOR_PROFILE_SCHEMA => &[("display_name", "str")],
+        ENCRYPTED_MESSAGE_SCHEMA => &[("ciphertext", "str"), ("key_group", "relation")],
+        _ => return None,
+    };
+
+    let definition = fields
+        .iter()
+        .map(|(name, field_type)| SchemaField {
+            schema: schema.clone(),
+            name: (*name).to_owned(),
+            field_type: (*field_type).to_owned(),
+        })
+        .collect();
+
+    Some(definition)
+}
+
+/// Returns true when this is one of the built-in system schemas.
+pub fn is_system_schema(schema: &Hash) -> bool {
+    system_schema_definition(schema).is_some()
+}
+
+/// Returns the value of a message field.
+fn field<'a>(message: &'a Message, name: &str) -> Option<&'a MessageValue> {
+    message
+        .fields()?
+        .iter()
+        .find(|(field_name, _)| field_name.as_str() == name)
+        .map(|(_, value)| value)
+}
+
+/// Apply a message of a key group to the key groups table.
+async fn materialize_key_group(
+    pool: &Pool,
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<()> {
+    if let MessageAction::Create = message.action() {
+        if let Some(MessageValue::Text(name)) = field(message, "name") {
+            KeyGroup::insert(pool, &entry.entry_hash, &entry.author, name).await?;
+        }
+
+        return Ok(());
+    }
+
+    let key_group = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+    match KeyGroup::by_key_group(pool, key_group).await? {
+        Some(stored) if stored.owner == entry.author => (),
+        _ => {
+            warn!("Ignore change of unknown key group {}", key_group.as_str());
+            return Ok(());
+        }
+    }
+
+    match (message.action(), field(message, "name")) {
+        (MessageAction::Delete, _) => KeyGroup::delete(pool, key_group).await?,
+        (_, Some(MessageValue::Text(name))) => KeyGroup::update_name(pool, key_group, name).await?,
+        _ => debug!("Ignore update of key group without changes"),
+    }
+
+    Ok(())
+}
+
+/// Apply a message of a key group membership to the key group members table.
+async fn materialize_membership(
+    pool: &Pool,
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<()> {
+    match message.action() {
+        MessageAction::Create => {
+            if let (Some(MessageValue::Relation(key_group)), Some(MessageValue::Text(member))) =
+                (field(message, "key_group"), field(message, "member"))
+            {
+                let member = Author::new(member)?;
+                KeyGroupMembership::insert(
+                    pool,
+                    &entry.entry_hash,
+                    key_group,
+                    &member,
+                    &entry.author,
+                )
+                .await?;
+            }
+        }
+        MessageAction::Update => {
+            warn!("Ignore update of key group membership, they can not be changed");
+        }
+        MessageAction::Delete => {
+            let membership = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+            match KeyGroupMembership::by_membership(pool, membership).await? {
+                Some(stored)
+                    if stored.added_by == entry.author || stored.member == entry.author =>
+                {
+                    KeyGroupMembership::delete(pool, membership).await?;
+                }
+                _ => warn!(
+                    "Ignore deletion of unknown key group membership {}",
+                    membership.as_str()
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Apply a message of an author profile to the author profiles table.
+async fn materialize_author_profile(
+    pool: &Pool,
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<()> {
+    match (message.action(), field(message, "display_name")) {
+        (MessageAction::Delete, _) => AuthorProfile::delete(pool, &entry.author).await?,
+        (_, Some(MessageValue::Text(display_name))) => {
+            AuthorProfile::upsert(
+                pool,
+                &entry.author,
+                display_name,
+                &entry.entry_hash,
+                &entry.seq_num,
+            )
+            .await?;
+        }
+        _ => debug!("Ignore update of author profile without changes"),
+    }
+
+    Ok(())
+}
+
+/// Apply a message encrypted for a key group to the encrypted messages table.
+///
+/// Messages are stored even when their author is not a member of the key group (yet), access to
+/// them is checked when they are requested.
+async fn materialize_encrypted_message(
+    pool: &Pool,
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<()> {
+    match message.action() {
+        MessageAction::Create => {
+            if let (Some(MessageValue::Relation(key_group)), Some(MessageValue::Text(ciphertext))) =
+                (field(message, "key_group"), field(message, "ciphertext"))
+            {
+                let encrypted_message = EncryptedMessage {
+                    entry_hash: entry.entry_hash.clone(),
+                    key_group: key_group.clone(),
+                    author: entry.author.clone(),
+                    seq_num: entry.seq_num.clone(),
+                    ciphertext: ciphertext.to_owned(),
+                };
+
+                EncryptedMessage::insert(pool, &encrypted_message).await?;
+            }
+        }
+        MessageAction::Update => {
+            warn!("Ignore update of encrypted message, they can not be changed");
+        }
+        MessageAction::Delete => {
+            let entry_hash = message.id().ok_or(MaterializerError::MissingDocumentId)?;
+
+            match EncryptedMessage::by_entry_hash(pool, entry_hash).await? {
+                Some(stored) if stored.author == entry.author => {
+                    EncryptedMessage::delete(pool, entry_hash).await?;
+                }
+                _ => warn!(
+                    "Ignore deletion of unknown encrypted message {}",
+                    entry_hash.as_str()
+                ),
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Materialize the message of a system schema into its dedicated table.
+///
+/// Messages need to match the built-in definition of their schema.
+pub async fn materialize_system_message(
+    pool: &Pool,
+    entry: &EntryNotification,
+    message: &Message,
+) -> Result<()> {
+    let definition = match system_schema_definition(&entry.schema) {
+        Some(definition) => definition,
+        None => return Ok(()),
+    };
+
+    validate_message(message, &definition)?;
+
+    match entry.schema.as_str() {
+        KEY_GROUP_SCHEMA => materialize_key_group(pool, entry, message).await,
+        KEY_GROUP_MEMBERSHIP_SCHEMA => materialize_membership(pool, entry, message).await,
+        ENCRYPTED_MESSAGE_SCHEMA => materialize_encrypted_message(pool, entry, message).await,
+        _ => materialize_author_profile(pool, entry, message).await,
+    }
+}
+
+/// Remove the rows of system schemas which were created or last set by an entry.
+///
+/// Changes the entry made to rows of other entries, for example removed memberships, are kept.
+pub async fn remove_system_message(pool: &Pool, schema: &Hash, entry_hash: &Hash) -> Result<()> {
+    match schema.as_str() {
+        KEY_GROUP_SCHEMA => KeyGroup::delete(pool, entry_hash).await,
+        KEY_GROUP_MEMBERSHIP_SCHEMA => KeyGroupMembership::delete(pool, entry_hash).await,
+        ENCRYPTED_MESSAGE_SCHEMA => EncryptedMessage::delete(pool, entry_hash).await,
+        AUTHOR_PROFILE_SCHEMA => AuthorProfile::delete_by_entry_hash(pool, entry_hash).await,
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::message::{Message, MessageFields, MessageValue};
+
+    use super::{
+        is_system_schema, materialize_system_message, AUTHOR_PROFILE_SCHEMA,
+        ENCRYPTED_MESSAGE_SCHEMA, KEY_GROUP_MEMBERSHIP_SCHEMA, KEY_GROUP_SCHEMA,
+    };
+
+    use crate::db::models::{AuthorProfile, EncryptedMessage, KeyGroup};
+    use crate::db::Pool;
+    use crate::rpc::EntryNotification;
+    use crate::test_helpers::{create_test_entry_with_message, initialize_db};
+
+    // Materialize a message of a system schema as the given author, returns the entry
+    async fn publish(
+        pool: &Pool,
+        key_pair: &KeyPair,
+        message: &Message,
+        seq_num: i64,
+    ) -> EntrySigned {
+        let (entry, message_encoded) = create_test_entry_with_message(
+            key_pair,
+            message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(seq_num).unwrap(),
+        );
+
+        let notification = EntryNotification {
+            author: entry.author(),
+            entry_encoded: entry.as_str().to_owned(),
+            entry_hash: entry.hash(),
+            log_id: LogId::new(1),
+            message_encoded: message_encoded.as_str().to_owned(),
+            schema: message.schema().clone(),
+            seq_num: SeqNum::new(seq_num).unwrap(),
+        };
+
+        materialize_system_message(pool, &notification, message)
+            .await
+            .unwrap();
+
+        entry
+    }
+
+    fn text_fields(values: &[(&str, &str)]) -> MessageFields {
+        let mut fields = MessageFields::new();
+        for (name, value) in values {
+            fields
+                .add(name, MessageValue::Text((*value).to_owned()))
+                .unwrap();
+        }
+        fields
+    }
+
+    #[test]
+    fn system_schema_hashes() {
+        for schema in [
+            KEY_GROUP_SCHEMA,
+            KEY_GROUP_MEMBERSHIP_SCHEMA,
+            AUTHOR_PROFILE_SCHEMA,
+            ENCRYPTED_MESSAGE_SCHEMA,
+        ]
+        .iter()
+        {
+            assert!(is_system_schema(&Hash::new(schema).unwrap()));
+        }
+
+        assert!(!is_system_schema(
+            &Hash::new_from_bytes(vec![1, 2, 3]).unwrap()
+        ));
+    }
+
+    #[async_std::test]
+    async fn key_groups() {
+        let pool = initialize_db().await;
+
+        let owner = KeyPair::new();
+        let member = KeyPair::new();
+        let key_group_schema = Hash::new(KEY_GROUP_SCHEMA).unwrap();
+        let membership_schema = Hash::new(KEY_GROUP_MEMBERSHIP_SCHEMA).unwrap();
+
+        let message =
+            Message::new_create(key_group_schema.clone(), text_fields(&[("name", "Pandas")]))
+                .unwrap();
+        let key_group = publish(&pool, &owner, &message, 1).await.hash();
+
+        // Member introduces themselves with a profile
+        let message = Message::new_create(
+            Hash::new(AUTHOR_PROFILE_SCHEMA).unwrap(),
+            text_fields(&[("display_name", "Panda")]),
+        )
+        .unwrap();
+        let member_author = publish(&pool, &member, &message, 1).await.author();
+
+        // Owner adds the member
+        let mut fields = text_fields(&[("member", member_author.as_str())]);
+        fields
+            .add("key_group", MessageValue::Relation(key_group.clone()))
+            .unwrap();
+        let message = Message::new_create(membership_schema.clone(), fields).unwrap();
+        let membership = publish(&pool, &owner, &message, 1).await.hash();
+
+        assert_eq!(KeyGroup::members(&pool, &key_group).await.unwrap().len(), 2);
+        assert_eq!(
+            KeyGroup::by_member(&pool, &member_author).await.unwrap(),
+            vec![key_group.clone()]
+        );
+
+        // Other authors can not rename the key group
+        let message = Message::new_update(
+            key_group_schema.clone(),
+            key_group.clone(),
+            text_fields(&[("name", "Bears")]),
+        )
+        .unwrap();
+        publish(&pool, &member, &message, 2).await;
+
+        let stored = KeyGroup::by_key_group(&pool, &key_group)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.name, "Pandas");
+
+        // Members can leave the key group
+        let message = Message::new_delete(membership_schema.clone(), membership).unwrap();
+        publish(&pool, &member, &message, 3).await;
+
+        assert_eq!(KeyGroup::members(&pool, &key_group).await.unwrap().len(), 1);
+    }
+
+    #[async_std::test]
+    async fn author_profiles() {
+        let pool = initialize_db().await;
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new(AUTHOR_PROFILE_SCHEMA).unwrap();
+
+        let message =
+            Message::new_create(schema.clone(), text_fields(&[("display_name", "Panda")])).unwrap();
+        let entry = publish(&pool, &key_pair, &message, 1).await;
+
+        let message = Message::new_update(
+            schema.clone(),
+            entry.hash(),
+            text_fields(&[("display_name", "Red Panda")]),
+        )
+        .unwrap();
+        publish(&pool, &key_pair, &message, 2).await;
+
+        let stored = AuthorProfile::by_author(&pool, &entry.author())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.display_name, "Red Panda");
+
+        // Messages need to match the definition of the system schema
+        let message =
+            Message::new_create(schema.clone(), text_fields(&[("name", "Panda")])).unwrap();
+        let (entry, message_encoded) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(3).unwrap(),
+        );
+        let notification = EntryNotification {
+            author: entry.author(),
+            entry_encoded: entry.as_str().to_owned(),
+            entry_hash: entry.hash(),
+            log_id: LogId::new(1),
+            message_encoded: message_encoded.as_str().to_owned(),
+            schema,
+            seq_num: SeqNum::new(3).unwrap(),
+        };
+        assert!(materialize_system_message(&pool, &notification, &message)
+            .await
+            .is_err());
+    }
+
+    #[async_std::test]
+    async fn encrypted_messages() {
+        let pool = initialize_db().await;
+
+        let author = KeyPair::new();
+        let schema = Hash::new(ENCRYPTED_MESSAGE_SCHEMA).unwrap();
+        let key_group = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        let mut fields = text_fields(&[("ciphertext", "c2VjcmV0")]);
+        fields
+            .add("key_group", MessageValue::Relation(key_group.clone()))
+            .unwrap();
+        let message = Message::new_create(schema.clone(), fields).unwrap();
+        let entry = publish(&pool, &author, &message, 1).await;
+
+        let messages = EncryptedMessage::by_key_group(&pool, &key_group)
+            .await
+            .unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].ciphertext, "c2VjcmV0");
+        assert_eq!(messages[0].author, entry.author());
+
+        // Only the author can delete the message
+        let message = Message::new_delete(schema.clone(), entry.hash()).unwrap();
+        publish(&pool, &KeyPair::new(), &message, 1).await;
+        assert!(EncryptedMessage::by_entry_hash(&pool, &entry.hash())
+            .await
+            .unwrap()
+            .is_some());
+
+        publish(&pool, &author, &message, 2).await;
+        assert!(EncryptedMessage::by_key_group(&pool, &key_group)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+}
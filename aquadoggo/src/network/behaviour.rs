@@ -0,0 +1,160 @@
+use std::iter;
+
+use anyhow::{anyhow, Result};
+use libp2p::gossipsub::{
+    Gossipsub, GossipsubConfigBuilder, GossipsubEvent, IdentTopic, MessageAuthenticity,
+    ValidationMode,
+};
+use libp2p::identify::{Identify, IdentifyConfig, IdentifyEvent};
+use libp2p::identity::Keypair;
+use libp2p::kad::record::store::MemoryStore;
+use libp2p::kad::{Kademlia, KademliaConfig, KademliaEvent};
+use libp2p::mdns::{Mdns, MdnsConfig, MdnsEvent};
+use libp2p::ping::{Ping, PingConfig, PingEvent};
+use libp2p::relay::Relay;
+use libp2p::request_response::{
+    ProtocolSupport, RequestResponse, RequestResponseConfig, RequestResponseEvent,
+};
+use libp2p::swarm::toggle::Toggle;
+use libp2p::{NetworkBehaviour, PeerId};
+
+use crate::network::gossip::ENTRIES_TOPIC;
+use crate::network::replication::{
+    ReplicationCodec, ReplicationProtocol, SyncRequest, SyncResponse,
+};
+
+/// Protocol name of the Kademlia DHT, this separates it from other libp2p networks.
+const KADEMLIA_PROTOCOL_NAME: &[u8] = b"/aquadoggo/kad/1.0.0";
+
+/// Protocol version announced to other peers, used to only keep connections to aquadoggo nodes.
+pub const PROTOCOL_VERSION: &str = "/aquadoggo/0.1.0";
+
+/// Events emitted by the network behaviour of the node.
+#[derive(Debug)]
+pub enum BehaviourEvent {
+    Gossipsub(GossipsubEvent),
+    Identify(IdentifyEvent),
+    Kademlia(KademliaEvent),
+    Mdns(MdnsEvent),
+    Ping(PingEvent),
+    Relay,
+    Replication(RequestResponseEvent<SyncRequest, SyncResponse>),
+}
+
+impl From<GossipsubEvent> for BehaviourEvent {
+    fn from(event: GossipsubEvent) -> Self {
+        BehaviourEvent::Gossipsub(event)
+    }
+}
+
+impl From<IdentifyEvent> for BehaviourEvent {
+    fn from(event: IdentifyEvent) -> Self {
+        BehaviourEvent::Identify(event)
+    }
+}
+
+impl From<KademliaEvent> for BehaviourEvent {
+    fn from(event: KademliaEvent) -> Self {
+        BehaviourEvent::Kademlia(event)
+    }
+}
+
+impl From<MdnsEvent> for BehaviourEvent {
+    fn from(event: MdnsEvent) -> Self {
+        BehaviourEvent::Mdns(event)
+    }
+}
+
+impl From<PingEvent> for BehaviourEvent {
+    fn from(event: PingEvent) -> Self {
+        BehaviourEvent::Ping(event)
+    }
+}
+
+// Relay behaviour does not emit any events
+impl From<()> for BehaviourEvent {
+    fn from(_: ()) -> Self {
+        BehaviourEvent::Relay
+    }
+}
+
+impl From<RequestResponseEvent<SyncRequest, SyncResponse>> for BehaviourEvent {
+    fn from(event: RequestResponseEvent<SyncRequest, SyncResponse>) -> Self {
+        BehaviourEvent::Replication(event)
+    }
+}
+
+/// Network behaviour combining all protocols spoken by the node.
+#[derive(NetworkBehaviour)]
+#[behaviour(out_event = "BehaviourEvent", event_process = false)]
+pub struct Behaviour {
+    /// Announce new entries to connected peers.
+    pub gossipsub: Gossipsub,
+
+    /// Exchange protocol version and listen addresses with connected peers.
+    pub identify: Identify,
+
+    /// Discover nodes providing entries of a schema across the internet.
+    pub kademlia: Kademlia<MemoryStore>,
+
+    /// Discover other nodes on the local network, can be disabled.
+    pub mdns: Toggle<Mdns>,
+
+    /// Check if connected peers are still alive.
+    pub ping: Ping,
+
+    /// Forward traffic between nodes which can not connect directly, can be disabled.
+    pub relay: Toggle<Relay>,
+
+    /// Replicate entries with other nodes.
+    pub replication: RequestResponse<ReplicationCodec>,
+}
+
+impl Behaviour {
+    /// Returns the network behaviour of the node.
+    ///
+    /// The relay behaviour is created together with the transport as both share the relayed
+    /// connections.
+    pub async fn new(keypair: &Keypair, enable_mdns: bool, relay: Option<Relay>) -> Result<Self> {
+        let mdns = if enable_mdns {
+            Some(Mdns::new(MdnsConfig::default()).await?)
+        } else {
+            None
+        };
+
+        let peer_id = PeerId::from(keypair.public());
+        let mut kademlia_config = KademliaConfig::default();
+        kademlia_config.set_protocol_name(KADEMLIA_PROTOCOL_NAME);
+
+        // Only accept announcements signed by the peer which published them
+        let gossipsub_config = GossipsubConfigBuilder::default()
+            .validation_mode(ValidationMode::Strict)
+            .build()
+            .map_err(|err| anyhow!(err))?;
+        let mut gossipsub = Gossipsub::new(
+            MessageAuthenticity::Signed(keypair.clone()),
+            gossipsub_config,
+        )
+        .map_err(|err| anyhow!(err))?;
+        gossipsub
+            .subscribe(&IdentTopic::new(ENTRIES_TOPIC))
+            .map_err(|err| anyhow!("{:?}", err))?;
+
+        Ok(Self {
+            gossipsub,
+            identify: Identify::new(IdentifyConfig::new(
+                PROTOCOL_VERSION.to_owned(),
+                keypair.public(),
+            )),
+            kademlia: Kademlia::with_config(peer_id, MemoryStore::new(peer_id), kademlia_config),
+            mdns: Toggle::from(mdns),
+            ping: Ping::new(PingConfig::new().with_keep_alive(true)),
+            relay: Toggle::from(relay),
+            replication: RequestResponse::new(
+                ReplicationCodec,
+                iter::once((ReplicationProtocol, ProtocolSupport::Full)),
+                RequestResponseConfig::default(),
+            ),
+        })
+    }
+}
@@ -0,0 +1,95 @@
+use anyhow::Result;
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::MessageEncoded;
+use serde::{Deserialize, Serialize};
+
+use crate::rpc::EntryNotification;
+
+/// Gossip topic on which nodes announce newly published entries.
+pub const ENTRIES_TOPIC: &str = "/aquadoggo/entries/1.0.0";
+
+/// Announcement of a new entry sent to connected peers.
+///
+/// Next to the position of the entry in the author's log it contains the encoded entry and
+/// message, receiving nodes can validate and store it without requesting it first.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryAnnouncement {
+    pub author: Author,
+    pub log_id: LogId,
+    pub seq_num: SeqNum,
+    pub schema: Hash,
+    pub entry_encoded: EntrySigned,
+    pub message_encoded: MessageEncoded,
+}
+
+impl EntryAnnouncement {
+    /// Returns announcement of an entry which got stored on this node.
+    pub fn from_notification(notification: &EntryNotification) -> Result<Self> {
+        Ok(Self {
+            author: notification.author.clone(),
+            log_id: notification.log_id.clone(),
+            seq_num: notification.seq_num.clone(),
+            schema: notification.schema.clone(),
+            entry_encoded: EntrySigned::new(&notification.entry_encoded)?,
+            message_encoded: MessageEncoded::new(&notification.message_encoded)?,
+        })
+    }
+
+    /// Decode announcement received from the gossip topic.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        Ok(serde_json::from_slice(bytes)?)
+    }
+
+    /// Encode announcement to be published on the gossip topic.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(serde_json::to_vec(self)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use super::EntryAnnouncement;
+
+    use crate::rpc::EntryNotification;
+    use crate::test_helpers::create_test_entry;
+
+    #[test]
+    fn encode_and_decode() {
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        let seq_num = SeqNum::new(1).unwrap();
+        let (entry_encoded, message_encoded) =
+            create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
+
+        let notification = EntryNotification {
+            author: entry_encoded.author(),
+            entry_encoded: entry_encoded.as_str().to_owned(),
+            entry_hash: entry_encoded.hash(),
+            log_id: log_id.clone(),
+            message_encoded: message_encoded.as_str().to_owned(),
+            schema: schema.clone(),
+            seq_num: seq_num.clone(),
+        };
+
+        let announcement = EntryAnnouncement::from_notification(&notification).unwrap();
+        let bytes = announcement.to_bytes().unwrap();
+        let decoded = EntryAnnouncement::from_bytes(&bytes).unwrap();
+
+        assert_eq!(decoded.author, entry_encoded.author());
+        assert_eq!(decoded.log_id, log_id);
+        assert_eq!(decoded.seq_num, seq_num);
+        assert_eq!(decoded.schema, schema);
+        assert_eq!(decoded.entry_encoded.hash(), entry_encoded.hash());
+        assert_eq!(decoded.message_encoded.as_str(), message_encoded.as_str());
+
+        assert!(EntryAnnouncement::from_bytes(b"not an announcement").is_err());
+    }
+}
@@ -0,0 +1,61 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::Result;
+use libp2p::identity::{ed25519, Keypair, PublicKey};
+
+/// Filename of the network private key inside the data directory.
+const KEY_FILE_NAME: &str = "network.key";
+
+/// Returns the network key pair of this node.
+///
+/// The private key is stored hex-encoded in the data directory, a new key pair gets generated and
+/// persisted when it does not exist yet. This gives the node the same peer id across restarts.
+pub fn load_or_generate_keypair(base_path: &Path) -> Result<Keypair> {
+    let path = base_path.join(KEY_FILE_NAME);
+
+    if path.exists() {
+        let mut bytes = hex::decode(fs::read_to_string(&path)?.trim())?;
+        let secret_key = ed25519::SecretKey::from_bytes(&mut bytes)?;
+        return Ok(Keypair::Ed25519(secret_key.into()));
+    }
+
+    let keypair = ed25519::Keypair::generate();
+    fs::write(&path, hex::encode(keypair.secret().as_ref()))?;
+
+    Ok(Keypair::Ed25519(keypair))
+}
+
+/// Returns the hex encoded public key of an Ed25519 key pair, `None` for other key types.
+pub fn public_key_hex(keypair: &Keypair) -> Option<String> {
+    match keypair.public() {
+        PublicKey::Ed25519(public_key) => Some(hex::encode(public_key.encode())),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use libp2p::PeerId;
+
+    use super::{load_or_generate_keypair, public_key_hex};
+
+    #[test]
+    fn persist_keypair() {
+        let base_path = std::env::temp_dir().join(format!("aquadoggo-{}", rand::random::<u64>()));
+        fs::create_dir_all(&base_path).unwrap();
+
+        let keypair = load_or_generate_keypair(&base_path).unwrap();
+        let keypair_loaded = load_or_generate_keypair(&base_path).unwrap();
+
+        assert_eq!(
+            PeerId::from(keypair.public()),
+            PeerId::from(keypair_loaded.public())
+        );
+        assert_eq!(public_key_hex(&keypair).unwrap().len(), 64);
+
+        fs::remove_dir_all(&base_path).unwrap();
+    }
+}
@@ -0,0 +1,758 @@
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_std::channel::Receiver;
+use async_std::stream;
+use futures::{select, StreamExt};
+use libp2p::core::muxing::StreamMuxerBox;
+use libp2p::core::transport::{Boxed, OptionalTransport};
+use libp2p::core::upgrade;
+use libp2p::gossipsub::{GossipsubEvent, IdentTopic};
+use libp2p::identify::IdentifyEvent;
+use libp2p::identity::Keypair;
+use libp2p::kad::record::Key;
+use libp2p::kad::{GetProvidersOk, KademliaEvent, QueryResult};
+use libp2p::mdns::MdnsEvent;
+use libp2p::multiaddr::Protocol;
+use libp2p::noise::{Keypair as NoiseKeypair, NoiseConfig, X25519Spec};
+use libp2p::relay::{new_transport_and_behaviour, Relay, RelayConfig};
+use libp2p::request_response::{RequestResponseEvent, RequestResponseMessage, ResponseChannel};
+use libp2p::swarm::{AddressScore, SwarmBuilder, SwarmEvent};
+use libp2p::tcp::TcpConfig;
+use libp2p::yamux::YamuxConfig;
+use libp2p::{Multiaddr, PeerId, Swarm, Transport};
+use p2panda_rs::entry::EntrySigned;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::message::{Message, MessageEncoded};
+use p2panda_rs::Validate;
+use tracing::{debug, info, warn};
+
+use crate::config::Configuration;
+use crate::db::models::Log;
+use crate::db::{EntryStore, Pool, SqlStorage};
+use crate::errors::Error;
+use crate::events::{Events, NodeEvent};
+use crate::policy::EntryPolicy;
+use crate::rpc::{
+    is_fork, store_entry, store_pruned_entry, EntryNotification, PublishEntryError, Subscriptions,
+};
+
+mod behaviour;
+mod gossip;
+mod identity;
+mod replication;
+mod tor;
+
+use behaviour::{Behaviour, BehaviourEvent, PROTOCOL_VERSION};
+use gossip::{EntryAnnouncement, ENTRIES_TOPIC};
+pub use identity::{load_or_generate_keypair, public_key_hex};
+use replication::{
+    ranges_request, sync_request, sync_response, SchemaFilter, SyncRequest, SyncResponse,
+    MAX_SYNC_ENTRIES,
+};
+use tor::TorTransport;
+
+/// Timeout for establishing and upgrading new connections.
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Interval in which the node looks for other nodes providing the same schemas.
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Interval in which the node reconnects to configured peers it lost the connection to.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Returns a TCP transport with encrypted and authenticated connections via Noise and multiplexed
+/// streams via Yamux.
+///
+/// The Noise XX handshake proves that the remote node holds the private key of its peer id, all
+/// following traffic is encrypted. Unencrypted connections are not supported.
+///
+/// With relaying enabled connections can also be established through a relay node, the returned
+/// relay behaviour handles these circuits. When a Tor proxy is given `.onion` addresses are dialed
+/// through it.
+fn build_transport(
+    keypair: &Keypair,
+    enable_relay: bool,
+    tor_proxy: Option<SocketAddr>,
+) -> Result<(Boxed<(PeerId, StreamMuxerBox)>, Option<Relay>)> {
+    let noise_keys = NoiseKeypair::<X25519Spec>::new().into_authentic(keypair)?;
+    let tor = match tor_proxy {
+        Some(proxy) => OptionalTransport::some(TorTransport::new(proxy)),
+        None => OptionalTransport::none(),
+    };
+    let tcp = TcpConfig::new().nodelay(true).or_transport(tor);
+
+    if enable_relay {
+        let (relay_transport, relay) = new_transport_and_behaviour(RelayConfig::default(), tcp);
+
+        let transport = relay_transport
+            .upgrade(upgrade::Version::V1)
+            .authenticate(NoiseConfig::xx(noise_keys).into_authenticated())
+            .multiplex(YamuxConfig::default())
+            .timeout(CONNECTION_TIMEOUT)
+            .boxed();
+
+        return Ok((transport, Some(relay)));
+    }
+
+    let transport = tcp
+        .upgrade(upgrade::Version::V1)
+        .authenticate(NoiseConfig::xx(noise_keys).into_authenticated())
+        .multiplex(YamuxConfig::default())
+        .timeout(CONNECTION_TIMEOUT)
+        .boxed();
+
+    Ok((transport, None))
+}
+
+/// Returns the peer id and address of a multiaddress ending with a `/p2p/<peer id>` component.
+fn parse_peer_address(value: &str) -> Result<(PeerId, Multiaddr)> {
+    let mut address: Multiaddr = value.parse()?;
+
+    match address.pop() {
+        Some(Protocol::P2p(hash)) => {
+            let peer_id =
+                PeerId::from_multihash(hash).map_err(|_| anyhow!("Invalid peer id in address"))?;
+            Ok((peer_id, address))
+        }
+        _ => Err(anyhow!("Address {} is missing a peer id", value)),
+    }
+}
+
+/// Returns the DHT key used to announce nodes storing entries of a schema.
+fn schema_key(schema: &str) -> Key {
+    Key::from(schema.as_bytes().to_vec())
+}
+
+/// Returns true when an entry could not be stored as earlier entries of its log are missing.
+fn is_missing_link(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<Error>() {
+        Some(Error::PublishEntryValidation(err)) => matches!(
+            err,
+            PublishEntryError::BacklinkMissing | PublishEntryError::SkiplinkMissing
+        ),
+        _ => false,
+    }
+}
+
+/// Peer-to-peer networking service of the node.
+///
+/// Every node has its own peer identity, listens for incoming connections and connects to other
+/// aquadoggo nodes it discovered. New entries are announced to connected peers and entries
+/// announced by them get validated and stored on this node.
+///
+/// Nodes replicate entries by exchanging the latest sequence numbers of their logs, each side
+/// then sends the entries the other one is missing. Nodes with many logs first compare digests
+/// per range of authors and only exchange the log heights of differing ranges. This happens
+/// whenever we identify another node and when an announced entry can not be verified as earlier
+/// entries of its log are missing.
+///
+/// All connections are encrypted and peers authenticated by their network key, see
+/// `build_transport`.
+///
+/// Nodes which can not accept connections, for example behind a NAT, can listen via a publicly
+/// reachable relay node which forwards the traffic of other nodes to them.
+///
+/// Nodes can also be run as a Tor onion service and connect to other nodes via their `.onion`
+/// addresses for location privacy.
+///
+/// Operators can limit replication to the schemas they are interested in. These interests are
+/// sent to peers which then only offer entries of matching schemas.
+pub struct Network {
+    events: Events,
+    interests: SchemaFilter,
+    peers: Vec<PeerId>,
+    policy: EntryPolicy,
+    pool: Pool,
+    received: HashSet<String>,
+    schemas: HashSet<String>,
+    storage: SqlStorage,
+    subscriptions: Subscriptions,
+    swarm: Swarm<Behaviour>,
+}
+
+impl Network {
+    /// Create networking service with the node's key pair and start listening for connections.
+    ///
+    /// Entries received from other nodes are published to the given subscriptions, like entries
+    /// published by clients. Connections to peers are emitted as node events.
+    pub async fn new(
+        config: &Configuration,
+        keypair: Keypair,
+        pool: Pool,
+        subscriptions: Subscriptions,
+        events: Events,
+    ) -> Result<Self> {
+        let peer_id = PeerId::from(keypair.public());
+        info!("Local peer id {}", peer_id);
+
+        let enable_relay = config.network_relay || config.network_relay_address.is_some();
+        let tor_proxy = match &config.network_tor_proxy {
+            Some(proxy) => Some(proxy.parse()?),
+            None => None,
+        };
+        let (transport, relay) = build_transport(&keypair, enable_relay, tor_proxy)?;
+        let mut behaviour = Behaviour::new(&keypair, config.network_mdns, relay).await?;
+
+        // Join the DHT via the configured bootstrap nodes
+        for address in config.network_bootstrap_peers.iter() {
+            let (peer_id, address) = parse_peer_address(address)?;
+            behaviour.kademlia.add_address(&peer_id, address);
+        }
+
+        // Remember addresses of configured peers, they are always dialed by their peer id
+        let mut peers = Vec::new();
+        for address in config.network_peers.iter() {
+            let (peer_id, address) = parse_peer_address(address)?;
+            behaviour.kademlia.add_address(&peer_id, address);
+            peers.push(peer_id);
+        }
+
+        let mut swarm = SwarmBuilder::new(transport, behaviour, peer_id)
+            .executor(Box::new(|future| {
+                async_std::task::spawn(future);
+            }))
+            .build();
+
+        let address: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", config.network_port).parse()?;
+        swarm.listen_on(address)?;
+
+        // Tell other nodes about our onion service address
+        if let Some(onion_address) = &config.network_onion_address {
+            swarm.add_external_address(onion_address.parse()?, AddressScore::Infinite);
+        }
+
+        // Become reachable for other nodes through a relay when we can not accept connections
+        if let Some(relay_address) = &config.network_relay_address {
+            // Relay addresses need to contain the peer id of the relay node
+            parse_peer_address(relay_address)?;
+            let address: Multiaddr = relay_address.parse()?;
+            swarm.listen_on(address.with(Protocol::P2pCircuit))?;
+        }
+
+        let policy = EntryPolicy::new(config)?;
+        let mut interests = SchemaFilter::new(
+            &config.replication_schemas,
+            &config.replication_ignored_schemas,
+        )?;
+
+        // Only ask other nodes for entries of schemas this node accepts
+        if !policy.accepted_schemas().is_empty() {
+            interests.allowed = if interests.allowed.is_empty() {
+                policy.accepted_schemas().to_vec()
+            } else {
+                interests
+                    .allowed
+                    .into_iter()
+                    .filter(|schema| policy.accepted_schemas().contains(schema))
+                    .collect()
+            };
+
+            if interests.allowed.is_empty() {
+                bail!("None of the replicated schemas is accepted by this node");
+            }
+        }
+
+        Ok(Self {
+            events,
+            interests,
+            peers,
+            policy,
+            storage: SqlStorage::new(pool.clone()),
+            pool,
+            received: HashSet::new(),
+            schemas: HashSet::new(),
+            subscriptions,
+            swarm,
+        })
+    }
+
+    /// Announce in the DHT that this node stores entries of a schema.
+    fn provide_schema(&mut self, schema: &Hash) {
+        if !self.schemas.insert(schema.as_str().to_owned()) {
+            return;
+        }
+
+        if let Err(err) = self
+            .swarm
+            .behaviour_mut()
+            .kademlia
+            .start_providing(schema_key(schema.as_str()))
+        {
+            warn!("Could not announce schema {}: {:?}", schema.as_str(), err);
+        }
+    }
+
+    /// Announce an entry stored on this node to connected peers.
+    fn announce_entry(&mut self, entry: &EntryNotification) {
+        // Entries received from other nodes are already forwarded by gossipsub
+        if self.received.remove(entry.entry_hash.as_str()) {
+            return;
+        }
+
+        let data = match EntryAnnouncement::from_notification(entry)
+            .and_then(|announcement| announcement.to_bytes())
+        {
+            Ok(data) => data,
+            Err(err) => {
+                warn!("Could not encode announcement: {}", err);
+                return;
+            }
+        };
+
+        // Fails when we are not connected to any other nodes yet
+        if let Err(err) = self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(IdentTopic::new(ENTRIES_TOPIC), data)
+        {
+            debug!(
+                "Could not announce entry {}: {:?}",
+                entry.entry_hash.as_str(),
+                err
+            );
+        }
+    }
+
+    /// Validate and store an entry received from another node, returns false when it was
+    /// already stored before.
+    async fn ingest_entry(
+        &mut self,
+        entry_encoded: &EntrySigned,
+        message_encoded: &MessageEncoded,
+    ) -> Result<bool> {
+        let entry_hash = entry_encoded.hash();
+
+        // Ignore entries of schemas we are not interested in or do not accept
+        message_encoded.validate()?;
+        let message = Message::from(message_encoded);
+        if !self.interests.accepts(message.schema())
+            || self.policy.check_schema(message.schema()).is_err()
+        {
+            return Ok(false);
+        }
+
+        // Ignore entries with messages exceeding our maximum size
+        if let Err(err) = self.policy.check_message_size(message_encoded) {
+            debug!("Ignore entry {}: {}", entry_hash.as_str(), err);
+            return Ok(false);
+        }
+
+        // Ignore entries we already know about
+        if self.storage.entry_by_hash(&entry_hash).await?.is_some() {
+            return Ok(false);
+        }
+
+        // Keep replicating other entries when this one forks its log, the fork got recorded
+        if let Err(err) = store_entry(
+            &self.storage,
+            &self.subscriptions,
+            entry_encoded,
+            message_encoded,
+        )
+        .await
+        {
+            if is_fork(&err) {
+                return Ok(false);
+            }
+
+            return Err(err.into());
+        }
+
+        self.received.insert(entry_hash.as_str().to_owned());
+
+        Ok(true)
+    }
+
+    /// Validate and store an entry received from another node which pruned its payload, returns
+    /// false when it was already stored before.
+    ///
+    /// Entries with their messages received together with it can prove the schema of its log.
+    async fn ingest_pruned_entry(
+        &mut self,
+        entry_encoded: &EntrySigned,
+        schema: &Hash,
+        log_entries: &[(&EntrySigned, &MessageEncoded)],
+    ) -> Result<bool> {
+        // Ignore entries of schemas we are not interested in or do not accept
+        if !self.interests.accepts(schema) || self.policy.check_schema(schema).is_err() {
+            return Ok(false);
+        }
+
+        // Ignore entries we already know about
+        if self
+            .storage
+            .entry_by_hash(&entry_encoded.hash())
+            .await?
+            .is_some()
+        {
+            return Ok(false);
+        }
+
+        // Keep replicating other entries when this one forks its log, the fork got recorded
+        if let Err(err) =
+            store_pruned_entry(&self.storage, entry_encoded, schema, log_entries).await
+        {
+            if is_fork(&err) {
+                return Ok(false);
+            }
+
+            return Err(err.into());
+        }
+
+        Ok(true)
+    }
+
+    /// Store an entry announced by another node.
+    async fn handle_announcement(&mut self, data: &[u8], peer_id: PeerId) -> Result<()> {
+        let announcement = EntryAnnouncement::from_bytes(data)?;
+
+        match self
+            .ingest_entry(&announcement.entry_encoded, &announcement.message_encoded)
+            .await
+        {
+            Ok(true) => {
+                debug!(
+                    "Received entry {} of author {}",
+                    announcement.entry_encoded.hash().as_str(),
+                    announcement.author.as_str()
+                );
+                Ok(())
+            }
+            Ok(false) => Ok(()),
+            // Catch up with the announcing peer when we are missing earlier entries of this log
+            Err(err) if is_missing_link(&err) => self.request_sync(peer_id).await,
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Send the latest sequence numbers of our logs to a peer, it responds with all entries we
+    /// are missing.
+    async fn request_sync(&mut self, peer_id: PeerId) -> Result<()> {
+        let request = sync_request(&self.pool, &self.interests).await?;
+
+        self.swarm
+            .behaviour_mut()
+            .replication
+            .send_request(&peer_id, request);
+
+        Ok(())
+    }
+
+    /// Respond to a peer with all entries it is missing.
+    async fn handle_sync_request(
+        &mut self,
+        peer_id: PeerId,
+        request: SyncRequest,
+        channel: ResponseChannel<SyncResponse>,
+    ) -> Result<()> {
+        let response = sync_response(&self.pool, &request).await?;
+
+        if self
+            .swarm
+            .behaviour_mut()
+            .replication
+            .send_response(channel, response)
+            .is_err()
+        {
+            debug!("Could not respond to sync request of peer {}", peer_id);
+        }
+
+        Ok(())
+    }
+
+    /// Handle a peer's response during replication.
+    async fn handle_sync_response(
+        &mut self,
+        peer_id: PeerId,
+        response: SyncResponse,
+    ) -> Result<()> {
+        match response {
+            // Exchange log heights of all ranges where the peer stores entries we might miss
+            SyncResponse::DifferingRanges { ranges } => {
+                if !ranges.is_empty() {
+                    let request = ranges_request(&self.pool, &self.interests, ranges).await?;
+                    self.swarm
+                        .behaviour_mut()
+                        .replication
+                        .send_request(&peer_id, request);
+                }
+            }
+            SyncResponse::Entries { entries } => {
+                let mut received = 0;
+
+                // Entries with messages prove the schema of pruned entries of the same log
+                let log_entries: Vec<(&EntrySigned, &MessageEncoded)> = entries
+                    .iter()
+                    .filter_map(|entry| {
+                        entry
+                            .message_encoded
+                            .as_ref()
+                            .map(|message_encoded| (&entry.entry_encoded, message_encoded))
+                    })
+                    .collect();
+
+                for entry in entries.iter() {
+                    let ingested = match (&entry.message_encoded, &entry.schema) {
+                        (Some(message_encoded), _) => {
+                            self.ingest_entry(&entry.entry_encoded, message_encoded)
+                                .await
+                        }
+                        (None, Some(schema)) => {
+                            self.ingest_pruned_entry(&entry.entry_encoded, schema, &log_entries)
+                                .await
+                        }
+                        // Entries without message and schema can not be stored
+                        (None, None) => Ok(false),
+                    };
+
+                    match ingested {
+                        Ok(true) => received += 1,
+                        Ok(false) => (),
+                        // Logs of the peer can be sparse, entries outside of the lipmaa
+                        // certificate pool of its later entries can not be linked and are skipped
+                        Err(err) if is_missing_link(&err) => debug!(
+                            "Skip entry {} without links: {}",
+                            entry.entry_encoded.hash().as_str(),
+                            err
+                        ),
+                        Err(err) => return Err(err),
+                    }
+                }
+
+                if received > 0 {
+                    info!("Received {} entries from peer {}", received, peer_id);
+                }
+
+                // Request remaining entries when the peer could not send all of them at once
+                if entries.len() >= MAX_SYNC_ENTRIES {
+                    self.request_sync(peer_id).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Connect to all configured peers we are not connected to yet.
+    ///
+    /// Dialing by peer id makes sure the connection is only established when the remote node
+    /// authenticates with the key of the configured peer id during the Noise handshake.
+    fn connect_peers(&mut self) {
+        for peer_id in self.peers.clone() {
+            if self.swarm.is_connected(&peer_id) {
+                continue;
+            }
+
+            if let Err(err) = self.swarm.dial(&peer_id) {
+                warn!("Could not dial peer {}: {}", peer_id, err);
+            }
+        }
+    }
+
+    /// Refresh the DHT routing table and look for other nodes providing the same schemas.
+    fn discover_peers(&mut self) {
+        let kademlia = &mut self.swarm.behaviour_mut().kademlia;
+
+        // Fails when we do not know any other nodes yet
+        if kademlia.bootstrap().is_err() {
+            debug!("No known peers to bootstrap DHT");
+            return;
+        }
+
+        for schema in self.schemas.iter() {
+            kademlia.get_providers(schema_key(schema));
+        }
+    }
+
+    /// Handle events of the network behaviour.
+    async fn handle_behaviour_event(&mut self, event: BehaviourEvent) {
+        match event {
+            // Store entries announced by other nodes
+            BehaviourEvent::Gossipsub(GossipsubEvent::Message {
+                propagation_source,
+                message,
+                ..
+            }) => {
+                if let Err(err) = self
+                    .handle_announcement(&message.data, propagation_source)
+                    .await
+                {
+                    debug!(
+                        "Could not ingest entry announced by peer {}: {}",
+                        propagation_source, err
+                    );
+                }
+            }
+            // Connect to nodes discovered on the local network
+            BehaviourEvent::Mdns(MdnsEvent::Discovered(peers)) => {
+                for (peer_id, address) in peers {
+                    if self.swarm.is_connected(&peer_id) {
+                        continue;
+                    }
+
+                    debug!("Discovered peer {} at {}", peer_id, address);
+
+                    if let Err(err) = self.swarm.dial_addr(address) {
+                        warn!("Could not dial peer {}: {}", peer_id, err);
+                    }
+                }
+            }
+            // Only keep connections to other aquadoggo nodes and add them to the DHT
+            BehaviourEvent::Identify(IdentifyEvent::Received { peer_id, info }) => {
+                if info.protocol_version != PROTOCOL_VERSION {
+                    debug!(
+                        "Disconnect from peer {} with unsupported protocol {}",
+                        peer_id, info.protocol_version
+                    );
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    return;
+                }
+
+                for address in info.listen_addrs {
+                    self.swarm
+                        .behaviour_mut()
+                        .kademlia
+                        .add_address(&peer_id, address);
+                }
+
+                // Exchange entries with the node, identification repeats in regular intervals
+                if let Err(err) = self.request_sync(peer_id).await {
+                    warn!("Could not sync with peer {}: {}", peer_id, err);
+                }
+            }
+            BehaviourEvent::Replication(RequestResponseEvent::Message { peer, message }) => {
+                match message {
+                    RequestResponseMessage::Request {
+                        request, channel, ..
+                    } => {
+                        if let Err(err) = self.handle_sync_request(peer, request, channel).await {
+                            warn!("Could not answer sync request of peer {}: {}", peer, err);
+                        }
+                    }
+                    RequestResponseMessage::Response { response, .. } => {
+                        if let Err(err) = self.handle_sync_response(peer, response).await {
+                            warn!("Could not store entries of peer {}: {}", peer, err);
+                        }
+                    }
+                }
+            }
+            BehaviourEvent::Replication(RequestResponseEvent::OutboundFailure {
+                peer,
+                error,
+                ..
+            }) => {
+                debug!("Sync with peer {} failed: {:?}", peer, error);
+            }
+            // Connect to nodes providing the same schemas
+            BehaviourEvent::Kademlia(KademliaEvent::OutboundQueryCompleted {
+                result: QueryResult::GetProviders(Ok(GetProvidersOk { providers, .. })),
+                ..
+            }) => {
+                for peer_id in providers {
+                    if &peer_id == self.swarm.local_peer_id() || self.swarm.is_connected(&peer_id) {
+                        continue;
+                    }
+
+                    debug!("Found peer {} providing the same schema", peer_id);
+
+                    if let Err(err) = self.swarm.dial(&peer_id) {
+                        warn!("Could not dial peer {}: {}", peer_id, err);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Handle events of the swarm.
+    async fn handle_swarm_event<E>(&mut self, event: SwarmEvent<BehaviourEvent, E>) {
+        match event {
+            SwarmEvent::NewListenAddr { address, .. } => {
+                info!("Listening for peers on {}", address);
+            }
+            SwarmEvent::ConnectionEstablished {
+                peer_id, endpoint, ..
+            } => {
+                info!(
+                    "Connected to peer {} at {}",
+                    peer_id,
+                    endpoint.get_remote_address()
+                );
+                self.events.emit(NodeEvent::PeerConnected(peer_id.to_string()));
+            }
+            SwarmEvent::ConnectionClosed { peer_id, .. } => {
+                info!("Disconnected from peer {}", peer_id);
+                self.events.emit(NodeEvent::PeerDisconnected(peer_id.to_string()));
+            }
+            SwarmEvent::Behaviour(event) => self.handle_behaviour_event(event).await,
+            _ => (),
+        }
+    }
+
+    /// Run networking service, handling incoming connections and events.
+    ///
+    /// New entries arriving through the receiver are announced to connected peers and their
+    /// schemas in the DHT.
+    pub async fn run(mut self, entries: Receiver<EntryNotification>) -> Result<()> {
+        // Announce all schemas we already store entries of
+        for schema in Log::schemas(&self.pool).await? {
+            if self.interests.accepts(&schema) {
+                self.provide_schema(&schema);
+            }
+        }
+
+        // Look for nodes storing entries of the schemas we want to replicate
+        for schema in self.interests.allowed.clone() {
+            self.provide_schema(&schema);
+        }
+
+        // Join the DHT when bootstrap nodes are configured
+        self.discover_peers();
+
+        // Connect to nodes we always want to stay connected to
+        self.connect_peers();
+
+        let mut entries = entries.fuse();
+        let mut discovery = stream::interval(DISCOVERY_INTERVAL).fuse();
+        let mut reconnect = stream::interval(RECONNECT_INTERVAL).fuse();
+
+        loop {
+            select! {
+                event = self.swarm.select_next_some() => self.handle_swarm_event(event).await,
+                entry = entries.select_next_some() => {
+                    if self.interests.accepts(&entry.schema) {
+                        self.provide_schema(&entry.schema);
+                    }
+                    self.announce_entry(&entry);
+                }
+                _ = discovery.select_next_some() => self.discover_peers(),
+                _ = reconnect.select_next_some() => self.connect_peers(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_peer_address;
+
+    #[test]
+    fn peer_addresses() {
+        let (peer_id, address) = parse_peer_address(
+            "/ip4/192.168.0.1/tcp/2024/p2p/12D3KooWD3eckifWpRn9wQpMG9R9hX3sD158z7EqHWmweQAJU5SA",
+        )
+        .unwrap();
+
+        assert_eq!(
+            peer_id.to_string(),
+            "12D3KooWD3eckifWpRn9wQpMG9R9hX3sD158z7EqHWmweQAJU5SA"
+        );
+        assert_eq!(address.to_string(), "/ip4/192.168.0.1/tcp/2024");
+
+        assert!(parse_peer_address("/ip4/192.168.0.1/tcp/2024").is_err());
+    }
+}
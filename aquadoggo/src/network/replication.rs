@@ -0,0 +1,758 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use libp2p::core::upgrade::{read_length_prefixed, write_length_prefixed};
+use libp2p::core::ProtocolName;
+use libp2p::request_response::RequestResponseCodec;
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::MessageEncoded;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::Entry;
+use crate::db::Pool;
+
+/// Protocol name of the replication protocol spoken between nodes.
+const REPLICATION_PROTOCOL_NAME: &[u8] = b"/aquadoggo/replication/1.0.0";
+
+/// Maximum size of a single replication message in bytes.
+const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Maximum number of entries sent in one response, peers request the rest afterwards.
+pub const MAX_SYNC_ENTRIES: usize = 500;
+
+/// Number of logs up to which nodes exchange all log heights instead of reconciling ranges first.
+const RECONCILIATION_THRESHOLD: usize = 256;
+
+/// Latest sequence number of an author's log.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LogHeight {
+    pub author: Author,
+    pub log_id: LogId,
+    pub seq_num: SeqNum,
+    pub schema: Hash,
+}
+
+/// Schemas a node is interested in, advertised to peers during replication.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemaFilter {
+    /// Only replicate entries of these schemas, all schemas are replicated when empty.
+    pub allowed: Vec<Hash>,
+
+    /// Never replicate entries of these schemas.
+    pub ignored: Vec<Hash>,
+}
+
+impl SchemaFilter {
+    /// Returns a filter from lists of allowed and ignored schema hashes.
+    pub fn new(allowed: &[String], ignored: &[String]) -> Result<Self> {
+        Ok(Self {
+            allowed: allowed
+                .iter()
+                .map(|schema| Hash::new(schema))
+                .collect::<std::result::Result<_, _>>()?,
+            ignored: ignored
+                .iter()
+                .map(|schema| Hash::new(schema))
+                .collect::<std::result::Result<_, _>>()?,
+        })
+    }
+
+    /// Returns true when entries of this schema should be replicated.
+    pub fn accepts(&self, schema: &Hash) -> bool {
+        !self.ignored.contains(schema) && (self.allowed.is_empty() || self.allowed.contains(schema))
+    }
+}
+
+/// Requests sent to a peer during replication.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SyncRequest {
+    /// Digests of our log heights per range of authors, the peer responds with the ranges which
+    /// differ from its own.
+    ///
+    /// All requests contain the schemas we are interested in, the peer only compares logs and
+    /// sends entries of these schemas.
+    #[serde(rename_all = "camelCase")]
+    RangeDigests {
+        digests: BTreeMap<String, String>,
+        interests: SchemaFilter,
+    },
+
+    /// Latest sequence numbers of our logs, the peer responds with all entries we are missing.
+    ///
+    /// When ranges are given only logs of authors within these ranges are compared.
+    #[serde(rename_all = "camelCase")]
+    LogHeights {
+        log_heights: Vec<LogHeight>,
+        ranges: Option<Vec<String>>,
+        interests: SchemaFilter,
+    },
+}
+
+/// Entry with its message payload sent during replication.
+///
+/// Entries whose payload got pruned are sent without message but with the schema of their log,
+/// the peer can still verify them and use them as links of following entries.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncEntry {
+    pub entry_encoded: EntrySigned,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message_encoded: Option<MessageEncoded>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<Hash>,
+}
+
+/// Responses sent to a peer during replication.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SyncResponse {
+    /// Ranges of authors in which we store entries the peer might be missing.
+    #[serde(rename_all = "camelCase")]
+    DifferingRanges { ranges: Vec<String> },
+
+    /// All entries the peer is missing.
+    ///
+    /// Entries are ordered by log and sequence number so that backlinks and skiplinks of every
+    /// entry are already stored when it gets verified.
+    #[serde(rename_all = "camelCase")]
+    Entries { entries: Vec<SyncEntry> },
+}
+
+/// Returns the range of an author, given by the first byte of its public key.
+fn author_range(author: &Author) -> String {
+    author.as_str()[..2].to_owned()
+}
+
+/// Returns the latest sequence numbers of all logs stored on this node with accepted schemas.
+async fn log_heights(pool: &Pool, interests: &SchemaFilter) -> Result<Vec<LogHeight>> {
+    let log_heights = Entry::log_heights(pool)
+        .await?
+        .into_iter()
+        .filter(|(_, _, _, schema)| interests.accepts(schema))
+        .map(|(author, log_id, seq_num, schema)| LogHeight {
+            author,
+            log_id,
+            seq_num,
+            schema,
+        })
+        .collect();
+
+    Ok(log_heights)
+}
+
+/// Returns a digest of the log heights for every range of authors.
+///
+/// Two nodes storing the same logs in a range compute the same digest as log heights are ordered
+/// by author and log id.
+fn range_digests(log_heights: &[LogHeight]) -> Result<BTreeMap<String, String>> {
+    let mut ranges: BTreeMap<String, Vec<u8>> = BTreeMap::new();
+
+    for height in log_heights {
+        let line = format!(
+            "{}:{}:{}\n",
+            height.author.as_str(),
+            height.log_id.as_i64(),
+            height.seq_num.as_i64()
+        );
+
+        ranges
+            .entry(author_range(&height.author))
+            .or_default()
+            .extend(line.as_bytes());
+    }
+
+    let mut digests = BTreeMap::new();
+
+    for (range, bytes) in ranges {
+        digests.insert(range, Hash::new_from_bytes(bytes)?.as_str().to_owned());
+    }
+
+    Ok(digests)
+}
+
+/// Returns the first request of a replication session with a peer.
+///
+/// Nodes with only a few logs send their log heights directly, otherwise they reconcile the
+/// differing ranges of authors first.
+pub async fn sync_request(pool: &Pool, interests: &SchemaFilter) -> Result<SyncRequest> {
+    let log_heights = log_heights(pool, interests).await?;
+
+    if log_heights.len() <= RECONCILIATION_THRESHOLD {
+        return Ok(SyncRequest::LogHeights {
+            log_heights,
+            ranges: None,
+            interests: interests.clone(),
+        });
+    }
+
+    Ok(SyncRequest::RangeDigests {
+        digests: range_digests(&log_heights)?,
+        interests: interests.clone(),
+    })
+}
+
+/// Returns a request with the log heights of the ranges which differ from the peer's ones.
+pub async fn ranges_request(
+    pool: &Pool,
+    interests: &SchemaFilter,
+    ranges: Vec<String>,
+) -> Result<SyncRequest> {
+    let log_heights = log_heights(pool, interests)
+        .await?
+        .into_iter()
+        .filter(|height| ranges.contains(&author_range(&height.author)))
+        .collect();
+
+    Ok(SyncRequest::LogHeights {
+        log_heights,
+        ranges: Some(ranges),
+        interests: interests.clone(),
+    })
+}
+
+/// Returns the response to a peer's request.
+pub async fn sync_response(pool: &Pool, request: &SyncRequest) -> Result<SyncResponse> {
+    match request {
+        SyncRequest::RangeDigests { digests, interests } => {
+            let local_digests = range_digests(&log_heights(pool, interests).await?)?;
+
+            // Ranges the peer does not know about or where it stores different log heights
+            let ranges = local_digests
+                .into_iter()
+                .filter(|(range, digest)| digests.get(range) != Some(digest))
+                .map(|(range, _)| range)
+                .collect();
+
+            Ok(SyncResponse::DifferingRanges { ranges })
+        }
+        SyncRequest::LogHeights {
+            log_heights,
+            ranges,
+            interests,
+        } => {
+            let entries = missing_entries(pool, log_heights, ranges.as_deref(), interests).await?;
+            Ok(SyncResponse::Entries { entries })
+        }
+    }
+}
+
+/// Returns all entries stored on this node which are newer than the given log heights.
+async fn missing_entries(
+    pool: &Pool,
+    remote_log_heights: &[LogHeight],
+    ranges: Option<&[String]>,
+    interests: &SchemaFilter,
+) -> Result<Vec<SyncEntry>> {
+    let remote_heights: HashMap<(String, i64), i64> = remote_log_heights
+        .iter()
+        .map(|height| {
+            (
+                (height.author.as_str().to_owned(), height.log_id.as_i64()),
+                height.seq_num.as_i64(),
+            )
+        })
+        .collect();
+
+    let mut entries = Vec::new();
+
+    for LogHeight {
+        author,
+        log_id,
+        seq_num,
+        schema,
+    } in log_heights(pool, interests).await?
+    {
+        if entries.len() >= MAX_SYNC_ENTRIES {
+            break;
+        }
+
+        // Only compare logs within the requested ranges
+        if let Some(ranges) = ranges {
+            if !ranges.contains(&author_range(&author)) {
+                continue;
+            }
+        }
+
+        // Peer does not know about this log when no height was sent
+        let remote_seq_num = remote_heights
+            .get(&(author.as_str().to_owned(), log_id.as_i64()))
+            .copied()
+            .unwrap_or(0);
+
+        if remote_seq_num >= seq_num.as_i64() {
+            continue;
+        }
+
+        let limit = (MAX_SYNC_ENTRIES - entries.len()) as i64;
+
+        for entry in Entry::after_seq_num(pool, &author, &log_id, remote_seq_num, limit).await? {
+            let entry_encoded = EntrySigned::new(&entry.entry_bytes)?;
+
+            // Entries with pruned payloads are sent with the schema of their log instead
+            entries.push(match entry.payload_bytes {
+                Some(payload_bytes) => SyncEntry {
+                    entry_encoded,
+                    message_encoded: Some(MessageEncoded::new(&payload_bytes)?),
+                    schema: None,
+                },
+                None => SyncEntry {
+                    entry_encoded,
+                    message_encoded: None,
+                    schema: Some(schema.clone()),
+                },
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Protocol used to replicate entries between two nodes.
+#[derive(Clone, Debug)]
+pub struct ReplicationProtocol;
+
+impl ProtocolName for ReplicationProtocol {
+    fn protocol_name(&self) -> &[u8] {
+        REPLICATION_PROTOCOL_NAME
+    }
+}
+
+/// Encodes replication requests and responses as length-prefixed JSON.
+#[derive(Clone, Debug)]
+pub struct ReplicationCodec;
+
+async fn read_json<T, D>(io: &mut T) -> io::Result<D>
+where
+    T: AsyncRead + Unpin + Send,
+    D: DeserializeOwned,
+{
+    let bytes = read_length_prefixed(io, MAX_MESSAGE_SIZE).await?;
+    serde_json::from_slice(&bytes).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+async fn write_json<T, S>(io: &mut T, value: &S) -> io::Result<()>
+where
+    T: AsyncWrite + Unpin + Send,
+    S: Serialize,
+{
+    let bytes =
+        serde_json::to_vec(value).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    write_length_prefixed(io, bytes).await?;
+    io.close().await
+}
+
+#[async_trait]
+impl RequestResponseCodec for ReplicationCodec {
+    type Protocol = ReplicationProtocol;
+    type Request = SyncRequest;
+    type Response = SyncResponse;
+
+    async fn read_request<T>(
+        &mut self,
+        _: &ReplicationProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_json(io).await
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &ReplicationProtocol,
+        io: &mut T,
+    ) -> io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        read_json(io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        _: &ReplicationProtocol,
+        io: &mut T,
+        request: Self::Request,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_json(io, &request).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &ReplicationProtocol,
+        io: &mut T,
+        response: Self::Response,
+    ) -> io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        write_json(io, &response).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use std::collections::BTreeMap;
+
+    use super::{
+        author_range, log_heights, range_digests, sync_request, sync_response, LogHeight,
+        SchemaFilter, SyncEntry, SyncRequest, SyncResponse,
+    };
+
+    use crate::db::{EntryStore, PurgeStore, SqlStorage};
+    use crate::rpc::{store_entry, store_pruned_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, initialize_db};
+
+    fn entries(response: SyncResponse) -> Vec<SyncEntry> {
+        match response {
+            SyncResponse::Entries { entries } => entries,
+            _ => panic!("Expected entries"),
+        }
+    }
+
+    fn ranges(response: SyncResponse) -> Vec<String> {
+        match response {
+            SyncResponse::DifferingRanges { ranges } => ranges,
+            _ => panic!("Expected differing ranges"),
+        }
+    }
+
+    #[async_std::test]
+    async fn missing_entries() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let subscriptions = Subscriptions::new();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2] <-- [3]
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        let (entry_3, message_3) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_2),
+            &SeqNum::new(3).unwrap(),
+        );
+
+        for (entry, message) in [
+            (&entry_1, &message_1),
+            (&entry_2, &message_2),
+            (&entry_3, &message_3),
+        ]
+        .iter()
+        {
+            store_entry(&storage, &subscriptions, entry, message)
+                .await
+                .unwrap();
+        }
+
+        // Send log heights directly as we only have a single log
+        let request = sync_request(&pool, &SchemaFilter::default()).await.unwrap();
+        match &request {
+            SyncRequest::LogHeights { log_heights, .. } => {
+                assert_eq!(log_heights.len(), 1);
+                assert_eq!(log_heights[0].seq_num, SeqNum::new(3).unwrap());
+            }
+            _ => panic!("Expected log heights"),
+        }
+
+        // Peer without any entries receives everything in order
+        let response = sync_response(
+            &pool,
+            &SyncRequest::LogHeights {
+                log_heights: vec![],
+                ranges: None,
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        let hashes: Vec<Hash> = entries(response)
+            .iter()
+            .map(|entry| entry.entry_encoded.hash())
+            .collect();
+        assert_eq!(hashes, vec![entry_1.hash(), entry_2.hash(), entry_3.hash()]);
+
+        // Peer which knows the first entry only receives the following ones
+        let response = sync_response(
+            &pool,
+            &SyncRequest::LogHeights {
+                log_heights: vec![LogHeight {
+                    author: entry_1.author(),
+                    log_id: log_id.clone(),
+                    seq_num: SeqNum::new(1).unwrap(),
+                    schema: schema.clone(),
+                }],
+                ranges: None,
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        let response = entries(response);
+        assert_eq!(response.len(), 2);
+        assert_eq!(response[0].entry_encoded.hash(), entry_2.hash());
+
+        // Peer which is up-to-date does not receive anything
+        let response = sync_response(&pool, &request).await.unwrap();
+        assert!(entries(response).is_empty());
+    }
+
+    #[async_std::test]
+    async fn reconcile_ranges() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let subscriptions = Subscriptions::new();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &subscriptions, &entry_1, &message_1)
+            .await
+            .unwrap();
+
+        let range = author_range(&entry_1.author());
+        let digests =
+            range_digests(&log_heights(&pool, &SchemaFilter::default()).await.unwrap()).unwrap();
+        assert_eq!(digests.len(), 1);
+
+        // Peer with the same log heights does not differ in any range
+        let response = sync_response(
+            &pool,
+            &SyncRequest::RangeDigests {
+                digests: digests.clone(),
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(ranges(response), Vec::<String>::new());
+
+        // Peer without any entries differs in the range of our author
+        let response = sync_response(
+            &pool,
+            &SyncRequest::RangeDigests {
+                digests: BTreeMap::new(),
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(ranges(response), vec![range.clone()]);
+
+        // Log heights are only compared within the requested ranges
+        let response = sync_response(
+            &pool,
+            &SyncRequest::LogHeights {
+                log_heights: vec![],
+                ranges: Some(vec![range]),
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(entries(response).len(), 1);
+
+        let response = sync_response(
+            &pool,
+            &SyncRequest::LogHeights {
+                log_heights: vec![],
+                ranges: Some(vec![]),
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        assert!(entries(response).is_empty());
+    }
+
+    #[async_std::test]
+    async fn schema_interests() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let subscriptions = Subscriptions::new();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let other_schema = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &subscriptions, &entry_1, &message_1)
+            .await
+            .unwrap();
+
+        let allowed = SchemaFilter::new(&[schema.as_str().to_owned()], &[]).unwrap();
+        assert!(allowed.accepts(&schema));
+        assert!(!allowed.accepts(&other_schema));
+
+        let ignored = SchemaFilter::new(&[], &[schema.as_str().to_owned()]).unwrap();
+        assert!(!ignored.accepts(&schema));
+        assert!(ignored.accepts(&other_schema));
+
+        assert!(SchemaFilter::new(&["invalid".to_owned()], &[]).is_err());
+
+        // Peer only receives entries of schemas it is interested in
+        let request = |interests: &SchemaFilter| SyncRequest::LogHeights {
+            log_heights: vec![],
+            ranges: None,
+            interests: interests.clone(),
+        };
+
+        let response = sync_response(&pool, &request(&allowed)).await.unwrap();
+        assert_eq!(entries(response).len(), 1);
+
+        let response = sync_response(&pool, &request(&ignored)).await.unwrap();
+        assert!(entries(response).is_empty());
+    }
+
+    #[async_std::test]
+    async fn pruned_entries() {
+        let pool = initialize_db().await;
+        let storage = SqlStorage::new(pool.clone());
+        let subscriptions = Subscriptions::new();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2]
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        for (entry, message) in [(&entry_1, &message_1), (&entry_2, &message_2)].iter() {
+            store_entry(&storage, &subscriptions, entry, message)
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(storage.prune_payloads(&schema, 1).await.unwrap(), 1);
+
+        // Entries with pruned payloads are sent with their schema instead
+        let response = sync_response(
+            &pool,
+            &SyncRequest::LogHeights {
+                log_heights: vec![],
+                ranges: None,
+                interests: SchemaFilter::default(),
+            },
+        )
+        .await
+        .unwrap();
+        let response = entries(response);
+        assert_eq!(response.len(), 2);
+        assert!(response[0].message_encoded.is_none());
+        assert_eq!(response[0].schema, Some(schema.clone()));
+        assert!(response[1].message_encoded.is_some());
+        assert!(response[1].schema.is_none());
+
+        // Peer refuses entries without payload of logs it does not know
+        let peer = SqlStorage::new(initialize_db().await);
+        assert!(
+            store_pruned_entry(&peer, &response[0].entry_encoded, &schema, &[])
+                .await
+                .is_err()
+        );
+
+        // Peer verifies and stores the entry without payload when the following entry of the
+        // same log proves its schema
+        let log_entries = [(
+            &response[1].entry_encoded,
+            response[1].message_encoded.as_ref().unwrap(),
+        )];
+        let other_schema = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+        assert!(store_pruned_entry(
+            &peer,
+            &response[0].entry_encoded,
+            &other_schema,
+            &log_entries
+        )
+        .await
+        .is_err());
+        store_pruned_entry(&peer, &response[0].entry_encoded, &schema, &log_entries)
+            .await
+            .unwrap();
+        store_entry(
+            &peer,
+            &subscriptions,
+            &response[1].entry_encoded,
+            response[1].message_encoded.as_ref().unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let stored = peer
+            .query_entries(None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(stored.len(), 2);
+        assert!(stored[0].payload_bytes.is_none());
+        assert_eq!(stored[0].payload_hash, message_1.hash());
+        assert!(stored[1].payload_bytes.is_some());
+    }
+}
@@ -0,0 +1,151 @@
+use std::io;
+use std::net::SocketAddr;
+
+use async_std::net::TcpStream;
+use data_encoding::BASE32;
+use futures::future::BoxFuture;
+use futures::stream::BoxStream;
+use futures::{AsyncReadExt, AsyncWriteExt, FutureExt};
+use libp2p::core::transport::{ListenerEvent, Transport, TransportError};
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+
+/// SOCKS protocol version used to talk to the Tor proxy.
+const SOCKS_VERSION: u8 = 5;
+
+/// Returns the host name and port of an `/onion3/<address>:<port>` multiaddress.
+fn onion_host(address: &Multiaddr) -> Option<(String, u16)> {
+    let mut protocols = address.iter();
+
+    match (protocols.next(), protocols.next()) {
+        (Some(Protocol::Onion3(onion)), None)
+        | (Some(Protocol::Onion3(onion)), Some(Protocol::P2p(_))) => {
+            let host = format!("{}.onion", BASE32.encode(onion.hash()).to_lowercase());
+            Some((host, onion.port()))
+        }
+        _ => None,
+    }
+}
+
+/// Open a connection to a host through a SOCKS5 proxy, the host name gets resolved by the proxy.
+async fn socks_connect(proxy: SocketAddr, host: String, port: u16) -> io::Result<TcpStream> {
+    let mut stream = TcpStream::connect(proxy).await?;
+
+    // Greet proxy without authentication
+    stream.write_all(&[SOCKS_VERSION, 1, 0]).await?;
+    let mut response = [0; 2];
+    stream.read_exact(&mut response).await?;
+    if response != [SOCKS_VERSION, 0] {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            "Tor proxy requires authentication",
+        ));
+    }
+
+    // Request connection to host name
+    let mut request = vec![SOCKS_VERSION, 1, 0, 3, host.len() as u8];
+    request.extend(host.as_bytes());
+    request.extend(&port.to_be_bytes());
+    stream.write_all(&request).await?;
+
+    let mut response = [0; 4];
+    stream.read_exact(&mut response).await?;
+    if response[1] != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::ConnectionRefused,
+            format!(
+                "Tor proxy could not connect to {} (code {})",
+                host, response[1]
+            ),
+        ));
+    }
+
+    // Skip bound address sent by the proxy, followed by two bytes for the port
+    let address_len = match response[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0; 1];
+            stream.read_exact(&mut len).await?;
+            len[0] as usize
+        }
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Tor proxy sent invalid address type",
+            ))
+        }
+    };
+    let mut bound_address = vec![0; address_len + 2];
+    stream.read_exact(&mut bound_address).await?;
+
+    Ok(stream)
+}
+
+/// Transport dialing `.onion` addresses through the SOCKS5 proxy of a running Tor daemon.
+///
+/// The transport can not listen for connections. To be reachable as an onion service the Tor
+/// daemon forwards incoming connections to the regular TCP port of the node.
+#[derive(Clone, Debug)]
+pub struct TorTransport {
+    proxy: SocketAddr,
+}
+
+impl TorTransport {
+    /// Returns a transport connecting through the given SOCKS5 proxy address.
+    pub fn new(proxy: SocketAddr) -> Self {
+        Self { proxy }
+    }
+}
+
+impl Transport for TorTransport {
+    type Output = TcpStream;
+    type Error = io::Error;
+    type Listener =
+        BoxStream<'static, Result<ListenerEvent<Self::ListenerUpgrade, Self::Error>, Self::Error>>;
+    type ListenerUpgrade = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+    type Dial = BoxFuture<'static, Result<Self::Output, Self::Error>>;
+
+    fn listen_on(self, address: Multiaddr) -> Result<Self::Listener, TransportError<Self::Error>> {
+        Err(TransportError::MultiaddrNotSupported(address))
+    }
+
+    fn dial(self, address: Multiaddr) -> Result<Self::Dial, TransportError<Self::Error>> {
+        let (host, port) = match onion_host(&address) {
+            Some(host) => host,
+            None => return Err(TransportError::MultiaddrNotSupported(address)),
+        };
+
+        Ok(socks_connect(self.proxy, host, port).boxed())
+    }
+
+    fn address_translation(&self, _: &Multiaddr, _: &Multiaddr) -> Option<Multiaddr> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use libp2p::Multiaddr;
+
+    use super::onion_host;
+
+    #[test]
+    fn onion_addresses() {
+        let address: Multiaddr =
+            "/onion3/vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd:2024"
+                .parse()
+                .unwrap();
+
+        assert_eq!(
+            onion_host(&address),
+            Some((
+                "vww6ybal4bd7szmgncyruucpgfkqahzddi37ktceo3ah7ngmcopnpyyd.onion".to_owned(),
+                2024
+            ))
+        );
+
+        let address: Multiaddr = "/ip4/192.168.0.1/tcp/2024".parse().unwrap();
+        assert_eq!(onion_host(&address), None);
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::MessageEncoded;
+
+use crate::config::Configuration;
+
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_copy_implementations)]
+pub enum EntryPolicyError {
+    #[error("Author is not allowed to publish entries on this node")]
+    AuthorNotAllowed,
+
+    #[error("Entries of this schema are not accepted by this node")]
+    SchemaNotAccepted,
+
+    #[error("Message exceeds maximum size of {0} bytes")]
+    MessageTooLarge(usize),
+}
+
+/// Rules configured by the operator deciding which entries this node accepts.
+#[derive(Debug, Clone, Default)]
+pub struct EntryPolicy {
+    /// Only accept entries of these authors, entries of all authors are accepted when empty.
+    allowed_authors: Vec<Author>,
+
+    /// Never accept entries of these authors.
+    blocked_authors: Vec<Author>,
+
+    /// Only accept entries of these schemas, entries of all schemas are accepted when empty.
+    accepted_schemas: Vec<Hash>,
+
+    /// Maximum size of encoded messages in bytes, messages of any size are accepted when not set.
+    max_message_size: Option<usize>,
+}
+
+impl EntryPolicy {
+    /// Returns the policy configured for this node.
+    pub fn new(config: &Configuration) -> Result<Self> {
+        Ok(Self {
+            allowed_authors: config
+                .allowed_authors
+                .iter()
+                .map(|author| Author::new(author))
+                .collect::<std::result::Result<_, _>>()?,
+            blocked_authors: config
+                .blocked_authors
+                .iter()
+                .map(|author| Author::new(author))
+                .collect::<std::result::Result<_, _>>()?,
+            accepted_schemas: config
+                .accepted_schemas
+                .iter()
+                .map(|schema| Hash::new(schema))
+                .collect::<std::result::Result<_, _>>()?,
+            max_message_size: match config.max_message_size {
+                0 => None,
+                size => Some(size),
+            },
+        })
+    }
+
+    /// Returns the schemas this node accepts entries of, all schemas are accepted when empty.
+    pub fn accepted_schemas(&self) -> &[Hash] {
+        &self.accepted_schemas
+    }
+
+    /// Returns an error when entries of this author are not accepted.
+    pub fn check_author(&self, author: &Author) -> Result<(), EntryPolicyError> {
+        if self.blocked_authors.contains(author)
+            || (!self.allowed_authors.is_empty() && !self.allowed_authors.contains(author))
+        {
+            return Err(EntryPolicyError::AuthorNotAllowed);
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error when entries of this schema are not accepted.
+    pub fn check_schema(&self, schema: &Hash) -> Result<(), EntryPolicyError> {
+        if !self.accepted_schemas.is_empty() && !self.accepted_schemas.contains(schema) {
+            return Err(EntryPolicyError::SchemaNotAccepted);
+        }
+
+        Ok(())
+    }
+
+    /// Returns an error when the encoded message exceeds the maximum size.
+    pub fn check_message_size(&self, message: &MessageEncoded) -> Result<(), EntryPolicyError> {
+        match self.max_message_size {
+            Some(max_size) if message.to_bytes().len() > max_size => {
+                Err(EntryPolicyError::MessageTooLarge(max_size))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::{Author, KeyPair};
+
+    use crate::config::Configuration;
+    use crate::test_helpers::create_test_entry;
+
+    use super::EntryPolicy;
+
+    const TEST_AUTHOR_1: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+    const TEST_AUTHOR_2: &str = "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a";
+
+    #[test]
+    fn allowed_and_blocked_authors() {
+        let author_1 = Author::new(TEST_AUTHOR_1).unwrap();
+        let author_2 = Author::new(TEST_AUTHOR_2).unwrap();
+
+        // All authors are accepted by default
+        let policy = EntryPolicy::default();
+        assert!(policy.check_author(&author_1).is_ok());
+
+        let config = Configuration {
+            blocked_authors: vec![TEST_AUTHOR_2.to_owned()],
+            ..Configuration::default()
+        };
+        let policy = EntryPolicy::new(&config).unwrap();
+        assert!(policy.check_author(&author_1).is_ok());
+        assert!(policy.check_author(&author_2).is_err());
+
+        let config = Configuration {
+            allowed_authors: vec![TEST_AUTHOR_1.to_owned()],
+            ..Configuration::default()
+        };
+        let policy = EntryPolicy::new(&config).unwrap();
+        assert!(policy.check_author(&author_1).is_ok());
+        assert!(policy.check_author(&author_2).is_err());
+
+        // Invalid author keys are rejected
+        let config = Configuration {
+            blocked_authors: vec!["abc".to_owned()],
+            ..Configuration::default()
+        };
+        assert!(EntryPolicy::new(&config).is_err());
+    }
+
+    #[test]
+    fn accepted_schemas() {
+        let schema_1 = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let schema_2 = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        // All schemas are accepted by default
+        let policy = EntryPolicy::default();
+        assert!(policy.check_schema(&schema_2).is_ok());
+
+        let config = Configuration {
+            accepted_schemas: vec![schema_1.as_str().to_owned()],
+            ..Configuration::default()
+        };
+        let policy = EntryPolicy::new(&config).unwrap();
+        assert!(policy.check_schema(&schema_1).is_ok());
+        assert!(policy.check_schema(&schema_2).is_err());
+    }
+
+    #[test]
+    fn max_message_size() {
+        let (_, message) = create_test_entry(
+            &KeyPair::new(),
+            &Hash::new_from_bytes(vec![1, 2, 3]).unwrap(),
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let size = message.to_bytes().len();
+
+        // Messages of any size are accepted by default
+        assert!(EntryPolicy::default().check_message_size(&message).is_ok());
+
+        let config = Configuration {
+            max_message_size: size,
+            ..Configuration::default()
+        };
+        let policy = EntryPolicy::new(&config).unwrap();
+        assert!(policy.check_message_size(&message).is_ok());
+
+        let config = Configuration {
+            max_message_size: size - 1,
+            ..Configuration::default()
+        };
+        let policy = EntryPolicy::new(&config).unwrap();
+        assert!(policy.check_message_size(&message).is_err());
+    }
+}
@@ -1,24 +1,189 @@
 use std::sync::Arc;
 
 use jsonrpc_v2::{Data, MapRouter, Server as Service};
+use libp2p::identity::Keypair;
 
-use crate::db::Pool;
-use crate::rpc::methods::{get_entry_args, publish_entry, query_entries};
+use crate::backup::Backups;
+use crate::db::StorageProvider;
+use crate::events::Events;
+use crate::policy::EntryPolicy;
+use crate::rpc::auth::WriteTokens;
+use crate::rpc::cors::CorsPolicy;
+use crate::rpc::limits::RequestLimits;
+use crate::rpc::methods::{
+    aggregate_documents, create_backup, discover, get_all_schemas, get_author_profile,
+    get_document, get_encrypted_messages, get_entries_by_author, get_entry_args,
+    get_entry_args_batch, get_entry_by_hash, get_forks, get_key_group, get_logs_by_author,
+    get_node_info, get_stats, prune_payloads, publish_entries, publish_entry, purge_author,
+    purge_log, query_documents, query_entries, validate_entry,
+};
+use crate::rpc::openrpc::DISCOVER_METHOD;
+use crate::rpc::subscriptions::{SUBSCRIBE_ENTRIES_METHOD, UNSUBSCRIBE_ENTRIES_METHOD};
+use crate::rpc::Subscriptions;
 
-pub type RpcApiService = Arc<Service<MapRouter>>;
+/// Names of all supported RPC methods, subscription methods are only available via WebSocket.
+pub const RPC_METHODS: [&str; 22] = [
+    "panda_aggregateDocuments",
+    "panda_getAllSchemas",
+    "panda_getAuthorProfile",
+    "panda_getDocument",
+    "panda_getEncryptedMessages",
+    "panda_getEntriesByAuthor",
+    "panda_getEntryArguments",
+    "panda_getEntryArgumentsBatch",
+    "panda_getEntryByHash",
+    "panda_getForks",
+    "panda_getKeyGroup",
+    "panda_getLogsByAuthor",
+    "panda_getNodeInfo",
+    "panda_getStats",
+    "panda_publishEntries",
+    "panda_publishEntry",
+    "panda_queryDocuments",
+    "panda_queryEntries",
+    SUBSCRIBE_ENTRIES_METHOD,
+    UNSUBSCRIBE_ENTRIES_METHOD,
+    "panda_validateEntry",
+    DISCOVER_METHOD,
+];
+
+/// Details about this node returned by `panda_getNodeInfo`.
+#[derive(Debug, Clone, Default)]
+pub struct NodeInfo {
+    /// Peer id of this node in the peer-to-peer network.
+    pub peer_id: Option<String>,
+
+    /// Hex encoded Ed25519 public key of this node, used to verify signed responses.
+    pub public_key: Option<String>,
+
+    /// Schemas whose entries are never replicated from other nodes.
+    pub replication_ignored_schemas: Vec<String>,
+
+    /// Schemas replicated from other nodes, all schemas are replicated when empty.
+    pub replication_schemas: Vec<String>,
+}
+
+/// Settings of the JSON RPC API.
+#[derive(Debug, Clone, Default)]
+pub struct RpcApiOptions {
+    /// Details about this node returned by `panda_getNodeInfo`.
+    pub node_info: NodeInfo,
+
+    /// Rules deciding which published entries are accepted.
+    pub policy: EntryPolicy,
+
+    /// Secret token required to call admin methods, they are disabled when not set.
+    pub admin_token: Option<String>,
+
+    /// Database backups created by `panda_createBackup`, the method is disabled when not set.
+    pub backups: Option<Arc<Backups>>,
+
+    /// Node events streamed to clients of the `/events` endpoint.
+    pub events: Events,
+
+    /// Key pair of this node signing HTTP responses, responses are not signed when not set.
+    pub signing_key: Option<Keypair>,
+
+    /// Bearer tokens of clients allowed to publish entries, everyone can publish when empty.
+    pub write_tokens: Vec<String>,
+
+    /// Origins, methods and headers allowed in cross-origin requests.
+    pub cors: CorsPolicy,
+
+    /// Timeout and maximum number of concurrently handled requests.
+    pub limits: RequestLimits,
+}
+
+/// JSON RPC API service shared by the HTTP and WebSocket server.
+#[derive(Clone)]
+pub struct RpcApiService {
+    /// Router handling all JSON RPC methods.
+    pub router: Arc<Service<MapRouter>>,
+
+    /// Storage provider used by the RPC methods.
+    pub storage: Arc<dyn StorageProvider>,
+
+    /// Registry of WebSocket clients subscribed to new entries.
+    pub subscriptions: Subscriptions,
+
+    /// Node events streamed to clients of the `/events` endpoint.
+    pub events: Events,
+
+    /// Key pair of this node signing HTTP responses.
+    pub signing_key: Option<Keypair>,
+
+    /// Bearer tokens of clients allowed to call write methods.
+    pub write_tokens: WriteTokens,
+
+    /// Origins, methods and headers allowed in cross-origin requests.
+    pub cors: CorsPolicy,
+
+    /// Timeout and maximum number of concurrently handled requests.
+    pub limits: RequestLimits,
+}
 
 #[derive(Debug, Clone)]
 pub struct RpcApiState {
-    pub pool: Pool,
+    pub admin_token: Option<String>,
+    pub backups: Option<Arc<Backups>>,
+    pub node_info: NodeInfo,
+    pub policy: EntryPolicy,
+    pub storage: Arc<dyn StorageProvider>,
+    pub subscriptions: Subscriptions,
 }
 
-pub fn build_rpc_api_service(pool: Pool) -> RpcApiService {
-    let state = RpcApiState { pool };
+pub fn build_rpc_api_service<S: StorageProvider + 'static>(
+    storage: S,
+    options: RpcApiOptions,
+) -> RpcApiService {
+    let subscriptions = Subscriptions::new();
+    let storage: Arc<dyn StorageProvider> = Arc::new(storage);
+
+    let state = RpcApiState {
+        admin_token: options.admin_token.clone(),
+        backups: options.backups,
+        node_info: options.node_info,
+        policy: options.policy,
+        storage: storage.clone(),
+        subscriptions: subscriptions.clone(),
+    };
 
-    Service::new()
+    let router = Service::new()
         .with_data(Data(Arc::new(state)))
+        .with_method("panda_aggregateDocuments", aggregate_documents)
+        .with_method("panda_createBackup", create_backup)
+        .with_method("panda_getAllSchemas", get_all_schemas)
+        .with_method("panda_getAuthorProfile", get_author_profile)
+        .with_method("panda_getDocument", get_document)
+        .with_method("panda_getEncryptedMessages", get_encrypted_messages)
+        .with_method("panda_getEntriesByAuthor", get_entries_by_author)
         .with_method("panda_getEntryArguments", get_entry_args)
+        .with_method("panda_getEntryArgumentsBatch", get_entry_args_batch)
+        .with_method("panda_getEntryByHash", get_entry_by_hash)
+        .with_method("panda_getForks", get_forks)
+        .with_method("panda_getKeyGroup", get_key_group)
+        .with_method("panda_getLogsByAuthor", get_logs_by_author)
+        .with_method("panda_getNodeInfo", get_node_info)
+        .with_method("panda_getStats", get_stats)
+        .with_method("panda_prunePayloads", prune_payloads)
+        .with_method("panda_publishEntries", publish_entries)
         .with_method("panda_publishEntry", publish_entry)
+        .with_method("panda_purgeAuthor", purge_author)
+        .with_method("panda_purgeLog", purge_log)
+        .with_method("panda_queryDocuments", query_documents)
         .with_method("panda_queryEntries", query_entries)
-        .finish()
+        .with_method("panda_validateEntry", validate_entry)
+        .with_method(DISCOVER_METHOD, discover)
+        .finish();
+
+    RpcApiService {
+        router,
+        storage,
+        subscriptions,
+        events: options.events,
+        signing_key: options.signing_key,
+        write_tokens: WriteTokens::new(&options.write_tokens, options.admin_token.as_deref()),
+        cors: options.cors,
+        limits: options.limits,
+    }
 }
@@ -0,0 +1,140 @@
+use serde_json::Value;
+
+/// Methods storing entries on this node, restricted to clients with a write token.
+const WRITE_METHODS: [&str; 2] = ["panda_publishEntries", "panda_publishEntry"];
+
+/// Methods removing data from this node or writing backups, restricted to clients with the admin
+/// token.
+const ADMIN_METHODS: [&str; 4] = [
+    "panda_createBackup",
+    "panda_prunePayloads",
+    "panda_purgeAuthor",
+    "panda_purgeLog",
+];
+
+/// Compares two strings in constant time to not reveal secret tokens through response times.
+pub fn secure_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Returns true when the given `Authorization` header value carries one of the bearer tokens.
+fn authorized(tokens: &[String], authorization: Option<&str>) -> bool {
+    let token = match authorization.and_then(|value| value.strip_prefix("Bearer ")) {
+        Some(token) => token.trim(),
+        None => return false,
+    };
+
+    tokens.iter().any(|allowed| secure_eq(allowed, token))
+}
+
+/// Bearer tokens of clients allowed to call write and admin methods.
+///
+/// Write methods are public when no write tokens are configured, read methods are always public.
+/// Admin methods require the admin token, they are disabled when it is not configured.
+#[derive(Debug, Clone, Default)]
+pub struct WriteTokens {
+    tokens: Vec<String>,
+    admin_tokens: Vec<String>,
+}
+
+impl WriteTokens {
+    /// Returns write tokens and the admin token, ignoring empty ones.
+    pub fn new(tokens: &[String], admin_token: Option<&str>) -> Self {
+        Self {
+            tokens: tokens
+                .iter()
+                .filter(|token| !token.is_empty())
+                .cloned()
+                .collect(),
+            admin_tokens: admin_token
+                .filter(|token| !token.is_empty())
+                .map(|token| vec![token.to_owned()])
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns true when a client with the given `Authorization` header value may send this RPC
+    /// request.
+    ///
+    /// Batch requests are only allowed when the client may call all of their methods. Calls of
+    /// admin methods pass when no admin token is configured, the methods refuse them.
+    pub fn allows(&self, request: &Value, authorization: Option<&str>) -> bool {
+        let requests: Vec<&Value> = match request {
+            Value::Array(requests) => requests.iter().collect(),
+            request => vec![request],
+        };
+
+        let calls = |methods: &[&str]| {
+            requests.iter().any(|request| {
+                request["method"]
+                    .as_str()
+                    .map_or(false, |method| methods.contains(&method))
+            })
+        };
+
+        let writes_allowed = self.tokens.is_empty()
+            || !calls(&WRITE_METHODS)
+            || authorized(&self.tokens, authorization);
+        let admin_allowed = self.admin_tokens.is_empty()
+            || !calls(&ADMIN_METHODS)
+            || authorized(&self.admin_tokens, authorization);
+
+        writes_allowed && admin_allowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::WriteTokens;
+
+    #[test]
+    fn restrict_write_methods() {
+        let publish = json!({ "method": "panda_publishEntry" });
+        let query = json!({ "method": "panda_queryEntries" });
+        let batch = json!([query.clone(), publish.clone()]);
+
+        // Everything is allowed when no tokens are configured
+        let tokens = WriteTokens::new(&["".to_owned()], None);
+        assert!(tokens.allows(&publish, None));
+
+        let tokens = WriteTokens::new(&["writer".to_owned()], None);
+        assert!(tokens.allows(&query, None));
+        assert!(!tokens.allows(&publish, None));
+        assert!(!tokens.allows(&publish, Some("Bearer reader")));
+        assert!(!tokens.allows(&publish, Some("writer")));
+        assert!(tokens.allows(&publish, Some("Bearer writer")));
+
+        // Write methods can not be hidden in batch requests
+        assert!(!tokens.allows(&batch, None));
+        assert!(tokens.allows(&batch, Some("Bearer writer")));
+    }
+
+    #[test]
+    fn restrict_admin_methods() {
+        let purge = json!({ "method": "panda_purgeAuthor" });
+        let publish = json!({ "method": "panda_publishEntry" });
+        let batch = json!([publish.clone(), purge.clone()]);
+
+        // Admin methods refuse calls themselves when no admin token is configured
+        let tokens = WriteTokens::new(&[], None);
+        assert!(tokens.allows(&purge, None));
+
+        let tokens = WriteTokens::new(&[], Some("secret"));
+        assert!(tokens.allows(&publish, None));
+        assert!(!tokens.allows(&purge, None));
+        assert!(!tokens.allows(&purge, Some("Bearer wrong")));
+        assert!(tokens.allows(&purge, Some("Bearer secret")));
+
+        // Write tokens do not allow admin methods
+        let tokens = WriteTokens::new(&["writer".to_owned()], Some("secret"));
+        assert!(!tokens.allows(&purge, Some("Bearer writer")));
+        assert!(!tokens.allows(&batch, Some("Bearer secret")));
+        assert!(!tokens.allows(&batch, Some("Bearer writer")));
+    }
+}
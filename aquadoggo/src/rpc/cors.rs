@@ -0,0 +1,132 @@
+use anyhow::{anyhow, Result};
+use http_types::headers::HeaderValue;
+use tide::security::{CorsMiddleware, Origin};
+
+use crate::config::Configuration;
+
+/// Cross-origin resource sharing policy of the HTTP server, allowing browser-based clients to
+/// call the node.
+#[derive(Debug, Clone)]
+pub struct CorsPolicy {
+    /// Origins allowed to send requests, `*` allows all origins.
+    allowed_origins: Vec<String>,
+
+    /// HTTP methods allowed in cross-origin requests.
+    allowed_methods: HeaderValue,
+
+    /// Request headers allowed in cross-origin requests.
+    allowed_headers: HeaderValue,
+}
+
+impl Default for CorsPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_origins: vec!["*".to_owned()],
+            allowed_methods: "GET, POST, OPTIONS".parse().unwrap(),
+            allowed_headers: "*".parse().unwrap(),
+        }
+    }
+}
+
+impl CorsPolicy {
+    /// Returns the policy configured for this node.
+    pub fn new(config: &Configuration) -> Result<Self> {
+        Ok(Self {
+            allowed_origins: non_empty(&config.cors_allowed_origins),
+            allowed_methods: header_value(&config.cors_allowed_methods)?,
+            allowed_headers: header_value(&config.cors_allowed_headers)?,
+        })
+    }
+
+    /// Returns middleware adding the CORS headers of this policy to all responses.
+    pub fn middleware(&self) -> CorsMiddleware {
+        // Requests of all origins are refused when the list is empty
+        let origin = match self.allowed_origins.len() {
+            1 => Origin::from(self.allowed_origins[0].as_str()),
+            _ => Origin::List(self.allowed_origins.clone()),
+        };
+
+        CorsMiddleware::new()
+            .allow_methods(self.allowed_methods.clone())
+            .allow_headers(self.allowed_headers.clone())
+            .allow_origin(origin)
+            .allow_credentials(false)
+    }
+}
+
+/// Returns the given values as comma-separated header value.
+fn header_value(values: &[String]) -> Result<HeaderValue> {
+    let value = non_empty(values).join(", ");
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid CORS header value {}", value))
+}
+
+/// Returns the given values without empty ones.
+fn non_empty(values: &[String]) -> Vec<String> {
+    values
+        .iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_owned())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use http_types::StatusCode;
+    use tide_testing::TideTestingExt;
+
+    use super::CorsPolicy;
+
+    use crate::config::Configuration;
+
+    #[async_std::test]
+    async fn allow_configured_origins() {
+        let config = Configuration {
+            cors_allowed_origins: vec!["https://example.org".to_owned()],
+            cors_allowed_headers: vec!["Content-Type".to_owned(), "Authorization".to_owned()],
+            ..Configuration::default()
+        };
+        let policy = CorsPolicy::new(&config).unwrap();
+
+        let mut app = tide::new();
+        app.with(policy.middleware());
+        app.at("/").get(|_| async { Ok("") });
+
+        let response = app
+            .get("/")
+            .header("Origin", "https://example.org")
+            .await
+            .unwrap();
+        assert_eq!(
+            response
+                .header("Access-Control-Allow-Origin")
+                .unwrap()
+                .as_str(),
+            "https://example.org"
+        );
+
+        let response = app
+            .options("/")
+            .header("Origin", "https://example.org")
+            .header("Access-Control-Request-Method", "POST")
+            .await
+            .unwrap();
+        assert_eq!(
+            response
+                .header("Access-Control-Allow-Headers")
+                .unwrap()
+                .as_str(),
+            "Content-Type, Authorization"
+        );
+
+        // Requests of other origins are refused
+        let response = app
+            .get("/")
+            .header("Origin", "https://example.com")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::Unauthorized);
+    }
+}
@@ -0,0 +1,63 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::errors::Result;
+use crate::rpc::QueryEntriesError;
+
+/// Returns an opaque pagination cursor pointing at the given position.
+///
+/// Cursors are hex encoded JSON to keep clients from depending on their contents.
+pub fn encode_cursor<T: Serialize>(position: &T) -> String {
+    // Serializing positions of entries and documents can not fail
+    hex::encode(serde_json::to_vec(position).unwrap())
+}
+
+/// Returns the position a pagination cursor points at.
+pub fn decode_cursor<T: DeserializeOwned>(cursor: &str) -> Result<T> {
+    let bytes = hex::decode(cursor).map_err(|_| QueryEntriesError::InvalidCursor)?;
+    Ok(serde_json::from_slice(&bytes).map_err(|_| QueryEntriesError::InvalidCursor)?)
+}
+
+/// Returns a cursor pointing at the last item of a page, `None` when the page was not full and
+/// there are no further results.
+pub fn next_cursor<I, T, F>(items: &[I], limit: u32, position: F) -> Option<String>
+where
+    T: Serialize,
+    F: Fn(&I) -> Option<T>,
+{
+    if items.len() < limit as usize {
+        return None;
+    }
+
+    items
+        .last()
+        .and_then(position)
+        .map(|position| encode_cursor(&position))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_cursor, encode_cursor, next_cursor};
+
+    #[test]
+    fn encode_and_decode_cursors() {
+        let cursor = encode_cursor(&(3, "abc".to_owned()));
+        assert_eq!(
+            decode_cursor::<(i64, String)>(&cursor).unwrap(),
+            (3, "abc".to_owned())
+        );
+
+        assert!(decode_cursor::<(i64, String)>("xyz").is_err());
+        assert!(decode_cursor::<(i64, String)>(&encode_cursor(&"abc")).is_err());
+    }
+
+    #[test]
+    fn cursor_of_full_pages() {
+        let items = vec![1, 2, 3];
+        assert_eq!(next_cursor(&items, 4, |item| Some(*item)), None);
+        assert_eq!(
+            next_cursor(&items, 3, |item| Some(*item)),
+            Some(encode_cursor(&3))
+        );
+    }
+}
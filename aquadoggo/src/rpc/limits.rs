@@ -0,0 +1,122 @@
+use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config::Configuration;
+use crate::rpc::rate_limit::RateLimit;
+
+/// Limits protecting the node from slow and too many concurrent RPC requests.
+#[derive(Debug, Clone, Default)]
+pub struct RequestLimits {
+    /// Maximum time to handle a request, requests are not limited when not set.
+    timeout: Option<Duration>,
+
+    /// Maximum number of requests handled at the same time, unlimited when not set.
+    max_in_flight: Option<usize>,
+
+    /// Number of requests currently handled.
+    in_flight: Arc<AtomicUsize>,
+
+    /// Maximum number of requests per minute from a single IP address, unlimited when not set.
+    rate_limit: Option<RateLimit>,
+}
+
+impl RequestLimits {
+    /// Returns the limits configured for this node.
+    pub fn new(config: &Configuration) -> Self {
+        Self {
+            timeout: match config.rpc_request_timeout {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            },
+            max_in_flight: match config.rpc_max_in_flight_requests {
+                0 => None,
+                max => Some(max),
+            },
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            rate_limit: match config.rpc_rate_limit {
+                0 => None,
+                limit => Some(RateLimit::new(limit)),
+            },
+        }
+    }
+
+    /// Returns the maximum time to handle a request.
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Registers a request from the given IP address against the rate limit.
+    ///
+    /// Returns the time until the client can send requests again when it exceeded the limit.
+    /// Requests without an IP address, like the ones via the unix domain socket, are not limited.
+    pub fn check_rate(&self, ip: Option<IpAddr>) -> Option<Duration> {
+        match (&self.rate_limit, ip) {
+            (Some(rate_limit), Some(ip)) => rate_limit.check(ip, Instant::now()),
+            _ => None,
+        }
+    }
+
+    /// Registers a new request, returns `None` when the maximum number of requests is already
+    /// handled.
+    ///
+    /// The request counts as handled until the returned guard is dropped.
+    pub fn acquire(&self) -> Option<InFlightGuard> {
+        let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = InFlightGuard(self.in_flight.clone());
+
+        match self.max_in_flight {
+            Some(max) if in_flight >= max => None,
+            _ => Some(guard),
+        }
+    }
+}
+
+/// Request handled by the node, releases its slot when dropped.
+#[derive(Debug)]
+pub struct InFlightGuard(Arc<AtomicUsize>);
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RequestLimits;
+
+    use crate::config::Configuration;
+
+    #[test]
+    fn limit_requests_in_flight() {
+        let config = Configuration {
+            rpc_request_timeout: 5,
+            rpc_max_in_flight_requests: 2,
+            ..Configuration::default()
+        };
+        let limits = RequestLimits::new(&config);
+        assert_eq!(limits.timeout(), Some(Duration::from_secs(5)));
+
+        let first = limits.acquire().unwrap();
+        let _second = limits.acquire().unwrap();
+        assert!(limits.acquire().is_none());
+
+        // Finished requests release their slot
+        drop(first);
+        assert!(limits.acquire().is_some());
+
+        // Everything is unlimited when set to 0
+        let config = Configuration {
+            rpc_request_timeout: 0,
+            rpc_max_in_flight_requests: 0,
+            ..Configuration::default()
+        };
+        let limits = RequestLimits::new(&config);
+        assert!(limits.timeout().is_none());
+        let _guards: Vec<_> = (0..100).map(|_| limits.acquire().unwrap()).collect();
+    }
+}
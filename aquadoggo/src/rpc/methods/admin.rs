@@ -0,0 +1,343 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+use tracing::info;
+
+use crate::errors::{Error, Result};
+use crate::rpc::request::{PrunePayloadsRequest, PurgeAuthorRequest, PurgeLogRequest};
+use crate::rpc::response::{CreateBackupResponse, PrunePayloadsResponse, PurgeResponse};
+use crate::rpc::RpcApiState;
+
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_copy_implementations)]
+pub enum AdminError {
+    #[error("Admin methods are disabled on this node")]
+    Disabled,
+
+    #[error("Backups are disabled on this node")]
+    BackupsDisabled,
+}
+
+/// Returns an error when admin methods are disabled.
+///
+/// Clients calling admin methods are authenticated with the admin token as bearer token before
+/// their request gets handled.
+fn enabled(data: &RpcApiState) -> Result<()> {
+    match &data.admin_token {
+        Some(_) => Ok(()),
+        None => Err(AdminError::Disabled.into()),
+    }
+}
+
+/// Implementation of `panda_purgeAuthor` RPC method.
+///
+/// Removes all entries and logs of an author including their materialized documents. Requires
+/// the admin token configured for this node.
+pub async fn purge_author(
+    data: Data<RpcApiState>,
+    Params(params): Params<PurgeAuthorRequest>,
+) -> Result<PurgeResponse> {
+    enabled(&data)?;
+
+    // Validate request parameters
+    params.author.validate()?;
+
+    let entries = data.storage.purge_author(&params.author).await?;
+    info!(
+        "Purged {} entries of author {}",
+        entries,
+        params.author.as_str()
+    );
+
+    Ok(PurgeResponse { entries })
+}
+
+/// Implementation of `panda_purgeLog` RPC method.
+///
+/// Removes all entries of an author's log and the log itself including its materialized
+/// documents. Requires the admin token configured for this node.
+pub async fn purge_log(
+    data: Data<RpcApiState>,
+    Params(params): Params<PurgeLogRequest>,
+) -> Result<PurgeResponse> {
+    enabled(&data)?;
+
+    // Validate request parameters
+    params.author.validate()?;
+
+    let entries = data
+        .storage
+        .purge_log(&params.author, &params.log_id)
+        .await?;
+    info!(
+        "Purged {} entries of log {} of author {}",
+        entries,
+        params.log_id.as_i64(),
+        params.author.as_str()
+    );
+
+    Ok(PurgeResponse { entries })
+}
+
+/// Implementation of `panda_prunePayloads` RPC method.
+///
+/// Removes the payloads of all but the latest `keep` entries of every log of a schema. The entries
+/// stay stored, they can still be verified and used as links of new entries. Requires the admin
+/// token configured for this node.
+pub async fn prune_payloads(
+    data: Data<RpcApiState>,
+    Params(params): Params<PrunePayloadsRequest>,
+) -> Result<PrunePayloadsResponse> {
+    enabled(&data)?;
+
+    // Validate request parameters
+    params.schema.validate()?;
+
+    let payloads = data
+        .storage
+        .prune_payloads(&params.schema, params.keep)
+        .await?;
+    info!(
+        "Pruned {} payloads of schema {}",
+        payloads,
+        params.schema.as_str()
+    );
+
+    Ok(PrunePayloadsResponse { payloads })
+}
+
+/// Implementation of `panda_createBackup` RPC method.
+///
+/// Writes a snapshot of the database to the backup directory of this node and returns its path.
+/// Requires the admin token configured for this node.
+pub async fn create_backup(data: Data<RpcApiState>) -> Result<CreateBackupResponse> {
+    enabled(&data)?;
+
+    let backups = data.backups.as_ref().ok_or(AdminError::BackupsDisabled)?;
+    let path = backups.create().await.map_err(Error::Backup)?;
+
+    Ok(CreateBackupResponse {
+        path: path.display().to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use http_types::StatusCode;
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use tide_testing::TideTestingExt;
+
+    use crate::db::{EntryStore, MemoryStorage};
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::auth::secure_eq;
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, RpcServer, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry, handle_http, rpc_error, rpc_request, rpc_response,
+    };
+
+    // Send request with the admin token as bearer token and return the response
+    async fn handle_admin_http(app: &RpcServer, request: String) -> String {
+        let response_body: serde_json::value::Value = app
+            .post("/")
+            .body(tide::Body::from_string(request))
+            .content_type("application/json")
+            .header("Authorization", "Bearer secret")
+            .recv_json()
+            .await
+            .unwrap();
+
+        response_body.to_string()
+    }
+
+    #[test]
+    fn compare_tokens() {
+        assert!(secure_eq("secret", "secret"));
+        assert!(!secure_eq("secret", "secreT"));
+        assert!(!secure_eq("secret", "secret2"));
+    }
+
+    #[async_std::test]
+    async fn purge_author_and_log() {
+        let storage = MemoryStorage::new();
+
+        // Store entries in two logs of the same author
+        let key_pair = KeyPair::new();
+        let mut entries = Vec::new();
+        for (log_id, schema) in [(1, vec![1, 2, 3]), (2, vec![4, 5, 6])].iter() {
+            let (entry, message) = create_test_entry(
+                &key_pair,
+                &Hash::new_from_bytes(schema.clone()).unwrap(),
+                &LogId::new(*log_id),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        let author = entries[0].author().as_str().to_owned();
+        let options = RpcApiOptions {
+            admin_token: Some("secret".to_owned()),
+            ..RpcApiOptions::default()
+        };
+        let app = build_rpc_server(build_rpc_api_service(storage.clone(), options));
+
+        // Admin methods require the configured token as bearer token
+        let request = rpc_request(
+            "panda_purgeLog",
+            &format!(
+                r#"{{
+                    "author": "{}",
+                    "logId": 1
+                }}"#,
+                author
+            ),
+        );
+        let response = app
+            .post("/")
+            .body(tide::Body::from_string(request.clone()))
+            .content_type("application/json")
+            .header("Authorization", "Bearer wrong")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::Unauthorized);
+
+        assert_eq!(
+            handle_admin_http(&app, request).await,
+            rpc_response(r#"{"entries": 1}"#)
+        );
+
+        let request = rpc_request(
+            "panda_purgeAuthor",
+            &format!(
+                r#"{{
+                    "author": "{}"
+                }}"#,
+                author
+            ),
+        );
+        assert_eq!(
+            handle_admin_http(&app, request).await,
+            rpc_response(r#"{"entries": 1}"#)
+        );
+
+        assert!(storage
+            .query_entries(None, None, None, 10, 0)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[async_std::test]
+    async fn prune_payloads() {
+        let storage = MemoryStorage::new();
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2] <-- [3]
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        let (entry_3, message_3) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_2),
+            &SeqNum::new(3).unwrap(),
+        );
+
+        for (entry, message) in [(&entry_1, &message_1), (&entry_2, &message_2)].iter() {
+            store_entry(&storage, &Subscriptions::new(), entry, message)
+                .await
+                .unwrap();
+        }
+
+        let options = RpcApiOptions {
+            admin_token: Some("secret".to_owned()),
+            ..RpcApiOptions::default()
+        };
+        let app = build_rpc_server(build_rpc_api_service(storage.clone(), options));
+
+        let request = rpc_request(
+            "panda_prunePayloads",
+            &format!(
+                r#"{{
+                    "schema": "{}"
+                }}"#,
+                schema.as_str()
+            ),
+        );
+        assert_eq!(
+            handle_admin_http(&app, request).await,
+            rpc_response(r#"{"payloads": 2}"#)
+        );
+
+        // Entries stay stored without their payloads
+        let entries = storage
+            .query_entries(None, Some(&schema), None, 10, 0)
+            .await
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries.iter().all(|entry| entry.payload_bytes.is_none()));
+
+        // Entries with pruned payloads can still be used as links of new entries
+        store_entry(&storage, &Subscriptions::new(), &entry_3, &message_3)
+            .await
+            .unwrap();
+    }
+
+    #[async_std::test]
+    async fn admin_methods_disabled() {
+        let app = build_rpc_server(build_rpc_api_service(
+            MemoryStorage::new(),
+            RpcApiOptions::default(),
+        ));
+
+        let request = rpc_request(
+            "panda_purgeAuthor",
+            r#"{
+                "author": "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a"
+            }"#,
+        );
+        assert_eq!(
+            handle_http(&app, request).await,
+            rpc_error("Admin methods are disabled on this node")
+        );
+    }
+
+    #[async_std::test]
+    async fn backups_disabled() {
+        let options = RpcApiOptions {
+            admin_token: Some("secret".to_owned()),
+            ..RpcApiOptions::default()
+        };
+        let app = build_rpc_server(build_rpc_api_service(MemoryStorage::new(), options));
+
+        let request = rpc_request("panda_createBackup", "{}");
+        assert_eq!(
+            handle_admin_http(&app, request).await,
+            rpc_error("Backups are disabled on this node")
+        );
+    }
+}
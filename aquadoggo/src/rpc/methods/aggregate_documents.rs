@@ -0,0 +1,130 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::materializer::DocumentAggregation;
+use crate::rpc::request::AggregateDocumentsRequest;
+use crate::rpc::response::AggregateDocumentsResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_aggregateDocuments` RPC method.
+///
+/// Applies aggregate functions like `count`, `min` and `max` to the materialized documents of a
+/// schema matching the filters. Documents are grouped by the values of a field when `groupBy` is
+/// given, this allows clients to show statistics without downloading every document.
+pub async fn aggregate_documents(
+    data: Data<RpcApiState>,
+    Params(params): Params<AggregateDocumentsRequest>,
+) -> Result<AggregateDocumentsResponse> {
+    // Validate request parameters
+    params.schema.validate()?;
+
+    let aggregation = DocumentAggregation {
+        filters: params.filters,
+        group_by: params.group_by,
+        aggregates: params.aggregates,
+    };
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let groups = storage
+        .aggregate_documents(&params.schema, &aggregation)
+        .await?;
+
+    Ok(AggregateDocumentsResponse { groups })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::message::{Message, MessageFields, MessageValue};
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry_with_message, handle_http, rpc_error, rpc_request, rpc_response,
+    };
+
+    #[async_std::test]
+    async fn aggregate_documents_by_group() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        // Authors create documents on different days
+        for (day, views) in [("monday", 3), ("monday", 5), ("tuesday", 1)].iter() {
+            let mut fields = MessageFields::new();
+            fields
+                .add("day", MessageValue::Text(day.to_string()))
+                .unwrap();
+            fields.add("views", MessageValue::Integer(*views)).unwrap();
+            let message = Message::new_create(schema.clone(), fields).unwrap();
+
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+        }
+
+        let request = rpc_request(
+            "panda_aggregateDocuments",
+            &format!(
+                r#"{{
+                    "schema": "{}",
+                    "groupBy": "day",
+                    "aggregates": [
+                        {{ "function": "count" }},
+                        {{ "function": "max", "field": "views" }}
+                    ]
+                }}"#,
+                schema.as_str(),
+            ),
+        );
+
+        let response = rpc_response(
+            r#"{
+                "groups": [
+                    { "group": "monday", "values": [2, 5] },
+                    { "group": "tuesday", "values": [1, 1] }
+                ]
+            }"#,
+        );
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Minimum and maximum need a field
+        let request = rpc_request(
+            "panda_aggregateDocuments",
+            &format!(
+                r#"{{
+                    "schema": "{}",
+                    "aggregates": [{{ "function": "min" }}]
+                }}"#,
+                schema.as_str(),
+            ),
+        );
+
+        assert_eq!(
+            handle_http(&app, request).await,
+            rpc_error(
+                "Aggregate function needs a field, only documents can be counted without one"
+            )
+        );
+    }
+}
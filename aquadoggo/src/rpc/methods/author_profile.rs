@@ -0,0 +1,112 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::request::AuthorProfileRequest;
+use crate::rpc::response::AuthorProfileResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getAuthorProfile` RPC method.
+///
+/// Returns the profile an author published with the author profile system schema together with
+/// the key groups the author is a member of, or `null` when the author has no profile.
+pub async fn get_author_profile(
+    data: Data<RpcApiState>,
+    Params(params): Params<AuthorProfileRequest>,
+) -> Result<Option<AuthorProfileResponse>> {
+    // Validate request parameters
+    params.author.validate()?;
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let profile = match storage.author_profile(&params.author).await? {
+        Some(profile) => profile,
+        None => return Ok(None),
+    };
+
+    let key_groups = storage.key_groups_by_member(&params.author).await?;
+
+    Ok(Some(AuthorProfileResponse {
+        author: profile.author,
+        display_name: profile.display_name,
+        entry_hash: profile.entry_hash,
+        seq_num: profile.seq_num,
+        key_groups,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::SeqNum;
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use crate::db::models::{AuthorProfile, KeyGroup};
+    use crate::db::SqlStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{
+        handle_http, initialize_db, random_entry_hash, rpc_request, rpc_response,
+    };
+
+    const TEST_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
+    #[async_std::test]
+    async fn get_author_profile() {
+        let pool = initialize_db().await;
+        let author = Author::new(TEST_AUTHOR).unwrap();
+
+        let request = rpc_request(
+            "panda_getAuthorProfile",
+            &format!(
+                r#"{{
+                    "author": "{}"
+                }}"#,
+                author.as_str(),
+            ),
+        );
+
+        let app = build_rpc_server(build_rpc_api_service(
+            SqlStorage::new(pool.clone()),
+            RpcApiOptions::default(),
+        ));
+
+        // Authors without a profile are unknown
+        assert_eq!(
+            handle_http(&app, request.clone()).await,
+            rpc_response("null")
+        );
+
+        let entry_hash = Hash::new(&random_entry_hash()).unwrap();
+        AuthorProfile::upsert(
+            &pool,
+            &author,
+            "Panda",
+            &entry_hash,
+            &SeqNum::new(3).unwrap(),
+        )
+        .await
+        .unwrap();
+
+        let key_group = Hash::new(&random_entry_hash()).unwrap();
+        KeyGroup::insert(&pool, &key_group, &author, "Pandas")
+            .await
+            .unwrap();
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "author": "{}",
+                "displayName": "Panda",
+                "entryHash": "{}",
+                "keyGroups": ["{}"],
+                "seqNum": 3
+            }}"#,
+            author.as_str(),
+            entry_hash.as_str(),
+            key_group.as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
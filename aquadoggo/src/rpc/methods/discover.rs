@@ -0,0 +1,39 @@
+use serde_json::Value;
+
+use crate::errors::Result;
+use crate::rpc::openrpc::openrpc_document;
+
+/// Implementation of `rpc.discover` RPC method.
+///
+/// Returns an OpenRPC document describing all methods with their parameters and results, which
+/// can be used to generate clients in other languages.
+pub async fn discover() -> Result<Value> {
+    Ok(openrpc_document())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::Value;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{handle_http, rpc_request};
+
+    #[async_std::test]
+    async fn discover() {
+        let app = build_rpc_server(build_rpc_api_service(
+            MemoryStorage::new(),
+            RpcApiOptions::default(),
+        ));
+
+        let request = rpc_request("rpc.discover", "{}");
+        let response: Value = serde_json::from_str(&handle_http(&app, request).await).unwrap();
+
+        assert_eq!(response["result"]["openrpc"], "1.2.6");
+        assert_eq!(
+            response["result"]["methods"][0]["name"],
+            "panda_aggregateDocuments"
+        );
+    }
+}
@@ -0,0 +1,299 @@
+use std::collections::BTreeMap;
+
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::message::{Message, MessageAction, MessageEncoded, MessageValue};
+use p2panda_rs::Validate;
+
+use crate::db::StorageProvider;
+use crate::errors::Result;
+use crate::materializer::{Document, DocumentVersion, MaterializerError};
+use crate::rpc::request::DocumentRequest;
+use crate::rpc::response::DocumentResponse;
+use crate::rpc::RpcApiState;
+
+/// Returns the response of a materialized document of a schema without resolved relations.
+pub(crate) fn document_response(schema: Hash, document: Document) -> DocumentResponse {
+    DocumentResponse {
+        document_id: document.document_id,
+        schema,
+        author: document.author,
+        entry_hash: document.entry_hash,
+        seq_num: document.seq_num,
+        fields: document.fields,
+        deleted_at: document.deleted_at,
+        deleted_by: document.deleted_by,
+        relations: BTreeMap::new(),
+    }
+}
+
+/// Embed the current state of the documents referenced by the relation fields of the given
+/// documents.
+///
+/// All referenced documents are fetched at once, relations of the embedded documents are not
+/// resolved.
+pub(crate) async fn resolve_relations(
+    storage: &dyn StorageProvider,
+    documents: &mut [DocumentResponse],
+) -> Result<()> {
+    let mut document_ids: Vec<Hash> = Vec::new();
+    for document in documents.iter() {
+        for value in document.fields.values() {
+            if let MessageValue::Relation(document_id) = value {
+                if !document_ids.contains(document_id) {
+                    document_ids.push(document_id.clone());
+                }
+            }
+        }
+    }
+
+    if document_ids.is_empty() {
+        return Ok(());
+    }
+
+    let related = storage.documents_by_id(&document_ids).await?;
+
+    for document in documents.iter_mut() {
+        for (name, value) in document.fields.iter() {
+            let document_id = match value {
+                MessageValue::Relation(document_id) => document_id,
+                _ => continue,
+            };
+
+            // Unknown and deleted documents are not embedded
+            if let Some((schema, related_document)) = related
+                .iter()
+                .find(|(_, related)| related.document_id == document_id.as_str())
+            {
+                document.relations.insert(
+                    name.to_owned(),
+                    document_response(schema.clone(), related_document.clone()),
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Implementation of `panda_getDocument` RPC method.
+///
+/// Returns the materialized state of the document with the given id, which is the hash of the
+/// entry which created it. Returns `null` when the document is unknown, was deleted or was not
+/// materialized yet.
+///
+/// Earlier states of the document are reconstructed from its history when a sequence number or
+/// timestamp is given. With `resolveRelations` the current state of documents referenced by
+/// relation fields is embedded in `relations`.
+pub async fn get_document(
+    data: Data<RpcApiState>,
+    Params(params): Params<DocumentRequest>,
+) -> Result<Option<DocumentResponse>> {
+    // Validate request parameters
+    params.document_id.validate()?;
+
+    let version = match (&params.as_of_seq_num, params.as_of_timestamp) {
+        (None, None) => None,
+        (Some(seq_num), None) => Some(DocumentVersion::SeqNum(seq_num.as_i64())),
+        (None, Some(timestamp)) => Some(DocumentVersion::Timestamp(timestamp)),
+        (Some(_), Some(_)) => return Err(MaterializerError::AmbiguousVersion.into()),
+    };
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    // Find schema of document in the message which created it
+    let payload_bytes = match storage.entry_by_hash(&params.document_id).await? {
+        Some(entry) => match entry.payload_bytes {
+            Some(payload_bytes) => payload_bytes,
+            None => return Ok(None),
+        },
+        None => return Ok(None),
+    };
+
+    let message = Message::from(&MessageEncoded::new(&payload_bytes)?);
+    if !matches!(message.action(), MessageAction::Create) {
+        return Ok(None);
+    }
+
+    let schema = message.schema().clone();
+    let document = match version {
+        Some(version) => {
+            storage
+                .document_version(&schema, &params.document_id, version)
+                .await?
+        }
+        None => storage.document(&schema, &params.document_id).await?,
+    };
+
+    let mut document = match document {
+        Some(document) => document_response(schema, document),
+        None => return Ok(None),
+    };
+
+    if params.resolve_relations {
+        resolve_relations(storage.as_ref(), std::slice::from_mut(&mut document)).await?;
+    }
+
+    Ok(Some(document))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::message::{Message, MessageFields, MessageValue};
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry_with_message, handle_http, random_entry_hash, rpc_request, rpc_response,
+    };
+
+    fn fields(value: &str) -> MessageFields {
+        let mut fields = MessageFields::new();
+        fields
+            .add("message", MessageValue::Text(value.to_owned()))
+            .unwrap();
+        fields
+    }
+
+    #[async_std::test]
+    async fn get_updated_document() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // Create and update a document
+        let message = Message::new_create(schema.clone(), fields("Hello")).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry_1, &message_1)
+            .await
+            .unwrap();
+
+        let message = Message::new_update(schema.clone(), entry_1.hash(), fields("Panda")).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry_2, &message_2)
+            .await
+            .unwrap();
+
+        let request = rpc_request(
+            "panda_getDocument",
+            &format!(
+                r#"{{
+                    "documentId": "{}"
+                }}"#,
+                entry_1.hash().as_str(),
+            ),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "documentId": "{}",
+                "schema": "{}",
+                "author": "{}",
+                "entryHash": "{}",
+                "seqNum": 2,
+                "fields": {{
+                    "message": {{
+                        "type": "str",
+                        "value": "Panda"
+                    }}
+                }}
+            }}"#,
+            entry_1.hash().as_str(),
+            schema.as_str(),
+            entry_1.author().as_str(),
+            entry_2.hash().as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Earlier states are reconstructed from the history of the document
+        let request = rpc_request(
+            "panda_getDocument",
+            &format!(
+                r#"{{
+                    "documentId": "{}",
+                    "asOfSeqNum": 1
+                }}"#,
+                entry_1.hash().as_str(),
+            ),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "documentId": "{}",
+                "schema": "{}",
+                "author": "{}",
+                "entryHash": "{}",
+                "seqNum": 1,
+                "fields": {{
+                    "message": {{
+                        "type": "str",
+                        "value": "Hello"
+                    }}
+                }}
+            }}"#,
+            entry_1.hash().as_str(),
+            schema.as_str(),
+            entry_1.author().as_str(),
+            entry_1.hash().as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Updates are not documents themselves
+        let request = rpc_request(
+            "panda_getDocument",
+            &format!(
+                r#"{{
+                    "documentId": "{}"
+                }}"#,
+                entry_2.hash().as_str(),
+            ),
+        );
+        assert_eq!(handle_http(&app, request).await, rpc_response("null"));
+    }
+
+    #[async_std::test]
+    async fn get_unknown_document() {
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let request = rpc_request(
+            "panda_getDocument",
+            &format!(
+                r#"{{
+                    "documentId": "{}"
+                }}"#,
+                random_entry_hash(),
+            ),
+        );
+
+        assert_eq!(handle_http(&app, request).await, rpc_response("null"));
+    }
+}
@@ -0,0 +1,235 @@
+use jsonrpc_v2::{Data, Params};
+use libp2p::identity::ed25519::PublicKey;
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::Validate;
+
+use crate::db::unix_timestamp;
+use crate::errors::Result;
+use crate::rpc::request::EncryptedMessagesRequest;
+use crate::rpc::response::EncryptedMessagesResponse;
+use crate::rpc::RpcApiState;
+
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_copy_implementations)]
+pub enum EncryptedMessagesError {
+    #[error("Invalid signature of the key group id")]
+    InvalidSignature,
+
+    #[error("Signature expired, timestamp needs to be within {0} seconds of the node's time")]
+    ExpiredSignature(i64),
+
+    #[error("Author is not a member of the key group")]
+    NotAMember,
+}
+
+/// Number of seconds a signature is accepted before or after its timestamp.
+const SIGNATURE_WINDOW: i64 = 60;
+
+/// Returns the challenge a member signs to request the messages of a key group.
+///
+/// The timestamp makes sure that signatures can not be used again once they expired.
+fn signature_challenge(key_group: &Hash, timestamp: i64) -> String {
+    format!("{}:{}", key_group.as_str(), timestamp)
+}
+
+/// Returns true when the hex encoded signature of the challenge was created by the author.
+fn verify_signature(author: &Author, challenge: &str, signature: &str) -> bool {
+    let public_key = match hex::decode(author.as_str())
+        .ok()
+        .and_then(|bytes| PublicKey::decode(&bytes).ok())
+    {
+        Some(public_key) => public_key,
+        None => return false,
+    };
+
+    match hex::decode(signature) {
+        Ok(signature) => public_key.verify(challenge.as_bytes(), &signature),
+        Err(_) => false,
+    }
+}
+
+/// Implementation of `panda_getEncryptedMessages` RPC method.
+///
+/// Returns all messages encrypted for a key group as ciphertext together with the key group and
+/// its members, or `null` when the key group is unknown. Only members of the key group proving
+/// their public key with a signature of `<key group id>:<timestamp>` get the messages, the
+/// timestamp needs to be within `SIGNATURE_WINDOW` seconds of the node's time.
+pub async fn get_encrypted_messages(
+    data: Data<RpcApiState>,
+    Params(params): Params<EncryptedMessagesRequest>,
+) -> Result<Option<EncryptedMessagesResponse>> {
+    // Validate request parameters
+    params.key_group.validate()?;
+    params.member.validate()?;
+
+    let challenge = signature_challenge(&params.key_group, params.timestamp);
+    if !verify_signature(&params.member, &challenge, &params.signature) {
+        Err(EncryptedMessagesError::InvalidSignature)?;
+    }
+
+    // Reject old signatures so captured requests can not be replayed later
+    if (unix_timestamp() - params.timestamp).abs() > SIGNATURE_WINDOW {
+        Err(EncryptedMessagesError::ExpiredSignature(SIGNATURE_WINDOW))?;
+    }
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let key_group = match storage.key_group(&params.key_group).await? {
+        Some(key_group) => key_group,
+        None => return Ok(None),
+    };
+
+    let members = storage.key_group_members(&params.key_group).await?;
+    if !members.contains(&params.member) {
+        Err(EncryptedMessagesError::NotAMember)?;
+    }
+
+    let messages = storage.encrypted_messages(&params.key_group).await?;
+
+    Ok(Some(EncryptedMessagesResponse {
+        key_group: key_group.key_group,
+        owner: key_group.owner,
+        name: key_group.name,
+        members,
+        messages,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use libp2p::identity::ed25519::Keypair;
+    use p2panda_rs::entry::SeqNum;
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use crate::db::models::{EncryptedMessage, KeyGroup};
+    use crate::db::{unix_timestamp, SqlStorage};
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{
+        handle_http, initialize_db, random_entry_hash, rpc_error, rpc_request, rpc_response,
+    };
+
+    use super::signature_challenge;
+
+    fn request_at(key_group: &Hash, keypair: &Keypair, timestamp: i64) -> String {
+        let challenge = signature_challenge(key_group, timestamp);
+
+        rpc_request(
+            "panda_getEncryptedMessages",
+            &format!(
+                r#"{{
+                    "keyGroup": "{}",
+                    "member": "{}",
+                    "timestamp": {},
+                    "signature": "{}"
+                }}"#,
+                key_group.as_str(),
+                hex::encode(keypair.public().encode()),
+                timestamp,
+                hex::encode(keypair.sign(challenge.as_bytes())),
+            ),
+        )
+    }
+
+    fn request(key_group: &Hash, keypair: &Keypair) -> String {
+        request_at(key_group, keypair, unix_timestamp())
+    }
+
+    #[async_std::test]
+    async fn get_encrypted_messages() {
+        let pool = initialize_db().await;
+        let key_group = Hash::new(&random_entry_hash()).unwrap();
+
+        let owner = Keypair::generate();
+        let owner_author = Author::new(&hex::encode(owner.public().encode())).unwrap();
+
+        KeyGroup::insert(&pool, &key_group, &owner_author, "Pandas")
+            .await
+            .unwrap();
+
+        let entry_hash = Hash::new(&random_entry_hash()).unwrap();
+        EncryptedMessage::insert(
+            &pool,
+            &EncryptedMessage {
+                entry_hash: entry_hash.clone(),
+                key_group: key_group.clone(),
+                author: owner_author.clone(),
+                seq_num: SeqNum::new(1).unwrap(),
+                ciphertext: "c2VjcmV0".to_owned(),
+            },
+        )
+        .await
+        .unwrap();
+
+        let app = build_rpc_server(build_rpc_api_service(
+            SqlStorage::new(pool),
+            RpcApiOptions::default(),
+        ));
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "keyGroup": "{}",
+                "members": ["{}"],
+                "messages": [{{
+                    "author": "{}",
+                    "ciphertext": "c2VjcmV0",
+                    "entryHash": "{}",
+                    "keyGroup": "{}",
+                    "seqNum": 1
+                }}],
+                "name": "Pandas",
+                "owner": "{}"
+            }}"#,
+            key_group.as_str(),
+            owner_author.as_str(),
+            owner_author.as_str(),
+            entry_hash.as_str(),
+            key_group.as_str(),
+            owner_author.as_str(),
+        ));
+
+        assert_eq!(
+            handle_http(&app, request(&key_group, &owner)).await,
+            response
+        );
+
+        // Other authors can not read the messages
+        assert_eq!(
+            handle_http(&app, request(&key_group, &Keypair::generate())).await,
+            rpc_error("Author is not a member of the key group")
+        );
+
+        // Signatures need to be created by the member
+        let forged = request(&key_group, &owner).replace(
+            &hex::encode(owner.public().encode()),
+            &hex::encode(Keypair::generate().public().encode()),
+        );
+        assert_eq!(
+            handle_http(&app, forged).await,
+            rpc_error("Invalid signature of the key group id")
+        );
+
+        // Signatures can not be used again after they expired
+        let stale = request_at(&key_group, &owner, unix_timestamp() - 3600);
+        assert_eq!(
+            handle_http(&app, stale.clone()).await,
+            rpc_error(
+                "Signature expired, timestamp needs to be within 60 seconds of the node's time"
+            )
+        );
+
+        // Changing the timestamp of a replayed request invalidates its signature
+        let replayed = stale.replace(
+            &format!("\"timestamp\": {}", unix_timestamp() - 3600),
+            &format!("\"timestamp\": {}", unix_timestamp()),
+        );
+        assert_ne!(replayed, stale);
+        assert_eq!(
+            handle_http(&app, replayed).await,
+            rpc_error("Invalid signature of the key group id")
+        );
+    }
+}
@@ -0,0 +1,177 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::entry::{LogId, SeqNum};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::cursor::{decode_cursor, next_cursor};
+use crate::rpc::methods::query_entries::{QueryEntriesError, DEFAULT_LIMIT, MAX_LIMIT};
+use crate::rpc::request::EntriesByAuthorRequest;
+use crate::rpc::response::EntriesByAuthorResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getEntriesByAuthor` RPC method.
+///
+/// Returns entries of an author within an inclusive range of sequence numbers, optionally only of
+/// one log. The range starts at the first entry when `seqNumFrom` is not given and is open ended
+/// when `seqNumTo` is not given. Entries are sorted by log id and sequence number, pass the
+/// `nextCursor` of a response as `cursor` to request the following page.
+pub async fn get_entries_by_author(
+    data: Data<RpcApiState>,
+    Params(params): Params<EntriesByAuthorRequest>,
+) -> Result<EntriesByAuthorResponse> {
+    // Validate request parameters
+    params.author.validate()?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit > MAX_LIMIT {
+        Err(QueryEntriesError::LimitTooLarge(MAX_LIMIT))?;
+    }
+
+    let seq_num_from = params.seq_num_from.unwrap_or_default();
+
+    let after: Option<(LogId, SeqNum)> = match &params.cursor {
+        Some(cursor) => Some(decode_cursor(cursor)?),
+        None => None,
+    };
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let entries = storage
+        .entries_by_author(
+            &params.author,
+            params.log_id.as_ref(),
+            &seq_num_from,
+            params.seq_num_to.as_ref(),
+            after.as_ref(),
+            limit as i64,
+        )
+        .await?;
+
+    let next_cursor = next_cursor(&entries, limit, |entry| {
+        Some((entry.log_id.clone(), entry.seq_num.clone()))
+    });
+
+    Ok(EntriesByAuthorResponse {
+        entries,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, handle_http, rpc_request};
+
+    #[async_std::test]
+    async fn get_entries_by_author_in_range() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let log_id = LogId::new(1);
+
+        // Create two user logs of the same author with three entries each
+        let mut stored = Vec::new();
+        for (index, schema) in [vec![1, 2, 3], vec![4, 5, 6]].iter().enumerate() {
+            let schema = Hash::new_from_bytes(schema.clone()).unwrap();
+            let log_id = LogId::new(index as i64 * 2 + 1);
+            let mut entries = Vec::new();
+
+            for seq_num in 1..4 {
+                let (entry, message) = create_test_entry(
+                    &key_pair,
+                    &schema,
+                    &log_id,
+                    if seq_num == 3 { entries.first() } else { None },
+                    entries.last(),
+                    &SeqNum::new(seq_num).unwrap(),
+                );
+                store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                    .await
+                    .unwrap();
+                entries.push(entry);
+            }
+
+            stored.append(&mut entries);
+        }
+
+        let author = stored[0].author();
+        let hashes: Vec<String> = stored
+            .iter()
+            .map(|entry| entry.hash().as_str().to_owned())
+            .collect();
+
+        // Entries of all logs starting from the second one
+        let request = rpc_request(
+            "panda_getEntriesByAuthor",
+            &format!(
+                r#"{{
+                    "author": "{}",
+                    "seqNumFrom": 2
+                }}"#,
+                author.as_str(),
+            ),
+        );
+        let response = handle_http(&app, request).await;
+        for (index, hash) in hashes.iter().enumerate() {
+            assert_eq!(response.contains(hash), index % 3 != 0);
+        }
+
+        // Second entry of first log only
+        let request = rpc_request(
+            "panda_getEntriesByAuthor",
+            &format!(
+                r#"{{
+                    "author": "{}",
+                    "logId": {},
+                    "seqNumFrom": 2,
+                    "seqNumTo": 2
+                }}"#,
+                author.as_str(),
+                log_id.as_i64(),
+            ),
+        );
+        let response = handle_http(&app, request).await;
+        for (index, hash) in hashes.iter().enumerate() {
+            assert_eq!(response.contains(hash), index == 1);
+        }
+
+        // Page through all entries with cursors
+        let mut cursor = serde_json::Value::Null;
+        let mut pages = Vec::new();
+        loop {
+            let request = rpc_request(
+                "panda_getEntriesByAuthor",
+                &format!(
+                    r#"{{
+                        "author": "{}",
+                        "limit": 4,
+                        "cursor": {}
+                    }}"#,
+                    author.as_str(),
+                    cursor,
+                ),
+            );
+            let response: serde_json::Value =
+                serde_json::from_str(&handle_http(&app, request).await).unwrap();
+            pages.push(response["result"]["entries"].as_array().unwrap().len());
+
+            cursor = response["result"]["nextCursor"].clone();
+            if cursor.is_null() {
+                break;
+            }
+        }
+        assert_eq!(pages, vec![4, 2]);
+    }
+}
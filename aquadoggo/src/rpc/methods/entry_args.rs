@@ -2,13 +2,14 @@ use bamboo_rs_core::entry::is_lipmaa_required;
 use jsonrpc_v2::{Data, Params};
 use p2panda_rs::entry::SeqNum;
 use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
 use p2panda_rs::Validate;
 
-use crate::db::models::{Entry, Log};
-use crate::db::Pool;
+use crate::db::models::Entry;
+use crate::db::StorageProvider;
 use crate::errors::Result;
-use crate::rpc::request::EntryArgsRequest;
-use crate::rpc::response::EntryArgsResponse;
+use crate::rpc::request::{EntryArgsBatchRequest, EntryArgsRequest};
+use crate::rpc::response::{EntryArgsBatchResponse, EntryArgsResponse};
 use crate::rpc::RpcApiState;
 
 /// Implementation of `panda_getEntryArguments` RPC method.
@@ -23,19 +24,48 @@ pub async fn get_entry_args(
     params.author.validate()?;
     params.schema.validate()?;
 
-    // Get database connection pool
-    let pool = data.pool.clone();
+    entry_args(&*data.storage, &params.author, &params.schema).await
+}
+
+/// Implementation of `panda_getEntryArgumentsBatch` RPC method.
+///
+/// Returns the same data as `panda_getEntryArguments` for a list of authors and schemas, in the
+/// order of the request. Log ids of schemas without any entries are determined independently from
+/// each other, so two new schemas of the same author get the same log id.
+pub async fn get_entry_args_batch(
+    data: Data<RpcApiState>,
+    Params(params): Params<EntryArgsBatchRequest>,
+) -> Result<EntryArgsBatchResponse> {
+    // Validate request parameters
+    for request in params.requests.iter() {
+        request.author.validate()?;
+        request.schema.validate()?;
+    }
 
+    let mut entry_arguments = Vec::with_capacity(params.requests.len());
+    for request in params.requests.iter() {
+        entry_arguments.push(entry_args(&*data.storage, &request.author, &request.schema).await?);
+    }
+
+    Ok(EntryArgsBatchResponse { entry_arguments })
+}
+
+/// Returns the arguments to encode the next entry of an author's schema.
+async fn entry_args(
+    storage: &dyn StorageProvider,
+    author: &Author,
+    schema: &Hash,
+) -> Result<EntryArgsResponse> {
     // Determine log_id for author's schema
-    let log_id = Log::find_schema_log_id(&pool, &params.author, &params.schema).await?;
+    let log_id = storage.find_schema_log_id(author, schema).await?;
 
     // Find latest entry in this log
-    let entry_latest = Entry::latest(&pool, &params.author, &log_id).await?;
-    
+    let entry_latest = storage.latest_entry(author, &log_id).await?;
+
     match entry_latest {
         Some(mut entry_backlink) => {
             // Determine skiplink ("lipmaa"-link) entry in this log
-            let entry_hash_skiplink = determine_skiplink(pool.clone(), &entry_backlink).await?;
+            let entry_hash_skiplink = determine_skiplink(storage, &entry_backlink).await?;
 
             Ok(EntryArgsResponse {
                 entry_hash_backlink: Some(entry_backlink.entry_hash),
@@ -55,7 +85,10 @@ pub async fn get_entry_args(
 
 /// Determine skiplink entry hash ("lipmaa"-link) for entry in this log, return `None` when no
 /// skiplink is required for the next entry.
-pub async fn determine_skiplink(pool: Pool, entry: &Entry) -> Result<Option<Hash>> {
+pub async fn determine_skiplink(
+    storage: &dyn StorageProvider,
+    entry: &Entry,
+) -> Result<Option<Hash>> {
     let next_seq_num = entry.seq_num.clone().next().unwrap();
 
     // Unwrap as we know that an skiplink exists as soon as previous entry is given
@@ -63,10 +96,10 @@ pub async fn determine_skiplink(pool: Pool, entry: &Entry) -> Result<Option<Hash
 
     // Check if skiplink is required and return hash if so
     let entry_skiplink_hash = if is_lipmaa_required(next_seq_num.as_i64() as u64) {
-        let skiplink_entry =
-            Entry::at_seq_num(&pool, &entry.author, &entry.log_id, &skiplink_seq_num)
-                .await?
-                .unwrap();
+        let skiplink_entry = storage
+            .entry_at_seq_num(&entry.author, &entry.log_id, &skiplink_seq_num)
+            .await?
+            .unwrap();
         Some(skiplink_entry.entry_hash)
     } else {
         None
@@ -77,18 +110,19 @@ pub async fn determine_skiplink(pool: Pool, entry: &Entry) -> Result<Option<Hash
 
 #[cfg(test)]
 mod tests {
-    use crate::rpc::api::build_rpc_api_service;
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
     use crate::rpc::server::build_rpc_server;
     use crate::test_helpers::{
-        handle_http, initialize_db, random_entry_hash, rpc_error, rpc_request, rpc_response,
+        handle_http, random_entry_hash, rpc_error, rpc_request, rpc_response,
     };
 
     const TEST_AUTHOR: &str = "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a";
 
     #[async_std::test]
     async fn respond_with_wrong_author_error() {
-        let pool = initialize_db().await;
-        let rpc_api = build_rpc_api_service(pool.clone());
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
         let app = build_rpc_server(rpc_api);
 
         let request = rpc_request(
@@ -109,11 +143,8 @@ mod tests {
 
     #[async_std::test]
     async fn get_entry_arguments() {
-        // Prepare test database
-        let pool = initialize_db().await;
-
-        // Create tide server with endpoints
-        let rpc_api = build_rpc_api_service(pool);
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
         let app = build_rpc_server(rpc_api);
 
         let request = rpc_request(
@@ -139,4 +170,69 @@ mod tests {
 
         assert_eq!(handle_http(&app, request).await, response);
     }
+
+    #[async_std::test]
+    async fn get_entry_arguments_batch() {
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let request = rpc_request(
+            "panda_getEntryArgumentsBatch",
+            &format!(
+                r#"{{
+                    "requests": [
+                        {{ "author": "{}", "schema": "{}" }},
+                        {{ "author": "{}", "schema": "{}" }}
+                    ]
+                }}"#,
+                TEST_AUTHOR,
+                random_entry_hash(),
+                TEST_AUTHOR,
+                random_entry_hash(),
+            ),
+        );
+
+        let response = rpc_response(
+            r#"{
+                "entryArguments": [
+                    {
+                        "entryHashBacklink": null,
+                        "entryHashSkiplink": null,
+                        "logId": 1,
+                        "seqNum": 1
+                    },
+                    {
+                        "entryHashBacklink": null,
+                        "entryHashSkiplink": null,
+                        "logId": 1,
+                        "seqNum": 1
+                    }
+                ]
+            }"#,
+        );
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Invalid requests fail the whole batch
+        let request = rpc_request(
+            "panda_getEntryArgumentsBatch",
+            &format!(
+                r#"{{
+                    "requests": [
+                        {{ "author": "{}", "schema": "{}" }},
+                        {{ "author": "1234", "schema": "{}" }}
+                    ]
+                }}"#,
+                TEST_AUTHOR,
+                random_entry_hash(),
+                random_entry_hash(),
+            ),
+        );
+
+        assert_eq!(
+            handle_http(&app, request).await,
+            rpc_error("invalid author key length")
+        );
+    }
 }
@@ -0,0 +1,119 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::request::EntryByHashRequest;
+use crate::rpc::response::EntryByHashResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getEntryByHash` RPC method.
+///
+/// Returns the encoded entry and its encoded message for the given entry hash or `null` when no
+/// entry was found.
+pub async fn get_entry_by_hash(
+    data: Data<RpcApiState>,
+    Params(params): Params<EntryByHashRequest>,
+) -> Result<Option<EntryByHashResponse>> {
+    // Validate request parameters
+    params.hash.validate()?;
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    // Find entry in database
+    let entry = storage.entry_by_hash(&params.hash).await?;
+
+    Ok(entry.map(|entry| EntryByHashResponse {
+        entry_encoded: entry.entry_bytes,
+        message_encoded: entry.payload_bytes,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{
+        create_test_entry, handle_http, random_entry_hash, rpc_request, rpc_response,
+    };
+
+    #[async_std::test]
+    async fn get_entry_by_hash() {
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        // Publish an entry first
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        let request = rpc_request(
+            "panda_publishEntry",
+            &format!(
+                r#"{{
+                    "entryEncoded": "{}",
+                    "messageEncoded": "{}"
+                }}"#,
+                entry.as_str(),
+                message.as_str(),
+            ),
+        );
+        handle_http(&app, request).await;
+
+        // Request it again by its hash
+        let request = rpc_request(
+            "panda_getEntryByHash",
+            &format!(
+                r#"{{
+                    "hash": "{}"
+                }}"#,
+                entry.hash().as_str(),
+            ),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "entryEncoded": "{}",
+                "messageEncoded": "{}"
+            }}"#,
+            entry.as_str(),
+            message.as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+
+    #[async_std::test]
+    async fn get_unknown_entry() {
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let request = rpc_request(
+            "panda_getEntryByHash",
+            &format!(
+                r#"{{
+                    "hash": "{}"
+                }}"#,
+                random_entry_hash(),
+            ),
+        );
+
+        let response = rpc_response("null");
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
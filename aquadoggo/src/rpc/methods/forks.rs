@@ -0,0 +1,122 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::request::ForksRequest;
+use crate::rpc::response::ForksResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getForks` RPC method.
+///
+/// Returns all detected forks of an author's logs, optionally only of one log. Each fork contains
+/// the hash of the entry stored on this node and the refused entry conflicting with it.
+pub async fn get_forks(
+    data: Data<RpcApiState>,
+    Params(params): Params<ForksRequest>,
+) -> Result<ForksResponse> {
+    // Validate request parameters
+    params.author.validate()?;
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let forks = storage
+        .forks(&params.author, params.log_id.as_ref())
+        .await?;
+
+    Ok(ForksResponse { forks })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry, handle_http, rpc_error, rpc_request, rpc_response,
+    };
+
+    #[async_std::test]
+    async fn detect_forks() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // Two different entries at the same position of the same log
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (fork_entry, fork_message) = create_test_entry(
+            &key_pair,
+            &Hash::new_from_bytes(vec![4, 5, 6]).unwrap(),
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+
+        let request = rpc_request(
+            "panda_publishEntry",
+            &format!(
+                r#"{{
+                    "entryEncoded": "{}",
+                    "messageEncoded": "{}"
+                }}"#,
+                fork_entry.as_str(),
+                fork_message.as_str(),
+            ),
+        );
+        assert_eq!(
+            handle_http(&app, request).await,
+            rpc_error("Entry conflicts with another entry at the same position of this log")
+        );
+
+        let request = rpc_request(
+            "panda_getForks",
+            &format!(
+                r#"{{
+                    "author": "{}",
+                    "logId": 1
+                }}"#,
+                entry.author().as_str(),
+            ),
+        );
+        let response = rpc_response(&format!(
+            r#"{{
+                "forks": [{{
+                    "author": "{}",
+                    "logId": 1,
+                    "seqNum": 1,
+                    "entryHash": "{}",
+                    "forkEntryBytes": "{}",
+                    "forkEntryHash": "{}"
+                }}]
+            }}"#,
+            entry.author().as_str(),
+            entry.hash().as_str(),
+            fork_entry.as_str(),
+            fork_entry.hash().as_str(),
+        ));
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
@@ -0,0 +1,110 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::request::KeyGroupRequest;
+use crate::rpc::response::KeyGroupResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getKeyGroup` RPC method.
+///
+/// Returns a key group created with the key group system schema together with its members, or
+/// `null` when the key group is unknown.
+pub async fn get_key_group(
+    data: Data<RpcApiState>,
+    Params(params): Params<KeyGroupRequest>,
+) -> Result<Option<KeyGroupResponse>> {
+    // Validate request parameters
+    params.key_group.validate()?;
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let key_group = match storage.key_group(&params.key_group).await? {
+        Some(key_group) => key_group,
+        None => return Ok(None),
+    };
+
+    let members = storage.key_group_members(&params.key_group).await?;
+
+    Ok(Some(KeyGroupResponse {
+        key_group: key_group.key_group,
+        owner: key_group.owner,
+        name: key_group.name,
+        members,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use crate::db::models::{KeyGroup, KeyGroupMembership};
+    use crate::db::SqlStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{
+        handle_http, initialize_db, random_entry_hash, rpc_request, rpc_response,
+    };
+
+    const OWNER: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+    const MEMBER: &str = "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a";
+
+    #[async_std::test]
+    async fn get_key_group() {
+        let pool = initialize_db().await;
+        let key_group = Hash::new(&random_entry_hash()).unwrap();
+        let owner = Author::new(OWNER).unwrap();
+        let member = Author::new(MEMBER).unwrap();
+
+        let request = rpc_request(
+            "panda_getKeyGroup",
+            &format!(
+                r#"{{
+                    "keyGroup": "{}"
+                }}"#,
+                key_group.as_str(),
+            ),
+        );
+
+        let app = build_rpc_server(build_rpc_api_service(
+            SqlStorage::new(pool.clone()),
+            RpcApiOptions::default(),
+        ));
+
+        // Unknown key groups return nothing
+        assert_eq!(
+            handle_http(&app, request.clone()).await,
+            rpc_response("null")
+        );
+
+        KeyGroup::insert(&pool, &key_group, &owner, "Pandas")
+            .await
+            .unwrap();
+        KeyGroupMembership::insert(
+            &pool,
+            &Hash::new(&random_entry_hash()).unwrap(),
+            &key_group,
+            &member,
+            &owner,
+        )
+        .await
+        .unwrap();
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "keyGroup": "{}",
+                "members": ["{}", "{}"],
+                "name": "Pandas",
+                "owner": "{}"
+            }}"#,
+            key_group.as_str(),
+            owner.as_str(),
+            member.as_str(),
+            owner.as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
@@ -0,0 +1,121 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::request::LogsByAuthorRequest;
+use crate::rpc::response::LogsByAuthorResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getLogsByAuthor` RPC method.
+///
+/// Returns all logs of an author stored on this node with their schema and the sequence number of
+/// their latest entry.
+pub async fn get_logs_by_author(
+    data: Data<RpcApiState>,
+    Params(params): Params<LogsByAuthorRequest>,
+) -> Result<LogsByAuthorResponse> {
+    // Validate request parameters
+    params.author.validate()?;
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let logs = storage.logs_by_author(&params.author).await?;
+
+    Ok(LogsByAuthorResponse { logs })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, handle_http, rpc_request, rpc_response};
+
+    #[async_std::test]
+    async fn get_logs_by_author() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema_first = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let schema_second = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        // Two entries in the first log, one entry in the second log
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema_first,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema_first,
+            &LogId::new(1),
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        let (entry_3, message_3) = create_test_entry(
+            &key_pair,
+            &schema_second,
+            &LogId::new(3),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        for (entry, message) in [
+            (&entry_1, &message_1),
+            (&entry_2, &message_2),
+            (&entry_3, &message_3),
+        ]
+        .iter()
+        {
+            store_entry(&storage, &Subscriptions::new(), entry, message)
+                .await
+                .unwrap();
+        }
+
+        let request = rpc_request(
+            "panda_getLogsByAuthor",
+            &format!(
+                r#"{{
+                    "author": "{}"
+                }}"#,
+                entry_1.author().as_str(),
+            ),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "logs": [
+                    {{
+                        "logId": 1,
+                        "schema": "{}",
+                        "seqNum": 2
+                    }},
+                    {{
+                        "logId": 3,
+                        "schema": "{}",
+                        "seqNum": 1
+                    }}
+                ]
+            }}"#,
+            schema_first.as_str(),
+            schema_second.as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
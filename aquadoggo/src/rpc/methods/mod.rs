@@ -1,11 +1,48 @@
+mod admin;
+mod aggregate_documents;
+mod author_profile;
+mod discover;
+mod document;
+mod encrypted_messages;
+mod entries_by_author;
 mod entry_args;
+mod entry_by_hash;
+mod forks;
+mod key_group;
+mod logs_by_author;
+mod node_info;
+mod publish_entries;
 mod publish_entry;
+mod query_documents;
 mod query_entries;
+mod schemas;
+mod stats;
+mod validate_entry;
 
 pub mod error {
+    pub use super::admin::AdminError;
+    pub use super::encrypted_messages::EncryptedMessagesError;
     pub use super::publish_entry::PublishEntryError;
+    pub use super::query_entries::QueryEntriesError;
 }
 
-pub use entry_args::get_entry_args;
-pub use publish_entry::publish_entry;
+pub use admin::{create_backup, prune_payloads, purge_author, purge_log};
+pub use aggregate_documents::aggregate_documents;
+pub use author_profile::get_author_profile;
+pub use discover::discover;
+pub use document::get_document;
+pub use encrypted_messages::get_encrypted_messages;
+pub use entries_by_author::get_entries_by_author;
+pub use entry_args::{get_entry_args, get_entry_args_batch};
+pub use entry_by_hash::get_entry_by_hash;
+pub use forks::get_forks;
+pub use key_group::get_key_group;
+pub use logs_by_author::get_logs_by_author;
+pub use node_info::get_node_info;
+pub use publish_entries::publish_entries;
+pub use publish_entry::{is_fork, publish_entry, store_entry, store_pruned_entry};
+pub use query_documents::query_documents;
 pub use query_entries::query_entries;
+pub use schemas::get_all_schemas;
+pub use stats::get_stats;
+pub use validate_entry::validate_entry;
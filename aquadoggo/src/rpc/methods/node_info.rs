@@ -0,0 +1,125 @@
+use jsonrpc_v2::Data;
+
+use crate::errors::Result;
+use crate::rpc::api::RPC_METHODS;
+use crate::rpc::response::NodeInfoResponse;
+use crate::rpc::RpcApiState;
+
+/// Version of this node software.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Implementation of `panda_getNodeInfo` RPC method.
+///
+/// Returns the software version, supported RPC methods, replication settings and storage
+/// statistics of this node so clients can detect its features.
+pub async fn get_node_info(data: Data<RpcApiState>) -> Result<NodeInfoResponse> {
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    Ok(NodeInfoResponse {
+        author_count: storage.author_count().await?,
+        entry_count: storage.entry_count().await?,
+        methods: RPC_METHODS
+            .iter()
+            .map(|method| method.to_string())
+            .collect(),
+        peer_id: data.node_info.peer_id.clone(),
+        public_key: data.node_info.public_key.clone(),
+        replication_ignored_schemas: data.node_info.replication_ignored_schemas.clone(),
+        replication_schemas: data.node_info.replication_schemas.clone(),
+        version: VERSION.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, NodeInfo, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, handle_http, rpc_request, rpc_response};
+
+    use super::VERSION;
+
+    #[async_std::test]
+    async fn get_node_info() {
+        let storage = MemoryStorage::new();
+
+        // Store an entry first
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry, &message)
+            .await
+            .unwrap();
+
+        // Create tide server with endpoints using in-memory storage
+        let node_info = NodeInfo {
+            peer_id: Some("12D3KooWLxGKMgUtekXam9JsSjMa3b7M3rYEYUYUywdehHTRrLgU".to_owned()),
+            public_key: Some(
+                "9ee5c5a7ea0e5ba5b3d7e5a5b5c5e0a4e7c6bf0da23f16a52a0b1a9c1b5b2c3d".to_owned(),
+            ),
+            replication_ignored_schemas: vec![],
+            replication_schemas: vec![schema.as_str().to_owned()],
+        };
+        let options = RpcApiOptions {
+            node_info,
+            ..RpcApiOptions::default()
+        };
+        let rpc_api = build_rpc_api_service(storage, options);
+        let app = build_rpc_server(rpc_api);
+
+        let request = rpc_request("panda_getNodeInfo", "{}");
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "authorCount": 1,
+                "entryCount": 1,
+                "methods": [
+                    "panda_aggregateDocuments",
+                    "panda_getAllSchemas",
+                    "panda_getAuthorProfile",
+                    "panda_getDocument",
+                    "panda_getEncryptedMessages",
+                    "panda_getEntriesByAuthor",
+                    "panda_getEntryArguments",
+                    "panda_getEntryArgumentsBatch",
+                    "panda_getEntryByHash",
+                    "panda_getForks",
+                    "panda_getKeyGroup",
+                    "panda_getLogsByAuthor",
+                    "panda_getNodeInfo",
+                    "panda_getStats",
+                    "panda_publishEntries",
+                    "panda_publishEntry",
+                    "panda_queryDocuments",
+                    "panda_queryEntries",
+                    "panda_subscribeEntries",
+                    "panda_unsubscribeEntries",
+                    "panda_validateEntry",
+                    "rpc.discover"
+                ],
+                "peerId": "12D3KooWLxGKMgUtekXam9JsSjMa3b7M3rYEYUYUywdehHTRrLgU",
+                "publicKey": "9ee5c5a7ea0e5ba5b3d7e5a5b5c5e0a4e7c6bf0da23f16a52a0b1a9c1b5b2c3d",
+                "replicationIgnoredSchemas": [],
+                "replicationSchemas": ["{}"],
+                "version": "{}"
+            }}"#,
+            schema.as_str(),
+            VERSION,
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
@@ -0,0 +1,229 @@
+use jsonrpc_v2::{Data, Params};
+use tracing::warn;
+
+use crate::errors::Result;
+use crate::rpc::methods::publish_entry::{
+    check_policy, check_schema, is_fork, record_fork, write_entry,
+};
+use crate::rpc::request::PublishEntriesRequest;
+use crate::rpc::response::PublishEntriesResponse;
+use crate::rpc::{PublishEntryError, RpcApiState};
+
+/// Implementation of `panda_publishEntries` RPC method.
+///
+/// Validates and stores an ordered list of Bamboo entries with message payloads of one author.
+/// All entries are stored within one storage transaction, either all of them get stored or none.
+/// Entries which are already stored get skipped. Returns the hashes of all given entries.
+pub async fn publish_entries(
+    data: Data<RpcApiState>,
+    Params(params): Params<PublishEntriesRequest>,
+) -> Result<PublishEntriesResponse> {
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    // Reject batches of multiple authors and entries which are not accepted by this node
+    if let Some(first) = params.entries.first() {
+        let author = first.entry_encoded.author();
+
+        for entry in params.entries.iter() {
+            if entry.entry_encoded.author() != author {
+                return Err(PublishEntryError::AuthorMismatch.into());
+            }
+
+            check_policy(&data, &entry.entry_encoded, &entry.message_encoded)?;
+        }
+    }
+
+    let mut tx = storage.begin().await?;
+    let mut notifications = Vec::new();
+
+    for entry in params.entries.iter() {
+        // Ignore entries we already know about, the client might have retried its request
+        if tx
+            .entry_by_hash(&entry.entry_encoded.hash())
+            .await?
+            .is_some()
+        {
+            continue;
+        }
+
+        check_schema(&*storage, &entry.message_encoded).await?;
+
+        match write_entry(&mut *tx, &entry.entry_encoded, &entry.message_encoded).await {
+            Ok(notification) => notifications.push(notification),
+            Err(err) => {
+                warn!("Could not publish entries: {}", err);
+
+                if is_fork(&err) {
+                    drop(tx);
+                    record_fork(&*storage, &entry.entry_encoded).await?;
+                }
+
+                return Err(err);
+            }
+        }
+    }
+
+    tx.commit().await?;
+
+    // Notify subscribed clients about new entries after all of them got stored
+    for notification in notifications {
+        data.subscriptions.notify(notification);
+    }
+
+    Ok(PublishEntriesResponse {
+        entry_hashes: params
+            .entries
+            .iter()
+            .map(|entry| entry.entry_encoded.hash())
+            .collect(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::{EntryStore, MemoryStorage};
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{
+        create_test_entry, handle_http, rpc_error, rpc_request, rpc_response,
+    };
+
+    #[async_std::test]
+    async fn publish_entries() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2] <-- [3]
+        let mut entries: Vec<_> = Vec::new();
+        for seq_num in 1..4 {
+            let (entry, message) = create_test_entry(
+                &key_pair,
+                &schema,
+                &log_id,
+                if seq_num == 3 {
+                    Some(&entries[0].0)
+                } else {
+                    None
+                },
+                entries.last().map(|(entry, _)| entry),
+                &SeqNum::new(seq_num).unwrap(),
+            );
+            entries.push((entry, message));
+        }
+
+        let params = entries
+            .iter()
+            .map(|(entry, message)| {
+                format!(
+                    r#"{{ "entryEncoded": "{}", "messageEncoded": "{}" }}"#,
+                    entry.as_str(),
+                    message.as_str()
+                )
+            })
+            .collect::<Vec<String>>()
+            .join(",");
+
+        let request = rpc_request(
+            "panda_publishEntries",
+            &format!(r#"{{ "entries": [{}] }}"#, params),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "entryHashes": [{}]
+            }}"#,
+            entries
+                .iter()
+                .map(|(entry, _)| format!("\"{}\"", entry.hash().as_str()))
+                .collect::<Vec<String>>()
+                .join(",")
+        ));
+
+        assert_eq!(handle_http(&app, request.clone()).await, response);
+        assert!(storage
+            .latest_entry(&entries[0].0.author(), &log_id)
+            .await
+            .unwrap()
+            .is_some());
+
+        // Publishing the same batch again succeeds without storing anything
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+
+    #[async_std::test]
+    async fn reject_incomplete_batches() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, _) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        let (entry_3, message_3) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            Some(&entry_1),
+            Some(&entry_2),
+            &SeqNum::new(3).unwrap(),
+        );
+
+        // Second entry is missing, nothing of the batch gets stored
+        let request = rpc_request(
+            "panda_publishEntries",
+            &format!(
+                r#"{{
+                    "entries": [
+                        {{ "entryEncoded": "{}", "messageEncoded": "{}" }},
+                        {{ "entryEncoded": "{}", "messageEncoded": "{}" }}
+                    ]
+                }}"#,
+                entry_1.as_str(),
+                message_1.as_str(),
+                entry_3.as_str(),
+                message_3.as_str(),
+            ),
+        );
+
+        assert_eq!(
+            handle_http(&app, request).await,
+            rpc_error("Could not find backlink entry in database")
+        );
+        assert!(storage
+            .entry_by_hash(&entry_1.hash())
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -1,13 +1,19 @@
+use async_std::task;
+use bamboo_rs_core::entry::is_lipmaa_required;
 use jsonrpc_v2::{Data, Params};
-use p2panda_rs::entry::decode_entry;
-use p2panda_rs::message::Message;
+use p2panda_rs::entry::{decode_entry, Entry, EntrySigned, LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use p2panda_rs::message::{Message, MessageEncoded};
 use p2panda_rs::Validate;
+use tracing::{debug, instrument, warn, Span};
 
-use crate::db::models::{Entry, Log};
-use crate::errors::Result;
+use crate::db::{StorageProvider, StorageTransaction};
+use crate::errors::{Error, Result};
+use crate::materializer::validate_message;
 use crate::rpc::request::PublishEntryRequest;
 use crate::rpc::response::PublishEntryResponse;
-use crate::rpc::RpcApiState;
+use crate::rpc::{EntryNotification, RpcApiState, Subscriptions};
 
 #[derive(thiserror::Error, Debug)]
 #[allow(missing_copy_implementations)]
@@ -20,113 +26,501 @@ pub enum PublishEntryError {
 
     #[error("Claimed log_id for schema not the same as in database")]
     InvalidLogId,
+
+    #[error("All entries need to be published by the same author")]
+    AuthorMismatch,
+
+    #[error("Entry conflicts with another entry at the same position of this log")]
+    ForkDetected,
+
+    #[error("Schema of entry without payload can not be verified, its log is not known")]
+    UnknownLogSchema,
 }
 
-/// Implementation of `panda_publishEntry` RPC method.
+/// Returns true when an entry could not be stored as it forks its log.
+pub fn is_fork(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::PublishEntryValidation(PublishEntryError::ForkDetected)
+    )
+}
+
+/// Persist a fork record for an entry which was refused as it conflicts with a stored entry.
 ///
-/// Stores an author's Bamboo entry with message payload in database after validating it.
-pub async fn publish_entry(
-    data: Data<RpcApiState>,
-    Params(params): Params<PublishEntryRequest>,
-) -> Result<PublishEntryResponse> {
-    // Validate request parameters
-    params.entry_encoded.validate()?;
-    params.message_encoded.validate()?;
+/// This needs to happen outside of the transaction which detected the fork, otherwise the record
+/// gets discarded together with the transaction.
+pub async fn record_fork(storage: &dyn StorageProvider, entry_encoded: &EntrySigned) -> Result<()> {
+    let entry = decode_entry(entry_encoded, None)?;
+    let author = entry_encoded.author();
 
-    // Get database connection pool
-    let pool = data.pool.clone();
+    if let Some(stored) = storage
+        .entry_at_seq_num(&author, entry.log_id(), entry.seq_num())
+        .await?
+    {
+        if storage
+            .insert_fork(
+                entry_encoded,
+                entry.log_id(),
+                entry.seq_num(),
+                &stored.entry_hash,
+            )
+            .await?
+        {
+            warn!(
+                "Detected fork of log {} of author {} at sequence number {}",
+                entry.log_id().as_i64(),
+                author.as_str(),
+                entry.seq_num().as_i64()
+            );
+        }
+    }
 
-    // Handle error as this conversion validates message hash
-    let entry = decode_entry(&params.entry_encoded, Some(&params.message_encoded))?;
-    let message = Message::from(&params.message_encoded);
+    Ok(())
+}
 
-    // Retreive author and schema
-    let author = params.entry_encoded.author();
+/// Validates an author's Bamboo entry with message payload and stores it in the database.
+///
+/// All reads needed for validation and the writes of the log registration and entry happen within
+/// one storage transaction, a failure in between does not leave a log without its entry behind.
+///
+/// Subscribers get notified about the new entry. This is used for entries published by clients
+/// and for entries received from other nodes.
+pub async fn store_entry(
+    storage: &dyn StorageProvider,
+    subscriptions: &Subscriptions,
+    entry_encoded: &EntrySigned,
+    message_encoded: &MessageEncoded,
+) -> Result<()> {
+    let mut tx = storage.begin().await?;
+    let notification = match write_entry(&mut *tx, entry_encoded, message_encoded).await {
+        Err(err) if is_fork(&err) => {
+            drop(tx);
+            record_fork(storage, entry_encoded).await?;
+            return Err(err);
+        }
+        result => result?,
+    };
+    tx.commit().await?;
+
+    // Notify subscribed clients about new entry
+    subscriptions.notify(notification);
+
+    Ok(())
+}
+
+/// Validates an author's Bamboo entry with message payload against its log like `store_entry`,
+/// without storing it.
+///
+/// The entry gets written within a storage transaction which is rolled back afterwards, so the
+/// validation is exactly the same as for stored entries.
+pub async fn check_entry(
+    storage: &dyn StorageProvider,
+    entry_encoded: &EntrySigned,
+    message_encoded: &MessageEncoded,
+) -> Result<()> {
+    let mut tx = storage.begin().await?;
+    write_entry(&mut *tx, entry_encoded, message_encoded).await?;
+
+    // Dropping the transaction without committing it discards all writes
+    drop(tx);
+
+    Ok(())
+}
+
+/// Validates a Bamboo entry whose payload got pruned on the node we received it from and stores
+/// it in the database.
+///
+/// Entries with their messages received together with the entry can prove the schema of its log,
+/// see `write_pruned_entry`. Subscribers are not notified as there is no message to materialize
+/// or to pass on.
+pub async fn store_pruned_entry(
+    storage: &dyn StorageProvider,
+    entry_encoded: &EntrySigned,
+    schema: &Hash,
+    log_entries: &[(&EntrySigned, &MessageEncoded)],
+) -> Result<()> {
+    let mut tx = storage.begin().await?;
+    match write_pruned_entry(&mut *tx, entry_encoded, schema, log_entries).await {
+        Err(err) if is_fork(&err) => {
+            drop(tx);
+            record_fork(storage, entry_encoded).await?;
+            return Err(err);
+        }
+        result => result?,
+    };
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// Verifies the signature, message hash and links of a Bamboo entry.
+///
+/// The message hash can not be verified for entries whose payload got pruned.
+///
+/// Verification is CPU-bound, it runs on the blocking thread pool so clients publishing many
+/// entries at once do not stall other requests handled by the async executor.
+pub async fn verify_entry(
+    entry_bytes: Vec<u8>,
+    message_bytes: Option<Vec<u8>>,
+    skiplink_bytes: Option<Vec<u8>>,
+    backlink_bytes: Option<Vec<u8>>,
+) -> Result<()> {
+    task::spawn_blocking(move || {
+        bamboo_rs_core::verify(
+            &entry_bytes,
+            message_bytes.as_deref(),
+            skiplink_bytes.as_deref(),
+            backlink_bytes.as_deref(),
+        )
+    })
+    .await?;
+
+    Ok(())
+}
+
+/// Validates an author's Bamboo entry with message payload and writes it within a storage
+/// transaction.
+///
+/// Returns the notification for subscribers, it should be sent after the transaction got
+/// committed.
+pub async fn write_entry(
+    tx: &mut dyn StorageTransaction,
+    entry_encoded: &EntrySigned,
+    message_encoded: &MessageEncoded,
+) -> Result<EntryNotification> {
+    // Validate message, it determines the schema of the entry
+    message_encoded.validate()?;
+    let message = Message::from(message_encoded);
     let schema = message.schema();
 
+    let entry = write_log_entry(tx, entry_encoded, Some(message_encoded), schema).await?;
+
+    Ok(EntryNotification {
+        author: entry_encoded.author(),
+        entry_encoded: entry_encoded.as_str().to_owned(),
+        entry_hash: entry_encoded.hash(),
+        log_id: entry.log_id().clone(),
+        message_encoded: message_encoded.as_str().to_owned(),
+        schema: schema.clone(),
+        seq_num: entry.seq_num().clone(),
+    })
+}
+
+/// Validates a Bamboo entry whose payload got pruned and writes it within a storage transaction.
+///
+/// Without its message the schema of the entry can not be verified. The entry is only accepted
+/// into a log whose schema is known already, or when one of the given entries with its message
+/// belongs to the same log and proves its schema.
+pub async fn write_pruned_entry(
+    tx: &mut dyn StorageTransaction,
+    entry_encoded: &EntrySigned,
+    schema: &Hash,
+    log_entries: &[(&EntrySigned, &MessageEncoded)],
+) -> Result<()> {
+    let entry = decode_entry(entry_encoded, None)?;
+    let author = entry_encoded.author();
+
+    if tx.get_log_id(&author, schema).await?.as_ref() != Some(entry.log_id()) {
+        let mut proven = false;
+
+        for (log_entry_encoded, message_encoded) in log_entries {
+            if log_entry_encoded.author() == author
+                && is_log_schema(&entry, log_entry_encoded, message_encoded, schema).await?
+            {
+                proven = true;
+                break;
+            }
+        }
+
+        if !proven {
+            Err(PublishEntryError::UnknownLogSchema)?;
+        }
+    }
+
+    write_log_entry(tx, entry_encoded, None, schema).await?;
+
+    Ok(())
+}
+
+/// Returns true when a signed entry with its message belongs to the log of another entry of the
+/// same author and its message is of the given schema.
+async fn is_log_schema(
+    entry: &Entry,
+    log_entry_encoded: &EntrySigned,
+    message_encoded: &MessageEncoded,
+    schema: &Hash,
+) -> Result<bool> {
+    if message_encoded.validate().is_err() {
+        return Ok(false);
+    }
+
+    // Decoding validates the message hash
+    let log_entry = match decode_entry(log_entry_encoded, Some(message_encoded)) {
+        Ok(log_entry) => log_entry,
+        Err(_) => return Ok(false),
+    };
+
+    if log_entry.log_id() != entry.log_id() || Message::from(message_encoded).schema() != schema {
+        return Ok(false);
+    }
+
+    // Links of the entry are verified when it gets stored, its signature is verified here
+    log_entry_encoded.validate()?;
+    verify_entry(
+        log_entry_encoded.to_bytes(),
+        Some(message_encoded.to_bytes()),
+        None,
+        None,
+    )
+    .await?;
+
+    Ok(true)
+}
+
+/// Validates a Bamboo entry and writes it with its optional message into the log of its author's
+/// schema, returns the decoded entry.
+#[instrument(
+    skip_all,
+    fields(entry_hash = entry_encoded.hash().as_str(), author, log_id, seq_num, schema)
+)]
+async fn write_log_entry(
+    tx: &mut dyn StorageTransaction,
+    entry_encoded: &EntrySigned,
+    message_encoded: Option<&MessageEncoded>,
+    schema: &Hash,
+) -> Result<Entry> {
+    entry_encoded.validate()?;
+
+    // Handle error as this conversion validates message hash
+    let entry = decode_entry(entry_encoded, message_encoded)?;
+    let author = entry_encoded.author();
+
+    // Add entry details to the tracing span to make failures traceable
+    let span = Span::current();
+    span.record("author", &author.as_str());
+    span.record("log_id", &entry.log_id().as_i64());
+    span.record("seq_num", &entry.seq_num().as_i64());
+    span.record("schema", &schema.as_str());
+
     // Determine log_id for author's schema
-    let schema_log_id = Log::get(&pool, &author, &schema).await?;
+    let schema_log_id = tx.get_log_id(&author, schema).await?;
 
     // Check if log_id is the same as the previously claimed one (when given)
     if schema_log_id.is_some() && schema_log_id.as_ref() != Some(entry.log_id()) {
         Err(PublishEntryError::InvalidLogId)?;
     }
 
-    // Get related bamboo backlink and skiplink entries
-    let entry_backlink_bytes = if !entry.seq_num().is_first() {
-        Entry::at_seq_num(
-            &pool,
-            &author,
-            &entry.log_id(),
-            &entry.seq_num_backlink().unwrap(),
-        )
-        .await?
-        .map(|link| {
-            Some(
-                hex::decode(link.entry_bytes)
-                    .expect("Backlink entry with invalid hex-encoding detected in database"),
-            )
-        })
-        .ok_or(PublishEntryError::BacklinkMissing)
-    } else {
-        Ok(None)
-    }?;
-
-    let entry_skiplink_bytes = if !entry.seq_num().is_first() {
-        Entry::at_seq_num(
-            &pool,
-            &author,
-            &entry.log_id(),
-            &entry.seq_num_skiplink().unwrap(),
-        )
-        .await?
-        .map(|link| {
-            Some(
-                hex::decode(link.entry_bytes)
-                    .expect("Skiplink entry with invalid hex-encoding detected in database"),
-            )
-        })
-        .ok_or(PublishEntryError::SkiplinkMissing)
+    // Get related bamboo backlink and skiplink entries. Logs can be sparse when they got pruned or
+    // only their tail got replicated, the backlink might be missing then. The skiplink is always
+    // needed, it is part of the lipmaa certificate pool linking the entry to the start of its log
+    let (entry_skiplink_bytes, entry_backlink_bytes) = if !entry.seq_num().is_first() {
+        let backlink_seq_num = entry.seq_num_backlink().unwrap();
+        let skiplink_seq_num = entry.seq_num_skiplink().unwrap();
+
+        let backlink_bytes = link_bytes(tx, &author, entry.log_id(), &backlink_seq_num).await?;
+        let skiplink_bytes = link_bytes(tx, &author, entry.log_id(), &skiplink_seq_num).await?;
+
+        match skiplink_bytes {
+            Some(skiplink_bytes) => (Some(skiplink_bytes), backlink_bytes),
+            None if skiplink_seq_num.as_i64() == backlink_seq_num.as_i64() => {
+                Err(PublishEntryError::BacklinkMissing)?
+            }
+            None => Err(PublishEntryError::SkiplinkMissing)?,
+        }
     } else {
-        Ok(None)
-    }?;
+        (None, None)
+    };
 
     // Verify bamboo entry integrity
-    bamboo_rs_core::verify(
-        &params.entry_encoded.to_bytes(),
-        Some(&params.message_encoded.to_bytes()),
-        entry_skiplink_bytes.as_deref(),
-        entry_backlink_bytes.as_deref(),
-    )?;
+    verify_entry(
+        entry_encoded.to_bytes(),
+        message_encoded.map(|message| message.to_bytes()),
+        entry_skiplink_bytes,
+        entry_backlink_bytes,
+    )
+    .await?;
+
+    // Refuse valid entries conflicting with a stored entry at the same position, the author
+    // forked this log
+    if let Some(stored) = tx
+        .entry_at_seq_num(&author, &entry.log_id(), &entry.seq_num())
+        .await?
+    {
+        if stored.entry_hash != entry_encoded.hash() {
+            Err(PublishEntryError::ForkDetected)?;
+        }
+    }
 
     // Register used log id in database when not set yet
     if schema_log_id.is_none() {
-        Log::insert(&pool, &author, &schema, entry.log_id()).await?;
+        tx.insert_log(&author, schema, entry.log_id()).await?;
     }
 
     // Finally insert Entry in database
-    Entry::insert(
-        &pool,
-        &author,
-        &params.entry_encoded,
-        &params.entry_encoded.hash(),
+    tx.insert_entry(
+        entry_encoded,
+        message_encoded,
         &entry.log_id(),
-        &params.message_encoded,
-        &params.message_encoded.hash(),
         &entry.seq_num(),
     )
     .await?;
 
+    debug!("Stored entry");
+
+    Ok(entry)
+}
+
+/// Returns the bytes of an entry linked to from another entry of the same log, `None` when it is
+/// not stored.
+async fn link_bytes(
+    tx: &mut dyn StorageTransaction,
+    author: &Author,
+    log_id: &LogId,
+    seq_num: &SeqNum,
+) -> Result<Option<Vec<u8>>> {
+    let link = tx.entry_at_seq_num(author, log_id, seq_num).await?;
+
+    Ok(link.map(|link| {
+        hex::decode(link.entry_bytes)
+            .expect("Linked entry with invalid hex-encoding detected in database")
+    }))
+}
+
+/// Returns an error when the author, schema or message size of an entry is not accepted by the
+/// policy of this node.
+pub fn check_policy(
+    data: &RpcApiState,
+    entry_encoded: &EntrySigned,
+    message_encoded: &MessageEncoded,
+) -> Result<()> {
+    data.policy.check_author(&entry_encoded.author())?;
+    message_encoded.validate()?;
+    data.policy.check_message_size(message_encoded)?;
+    data.policy
+        .check_schema(Message::from(message_encoded).schema())?;
+
+    Ok(())
+}
+
+/// Returns an error naming the offending field when a message does not match the registered
+/// definition of its schema.
+///
+/// Messages of schemas without a published definition are accepted.
+pub async fn check_schema(
+    storage: &dyn StorageProvider,
+    message_encoded: &MessageEncoded,
+) -> Result<()> {
+    let message = Message::from(message_encoded);
+
+    if let Some(definition) = storage.schema_definition(message.schema()).await? {
+        validate_message(&message, &definition)?;
+    }
+
+    Ok(())
+}
+
+/// Implementation of `panda_publishEntry` RPC method.
+///
+/// Stores an author's Bamboo entry with message payload in database after validating it. Returns
+/// the hash of the stored entry and the arguments for the next entry in the same log, this saves
+/// clients a `panda_getEntryArguments` request.
+///
+/// Publishing an entry which is already stored succeeds without storing it again, this allows
+/// clients to safely retry requests.
+///
+/// Messages need to match the registered definition of their schema.
+///
+/// With `dryRun` the entry gets validated against its log without storing it, the returned
+/// arguments are the ones the entry would lead to when it got stored.
+pub async fn publish_entry(
+    data: Data<RpcApiState>,
+    Params(params): Params<PublishEntryRequest>,
+) -> Result<PublishEntryResponse> {
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    // Reject entries of authors, schemas or message sizes which are not accepted by this node
+    check_policy(&data, &params.entry_encoded, &params.message_encoded)?;
+
+    // Ignore entries we already know about, the client might have retried its request
+    let is_new = storage
+        .entry_by_hash(&params.entry_encoded.hash())
+        .await?
+        .is_none();
+
+    // Reject messages which do not match the definition of their schema
+    if is_new {
+        check_schema(&*storage, &params.message_encoded).await?;
+    }
+
+    if is_new && params.dry_run {
+        check_entry(&*storage, &params.entry_encoded, &params.message_encoded).await?;
+    } else if is_new {
+        store_entry(
+            &*storage,
+            &data.subscriptions,
+            &params.entry_encoded,
+            &params.message_encoded,
+        )
+        .await
+        .map_err(|err| {
+            warn!("Could not publish entry: {}", err);
+            err
+        })?;
+    }
+
+    let entry = decode_entry(&params.entry_encoded, Some(&params.message_encoded))?;
+    let author = params.entry_encoded.author();
+
+    // Return arguments as if the entry of a dry run got stored when it would be the latest entry
+    // of its log
+    if is_new && params.dry_run {
+        let latest_seq_num = storage
+            .latest_entry(&author, &entry.log_id())
+            .await?
+            .map(|entry_latest| entry_latest.seq_num.as_i64())
+            .unwrap_or(0);
+
+        if latest_seq_num < entry.seq_num().as_i64() {
+            let next_seq_num = entry.seq_num().clone().next().unwrap();
+
+            // The skiplink of the next entry is stored already when it is not this entry
+            let entry_hash_skiplink = if is_lipmaa_required(next_seq_num.as_i64() as u64) {
+                storage
+                    .entry_at_seq_num(
+                        &author,
+                        &entry.log_id(),
+                        &next_seq_num.skiplink_seq_num().unwrap(),
+                    )
+                    .await?
+                    .map(|skiplink| skiplink.entry_hash)
+            } else {
+                None
+            };
+
+            return Ok(PublishEntryResponse {
+                entry_hash: params.entry_encoded.hash(),
+                entry_hash_backlink: Some(params.entry_encoded.hash()),
+                entry_hash_skiplink,
+                seq_num: next_seq_num,
+                log_id: entry.log_id().to_owned(),
+            });
+        }
+    }
+
     // Already return arguments for next entry creation
-    let mut entry_latest = Entry::latest(&pool, &author, &entry.log_id())
+    let mut entry_latest = storage
+        .latest_entry(&author, &entry.log_id())
         .await?
         .expect("Database does not contain any entries");
-    let entry_hash_skiplink = super::entry_args::determine_skiplink(pool, &entry_latest).await?;
+    let entry_hash_skiplink =
+        super::entry_args::determine_skiplink(&*storage, &entry_latest).await?;
 
     let next_seq_num = entry_latest.seq_num.next().unwrap();
-    
+
     Ok(PublishEntryResponse {
-        entry_hash_backlink: Some(params.entry_encoded.hash()),
+        entry_hash: params.entry_encoded.hash(),
+        entry_hash_backlink: Some(entry_latest.entry_hash),
         entry_hash_skiplink,
         seq_num: next_seq_num,
         log_id: entry.log_id().to_owned(),
@@ -134,49 +528,20 @@ pub async fn publish_entry(
 }
 #[cfg(test)]
 mod tests {
-    use std::convert::TryFrom;
-
-    use p2panda_rs::entry::{Entry, EntrySigned, LogId, SeqNum, sign_and_encode};
+    use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
     use p2panda_rs::hash::Hash;
     use p2panda_rs::identity::KeyPair;
     use p2panda_rs::message::{Message, MessageEncoded, MessageFields, MessageValue};
 
-    use crate::rpc::api::build_rpc_api_service;
+    use crate::config::Configuration;
+    use crate::db::{MemoryStorage, SqlStorage};
+    use crate::policy::EntryPolicy;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
     use crate::rpc::server::{build_rpc_server, RpcServer};
-    use crate::test_helpers::{handle_http, initialize_db, rpc_error, rpc_request, rpc_response};
-
-    // Helper method to create encoded entries and messages
-    fn create_test_entry(
-        key_pair: &KeyPair,
-        schema: &Hash,
-        log_id: &LogId,
-        skiplink: Option<&EntrySigned>,
-        backlink: Option<&EntrySigned>,
-        seq_num: &SeqNum,
-    ) -> (EntrySigned, MessageEncoded) {
-        // Create message with dummy data
-        let mut fields = MessageFields::new();
-        fields
-            .add("test", MessageValue::Text("Hello".to_owned()))
-            .unwrap();
-        let message = Message::new_create(schema.clone(), fields).unwrap();
-
-        // Encode message
-        let message_encoded = MessageEncoded::try_from(&message).unwrap();
-
-        // Create, sign and encode entry
-        let entry = Entry::new(
-            log_id,
-            Some(&message),
-            skiplink.map(|e| e.hash()).as_ref(),
-            backlink.map(|e| e.hash()).as_ref(),
-            seq_num,
-        )
-        .unwrap();
-        let entry_encoded = sign_and_encode(&entry, key_pair).unwrap();
-
-        (entry_encoded, message_encoded)
-    }
+    use crate::test_helpers::{
+        create_test_entry, create_test_entry_with_message, define_test_schema, handle_http,
+        initialize_db, rpc_error, rpc_request, rpc_response,
+    };
 
     // Helper method to compare expected API responses with what was returned
     async fn assert_request(
@@ -210,12 +575,14 @@ mod tests {
 
         let response = rpc_response(&format!(
             r#"{{
+                "entryHash": "{}",
                 "entryHashBacklink": "{}",
                 "entryHashSkiplink": {},
                 "logId": {},
                 "seqNum": {}
             }}"#,
             entry_encoded.hash().as_str(),
+            entry_encoded.hash().as_str(),
             skiplink_str,
             log_id.as_i64(),
             seq_num.as_i64(),
@@ -228,12 +595,8 @@ mod tests {
     async fn publish_entry() {
         // Create key pair for author
         let key_pair = KeyPair::new();
-
-        // Prepare test database
-        let pool = initialize_db().await;
-
-        // Create tide server with endpoints
-        let rpc_api = build_rpc_api_service(pool);
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
         let app = build_rpc_server(rpc_api);
 
         // Define schema and log id for entries
@@ -244,7 +607,8 @@ mod tests {
         // Create a couple of entries in the same log and check for consistency
         //
         // [1] --
-        let (entry_1, message_1) = create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
+        let (entry_1, message_1) =
+            create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
         assert_request(
             &app,
             &entry_1,
@@ -338,12 +702,8 @@ mod tests {
     async fn validate() {
         // Create key pair for author
         let key_pair = KeyPair::new();
-
-        // Prepare test database
-        let pool = initialize_db().await;
-
-        // Create tide server with endpoints
-        let rpc_api = build_rpc_api_service(pool);
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
         let app = build_rpc_server(rpc_api);
 
         // Define schema and log id for entries
@@ -352,7 +712,8 @@ mod tests {
         let seq_num = SeqNum::new(1).unwrap();
 
         // Create two valid entries for testing
-        let (entry_1, message_1) = create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
+        let (entry_1, message_1) =
+            create_test_entry(&key_pair, &schema, &log_id, None, None, &seq_num);
         assert_request(
             &app,
             &entry_1,
@@ -461,4 +822,330 @@ mod tests {
 
         assert_eq!(handle_http(&app, request).await, response);
     }
+
+    #[async_std::test]
+    async fn publish_duplicate_entry() {
+        let key_pair = KeyPair::new();
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        // Publishing the same entry again returns the same result
+        for _ in 0..2 {
+            assert_request(
+                &app,
+                &entry,
+                &message,
+                None,
+                &log_id,
+                &SeqNum::new(2).unwrap(),
+            )
+            .await;
+        }
+    }
+
+    #[async_std::test]
+    async fn publish_entry_dry_run() {
+        let key_pair = KeyPair::new();
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+        let (entry_1, message_1) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        let dry_run_request = |entry: &EntrySigned, message: &MessageEncoded| {
+            rpc_request(
+                "panda_publishEntry",
+                &format!(
+                    r#"{{
+                        "entryEncoded": "{}",
+                        "messageEncoded": "{}",
+                        "dryRun": true
+                    }}"#,
+                    entry.as_str(),
+                    message.as_str(),
+                ),
+            )
+        };
+
+        // Arguments are returned as if the entry got stored
+        let response = rpc_response(&format!(
+            r#"{{
+                "entryHash": "{}",
+                "entryHashBacklink": "{}",
+                "entryHashSkiplink": null,
+                "logId": 1,
+                "seqNum": 2
+            }}"#,
+            entry_1.hash().as_str(),
+            entry_1.hash().as_str(),
+        ));
+        for _ in 0..2 {
+            assert_eq!(
+                handle_http(&app, dry_run_request(&entry_1, &message_1)).await,
+                response
+            );
+        }
+
+        // Nothing got stored, the following entry can not be linked yet
+        assert_eq!(
+            handle_http(&app, dry_run_request(&entry_2, &message_2)).await,
+            rpc_error("Could not find backlink entry in database")
+        );
+
+        // The entry can still be published
+        assert_request(
+            &app,
+            &entry_1,
+            &message_1,
+            None,
+            &log_id,
+            &SeqNum::new(2).unwrap(),
+        )
+        .await;
+    }
+
+    #[async_std::test]
+    async fn publish_entry_on_sparse_log() {
+        let key_pair = KeyPair::new();
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // Create entries with sequence numbers 1 to 12, only some of them get published
+        let mut entries: Vec<(EntrySigned, MessageEncoded)> = Vec::new();
+        for seq_num in 1..13 {
+            let seq_num = SeqNum::new(seq_num).unwrap();
+            let skiplink = seq_num
+                .skiplink_seq_num()
+                .map(|skiplink| entries[skiplink.as_i64() as usize - 1].0.clone());
+            let backlink = entries.last().map(|(entry, _)| entry.clone());
+            entries.push(create_test_entry(
+                &key_pair,
+                &schema,
+                &log_id,
+                skiplink.as_ref(),
+                backlink.as_ref(),
+                &seq_num,
+            ));
+        }
+
+        let publish_request = |seq_num: usize| {
+            let (entry, message) = &entries[seq_num - 1];
+            rpc_request(
+                "panda_publishEntry",
+                &format!(
+                    r#"{{
+                        "entryEncoded": "{}",
+                        "messageEncoded": "{}"
+                    }}"#,
+                    entry.as_str(),
+                    message.as_str(),
+                ),
+            )
+        };
+
+        //  /------------------ [4]
+        // [1]
+        //
+        // Entry 4 is accepted without its backlink, its skiplink is part of the lipmaa
+        // certificate pool linking it to the first entry
+        let (entry_1, message_1) = &entries[0];
+        assert_request(
+            &app,
+            entry_1,
+            message_1,
+            None,
+            &log_id,
+            &SeqNum::new(2).unwrap(),
+        )
+        .await;
+
+        let (entry_4, message_4) = &entries[3];
+        assert_request(
+            &app,
+            entry_4,
+            message_4,
+            None,
+            &log_id,
+            &SeqNum::new(5).unwrap(),
+        )
+        .await;
+
+        // Entry 3 links to the missing entry 2 only
+        assert_eq!(
+            handle_http(&app, publish_request(3)).await,
+            rpc_error("Could not find backlink entry in database")
+        );
+
+        // Entry 12 links to the missing entries 8 and 11
+        assert_eq!(
+            handle_http(&app, publish_request(12)).await,
+            rpc_error("Could not find skiplink entry in database")
+        );
+    }
+
+    #[async_std::test]
+    async fn reject_blocked_author() {
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let (entry, message) = create_test_entry(
+            &key_pair,
+            &schema,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        // Create tide server blocking this author
+        let config = Configuration {
+            blocked_authors: vec![entry.author().as_str().to_owned()],
+            ..Configuration::default()
+        };
+        let rpc_api = build_rpc_api_service(
+            MemoryStorage::new(),
+            RpcApiOptions {
+                policy: EntryPolicy::new(&config).unwrap(),
+                ..RpcApiOptions::default()
+            },
+        );
+        let app = build_rpc_server(rpc_api);
+
+        let request = rpc_request(
+            "panda_publishEntry",
+            &format!(
+                r#"{{
+                    "entryEncoded": "{}",
+                    "messageEncoded": "{}"
+                }}"#,
+                entry.as_str(),
+                message.as_str(),
+            ),
+        );
+
+        let response = rpc_error("Author is not allowed to publish entries on this node");
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+
+    #[async_std::test]
+    async fn reject_messages_not_matching_schema() {
+        let pool = initialize_db().await;
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        define_test_schema(&pool, &schema, &[("test", "str"), ("views", "int")]).await;
+
+        let app = build_rpc_server(build_rpc_api_service(
+            SqlStorage::new(pool),
+            RpcApiOptions::default(),
+        ));
+
+        let cases = [
+            (
+                vec![("test", MessageValue::Text("Hello".to_owned()))],
+                r#"Message is missing field \"views\" of the schema definition"#,
+            ),
+            (
+                vec![
+                    ("test", MessageValue::Text("Hello".to_owned())),
+                    ("views", MessageValue::Text("Many".to_owned())),
+                ],
+                r#"Field \"views\" needs to be of type int"#,
+            ),
+            (
+                vec![
+                    ("test", MessageValue::Text("Hello".to_owned())),
+                    ("views", MessageValue::Integer(12)),
+                    ("color", MessageValue::Text("Red".to_owned())),
+                ],
+                r#"Field \"color\" is not part of the schema definition"#,
+            ),
+        ];
+
+        for (values, error) in cases.iter() {
+            let mut fields = MessageFields::new();
+            for (name, value) in values.iter() {
+                fields.add(name, value.clone()).unwrap();
+            }
+
+            let message = Message::new_create(schema.clone(), fields).unwrap();
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+
+            let request = rpc_request(
+                "panda_publishEntry",
+                &format!(
+                    r#"{{
+                        "entryEncoded": "{}",
+                        "messageEncoded": "{}"
+                    }}"#,
+                    entry.as_str(),
+                    message.as_str(),
+                ),
+            );
+
+            assert_eq!(handle_http(&app, request).await, rpc_error(error));
+        }
+
+        // Messages matching the definition are accepted
+        let mut fields = MessageFields::new();
+        fields
+            .add("test", MessageValue::Text("Hello".to_owned()))
+            .unwrap();
+        fields.add("views", MessageValue::Integer(12)).unwrap();
+
+        let message = Message::new_create(schema.clone(), fields).unwrap();
+        let (entry, message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+
+        assert_request(
+            &app,
+            &entry,
+            &message,
+            None,
+            &LogId::new(1),
+            &SeqNum::new(2).unwrap(),
+        )
+        .await;
+    }
 }
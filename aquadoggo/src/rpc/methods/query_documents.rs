@@ -0,0 +1,362 @@
+use jsonrpc_v2::{Data, Params};
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::materializer::DocumentQuery;
+use crate::rpc::cursor::{decode_cursor, next_cursor};
+use crate::rpc::methods::document::{document_response, resolve_relations};
+use crate::rpc::methods::query_entries::{QueryEntriesError, DEFAULT_LIMIT, MAX_LIMIT};
+use crate::rpc::request::QueryDocumentsRequest;
+use crate::rpc::response::QueryDocumentsResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_queryDocuments` RPC method.
+///
+/// Returns a page of materialized documents of a schema. Documents can be filtered by comparing
+/// their fields with values and sorted by a field. Pass the `nextCursor` of a response as `cursor`
+/// to request the following page or use `limit` and `offset` to paginate through larger result
+/// sets. Deleted documents are only returned with `includeDeleted`. With `resolveRelations` the
+/// documents referenced by relation fields of all returned documents are fetched at once and
+/// embedded in `relations`.
+pub async fn query_documents(
+    data: Data<RpcApiState>,
+    Params(params): Params<QueryDocumentsRequest>,
+) -> Result<QueryDocumentsResponse> {
+    // Validate request parameters
+    params.schema.validate()?;
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit > MAX_LIMIT {
+        Err(QueryEntriesError::LimitTooLarge(MAX_LIMIT))?;
+    }
+
+    let query = DocumentQuery {
+        filters: params.filters,
+        order_by: params.order_by,
+        order: params.order.unwrap_or_default(),
+        limit: limit as i64,
+        offset: params.offset.unwrap_or(0) as i64,
+        after: match &params.cursor {
+            Some(cursor) => Some(decode_cursor(cursor)?),
+            None => None,
+        },
+        include_deleted: params.include_deleted,
+    };
+
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let documents = storage.query_documents(&params.schema, &query).await?;
+
+    let next_cursor = next_cursor(&documents, limit, |document| {
+        document.position(query.order_by.as_deref())
+    });
+
+    let mut documents: Vec<_> = documents
+        .into_iter()
+        .map(|document| document_response(params.schema.clone(), document))
+        .collect();
+
+    if params.resolve_relations {
+        resolve_relations(storage.as_ref(), &mut documents).await?;
+    }
+
+    Ok(QueryDocumentsResponse {
+        documents,
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use p2panda_rs::message::{Message, MessageFields, MessageValue};
+
+    use crate::db::query_builder::Value;
+    use crate::db::MemoryStorage;
+    use crate::materializer::DocumentPosition;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::cursor::encode_cursor;
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry_with_message, handle_http, random_entry_hash, rpc_request, rpc_response,
+    };
+
+    #[async_std::test]
+    async fn query_documents_with_filters() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        // Three authors create a document each
+        let mut entries = Vec::new();
+        for views in 1..4 {
+            let mut fields = MessageFields::new();
+            fields.add("views", MessageValue::Integer(views)).unwrap();
+            let message = Message::new_create(schema.clone(), fields).unwrap();
+
+            let (entry, message) = create_test_entry_with_message(
+                &KeyPair::new(),
+                &message,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+            entries.push(entry);
+        }
+
+        let request = rpc_request(
+            "panda_queryDocuments",
+            &format!(
+                r#"{{
+                    "schema": "{}",
+                    "filters": [
+                        {{ "field": "views", "operator": "gt", "value": 1 }}
+                    ],
+                    "orderBy": "views",
+                    "order": "desc",
+                    "limit": 1
+                }}"#,
+                schema.as_str(),
+            ),
+        );
+
+        let cursor = encode_cursor(&DocumentPosition {
+            value: Some(Value::Integer(3)),
+            document_id: entries[2].hash().as_str().to_owned(),
+        });
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "documents": [
+                    {{
+                        "documentId": "{}",
+                        "schema": "{}",
+                        "author": "{}",
+                        "entryHash": "{}",
+                        "seqNum": 1,
+                        "fields": {{
+                            "views": {{
+                                "type": "int",
+                                "value": 3
+                            }}
+                        }}
+                    }}
+                ],
+                "nextCursor": "{}"
+            }}"#,
+            entries[2].hash().as_str(),
+            schema.as_str(),
+            entries[2].author().as_str(),
+            entries[2].hash().as_str(),
+            cursor,
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Request the next page with the cursor
+        let request = rpc_request(
+            "panda_queryDocuments",
+            &format!(
+                r#"{{
+                    "schema": "{}",
+                    "filters": [
+                        {{ "field": "views", "operator": "gt", "value": 1 }}
+                    ],
+                    "orderBy": "views",
+                    "order": "desc",
+                    "limit": 1,
+                    "cursor": "{}"
+                }}"#,
+                schema.as_str(),
+                cursor,
+            ),
+        );
+
+        let response = handle_http(&app, request).await;
+        assert!(response.contains(entries[1].hash().as_str()));
+        assert!(!response.contains(entries[2].hash().as_str()));
+    }
+
+    #[async_std::test]
+    async fn query_deleted_documents() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // Create and delete a document
+        let mut fields = MessageFields::new();
+        fields.add("views", MessageValue::Integer(1)).unwrap();
+        let message = Message::new_create(schema.clone(), fields).unwrap();
+        let (entry_1, message_1) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry_1, &message_1)
+            .await
+            .unwrap();
+
+        let message = Message::new_delete(schema.clone(), entry_1.hash()).unwrap();
+        let (entry_2, message_2) = create_test_entry_with_message(
+            &key_pair,
+            &message,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &entry_2, &message_2)
+            .await
+            .unwrap();
+
+        // Deleted documents are excluded by default
+        let request = rpc_request(
+            "panda_queryDocuments",
+            &format!(r#"{{ "schema": "{}" }}"#, schema.as_str()),
+        );
+        let response = rpc_response(r#"{ "documents": [], "nextCursor": null }"#);
+        assert_eq!(handle_http(&app, request).await, response);
+
+        let request = rpc_request(
+            "panda_queryDocuments",
+            &format!(
+                r#"{{ "schema": "{}", "includeDeleted": true }}"#,
+                schema.as_str()
+            ),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "documents": [
+                    {{
+                        "documentId": "{}",
+                        "schema": "{}",
+                        "author": "{}",
+                        "entryHash": "{}",
+                        "seqNum": 2,
+                        "fields": {{
+                            "views": {{
+                                "type": "int",
+                                "value": 1
+                            }}
+                        }},
+                        "deletedBy": "{}"
+                    }}
+                ],
+                "nextCursor": null
+            }}"#,
+            entry_1.hash().as_str(),
+            schema.as_str(),
+            entry_1.author().as_str(),
+            entry_2.hash().as_str(),
+            entry_2.author().as_str(),
+        ));
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+
+    #[async_std::test]
+    async fn query_documents_with_relations() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        let post_schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let comment_schema = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+
+        // Create a post
+        let mut fields = MessageFields::new();
+        fields
+            .add("title", MessageValue::Text("Pandas".to_owned()))
+            .unwrap();
+        let message = Message::new_create(post_schema.clone(), fields).unwrap();
+        let (post_entry, post_message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(&storage, &Subscriptions::new(), &post_entry, &post_message)
+            .await
+            .unwrap();
+
+        // Create a comment referencing the post and an unknown document
+        let mut fields = MessageFields::new();
+        fields
+            .add("post", MessageValue::Relation(post_entry.hash()))
+            .unwrap();
+        fields
+            .add(
+                "reply_to",
+                MessageValue::Relation(Hash::new(&random_entry_hash()).unwrap()),
+            )
+            .unwrap();
+        let message = Message::new_create(comment_schema.clone(), fields).unwrap();
+        let (comment_entry, comment_message) = create_test_entry_with_message(
+            &KeyPair::new(),
+            &message,
+            &LogId::new(1),
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        store_entry(
+            &storage,
+            &Subscriptions::new(),
+            &comment_entry,
+            &comment_message,
+        )
+        .await
+        .unwrap();
+
+        // Relations are only resolved on request
+        let request = rpc_request(
+            "panda_queryDocuments",
+            &format!(r#"{{ "schema": "{}" }}"#, comment_schema.as_str()),
+        );
+        let response: serde_json::Value =
+            serde_json::from_str(&handle_http(&app, request).await).unwrap();
+        assert!(response["result"]["documents"][0]["relations"].is_null());
+
+        let request = rpc_request(
+            "panda_queryDocuments",
+            &format!(
+                r#"{{ "schema": "{}", "resolveRelations": true }}"#,
+                comment_schema.as_str()
+            ),
+        );
+        let response: serde_json::Value =
+            serde_json::from_str(&handle_http(&app, request).await).unwrap();
+
+        // Unknown documents are not embedded
+        let relations = &response["result"]["documents"][0]["relations"];
+        assert_eq!(relations.as_object().unwrap().len(), 1);
+        assert_eq!(relations["post"]["documentId"], post_entry.hash().as_str());
+        assert_eq!(relations["post"]["schema"], post_schema.as_str());
+        assert_eq!(relations["post"]["fields"]["title"]["value"], "Pandas");
+    }
+}
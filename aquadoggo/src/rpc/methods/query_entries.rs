@@ -1,62 +1,348 @@
 use jsonrpc_v2::{Data, Params};
 use p2panda_rs::Validate;
 
-use crate::db::models::Entry;
+use crate::db::models::EntryPosition;
 use crate::errors::Result;
+use crate::rpc::cursor::{decode_cursor, next_cursor};
 use crate::rpc::request::QueryEntriesRequest;
 use crate::rpc::response::QueryEntriesResponse;
 use crate::rpc::RpcApiState;
 
+/// Number of entries returned when no limit was given.
+pub(crate) const DEFAULT_LIMIT: u32 = 50;
+
+/// Maximum number of entries which can be requested at once.
+pub(crate) const MAX_LIMIT: u32 = 500;
+
+#[derive(thiserror::Error, Debug)]
+#[allow(missing_copy_implementations)]
+pub enum QueryEntriesError {
+    #[error("Requested limit exceeds maximum of {0} entries")]
+    LimitTooLarge(u32),
+
+    #[error("Invalid pagination cursor")]
+    InvalidCursor,
+}
+
+/// Implementation of `panda_queryEntries` RPC method.
+///
+/// Returns a page of entries filtered by author and / or schema, sorted by author, log id and
+/// sequence number. Pass the `nextCursor` of a response as `cursor` to request the following page,
+/// this keeps pages stable while new entries arrive. Paginating with `limit` and `offset` is also
+/// supported.
 pub async fn query_entries(
     data: Data<RpcApiState>,
     Params(params): Params<QueryEntriesRequest>,
 ) -> Result<QueryEntriesResponse> {
     // Validate request parameters
-    params.schema.validate()?;
+    if let Some(author) = &params.author {
+        author.validate()?;
+    }
 
-    // Get database connection pool
-    let pool = data.pool.clone();
+    if let Some(schema) = &params.schema {
+        schema.validate()?;
+    }
+
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+    if limit > MAX_LIMIT {
+        Err(QueryEntriesError::LimitTooLarge(MAX_LIMIT))?;
+    }
+
+    let offset = params.offset.unwrap_or(0);
+
+    let after = match &params.cursor {
+        Some(cursor) => {
+            let position: EntryPosition = decode_cursor(cursor)?;
+            position.0.validate()?;
+            Some(position)
+        }
+        None => None,
+    };
+
+    // Get storage provider
+    let storage = data.storage.clone();
 
     // Find and return raw entries from database
-    let entries = Entry::by_schema(&pool, &params.schema).await?;
-    Ok(QueryEntriesResponse { entries })
+    let entries = storage
+        .query_entries(
+            params.author.as_ref(),
+            params.schema.as_ref(),
+            after.as_ref(),
+            limit as i64,
+            offset as i64,
+        )
+        .await?;
+
+    let next_cursor = next_cursor(&entries, limit, |entry| {
+        Some((
+            entry.author.clone(),
+            entry.log_id.clone(),
+            entry.seq_num.clone(),
+        ))
+    });
+
+    Ok(QueryEntriesResponse {
+        entries,
+        next_cursor,
+    })
 }
 
 #[cfg(test)]
 mod tests {
+    use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
     use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+    use serde_json::Value;
 
-    use crate::rpc::api::build_rpc_api_service;
+    use crate::db::{MemoryStorage, SqlStorage, StorageProvider};
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
     use crate::rpc::server::build_rpc_server;
-    use crate::test_helpers::{handle_http, initialize_db, rpc_request, rpc_response};
+    use crate::rpc::{store_entry, RpcServer, Subscriptions};
+    use crate::test_helpers::{
+        create_test_entry, handle_http, initialize_db, rpc_error, rpc_request,
+    };
+
+    /// Stored entry with the schema of its log.
+    struct Stored {
+        entry: EntrySigned,
+        schema: Hash,
+    }
+
+    // Create tide server with endpoints using the given storage, seeded with two entries in a log
+    // of each schema for two authors. Returns the stored entries in the order of the API.
+    async fn seeded_app<S: StorageProvider + 'static>(
+        storage: S,
+        schemas: &[Hash],
+    ) -> (RpcServer, Vec<Stored>) {
+        let mut stored = Vec::new();
+
+        for key_pair in [KeyPair::new(), KeyPair::new()].iter() {
+            for (index, schema) in schemas.iter().enumerate() {
+                let log_id = LogId::new(index as i64 * 2 + 1);
+                let mut backlink = None;
+
+                for seq_num in 1..3 {
+                    let (entry, message) = create_test_entry(
+                        key_pair,
+                        schema,
+                        &log_id,
+                        None,
+                        backlink.as_ref(),
+                        &SeqNum::new(seq_num).unwrap(),
+                    );
+                    store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                        .await
+                        .unwrap();
+                    backlink = Some(entry.clone());
+                    stored.push(Stored {
+                        entry,
+                        schema: schema.clone(),
+                    });
+                }
+            }
+        }
+
+        // Entries are sorted by author, log id and sequence number
+        stored.sort_by_key(|stored| stored.entry.author().as_str().to_owned());
+
+        let app = build_rpc_server(build_rpc_api_service(storage, RpcApiOptions::default()));
+        (app, stored)
+    }
+
+    async fn seeded_apps(schemas: &[Hash]) -> Vec<(RpcServer, Vec<Stored>)> {
+        vec![
+            seeded_app(MemoryStorage::new(), schemas).await,
+            seeded_app(SqlStorage::new(initialize_db().await), schemas).await,
+        ]
+    }
+
+    // Send a `panda_queryEntries` request and return the hashes of the entries and the cursor
+    async fn query(app: &RpcServer, params: &str) -> (Vec<String>, Value) {
+        let response: Value = serde_json::from_str(
+            &handle_http(app, rpc_request("panda_queryEntries", params)).await,
+        )
+        .unwrap();
+
+        let hashes = response["result"]["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|entry| entry["entryHash"].as_str().unwrap().to_owned())
+            .collect();
+
+        (hashes, response["result"]["nextCursor"].clone())
+    }
+
+    fn hashes<'a>(stored: impl Iterator<Item = &'a Stored>) -> Vec<String> {
+        stored
+            .map(|stored| stored.entry.hash().as_str().to_owned())
+            .collect()
+    }
 
     #[async_std::test]
     async fn query_entries() {
-        // Prepare test database
-        let pool = initialize_db().await;
+        let schemas = vec![
+            Hash::new_from_bytes(vec![1, 2, 3]).unwrap(),
+            Hash::new_from_bytes(vec![4, 5, 6]).unwrap(),
+        ];
+
+        for (app, stored) in seeded_apps(&schemas).await.iter() {
+            let author = stored[0].entry.author();
+
+            // Entries of all authors and schemas
+            let (entries, cursor) = query(app, "{}").await;
+            assert_eq!(entries, hashes(stored.iter()));
+            assert!(cursor.is_null());
+
+            // Entries of one author
+            let (entries, _) =
+                query(app, &format!(r#"{{ "author": "{}" }}"#, author.as_str())).await;
+            assert_eq!(entries.len(), 4);
+            assert_eq!(
+                entries,
+                hashes(
+                    stored
+                        .iter()
+                        .filter(|stored| stored.entry.author() == author)
+                )
+            );
+
+            // Entries of one schema
+            let (entries, _) = query(
+                app,
+                &format!(r#"{{ "schema": "{}" }}"#, schemas[1].as_str()),
+            )
+            .await;
+            assert_eq!(entries.len(), 4);
+            assert_eq!(
+                entries,
+                hashes(stored.iter().filter(|stored| stored.schema == schemas[1]))
+            );
+
+            // Entries of one author and schema
+            let (entries, _) = query(
+                app,
+                &format!(
+                    r#"{{ "author": "{}", "schema": "{}" }}"#,
+                    author.as_str(),
+                    schemas[0].as_str()
+                ),
+            )
+            .await;
+            assert_eq!(entries.len(), 2);
+            assert_eq!(
+                entries,
+                hashes(stored.iter().filter(|stored| {
+                    stored.entry.author() == author && stored.schema == schemas[0]
+                }))
+            );
+        }
+    }
+
+    #[async_std::test]
+    async fn query_entries_by_author_with_pagination() {
+        let schemas = vec![
+            Hash::new_from_bytes(vec![1, 2, 3]).unwrap(),
+            Hash::new_from_bytes(vec![4, 5, 6]).unwrap(),
+        ];
+
+        for (app, stored) in seeded_apps(&schemas).await.iter() {
+            let author = stored[0].entry.author();
+
+            // Slice entries of an author with limit and offset
+            let (entries, cursor) = query(
+                app,
+                &format!(
+                    r#"{{ "author": "{}", "limit": 2, "offset": 1 }}"#,
+                    author.as_str()
+                ),
+            )
+            .await;
+            assert_eq!(entries, hashes(stored[1..3].iter()));
+            assert!(!cursor.is_null());
+
+            let (entries, cursor) = query(
+                app,
+                &format!(
+                    r#"{{ "author": "{}", "limit": 10, "offset": 3 }}"#,
+                    author.as_str()
+                ),
+            )
+            .await;
+            assert_eq!(entries, hashes(stored[3..4].iter()));
+            assert!(cursor.is_null());
+
+            // Following cursors returns every entry exactly once
+            let mut cursor = Value::Null;
+            let mut pages = Vec::new();
+            let mut entries = Vec::new();
+            loop {
+                let (mut page, next_cursor) =
+                    query(app, &format!(r#"{{ "limit": 3, "cursor": {} }}"#, cursor)).await;
+                pages.push(page.len());
+                entries.append(&mut page);
+
+                cursor = next_cursor;
+                if cursor.is_null() {
+                    break;
+                }
+            }
+            assert_eq!(pages, vec![3, 3, 2]);
+            assert_eq!(entries, hashes(stored.iter()));
 
-        // Create tide server with endpoints
-        let rpc_api = build_rpc_api_service(pool);
+            // Cursors keep the author filter
+            let (first_page, cursor) = query(
+                app,
+                &format!(r#"{{ "author": "{}", "limit": 3 }}"#, author.as_str()),
+            )
+            .await;
+            let (second_page, cursor) = query(
+                app,
+                &format!(
+                    r#"{{ "author": "{}", "limit": 3, "cursor": {} }}"#,
+                    author.as_str(),
+                    cursor
+                ),
+            )
+            .await;
+            assert_eq!(first_page, hashes(stored[0..3].iter()));
+            assert_eq!(second_page, hashes(stored[3..4].iter()));
+            assert!(cursor.is_null());
+        }
+    }
+
+    #[async_std::test]
+    async fn respond_with_limit_too_large_error() {
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let request = rpc_request(
+            "panda_queryEntries",
+            r#"{
+                "limit": 1000
+            }"#,
+        );
+
+        let response = rpc_error("Requested limit exceeds maximum of 500 entries");
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+
+    #[async_std::test]
+    async fn respond_with_invalid_cursor_error() {
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
         let app = build_rpc_server(rpc_api);
 
-        // Prepare request to API
-        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
         let request = rpc_request(
             "panda_queryEntries",
-            &format!(
-                r#"{{
-                    "schema": "{}"
-                }}"#,
-                schema.as_str(),
-            ),
+            r#"{
+                "cursor": "abc"
+            }"#,
         );
 
-        // Prepare expected response result
-        let response = rpc_response(&format!(
-            r#"{{
-                "entries": []
-            }}"#,
-        ));
+        let response = rpc_error("Invalid pagination cursor");
 
         assert_eq!(handle_http(&app, request).await, response);
     }
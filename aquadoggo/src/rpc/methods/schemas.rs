@@ -0,0 +1,73 @@
+use jsonrpc_v2::Data;
+
+use crate::errors::Result;
+use crate::rpc::response::SchemasResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getAllSchemas` RPC method.
+///
+/// Returns all schemas this node stores entries of with the number of their entries and
+/// materialized documents.
+pub async fn get_all_schemas(data: Data<RpcApiState>) -> Result<SchemasResponse> {
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    let schemas = storage.schemas().await?;
+
+    Ok(SchemasResponse { schemas })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, handle_http, rpc_request, rpc_response};
+
+    #[async_std::test]
+    async fn get_all_schemas() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        // Two authors create a document of the same schema each
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        for _ in 0..2 {
+            let (entry, message) = create_test_entry(
+                &KeyPair::new(),
+                &schema,
+                &LogId::new(1),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+        }
+
+        let request = rpc_request("panda_getAllSchemas", "{}");
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "schemas": [
+                    {{
+                        "schema": "{}",
+                        "entryCount": 2,
+                        "documentCount": 2
+                    }}
+                ]
+            }}"#,
+            schema.as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
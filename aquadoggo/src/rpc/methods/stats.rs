@@ -0,0 +1,73 @@
+use jsonrpc_v2::Data;
+
+use crate::errors::Result;
+use crate::rpc::response::StatsResponse;
+use crate::rpc::RpcApiState;
+
+/// Implementation of `panda_getStats` RPC method.
+///
+/// Returns statistics about the data stored on this node for operator dashboards.
+pub async fn get_stats(data: Data<RpcApiState>) -> Result<StatsResponse> {
+    // Get storage provider
+    let storage = data.storage.clone();
+
+    Ok(StatsResponse {
+        author_count: storage.author_count().await?,
+        database_size: storage.storage_size().await?,
+        entry_count: storage.entry_count().await?,
+        log_count: storage.log_count().await?,
+        materializer_backlog: storage.materializer_backlog().await?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::rpc::{store_entry, Subscriptions};
+    use crate::test_helpers::{create_test_entry, handle_http, rpc_request, rpc_response};
+
+    #[async_std::test]
+    async fn get_stats() {
+        let storage = MemoryStorage::new();
+        let app = build_rpc_server(build_rpc_api_service(
+            storage.clone(),
+            RpcApiOptions::default(),
+        ));
+
+        // Store two entries of the same author in different logs
+        let key_pair = KeyPair::new();
+        for (schema, log_id) in [(vec![1, 2, 3], 1), (vec![4, 5, 6], 3)].iter() {
+            let (entry, message) = create_test_entry(
+                &key_pair,
+                &Hash::new_from_bytes(schema.clone()).unwrap(),
+                &LogId::new(*log_id),
+                None,
+                None,
+                &SeqNum::new(1).unwrap(),
+            );
+            store_entry(&storage, &Subscriptions::new(), &entry, &message)
+                .await
+                .unwrap();
+        }
+
+        let request = rpc_request("panda_getStats", "{}");
+
+        let response = rpc_response(
+            r#"{
+                "authorCount": 1,
+                "databaseSize": null,
+                "entryCount": 2,
+                "logCount": 2,
+                "materializerBacklog": 0
+            }"#,
+        );
+
+        assert_eq!(handle_http(&app, request).await, response);
+    }
+}
@@ -0,0 +1,176 @@
+use jsonrpc_v2::Params;
+use p2panda_rs::entry::decode_entry;
+use p2panda_rs::message::Message;
+use p2panda_rs::Validate;
+
+use crate::errors::Result;
+use crate::rpc::methods::publish_entry::verify_entry;
+use crate::rpc::request::ValidateEntryRequest;
+use crate::rpc::response::ValidateEntryResponse;
+
+/// Implementation of `panda_validateEntry` RPC method.
+///
+/// Runs all checks of an entry which do not need the database: decoding, signature, message hash
+/// and the Bamboo links against the given link entries. Nothing gets stored, this helps client
+/// developers to find out why the node rejects their entries.
+pub async fn validate_entry(
+    Params(params): Params<ValidateEntryRequest>,
+) -> Result<ValidateEntryResponse> {
+    // Validate request parameters
+    params.entry_encoded.validate()?;
+
+    if let Some(message_encoded) = &params.message_encoded {
+        message_encoded.validate()?;
+    }
+
+    for link in params
+        .skiplink_encoded
+        .iter()
+        .chain(params.backlink_encoded.iter())
+    {
+        link.validate()?;
+    }
+
+    // Handle error as this conversion validates message hash
+    let entry = decode_entry(&params.entry_encoded, params.message_encoded.as_ref())?;
+
+    // Entries do not contain a skiplink when it points at the same entry as the backlink
+    let skiplink_encoded = match (
+        &params.skiplink_encoded,
+        entry.seq_num_skiplink(),
+        entry.seq_num_backlink(),
+    ) {
+        (None, Some(skiplink), Some(backlink)) if skiplink.as_i64() == backlink.as_i64() => {
+            params.backlink_encoded.as_ref()
+        }
+        (skiplink_encoded, _, _) => skiplink_encoded.as_ref(),
+    };
+
+    // Verify bamboo entry integrity against the given links
+    verify_entry(
+        params.entry_encoded.to_bytes(),
+        params
+            .message_encoded
+            .as_ref()
+            .map(|message| message.to_bytes()),
+        skiplink_encoded.map(|link| link.to_bytes()),
+        params.backlink_encoded.as_ref().map(|link| link.to_bytes()),
+    )
+    .await?;
+
+    Ok(ValidateEntryResponse {
+        entry_hash: params.entry_encoded.hash(),
+        author: params.entry_encoded.author(),
+        log_id: entry.log_id().to_owned(),
+        seq_num: entry.seq_num().to_owned(),
+        schema: params
+            .message_encoded
+            .as_ref()
+            .map(|message| Message::from(message).schema().to_owned()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::KeyPair;
+
+    use crate::db::MemoryStorage;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::server::build_rpc_server;
+    use crate::test_helpers::{
+        create_test_entry, handle_http, rpc_error, rpc_request, rpc_response,
+    };
+
+    #[async_std::test]
+    async fn validate_entry() {
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let key_pair = KeyPair::new();
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let log_id = LogId::new(1);
+
+        // [1] <-- [2]
+        let (entry_1, _) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            None,
+            &SeqNum::new(1).unwrap(),
+        );
+        let (entry_2, message_2) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            None,
+            Some(&entry_1),
+            &SeqNum::new(2).unwrap(),
+        );
+
+        // Entries are validated against the given links, the backlink does not need to be stored
+        let request = rpc_request(
+            "panda_validateEntry",
+            &format!(
+                r#"{{
+                    "entryEncoded": "{}",
+                    "messageEncoded": "{}",
+                    "backlinkEncoded": "{}"
+                }}"#,
+                entry_2.as_str(),
+                message_2.as_str(),
+                entry_1.as_str(),
+            ),
+        );
+
+        let response = rpc_response(&format!(
+            r#"{{
+                "author": "{}",
+                "entryHash": "{}",
+                "logId": 1,
+                "schema": "{}",
+                "seqNum": 2
+            }}"#,
+            entry_2.author().as_str(),
+            entry_2.hash().as_str(),
+            schema.as_str(),
+        ));
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Entries pointing at other links than the given ones are refused
+        let (entry_wrong_hash, _) = create_test_entry(
+            &key_pair,
+            &schema,
+            &log_id,
+            Some(&entry_2),
+            Some(&entry_1),
+            &SeqNum::new(3).unwrap(),
+        );
+
+        let request = rpc_request(
+            "panda_validateEntry",
+            &format!(
+                r#"{{
+                    "entryEncoded": "{}",
+                    "backlinkEncoded": "{}"
+                }}"#,
+                entry_wrong_hash.as_str(),
+                entry_2.as_str(),
+            ),
+        );
+
+        let response = rpc_error(
+            "The backlink hash encoded in the entry does not match the lipmaa entry provided",
+        );
+
+        assert_eq!(handle_http(&app, request).await, response);
+
+        // Nothing got stored
+        let request = rpc_request("panda_getStats", "{}");
+        let response = handle_http(&app, request).await;
+        assert!(response.contains(r#""entryCount":0"#));
+    }
+}
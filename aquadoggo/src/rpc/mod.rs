@@ -1,9 +1,25 @@
 mod api;
+mod auth;
+mod cors;
+mod cursor;
+mod limits;
 mod methods;
-mod request;
-mod response;
+mod openrpc;
+mod rate_limit;
+pub mod request;
+pub mod response;
 mod server;
+mod subscriptions;
 
-pub use api::{build_rpc_api_service, RpcApiService, RpcApiState};
-pub use methods::error::PublishEntryError;
-pub use server::{build_rpc_server, start_rpc_server, RpcServer, RpcServerRequest};
+pub use api::{build_rpc_api_service, NodeInfo, RpcApiOptions, RpcApiService, RpcApiState};
+pub use cors::CorsPolicy;
+pub use limits::RequestLimits;
+pub use methods::error::{
+    AdminError, EncryptedMessagesError, PublishEntryError, QueryEntriesError,
+};
+pub use methods::{is_fork, store_entry, store_pruned_entry};
+pub use server::{
+    build_rpc_server, handle_rpc_request, start_rpc_server, RpcServer, RpcServerRequest,
+    RATE_LIMIT_CODE, REQUEST_TIMEOUT_CODE, SERVER_BUSY_CODE,
+};
+pub use subscriptions::{EntryNotification, SubscriptionId, Subscriptions};
@@ -0,0 +1,254 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+use crate::rpc::request::{
+    AggregateDocumentsRequest, AuthorProfileRequest, DocumentRequest, EncryptedMessagesRequest,
+    EntriesByAuthorRequest, EntryArgsBatchRequest, EntryArgsRequest, EntryByHashRequest,
+    ForksRequest, KeyGroupRequest, LogsByAuthorRequest, PrunePayloadsRequest,
+    PublishEntriesRequest, PublishEntryRequest, PurgeAuthorRequest, PurgeLogRequest,
+    QueryDocumentsRequest, QueryEntriesRequest, SubscribeEntriesRequest, UnsubscribeEntriesRequest,
+    ValidateEntryRequest,
+};
+use crate::rpc::response::{
+    AggregateDocumentsResponse, AuthorProfileResponse, CreateBackupResponse, DocumentResponse,
+    EncryptedMessagesResponse, EntriesByAuthorResponse, EntryArgsBatchResponse, EntryArgsResponse,
+    EntryByHashResponse, ForksResponse, KeyGroupResponse, LogsByAuthorResponse, NodeInfoResponse,
+    PrunePayloadsResponse, PublishEntriesResponse, PublishEntryResponse, PurgeResponse,
+    QueryDocumentsResponse, QueryEntriesResponse, SchemasResponse, StatsResponse,
+    ValidateEntryResponse,
+};
+use crate::rpc::SubscriptionId;
+
+/// Version of the OpenRPC specification the document follows.
+const OPENRPC_VERSION: &str = "1.2.6";
+
+/// Name of the method returning the OpenRPC document.
+pub const DISCOVER_METHOD: &str = "rpc.discover";
+
+/// Builds an OpenRPC document from the request and response types of all methods.
+///
+/// Schemas of all types are collected in the components of the document and referenced by the
+/// methods.
+struct OpenRpcBuilder {
+    generator: SchemaGenerator,
+    methods: Vec<Value>,
+}
+
+impl OpenRpcBuilder {
+    fn new() -> Self {
+        let settings = SchemaSettings::draft07().with(|settings| {
+            settings.definitions_path = "#/components/schemas/".to_owned();
+        });
+
+        Self {
+            generator: SchemaGenerator::new(settings),
+            methods: Vec::new(),
+        }
+    }
+
+    /// Add method with parameters given by the properties of the request type `P`.
+    ///
+    /// Use `()` as request type for methods without parameters.
+    fn method<P: JsonSchema, R: JsonSchema>(mut self, name: &str, summary: &str) -> Self {
+        let params = match P::json_schema(&mut self.generator) {
+            Schema::Object(SchemaObject {
+                object: Some(object),
+                ..
+            }) => object
+                .properties
+                .iter()
+                .map(|(param, schema)| {
+                    json!({
+                        "name": param,
+                        "required": object.required.contains(param),
+                        "schema": schema,
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let result = self.generator.subschema_for::<R>();
+
+        self.methods.push(json!({
+            "name": name,
+            "summary": summary,
+            "paramStructure": "by-name",
+            "params": params,
+            "result": {
+                "name": "result",
+                "schema": result,
+            },
+        }));
+
+        self
+    }
+
+    fn build(self) -> Value {
+        json!({
+            "openrpc": OPENRPC_VERSION,
+            "info": {
+                "title": "aquadoggo",
+                "description": "Node server with JSON RPC API for the p2panda network.",
+                "version": env!("CARGO_PKG_VERSION"),
+            },
+            "methods": self.methods,
+            "components": {
+                "schemas": self.generator.definitions(),
+            },
+        })
+    }
+}
+
+/// Returns the OpenRPC document describing all methods of the JSON RPC API.
+///
+/// Subscription methods are only available via WebSocket, admin methods only when an admin token
+/// is configured.
+pub fn openrpc_document() -> Value {
+    OpenRpcBuilder::new()
+        .method::<AggregateDocumentsRequest, AggregateDocumentsResponse>(
+            "panda_aggregateDocuments",
+            "Count documents and find minimum and maximum values of their fields",
+        )
+        .method::<(), CreateBackupResponse>(
+            "panda_createBackup",
+            "Write a snapshot of the database, requires the admin token",
+        )
+        .method::<(), SchemasResponse>("panda_getAllSchemas", "Return all stored schemas")
+        .method::<AuthorProfileRequest, Option<AuthorProfileResponse>>(
+            "panda_getAuthorProfile",
+            "Return the profile of an author and the key groups the author is a member of",
+        )
+        .method::<DocumentRequest, Option<DocumentResponse>>(
+            "panda_getDocument",
+            "Return the materialized state of a document",
+        )
+        .method::<EncryptedMessagesRequest, Option<EncryptedMessagesResponse>>(
+            "panda_getEncryptedMessages",
+            "Return the messages encrypted for a key group, requires a signature of a member",
+        )
+        .method::<EntriesByAuthorRequest, EntriesByAuthorResponse>(
+            "panda_getEntriesByAuthor",
+            "Return entries of an author within a range of sequence numbers",
+        )
+        .method::<EntryArgsRequest, EntryArgsResponse>(
+            "panda_getEntryArguments",
+            "Return the arguments to create the next entry of an author",
+        )
+        .method::<EntryArgsBatchRequest, EntryArgsBatchResponse>(
+            "panda_getEntryArgumentsBatch",
+            "Return entry arguments for several authors and schemas",
+        )
+        .method::<EntryByHashRequest, Option<EntryByHashResponse>>(
+            "panda_getEntryByHash",
+            "Return an entry and its message by the entry hash",
+        )
+        .method::<ForksRequest, ForksResponse>(
+            "panda_getForks",
+            "Return refused entries of forked logs of an author",
+        )
+        .method::<KeyGroupRequest, Option<KeyGroupResponse>>(
+            "panda_getKeyGroup",
+            "Return a key group and its members",
+        )
+        .method::<LogsByAuthorRequest, LogsByAuthorResponse>(
+            "panda_getLogsByAuthor",
+            "Return the logs of an author with their latest sequence number",
+        )
+        .method::<(), NodeInfoResponse>("panda_getNodeInfo", "Return details about this node")
+        .method::<(), StatsResponse>(
+            "panda_getStats",
+            "Return entry, log and author counts of this node",
+        )
+        .method::<PrunePayloadsRequest, PrunePayloadsResponse>(
+            "panda_prunePayloads",
+            "Remove payloads of a schema's entries, requires the admin token",
+        )
+        .method::<PublishEntriesRequest, PublishEntriesResponse>(
+            "panda_publishEntries",
+            "Store a batch of entries of one author",
+        )
+        .method::<PublishEntryRequest, PublishEntryResponse>(
+            "panda_publishEntry",
+            "Store an entry with its message",
+        )
+        .method::<PurgeAuthorRequest, PurgeResponse>(
+            "panda_purgeAuthor",
+            "Remove all data of an author, requires the admin token",
+        )
+        .method::<PurgeLogRequest, PurgeResponse>(
+            "panda_purgeLog",
+            "Remove a log of an author, requires the admin token",
+        )
+        .method::<QueryDocumentsRequest, QueryDocumentsResponse>(
+            "panda_queryDocuments",
+            "Filter, sort and paginate documents of a schema",
+        )
+        .method::<QueryEntriesRequest, QueryEntriesResponse>(
+            "panda_queryEntries",
+            "Return entries filtered by author and schema",
+        )
+        .method::<SubscribeEntriesRequest, SubscriptionId>(
+            "panda_subscribeEntries",
+            "Subscribe to new entries via WebSocket, returns the subscription id",
+        )
+        .method::<UnsubscribeEntriesRequest, bool>(
+            "panda_unsubscribeEntries",
+            "Cancel a subscription via WebSocket",
+        )
+        .method::<ValidateEntryRequest, ValidateEntryResponse>(
+            "panda_validateEntry",
+            "Validate an entry with its message and links without storing it",
+        )
+        .method::<(), Value>(DISCOVER_METHOD, "Return this OpenRPC document")
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::openrpc_document;
+
+    use crate::rpc::api::RPC_METHODS;
+
+    #[test]
+    fn describe_all_methods() {
+        let document = openrpc_document();
+        let methods = document["methods"].as_array().unwrap();
+
+        for name in RPC_METHODS.iter() {
+            assert!(
+                methods.iter().any(|method| method["name"] == *name),
+                "{} is not described",
+                name
+            );
+        }
+
+        // Parameters are taken from the request types
+        let method = methods
+            .iter()
+            .find(|method| method["name"] == "panda_getEntriesByAuthor")
+            .unwrap();
+        let params = method["params"].as_array().unwrap();
+        assert_eq!(params.len(), 6);
+        assert_eq!(params[0]["name"], "author");
+        assert_eq!(params[0]["required"], true);
+        assert_eq!(params[1]["name"], "cursor");
+        assert_eq!(params[1]["required"], false);
+
+        // Response types are referenced from the components
+        assert_eq!(
+            method["result"]["schema"]["$ref"],
+            "#/components/schemas/EntriesByAuthorResponse"
+        );
+        assert!(document["components"]["schemas"]["Entry"].is_object());
+
+        // Methods without parameters
+        let method = methods
+            .iter()
+            .find(|method| method["name"] == "panda_getNodeInfo")
+            .unwrap();
+        assert!(method["params"].as_array().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Time window in which the number of requests of every client is limited.
+const RATE_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+
+/// Number of tracked clients after which clients with expired time windows get removed.
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// Number of requests a client sent in its current time window.
+#[derive(Debug, Clone, Copy)]
+struct ClientWindow {
+    started: Instant,
+    requests: u32,
+}
+
+/// Limits the number of RPC requests per minute from a single IP address.
+///
+/// Every JSON RPC call counts as one request, no matter if it was sent via HTTP, WebSocket or
+/// gRPC.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    limit: u32,
+    clients: Arc<Mutex<HashMap<IpAddr, ClientWindow>>>,
+}
+
+impl RateLimit {
+    /// Returns a rate limit allowing `limit` requests per minute from every IP address.
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            clients: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Registers a request from the given IP address.
+    ///
+    /// Returns the remaining time of the current window when the client exceeded the limit.
+    pub fn check(&self, ip: IpAddr, now: Instant) -> Option<Duration> {
+        let mut clients = self.clients.lock().unwrap();
+
+        // Forget about clients which did not send any requests lately
+        if clients.len() >= MAX_TRACKED_CLIENTS {
+            clients.retain(|_, window| now.duration_since(window.started) < RATE_LIMIT_WINDOW);
+        }
+
+        let window = clients.entry(ip).or_insert(ClientWindow {
+            started: now,
+            requests: 0,
+        });
+
+        // Start a new window when the last one ended
+        if now.duration_since(window.started) >= RATE_LIMIT_WINDOW {
+            window.started = now;
+            window.requests = 0;
+        }
+
+        if window.requests >= self.limit {
+            return Some(RATE_LIMIT_WINDOW - now.duration_since(window.started));
+        }
+
+        window.requests += 1;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, Instant};
+
+    use super::{RateLimit, RATE_LIMIT_WINDOW};
+
+    #[test]
+    fn limit_requests_per_ip() {
+        let rate_limit = RateLimit::new(2);
+        let client_1 = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1));
+        let client_2 = IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2));
+        let now = Instant::now();
+
+        assert_eq!(rate_limit.check(client_1, now), None);
+        assert_eq!(rate_limit.check(client_1, now), None);
+
+        // Third request within the same window gets rejected
+        let later = now + Duration::from_secs(20);
+        assert_eq!(
+            rate_limit.check(client_1, later),
+            Some(Duration::from_secs(40))
+        );
+
+        // Other clients are not affected
+        assert_eq!(rate_limit.check(client_2, later), None);
+
+        // Requests are allowed again in the next window
+        assert_eq!(rate_limit.check(client_1, now + RATE_LIMIT_WINDOW), None);
+    }
+}
@@ -1,27 +1,324 @@
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 
-use p2panda_rs::entry::EntrySigned;
+use p2panda_rs::entry::{EntrySigned, LogId, SeqNum};
 use p2panda_rs::hash::Hash;
 use p2panda_rs::identity::Author;
 use p2panda_rs::message::MessageEncoded;
 
+use crate::db::query_builder::Order;
+use crate::materializer::{FieldAggregate, FieldFilter};
+use crate::rpc::SubscriptionId;
+
 /// Request body of `panda_getEntryArguments`.
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
 pub struct EntryArgsRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
     pub author: Author,
+
+    /// Schema of the next entry.
+    #[schemars(with = "String")]
     pub schema: Hash,
 }
 
+/// Request body of `panda_getEntryArgumentsBatch`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+pub struct EntryArgsBatchRequest {
+    /// Authors and schemas to return entry arguments for.
+    pub requests: Vec<EntryArgsRequest>,
+}
+
+/// Request body of `panda_getEntryByHash`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryByHashRequest {
+    /// Hash of the entry.
+    #[schemars(with = "String")]
+    pub hash: Hash,
+}
+
+/// Request body of `panda_getDocument`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentRequest {
+    /// Id of the document, the hash of the entry which created it.
+    #[schemars(with = "String")]
+    pub document_id: Hash,
+
+    /// Return the state of the document after all changes up to this sequence number.
+    #[schemars(with = "Option<u64>")]
+    pub as_of_seq_num: Option<SeqNum>,
+
+    /// Return the state of the document at this time, given in seconds since the Unix epoch.
+    pub as_of_timestamp: Option<i64>,
+
+    /// Embed the documents referenced by relation fields.
+    #[serde(default)]
+    pub resolve_relations: bool,
+}
+
+/// Request body of `panda_getEntriesByAuthor`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntriesByAuthorRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Only return entries of this log.
+    #[schemars(with = "Option<u64>")]
+    pub log_id: Option<LogId>,
+
+    /// Lowest sequence number of returned entries.
+    #[schemars(with = "Option<u64>")]
+    pub seq_num_from: Option<SeqNum>,
+
+    /// Highest sequence number of returned entries.
+    #[schemars(with = "Option<u64>")]
+    pub seq_num_to: Option<SeqNum>,
+
+    /// Maximum number of returned entries.
+    pub limit: Option<u32>,
+
+    /// Continue with the page following this cursor.
+    pub cursor: Option<String>,
+}
+
+/// Request body of `panda_getForks`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForksRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Only return forks of this log.
+    #[schemars(with = "Option<u64>")]
+    pub log_id: Option<LogId>,
+}
+
+/// Request body of `panda_getLogsByAuthor`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsByAuthorRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+}
+
 /// Request body of `panda_publishEntry`.
-#[derive(Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PublishEntryRequest {
+    /// Signed and encoded Bamboo entry.
+    #[schemars(with = "String")]
     pub entry_encoded: EntrySigned,
+
+    /// Encoded message of the entry.
+    #[schemars(with = "String")]
     pub message_encoded: MessageEncoded,
+
+    /// Validate the entry against its log without storing it, ignored by `panda_publishEntries`.
+    #[serde(default)]
+    pub dry_run: bool,
 }
 
-#[derive(Deserialize, Debug)]
+/// Request body of `panda_publishEntries`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
-pub struct QueryEntriesRequest {
+pub struct PublishEntriesRequest {
+    /// Entries of one author in the order they should be stored.
+    pub entries: Vec<PublishEntryRequest>,
+}
+
+/// Request body of `panda_purgeAuthor`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeAuthorRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+}
+
+/// Request body of `panda_purgeLog`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeLogRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Log of the author to remove.
+    #[schemars(with = "u64")]
+    pub log_id: LogId,
+}
+
+/// Request body of `panda_prunePayloads`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunePayloadsRequest {
+    /// Schema of the entries whose payloads get pruned.
+    #[schemars(with = "String")]
+    pub schema: Hash,
+
+    /// Number of latest entries of every log which keep their payloads.
+    #[serde(default)]
+    pub keep: u64,
+}
+
+/// Request body of `panda_aggregateDocuments`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateDocumentsRequest {
+    /// Schema of the documents.
+    #[schemars(with = "String")]
     pub schema: Hash,
+
+    /// Only documents matching all filters are aggregated.
+    #[serde(default)]
+    pub filters: Vec<FieldFilter>,
+
+    /// Field to group the documents by.
+    pub group_by: Option<String>,
+
+    /// Aggregate functions applied to every group.
+    pub aggregates: Vec<FieldAggregate>,
+}
+
+/// Request body of `panda_queryDocuments`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryDocumentsRequest {
+    /// Schema of the documents.
+    #[schemars(with = "String")]
+    pub schema: Hash,
+
+    /// Only documents matching all filters are returned.
+    #[serde(default)]
+    pub filters: Vec<FieldFilter>,
+
+    /// Field to sort the documents by.
+    pub order_by: Option<String>,
+
+    /// Sort direction, ascending by default.
+    pub order: Option<Order>,
+
+    /// Maximum number of returned documents.
+    pub limit: Option<u32>,
+
+    /// Number of documents to skip.
+    pub offset: Option<u32>,
+
+    /// Continue with the page following this cursor.
+    pub cursor: Option<String>,
+
+    /// Return deleted documents as well.
+    #[serde(default)]
+    pub include_deleted: bool,
+
+    /// Embed the documents referenced by relation fields.
+    #[serde(default)]
+    pub resolve_relations: bool,
+}
+
+/// Request body of `panda_queryEntries`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryEntriesRequest {
+    /// Only return entries of this author.
+    #[schemars(with = "Option<String>")]
+    pub author: Option<Author>,
+
+    /// Only return entries of this schema.
+    #[schemars(with = "Option<String>")]
+    pub schema: Option<Hash>,
+
+    /// Maximum number of returned entries.
+    pub limit: Option<u32>,
+
+    /// Number of entries to skip.
+    pub offset: Option<u32>,
+
+    /// Continue with the page following this cursor.
+    pub cursor: Option<String>,
+}
+
+/// Request body of `panda_subscribeEntries`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscribeEntriesRequest {
+    /// Only notify about entries of this author.
+    #[schemars(with = "Option<String>")]
+    pub author: Option<Author>,
+
+    /// Only notify about entries of this schema.
+    #[schemars(with = "Option<String>")]
+    pub schema: Option<Hash>,
+}
+
+/// Request body of `panda_unsubscribeEntries`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UnsubscribeEntriesRequest {
+    /// Id of the subscription to cancel.
+    pub subscription: SubscriptionId,
+}
+
+/// Request body of `panda_validateEntry`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateEntryRequest {
+    /// Signed and encoded Bamboo entry.
+    #[schemars(with = "String")]
+    pub entry_encoded: EntrySigned,
+
+    /// Encoded message of the entry, the message hash is not checked when not given.
+    #[schemars(with = "Option<String>")]
+    pub message_encoded: Option<MessageEncoded>,
+
+    /// Signed and encoded entry the skiplink points at, not needed when it is the backlink.
+    #[schemars(with = "Option<String>")]
+    pub skiplink_encoded: Option<EntrySigned>,
+
+    /// Signed and encoded entry the backlink points at.
+    #[schemars(with = "Option<String>")]
+    pub backlink_encoded: Option<EntrySigned>,
+}
+
+/// Request body of `panda_getAuthorProfile`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+pub struct AuthorProfileRequest {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+}
+
+/// Request body of `panda_getKeyGroup`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyGroupRequest {
+    /// Id of the key group, the hash of the entry which created it.
+    #[schemars(with = "String")]
+    pub key_group: Hash,
+}
+
+/// Request body of `panda_getEncryptedMessages`.
+#[derive(Serialize, Deserialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedMessagesRequest {
+    /// Id of the key group the messages were encrypted for.
+    #[schemars(with = "String")]
+    pub key_group: Hash,
+
+    /// Public key of the requesting member of the key group.
+    #[schemars(with = "String")]
+    pub member: Author,
+
+    /// Time of the request in seconds since the Unix epoch, it needs to be within 60 seconds of
+    /// the node's time.
+    pub timestamp: i64,
+
+    /// Hex encoded Ed25519 signature of `<key group id>:<timestamp>` by the member.
+    pub signature: String,
 }
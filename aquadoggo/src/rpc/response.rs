@@ -1,32 +1,376 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
 
-use crate::db::models::Entry;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::db::models::{EncryptedMessage, Entry, Fork, LogSummary, SchemaSummary};
+use crate::materializer::{DocumentFields, DocumentGroup};
 use p2panda_rs::entry::{LogId, SeqNum};
 use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
 
 
 /// Response body of `panda_getEntryArguments`.
-#[derive(Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct EntryArgsResponse {
+    /// Hash of the previous entry in the log, empty for the first entry.
+    #[schemars(with = "Option<String>")]
     pub entry_hash_backlink: Option<Hash>,
+
+    /// Hash of the skiplink entry, empty when not required.
+    #[schemars(with = "Option<String>")]
     pub entry_hash_skiplink: Option<Hash>,
+
+    /// Sequence number of the next entry.
+    #[schemars(with = "u64")]
     pub seq_num: SeqNum,
+
+    /// Log of the next entry.
+    #[schemars(with = "u64")]
     pub log_id: LogId,
 }
 
+/// Response body of `panda_getEntryArgumentsBatch`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryArgsBatchResponse {
+    /// Entry arguments in the order of the requests.
+    pub entry_arguments: Vec<EntryArgsResponse>,
+}
+
+/// Response body of `panda_getEntryByHash`.
+///
+/// `messageEncoded` is empty when the payload of this entry got deleted.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryByHashResponse {
+    /// Signed and encoded Bamboo entry.
+    pub entry_encoded: String,
+
+    /// Encoded message of the entry.
+    pub message_encoded: Option<String>,
+}
+
+/// Response body of `panda_getDocument`.
+///
+/// `entryHash` and `seqNum` refer to the latest entry which was applied to this document.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DocumentResponse {
+    /// Id of the document, the hash of the entry which created it.
+    pub document_id: String,
+
+    /// Schema of the document.
+    #[schemars(with = "String")]
+    pub schema: Hash,
+
+    /// Public key of the author.
+    pub author: String,
+
+    /// Hash of the latest entry applied to the document.
+    pub entry_hash: String,
+
+    /// Sequence number of the latest entry applied to the document.
+    pub seq_num: i64,
+
+    /// Field names and values of the document.
+    #[schemars(with = "std::collections::BTreeMap<String, serde_json::Value>")]
+    pub fields: DocumentFields,
+
+    /// Time in seconds since the Unix epoch when the document got deleted, only given for
+    /// deleted documents when the time is known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<i64>,
+
+    /// Public key of the author who deleted the document, only given for deleted documents.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+
+    /// Current state of the documents referenced by relation fields by field name, only given
+    /// when relations were resolved. Unknown and deleted documents are missing.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub relations: BTreeMap<String, DocumentResponse>,
+}
+
+/// Response body of `panda_getEntriesByAuthor`.
+///
+/// `nextCursor` is `null` when the returned page is the last one.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EntriesByAuthorResponse {
+    /// Entries of this page.
+    pub entries: Vec<Entry>,
+
+    /// Cursor to request the following page.
+    pub next_cursor: Option<String>,
+}
+
+/// Response body of `panda_getForks`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ForksResponse {
+    /// Refused entries with the hashes of the stored entries they conflict with.
+    pub forks: Vec<Fork>,
+}
+
+/// Response body of `panda_getLogsByAuthor`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LogsByAuthorResponse {
+    /// Logs of the author.
+    pub logs: Vec<LogSummary>,
+}
+
+/// Response body of `panda_getNodeInfo`.
+///
+/// `replicationSchemas` is empty when this node replicates entries of all schemas.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeInfoResponse {
+    /// Number of authors with entries on this node.
+    pub author_count: i64,
+
+    /// Number of entries stored on this node.
+    pub entry_count: i64,
+
+    /// Names of all supported RPC methods.
+    pub methods: Vec<String>,
+
+    /// Peer id of this node in the peer-to-peer network.
+    pub peer_id: Option<String>,
+
+    /// Public key of this node, used to verify signed responses.
+    pub public_key: Option<String>,
+
+    /// Schemas whose entries are never replicated from other nodes.
+    pub replication_ignored_schemas: Vec<String>,
+
+    /// Schemas replicated from other nodes.
+    pub replication_schemas: Vec<String>,
+
+    /// Software version of the node.
+    pub version: String,
+}
+
+/// Response body of `panda_getAllSchemas`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct SchemasResponse {
+    /// Schemas with their number of entries and documents.
+    pub schemas: Vec<SchemaSummary>,
+}
+
 /// Response body of `panda_publishEntry`.
-#[derive(Serialize, Debug)]
+///
+/// Contains the hash of the stored entry and the arguments to create the next entry in the same
+/// log, like returned by `panda_getEntryArguments`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PublishEntryResponse {
+    /// Hash of the stored entry.
+    #[schemars(with = "String")]
+    pub entry_hash: Hash,
+
+    /// Hash of the previous entry for the next entry in the log.
+    #[schemars(with = "Option<String>")]
     pub entry_hash_backlink: Option<Hash>,
+
+    /// Hash of the skiplink entry for the next entry, empty when not required.
+    #[schemars(with = "Option<String>")]
     pub entry_hash_skiplink: Option<Hash>,
+
+    /// Sequence number of the next entry.
+    #[schemars(with = "u64")]
     pub seq_num: SeqNum,
+
+    /// Log of the next entry.
+    #[schemars(with = "u64")]
     pub log_id: LogId,
 }
 
-#[derive(Serialize, Debug)]
+/// Response body of `panda_getStats`.
+///
+/// `databaseSize` is given in bytes and empty when the node does not store any data on disk,
+/// `materializerBacklog` is the number of entries which still need to be materialized.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StatsResponse {
+    /// Number of authors with entries on this node.
+    pub author_count: i64,
+
+    /// Size of the database in bytes.
+    pub database_size: Option<i64>,
+
+    /// Number of entries stored on this node.
+    pub entry_count: i64,
+
+    /// Number of logs stored on this node.
+    pub log_count: i64,
+
+    /// Number of entries which still need to be materialized.
+    pub materializer_backlog: i64,
+}
+
+/// Response body of `panda_createBackup`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateBackupResponse {
+    /// Path of the written backup file.
+    pub path: String,
+}
+
+/// Response body of `panda_publishEntries`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishEntriesResponse {
+    /// Hashes of all given entries.
+    #[schemars(with = "Vec<String>")]
+    pub entry_hashes: Vec<Hash>,
+}
+
+/// Response body of `panda_purgeAuthor` and `panda_purgeLog`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeResponse {
+    /// Number of removed entries.
+    pub entries: u64,
+}
+
+/// Response body of `panda_prunePayloads`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PrunePayloadsResponse {
+    /// Number of pruned payloads.
+    pub payloads: u64,
+}
+
+/// Response body of `panda_aggregateDocuments`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AggregateDocumentsResponse {
+    /// Results of the aggregate functions per group.
+    pub groups: Vec<DocumentGroup>,
+}
+
+/// Response body of `panda_queryDocuments`.
+///
+/// `nextCursor` is `null` when the returned page is the last one.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryDocumentsResponse {
+    /// Documents of this page.
+    pub documents: Vec<DocumentResponse>,
+
+    /// Cursor to request the following page.
+    pub next_cursor: Option<String>,
+}
+
+/// Response body of `panda_queryEntries`.
+///
+/// `nextCursor` is `null` when the returned page is the last one.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryEntriesResponse {
+    /// Entries of this page.
     pub entries: Vec<Entry>,
+
+    /// Cursor to request the following page.
+    pub next_cursor: Option<String>,
+}
+
+/// Response body of `panda_validateEntry`.
+///
+/// Only returned for valid entries, the reason why an entry is invalid is returned as error.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidateEntryResponse {
+    /// Hash of the entry.
+    #[schemars(with = "String")]
+    pub entry_hash: Hash,
+
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Log of the entry.
+    #[schemars(with = "u64")]
+    pub log_id: LogId,
+
+    /// Sequence number of the entry.
+    #[schemars(with = "u64")]
+    pub seq_num: SeqNum,
+
+    /// Schema of the message, empty when no message was given.
+    #[schemars(with = "Option<String>")]
+    pub schema: Option<Hash>,
+}
+
+/// Response body of `panda_getAuthorProfile`.
+///
+/// `entryHash` and `seqNum` refer to the latest entry which set the profile.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthorProfileResponse {
+    /// Public key of the author.
+    #[schemars(with = "String")]
+    pub author: Author,
+
+    /// Name of the author to display in applications.
+    pub display_name: String,
+
+    /// Hash of the latest entry of the profile.
+    #[schemars(with = "String")]
+    pub entry_hash: Hash,
+
+    /// Sequence number of the latest entry of the profile.
+    #[schemars(with = "u64")]
+    pub seq_num: SeqNum,
+
+    /// Ids of all key groups the author is a member of, ordered by id.
+    #[schemars(with = "Vec<String>")]
+    pub key_groups: Vec<Hash>,
+}
+
+/// Response body of `panda_getKeyGroup`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct KeyGroupResponse {
+    /// Id of the key group, the hash of the entry which created it.
+    #[schemars(with = "String")]
+    pub key_group: Hash,
+
+    /// Public key of the author who created the key group.
+    #[schemars(with = "String")]
+    pub owner: Author,
+
+    /// Name of the key group.
+    pub name: String,
+
+    /// Public keys of all members including the owner, ordered by public key.
+    #[schemars(with = "Vec<String>")]
+    pub members: Vec<Author>,
+}
+
+/// Response body of `panda_getEncryptedMessages`.
+#[derive(Deserialize, Serialize, Debug, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct EncryptedMessagesResponse {
+    /// Id of the key group, the hash of the entry which created it.
+    #[schemars(with = "String")]
+    pub key_group: Hash,
+
+    /// Public key of the author who created the key group.
+    #[schemars(with = "String")]
+    pub owner: Author,
+
+    /// Name of the key group.
+    pub name: String,
+
+    /// Public keys of all members including the owner, ordered by public key.
+    #[schemars(with = "Vec<String>")]
+    pub members: Vec<Author>,
+
+    /// Messages encrypted for the key group, ordered by author and sequence number.
+    pub messages: Vec<EncryptedMessage>,
 }
@@ -1,68 +1,392 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
+use async_std::channel::Receiver;
+use async_std::future::timeout;
 use async_std::stream::StreamExt;
-use http_types::headers::HeaderValue;
+use async_std::task;
 use jsonrpc_v2::RequestObject;
-use tide::security::{CorsMiddleware, Origin};
+use p2panda_rs::Validate;
+use serde_json::{json, Value};
+use tide::listener::{ConcurrentListener, Listener};
+use tide::sse::Sender;
 use tide_websockets::{Message, WebSocket, WebSocketConnection};
+use tracing::{debug, info_span, warn, Instrument};
 
 use crate::config::Configuration;
-use crate::rpc::RpcApiService;
+use crate::rpc::request::{SubscribeEntriesRequest, UnsubscribeEntriesRequest};
+use crate::rpc::subscriptions::{
+    SUBSCRIBE_ENTRIES_METHOD, SUBSCRIPTION_NOTIFICATION_METHOD, UNSUBSCRIBE_ENTRIES_METHOD,
+};
+use crate::rpc::{EntryNotification, RpcApiService, SubscriptionId};
+
+/// JSON RPC error code for invalid method parameters.
+const INVALID_PARAMS_CODE: i64 = -32602;
+
+/// JSON RPC error code for requests refused because too many requests are handled already.
+pub const SERVER_BUSY_CODE: i64 = -32000;
+
+/// JSON RPC error code for requests which took too long to handle.
+pub const REQUEST_TIMEOUT_CODE: i64 = -32001;
+
+/// JSON RPC error code for requests of clients which exceeded the rate limit.
+pub const RATE_LIMIT_CODE: i64 = -32002;
+
+/// Content type of CBOR encoded RPC requests and responses.
+const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// HTTP header carrying the hex encoded signature of a response body.
+const SIGNATURE_HEADER: &str = "X-Aquadoggo-Signature";
+
+/// Counter to assign an id to every handled RPC request, used to correlate log events.
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
 pub type RpcServer = tide::Server<RpcApiService>;
 pub type RpcServerRequest = tide::Request<RpcApiService>;
 
+/// Handle RPC request within a tracing span carrying the request id and method name.
+///
+/// `authorization` is the value of the `Authorization` header of the client, requests calling
+/// write methods without a valid bearer token fail with status `401 Unauthorized`.
+///
+/// `peer` is the IP address of the client, every request counts towards its rate limit.
+///
+/// Requests exceeding the rate limit, the configured timeout or maximum number of concurrently
+/// handled requests are answered with a JSON RPC error.
+pub async fn handle_rpc_request(
+    rpc_server: &RpcApiService,
+    request: Value,
+    authorization: Option<&str>,
+    peer: Option<IpAddr>,
+) -> tide::Result<Value> {
+    if let Some(retry_after) = rpc_server.limits.check_rate(peer) {
+        debug!("Rate limit exceeded by {:?}", peer);
+
+        // Round up to full seconds
+        let retry_after_secs = (retry_after.as_millis() + 999) / 1000;
+
+        return Ok(rpc_error(
+            &request["id"],
+            RATE_LIMIT_CODE,
+            &format!("Rate limit exceeded, retry in {} seconds", retry_after_secs),
+        ));
+    }
+
+    if !rpc_server.write_tokens.allows(&request, authorization) {
+        return Err(tide::Error::from_str(
+            http_types::StatusCode::Unauthorized,
+            "Missing or invalid bearer token",
+        ));
+    }
+
+    let request_id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    let method = request["method"].as_str().unwrap_or_default().to_owned();
+    let span = info_span!("rpc_request", request_id, method = %method);
+
+    async move {
+        let id = request["id"].clone();
+
+        // Refuse request right away when the node is busy, it is released again when dropped
+        let _in_flight = match rpc_server.limits.acquire() {
+            Some(in_flight) => in_flight,
+            None => {
+                warn!("Refused RPC request, too many requests in flight");
+                return Ok(rpc_error(&id, SERVER_BUSY_CODE, "Too many requests"));
+            }
+        };
+
+        let rpc_request: RequestObject = serde_json::from_value(request)
+            .map_err(|err| tide::Error::new(http_types::StatusCode::UnprocessableEntity, err))?;
+
+        let started = Instant::now();
+        let handler = rpc_server.router.handle(rpc_request);
+        let rpc_result = match rpc_server.limits.timeout() {
+            Some(duration) => match timeout(duration, handler).await {
+                Ok(rpc_result) => rpc_result,
+                Err(_) => {
+                    warn!("RPC request timed out after {:?}", duration);
+                    return Ok(rpc_error(&id, REQUEST_TIMEOUT_CODE, "Request timed out"));
+                }
+            },
+            None => handler.await,
+        };
+        debug!(
+            elapsed_ms = started.elapsed().as_millis() as u64,
+            "Handled RPC request"
+        );
+
+        Ok(serde_json::to_value(rpc_result)?)
+    }
+    .instrument(span)
+    .await
+}
+
+/// Returns the IP address of the client which sent the request.
+///
+/// Uses the address of the connection, forwarding headers could be set by anyone. Requests via the
+/// unix domain socket have no IP address.
+fn peer_ip(request: &RpcServerRequest) -> Option<IpAddr> {
+    request
+        .peer_addr()
+        .and_then(|address| address.parse::<SocketAddr>().ok())
+        .map(|address| address.ip())
+}
+
 /// Handle incoming HTTP JSON RPC requests.
+///
+/// Requests and responses are encoded as CBOR instead of JSON when the client sends its request
+/// with the `application/cbor` content type. Clients can also ask for CBOR responses to JSON
+/// requests via the `Accept` header.
 pub async fn handle_http_request(mut request: RpcServerRequest) -> tide::Result {
+    let authorization = request
+        .header("Authorization")
+        .map(|value| value.as_str().to_owned());
+    let cbor_request = request
+        .content_type()
+        .map_or(false, |mime| mime.essence() == CBOR_CONTENT_TYPE);
+    let cbor_response = cbor_request
+        || request
+            .header("Accept")
+            .map_or(false, |accept| accept.as_str().contains(CBOR_CONTENT_TYPE));
+
     // Parse RPC request
-    let rpc_request: RequestObject = request.body_json().await?;
+    let rpc_request: Value = if cbor_request {
+        serde_cbor::from_slice(&request.body_bytes().await?)
+            .map_err(|err| tide::Error::new(http_types::StatusCode::UnprocessableEntity, err))?
+    } else {
+        request.body_json().await?
+    };
 
     // Handle RPC request
-    let rpc_server = request.state();
-    let rpc_result = rpc_server.handle(rpc_request).await;
+    let rpc_result = handle_rpc_request(
+        request.state(),
+        rpc_request,
+        authorization.as_deref(),
+        peer_ip(&request),
+    )
+    .await?;
+
+    // Serialize response to CBOR or JSON
+    let (body, content_type) = if cbor_response {
+        (serde_cbor::to_vec(&rpc_result)?, CBOR_CONTENT_TYPE)
+    } else {
+        (
+            serde_json::to_vec(&rpc_result)?,
+            "application/json-rpc;charset=utf-8",
+        )
+    };
+
+    let mut response = tide::Response::builder(http_types::StatusCode::Ok);
 
-    // Serialize response to JSON
-    let rpc_result_json = serde_json::to_string(&rpc_result)?;
+    // Sign response body so clients can verify it was created by this node
+    if let Some(keypair) = &request.state().signing_key {
+        let signature = keypair.sign(&body)?;
+        response = response.header(SIGNATURE_HEADER, hex::encode(signature));
+    }
 
     // Respond with RPC result
-    let response = tide::Response::builder(http_types::StatusCode::Ok)
-        .body(rpc_result_json)
-        .content_type("application/json-rpc;charset=utf-8")
-        .build();
+    Ok(response.body(body).content_type(content_type).build())
+}
+
+/// Handle HTTP health check requests.
+///
+/// Responds with status 200 when the node is ready to serve requests and with status 503 when the
+/// database can not be reached or has pending migrations.
+pub async fn handle_health_request(request: RpcServerRequest) -> tide::Result {
+    let response = match request.state().storage.check_health().await {
+        Ok(_) => tide::Response::builder(http_types::StatusCode::Ok)
+            .body(json!({ "status": "ok" }))
+            .build(),
+        Err(err) => {
+            warn!("Health check failed: {}", err);
+
+            tide::Response::builder(http_types::StatusCode::ServiceUnavailable)
+                .body(json!({
+                    "status": "unavailable",
+                    "error": err.to_string(),
+                }))
+                .build()
+        }
+    };
 
     Ok(response)
 }
 
+/// Stream node activity to HTTP clients as server-sent events.
+///
+/// Clients receive `entryPublished` events for every new entry next to the events emitted by the
+/// node, like `documentMaterialized` and `peerConnected`. The data of every event is JSON.
+pub async fn handle_events_request(request: RpcServerRequest, sender: Sender) -> tide::Result<()> {
+    let rpc_server = request.state();
+
+    let (subscription_id, entries) = rpc_server.subscriptions.subscribe(None, None);
+    let entries = entries.map(|notification| ("entryPublished", json!(notification)));
+    let events = rpc_server
+        .events
+        .subscribe()
+        .map(|event| (event.name(), event.to_json()));
+
+    // Stop streaming as soon as the client disconnected
+    let mut stream = entries.merge(events);
+    while let Some((name, data)) = stream.next().await {
+        if sender.send(name, data.to_string(), None).await.is_err() {
+            break;
+        }
+    }
+
+    rpc_server.subscriptions.unsubscribe(subscription_id);
+
+    Ok(())
+}
+
+/// Build JSON RPC response object for WebSocket clients.
+fn ws_response(id: &Value, result: Value) -> String {
+    json!({
+        "id": id,
+        "jsonrpc": "2.0",
+        "result": result,
+    })
+    .to_string()
+}
+
+/// Build JSON RPC error response object.
+fn rpc_error(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "error": {
+            "code": code,
+            "message": message,
+        },
+        "id": id,
+        "jsonrpc": "2.0",
+    })
+}
+
+/// Build JSON RPC error response object for WebSocket clients.
+fn ws_error(id: &Value, message: &str) -> String {
+    rpc_error(id, INVALID_PARAMS_CODE, message).to_string()
+}
+
+/// Forward entry notifications of a subscription to the WebSocket client until either the
+/// subscription got cancelled or the connection closed.
+async fn forward_notifications(
+    id: SubscriptionId,
+    receiver: Receiver<EntryNotification>,
+    stream: WebSocketConnection,
+) {
+    while let Ok(notification) = receiver.recv().await {
+        let notification_json = json!({
+            "jsonrpc": "2.0",
+            "method": SUBSCRIPTION_NOTIFICATION_METHOD,
+            "params": {
+                "subscription": id,
+                "result": notification,
+            },
+        })
+        .to_string();
+
+        if stream.send_string(notification_json).await.is_err() {
+            break;
+        }
+    }
+}
+
 /// Handle incoming WebSocket JSON RPC requests.
+///
+/// Next to regular RPC methods WebSocket clients can subscribe to new entries via
+/// `panda_subscribeEntries` and cancel subscriptions via `panda_unsubscribeEntries`.
 pub async fn handle_ws_request(
     request: RpcServerRequest,
     mut stream: WebSocketConnection,
 ) -> Result<(), tide::Error> {
+    let rpc_server = request.state();
+    let authorization = request
+        .header("Authorization")
+        .map(|value| value.as_str().to_owned());
+    let peer = peer_ip(&request);
+
+    // Keep track of subscriptions of this connection to remove them when it closes
+    let mut subscription_ids: Vec<SubscriptionId> = Vec::new();
+
     while let Some(Ok(Message::Text(ws_input))) = stream.next().await {
         // Parse RPC request
-        let rpc_request: RequestObject = serde_json::from_str(&ws_input)?;
+        let ws_request: Value = serde_json::from_str(&ws_input)?;
+        let id = ws_request["id"].clone();
+
+        let rpc_result_json = match ws_request["method"].as_str() {
+            Some(SUBSCRIBE_ENTRIES_METHOD) => {
+                let params: SubscribeEntriesRequest =
+                    serde_json::from_value(ws_request["params"].clone())?;
+
+                // Validate request parameters
+                let author_valid = params
+                    .author
+                    .as_ref()
+                    .map_or(true, |a| a.validate().is_ok());
+                let schema_valid = params
+                    .schema
+                    .as_ref()
+                    .map_or(true, |s| s.validate().is_ok());
 
-        // Handle RPC request
-        let rpc_server = request.state();
-        let rpc_result = rpc_server.handle(rpc_request).await;
+                if !author_valid || !schema_valid {
+                    ws_error(&id, "Invalid author or schema in subscription request")
+                } else {
+                    // Register subscription and forward its notifications to this client
+                    let (subscription_id, receiver) = rpc_server
+                        .subscriptions
+                        .subscribe(params.author, params.schema);
+                    subscription_ids.push(subscription_id);
+                    task::spawn(forward_notifications(
+                        subscription_id,
+                        receiver,
+                        stream.clone(),
+                    ));
 
-        // Serialize response to JSON
-        let rpc_result_json = serde_json::to_string(&rpc_result)?;
+                    ws_response(&id, json!(subscription_id))
+                }
+            }
+            Some(UNSUBSCRIBE_ENTRIES_METHOD) => {
+                let params: UnsubscribeEntriesRequest =
+                    serde_json::from_value(ws_request["params"].clone())?;
+
+                // Only allow cancelling subscriptions of this connection
+                let removed = subscription_ids.contains(&params.subscription)
+                    && rpc_server.subscriptions.unsubscribe(params.subscription);
+                subscription_ids.retain(|id| id != &params.subscription);
+
+                ws_response(&id, json!(removed))
+            }
+            _ => {
+                // Handle RPC request and serialize response to JSON
+                let rpc_result =
+                    handle_rpc_request(rpc_server, ws_request, authorization.as_deref(), peer);
+                match rpc_result.await {
+                    Ok(rpc_result) => serde_json::to_string(&rpc_result)?,
+                    Err(err) if err.status() == http_types::StatusCode::Unauthorized => {
+                        ws_error(&id, &err.to_string())
+                    }
+                    Err(err) => return Err(err),
+                }
+            }
+        };
 
         // Respond with RPC result
         stream.send_string(rpc_result_json).await?;
     }
 
+    // Clean up all subscriptions of this closed connection
+    for subscription_id in subscription_ids {
+        rpc_server.subscriptions.unsubscribe(subscription_id);
+    }
+
     Ok(())
 }
 
 /// Build HTTP and WebSocket server both exposing a JSON RPC API.
 pub fn build_rpc_server(api: RpcApiService) -> RpcServer {
     // Configure CORS middleware
-    let cors = CorsMiddleware::new()
-        .allow_methods("GET, POST, OPTIONS".parse::<HeaderValue>().unwrap())
-        .allow_origin(Origin::from("*"))
-        .allow_credentials(false);
+    let cors = api.cors.middleware();
 
     // Prepare HTTP server with RPC route
     let mut app = tide::with_state(api);
@@ -71,29 +395,101 @@ pub fn build_rpc_server(api: RpcApiService) -> RpcServer {
         .with(WebSocket::new(handle_ws_request))
         .get(|_| async { Ok("Used HTTP Method is not allowed. POST or OPTIONS is required") })
         .post(handle_http_request);
+    app.at("/health").get(handle_health_request);
+    app.at("/events")
+        .get(tide::sse::endpoint(handle_events_request));
     app
 }
 
+/// Serve the RPC API on a unix domain socket, readable and writable by the owner and group.
+#[cfg(unix)]
+async fn add_socket_listener(
+    listener: &mut ConcurrentListener<RpcApiService>,
+    path: &Path,
+) -> anyhow::Result<()> {
+    // Remove socket file left behind by a previous run
+    if async_std::fs::metadata(path).await.is_ok() {
+        async_std::fs::remove_file(path).await?;
+    }
+
+    listener.add(tide::listener::UnixListener::from_path(path))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+async fn add_socket_listener(
+    _listener: &mut ConcurrentListener<RpcApiService>,
+    _path: &Path,
+) -> anyhow::Result<()> {
+    anyhow::bail!("Unix domain sockets are not supported on this platform")
+}
+
+/// Restrict access to the unix domain socket to the owner and group of the node process.
+#[cfg(unix)]
+fn restrict_socket_permissions(path: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o660))?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn restrict_socket_permissions(_path: &Path) -> anyhow::Result<()> {
+    Ok(())
+}
+
 /// Start HTTP and WebSocket server.
+///
+/// The same API is served on a unix domain socket as well when a socket path is configured.
 pub async fn start_rpc_server(config: &Configuration, api: RpcApiService) -> anyhow::Result<()> {
-    let http_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), config.http_port);
+    let http_address = SocketAddr::new(config.http_address, config.http_port);
     let server = build_rpc_server(api);
-    server.listen(http_address).await?;
+
+    let mut listener = ConcurrentListener::new();
+    listener.add(http_address)?;
+
+    if let Some(path) = &config.rpc_socket_path {
+        add_socket_listener(&mut listener, path).await?;
+    }
+
+    let mut listener = server.bind(listener).await?;
+
+    // The socket file exists as soon as the listener is bound
+    if let Some(path) = &config.rpc_socket_path {
+        restrict_socket_permissions(path)?;
+    }
+
+    listener.accept().await?;
     Ok(())
 }
 
 #[cfg(test)]
 mod tests {
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::Duration;
+
+    use async_std::io::BufReadExt;
+    use async_std::task;
+    use http_types::StatusCode;
+    use libp2p::identity::Keypair;
+    use serde_json::{json, Value};
     use tide_testing::TideTestingExt;
 
-    use crate::rpc::api::build_rpc_api_service;
-    use crate::rpc::server::build_rpc_server;
+    use crate::config::Configuration;
+    use crate::db::{connection_pool, MemoryStorage, PoolOptions, SqlStorage};
+    use crate::events::NodeEvent;
+    use crate::rpc::api::{build_rpc_api_service, RpcApiOptions};
+    use crate::rpc::limits::RequestLimits;
+    use crate::rpc::server::{
+        build_rpc_server, handle_rpc_request, start_rpc_server, RATE_LIMIT_CODE, SERVER_BUSY_CODE,
+        SIGNATURE_HEADER,
+    };
     use crate::test_helpers::initialize_db;
 
     #[async_std::test]
     async fn respond_with_method_not_allowed() {
-        let pool = initialize_db().await;
-        let rpc_api = build_rpc_api_service(pool.clone());
+        // Create tide server with endpoints using in-memory storage
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
         let app = build_rpc_server(rpc_api);
 
         assert_eq!(
@@ -101,4 +497,319 @@ mod tests {
             "Used HTTP Method is not allowed. POST or OPTIONS is required"
         );
     }
+
+    #[async_std::test]
+    async fn encode_requests_as_cbor() {
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "panda_getAllSchemas",
+        });
+        let response = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "result": { "schemas": [] },
+        });
+
+        // CBOR request gets a CBOR response
+        let mut http_response = app
+            .post("/")
+            .body(serde_cbor::to_vec(&request).unwrap())
+            .content_type("application/cbor")
+            .await
+            .unwrap();
+        assert_eq!(
+            http_response.content_type().unwrap().essence(),
+            "application/cbor"
+        );
+        let body = http_response.body_bytes().await.unwrap();
+        assert_eq!(serde_cbor::from_slice::<Value>(&body).unwrap(), response);
+
+        // JSON request asking for a CBOR response
+        let body = app
+            .post("/")
+            .body(request.to_string())
+            .content_type("application/json")
+            .header("Accept", "application/cbor")
+            .recv_bytes()
+            .await
+            .unwrap();
+        assert_eq!(serde_cbor::from_slice::<Value>(&body).unwrap(), response);
+    }
+
+    #[async_std::test]
+    async fn sign_http_responses() {
+        let keypair = Keypair::generate_ed25519();
+        let options = RpcApiOptions {
+            signing_key: Some(keypair.clone()),
+            ..RpcApiOptions::default()
+        };
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), options);
+        let app = build_rpc_server(rpc_api);
+
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "panda_getAllSchemas",
+        });
+
+        let mut http_response = app
+            .post("/")
+            .body(request.to_string())
+            .content_type("application/json")
+            .await
+            .unwrap();
+        let signature =
+            hex::decode(http_response.header(SIGNATURE_HEADER).unwrap().as_str()).unwrap();
+        let body = http_response.body_bytes().await.unwrap();
+
+        // Signature of the response body can be verified with the public key of the node
+        assert!(keypair.public().verify(&body, &signature));
+        assert!(!keypair.public().verify(b"{}", &signature));
+    }
+
+    #[async_std::test]
+    async fn restrict_write_methods() {
+        let options = RpcApiOptions {
+            write_tokens: vec!["writer".to_owned()],
+            ..RpcApiOptions::default()
+        };
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), options);
+        let app = build_rpc_server(rpc_api);
+
+        let publish_request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "panda_publishEntry",
+            "params": {},
+        })
+        .to_string();
+
+        // Write methods require a bearer token
+        let response = app
+            .post("/")
+            .body(publish_request.clone())
+            .content_type("application/json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::Unauthorized);
+
+        let response = app
+            .post("/")
+            .body(publish_request)
+            .content_type("application/json")
+            .header("Authorization", "Bearer writer")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::Ok);
+
+        // Read methods stay public
+        let response = app
+            .post("/")
+            .body(
+                json!({
+                    "id": 1,
+                    "jsonrpc": "2.0",
+                    "method": "panda_getAllSchemas",
+                })
+                .to_string(),
+            )
+            .content_type("application/json")
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::Ok);
+    }
+
+    #[async_std::test]
+    async fn refuse_requests_when_busy() {
+        let config = Configuration {
+            rpc_max_in_flight_requests: 1,
+            ..Configuration::default()
+        };
+        let options = RpcApiOptions {
+            limits: RequestLimits::new(&config),
+            ..RpcApiOptions::default()
+        };
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), options);
+        let app = build_rpc_server(rpc_api.clone());
+
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "panda_getAllSchemas",
+        })
+        .to_string();
+
+        // Occupy the only slot as if another request is handled right now
+        let in_flight = rpc_api.limits.acquire().unwrap();
+        let response: Value = app
+            .post("/")
+            .body(request.clone())
+            .content_type("application/json")
+            .recv_json()
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], SERVER_BUSY_CODE);
+        assert_eq!(response["id"], 1);
+
+        drop(in_flight);
+        let response: Value = app
+            .post("/")
+            .body(request)
+            .content_type("application/json")
+            .recv_json()
+            .await
+            .unwrap();
+        assert!(response["error"].is_null());
+    }
+
+    #[async_std::test]
+    async fn limit_rpc_calls_per_ip() {
+        let config = Configuration {
+            rpc_rate_limit: 2,
+            ..Configuration::default()
+        };
+        let options = RpcApiOptions {
+            limits: RequestLimits::new(&config),
+            ..RpcApiOptions::default()
+        };
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), options);
+
+        let request = json!({
+            "id": 1,
+            "jsonrpc": "2.0",
+            "method": "panda_getAllSchemas",
+        });
+        let client_1 = Some(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 1)));
+        let client_2 = Some(IpAddr::V4(Ipv4Addr::new(192, 168, 0, 2)));
+
+        // Every call counts, even when sent via the same connection
+        for _ in 0..2 {
+            let response = handle_rpc_request(&rpc_api, request.clone(), None, client_1)
+                .await
+                .unwrap();
+            assert!(response["error"].is_null());
+        }
+
+        let response = handle_rpc_request(&rpc_api, request.clone(), None, client_1)
+            .await
+            .unwrap();
+        assert_eq!(response["error"]["code"], RATE_LIMIT_CODE);
+        assert_eq!(response["id"], 1);
+
+        // Other clients and requests via the unix domain socket are not affected
+        let response = handle_rpc_request(&rpc_api, request.clone(), None, client_2)
+            .await
+            .unwrap();
+        assert!(response["error"].is_null());
+
+        let response = handle_rpc_request(&rpc_api, request, None, None)
+            .await
+            .unwrap();
+        assert!(response["error"].is_null());
+    }
+
+    #[async_std::test]
+    async fn respond_to_health_checks() {
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let app = build_rpc_server(rpc_api);
+
+        let mut response = app.get("/health").await.unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.body_string().await.unwrap(), r#"{"status":"ok"}"#);
+
+        // Migrated database is ready
+        let pool = initialize_db().await;
+        let app = build_rpc_server(build_rpc_api_service(
+            SqlStorage::new(pool),
+            RpcApiOptions::default(),
+        ));
+        assert_eq!(app.get("/health").await.unwrap().status(), 200);
+
+        // Database without migrations is not ready yet
+        let options = PoolOptions {
+            max_connections: 1,
+            ..PoolOptions::default()
+        };
+        let pool = connection_pool("sqlite::memory:", &options).await.unwrap();
+        let app = build_rpc_server(build_rpc_api_service(
+            SqlStorage::new(pool),
+            RpcApiOptions::default(),
+        ));
+        assert_eq!(app.get("/health").await.unwrap().status(), 503);
+    }
+
+    #[async_std::test]
+    async fn stream_node_events() {
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        let events = rpc_api.events.clone();
+        let app = build_rpc_server(rpc_api);
+
+        let response = app.get("/events").await.unwrap();
+        assert_eq!(response.status(), 200);
+
+        // Keep emitting events as the client subscribes in the background
+        let emitter = task::spawn(async move {
+            loop {
+                events.emit(NodeEvent::PeerConnected("peer".to_owned()));
+                task::sleep(Duration::from_millis(10)).await;
+            }
+        });
+
+        let mut body = async_std::io::BufReader::new(response);
+        let mut event = String::new();
+        let mut data = String::new();
+        async_std::future::timeout(Duration::from_secs(5), async {
+            body.read_line(&mut event).await.unwrap();
+            body.read_line(&mut data).await.unwrap();
+        })
+        .await
+        .unwrap();
+        emitter.cancel().await;
+
+        assert_eq!(event.trim(), "event:peerConnected");
+        assert_eq!(data.trim(), r#"data:{"peerId":"peer"}"#);
+    }
+
+    #[cfg(unix)]
+    #[async_std::test]
+    async fn serve_on_unix_socket() {
+        use std::os::unix::fs::PermissionsExt;
+
+        use async_std::io::{ReadExt, WriteExt};
+        use async_std::os::unix::net::UnixStream;
+        use rand::Rng;
+
+        let path = std::env::temp_dir().join(format!("aquadoggo-{}.sock", rand::random::<u64>()));
+        let config = Configuration {
+            http_address: "127.0.0.1".parse().unwrap(),
+            http_port: rand::thread_rng().gen_range(30000..40000),
+            rpc_socket_path: Some(path.clone()),
+            ..Configuration::default()
+        };
+
+        let rpc_api = build_rpc_api_service(MemoryStorage::new(), RpcApiOptions::default());
+        task::spawn(async move { start_rpc_server(&config, rpc_api).await });
+        task::sleep(Duration::from_millis(100)).await;
+
+        // Only owner and group can connect to the socket
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o660);
+
+        let mut stream = UnixStream::connect(&path).await.unwrap();
+        stream
+            .write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .await
+            .unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with(r#"{"status":"ok"}"#));
+
+        std::fs::remove_file(path).unwrap();
+    }
 }
@@ -0,0 +1,202 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_std::channel::{unbounded, Receiver, Sender};
+use p2panda_rs::entry::{LogId, SeqNum};
+use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::Author;
+use serde::Serialize;
+
+/// Method to subscribe to new entries via WebSocket.
+pub const SUBSCRIBE_ENTRIES_METHOD: &str = "panda_subscribeEntries";
+
+/// Method to cancel a subscription via WebSocket.
+pub const UNSUBSCRIBE_ENTRIES_METHOD: &str = "panda_unsubscribeEntries";
+
+/// Method name used for notifications sent to subscribed clients.
+pub const SUBSCRIPTION_NOTIFICATION_METHOD: &str = "panda_subscription";
+
+/// Identifier of a single subscription.
+pub type SubscriptionId = u64;
+
+/// Notification sent to subscribers when a new entry got stored on this node.
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryNotification {
+    pub author: Author,
+    pub entry_encoded: String,
+    pub entry_hash: Hash,
+    pub log_id: LogId,
+    pub message_encoded: String,
+    pub schema: Hash,
+    pub seq_num: SeqNum,
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    id: SubscriptionId,
+    author: Option<Author>,
+    schema: Option<Hash>,
+    sender: Sender<EntryNotification>,
+}
+
+impl Subscriber {
+    /// Returns true when the subscriber is interested in this notification.
+    fn matches(&self, notification: &EntryNotification) -> bool {
+        let author_matches = self
+            .author
+            .as_ref()
+            .map_or(true, |author| author == &notification.author);
+
+        let schema_matches = self
+            .schema
+            .as_ref()
+            .map_or(true, |schema| schema == &notification.schema);
+
+        author_matches && schema_matches
+    }
+}
+
+/// Registry of clients which subscribed to new entries, optionally filtered by author and / or
+/// schema.
+///
+/// Subscriptions can be cheaply cloned as they share the same registry.
+#[derive(Clone, Debug, Default)]
+pub struct Subscriptions {
+    next_id: Arc<AtomicU64>,
+    subscribers: Arc<Mutex<Vec<Subscriber>>>,
+}
+
+impl Subscriptions {
+    /// Returns a new and empty subscription registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a new subscription and return its id with a receiver for incoming notifications.
+    pub fn subscribe(
+        &self,
+        author: Option<Author>,
+        schema: Option<Hash>,
+    ) -> (SubscriptionId, Receiver<EntryNotification>) {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (sender, receiver) = unbounded();
+
+        self.subscribers.lock().unwrap().push(Subscriber {
+            id,
+            author,
+            schema,
+            sender,
+        });
+
+        (id, receiver)
+    }
+
+    /// Remove a subscription, returns false when it did not exist.
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        let len = subscribers.len();
+        subscribers.retain(|subscriber| subscriber.id != id);
+        subscribers.len() != len
+    }
+
+    /// Send notification to all interested subscribers.
+    pub fn notify(&self, notification: EntryNotification) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+
+        // Remove subscribers whose receiving end got dropped in the meantime
+        subscribers.retain(|subscriber| {
+            if !subscriber.matches(&notification) {
+                return true;
+            }
+
+            subscriber.sender.try_send(notification.clone()).is_ok()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p2panda_rs::entry::{LogId, SeqNum};
+    use p2panda_rs::hash::Hash;
+    use p2panda_rs::identity::Author;
+
+    use super::{EntryNotification, Subscriptions};
+
+    use crate::test_helpers::random_entry_hash;
+
+    const TEST_AUTHOR: &str = "8b52ae153142288402382fd6d9619e018978e015e6bc372b1b0c7bd40c6a240a";
+
+    fn notification(schema: &Hash) -> EntryNotification {
+        EntryNotification {
+            author: Author::new(TEST_AUTHOR).unwrap(),
+            entry_encoded: "".to_owned(),
+            entry_hash: Hash::new(&random_entry_hash()).unwrap(),
+            log_id: LogId::new(1),
+            message_encoded: "".to_owned(),
+            schema: schema.clone(),
+            seq_num: SeqNum::new(1).unwrap(),
+        }
+    }
+
+    #[test]
+    fn filter_by_schema() {
+        let subscriptions = Subscriptions::new();
+
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+        let schema_other = Hash::new(&random_entry_hash()).unwrap();
+
+        let (_, receiver) = subscriptions.subscribe(None, Some(schema.clone()));
+        let (_, receiver_all) = subscriptions.subscribe(None, None);
+
+        subscriptions.notify(notification(&schema));
+        subscriptions.notify(notification(&schema_other));
+
+        assert_eq!(receiver.len(), 1);
+        assert_eq!(receiver_all.len(), 2);
+    }
+
+    #[test]
+    fn filter_by_author() {
+        let subscriptions = Subscriptions::new();
+
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+        let author = Author::new(TEST_AUTHOR).unwrap();
+        let author_other =
+            Author::new("58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c")
+                .unwrap();
+
+        let (_, receiver) = subscriptions.subscribe(Some(author), None);
+        let (_, receiver_other) = subscriptions.subscribe(Some(author_other), None);
+
+        subscriptions.notify(notification(&schema));
+
+        assert_eq!(receiver.len(), 1);
+        assert_eq!(receiver_other.len(), 0);
+    }
+
+    #[test]
+    fn unsubscribe() {
+        let subscriptions = Subscriptions::new();
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+
+        let (id, receiver) = subscriptions.subscribe(None, None);
+        assert!(subscriptions.unsubscribe(id));
+        assert!(!subscriptions.unsubscribe(id));
+
+        subscriptions.notify(notification(&schema));
+        assert!(receiver.is_empty());
+    }
+
+    #[test]
+    fn remove_dropped_subscribers() {
+        let subscriptions = Subscriptions::new();
+        let schema = Hash::new(&random_entry_hash()).unwrap();
+
+        let (id, receiver) = subscriptions.subscribe(None, None);
+        drop(receiver);
+
+        subscriptions.notify(notification(&schema));
+        assert!(!subscriptions.unsubscribe(id));
+    }
+}
@@ -1,9 +1,31 @@
-use anyhow::Result;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
+use anyhow::{Context, Result};
+use libp2p::PeerId;
+
+use crate::archive;
+use crate::backup::{start_backups, Backups};
 use crate::config::Configuration;
-use crate::db::{connection_pool, create_database, run_pending_migrations, Pool};
-use crate::rpc::{build_rpc_api_service, start_rpc_server};
+use crate::db::{
+    connection_pool, create_database, run_pending_migrations, Pool, PoolOptions, SqlStorage,
+};
+use crate::ephemeral::{start_expiry, EphemeralSchemas};
+use crate::events::Events;
+use crate::maintenance::{start_maintenance, Retention};
+use crate::materializer::{self, start_materializer};
+use crate::network::{load_or_generate_keypair, public_key_hex, Network};
+use crate::policy::EntryPolicy;
+use crate::rpc::{
+    build_rpc_api_service, start_rpc_server, CorsPolicy, NodeInfo, RequestLimits, RpcApiOptions,
+    RpcApiService,
+};
 use crate::task::TaskManager;
+use crate::webhooks::{start_webhooks, Webhooks};
 
 /// Makes sure database is created and migrated before returning connection pool.
 async fn initialize_db(config: &Configuration) -> Result<Pool> {
@@ -14,11 +36,8 @@ async fn initialize_db(config: &Configuration) -> Result<Pool> {
     create_database(&config.database_url.clone().unwrap()).await?;
 
     // Create connection pool
-    let pool = connection_pool(
-        &config.database_url.clone().unwrap(),
-        config.database_max_connections,
-    )
-    .await?;
+    let options = PoolOptions::new(config).context("Invalid database configuration")?;
+    let pool = connection_pool(&config.database_url.clone().unwrap(), &options).await?;
 
     // Run pending migrations
     run_pending_migrations(&pool).await?;
@@ -26,42 +45,370 @@ async fn initialize_db(config: &Configuration) -> Result<Pool> {
     Ok(pool)
 }
 
-/// Main runtime managing the p2panda node process.
+/// Returns a separate connection pool for read-only queries, connected to the read replica when
+/// configured.
+async fn connect_read_pool(config: &Configuration) -> Result<Pool> {
+    let options = PoolOptions {
+        max_connections: config.database_read_max_connections,
+        ..PoolOptions::new(config)?
+    };
+    let url = config
+        .database_read_url
+        .clone()
+        .or_else(|| config.database_url.clone())
+        .unwrap();
+
+    connection_pool(&url, &options).await
+}
+
+/// Drop all materialized documents and materialize them again from the entries in the database.
+///
+/// Use this after updating the node to apply fixes of the materialization logic. The node should
+/// not be running at the same time.
+pub async fn rebuild_views(config: Configuration) -> Result<()> {
+    let pool = initialize_db(&config).await?;
+    materializer::rebuild_views(&pool, config.document_history).await?;
+    pool.close().await;
+    Ok(())
+}
+
+/// Returns the peer id of this node, generating a new network key pair when none exists yet.
+pub fn keygen(config: &Configuration) -> Result<String> {
+    let keypair = load_or_generate_keypair(config.base_path.as_ref().unwrap())?;
+    Ok(PeerId::from(keypair.public()).to_string())
+}
+
+/// Write all entries stored in the database as newline-delimited JSON to a file, returns the
+/// number of exported entries.
+///
+/// Entries are written to standard output when the path is `-`.
+pub async fn export_entries(config: Configuration, path: &Path) -> Result<usize> {
+    let pool = initialize_db(&config).await?;
+
+    let writer: Box<dyn Write> = if path == Path::new("-") {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(path)?)
+    };
+
+    let exported = archive::export_entries(&pool, BufWriter::new(writer)).await?;
+    pool.close().await;
+    Ok(exported)
+}
+
+/// Validate and store entries from a file written by `export_entries`, returns the number of
+/// imported entries.
+///
+/// Entries are read from standard input when the path is `-`. The node should not be running at
+/// the same time, imported entries get materialized on its next start.
+pub async fn import_entries(config: Configuration, path: &Path) -> Result<usize> {
+    let pool = initialize_db(&config).await?;
+
+    let reader: Box<dyn Read> = if path == Path::new("-") {
+        Box::new(io::stdin())
+    } else {
+        Box::new(File::open(path)?)
+    };
+
+    let imported = archive::import_entries(&pool, BufReader::new(reader)).await?;
+    pool.close().await;
+    Ok(imported)
+}
+
+/// Start gRPC server next to the JSON RPC API.
+#[cfg(feature = "grpc")]
+fn spawn_grpc_server(
+    task_manager: &mut TaskManager,
+    address: SocketAddr,
+    api: RpcApiService,
+) -> Result<()> {
+    task_manager.spawn_supervised("gRPC Server", move || {
+        let api = api.clone();
+        async move {
+            crate::grpc::start_grpc_server(address, api).await?;
+            Ok(())
+        }
+    });
+    Ok(())
+}
+
+#[cfg(not(feature = "grpc"))]
+fn spawn_grpc_server(_: &mut TaskManager, _: SocketAddr, _: RpcApiService) -> Result<()> {
+    anyhow::bail!("GRPC_PORT is set but aquadoggo was built without the grpc feature");
+}
+
+/// Spawn database workers, RPC API and networking service of a node as tasks.
+async fn spawn_tasks(
+    config: Configuration,
+    pool: Pool,
+    read_pool: Pool,
+    task_manager: &mut TaskManager,
+) -> Result<()> {
+    // Load the node's persisted network identity
+    let keypair = load_or_generate_keypair(config.base_path.as_ref().unwrap())
+        .context("Could not load network key pair")?;
+
+    // Snapshot the database on request and in the configured interval
+    let backups = Arc::new(Backups::new(
+        pool.clone(),
+        config.database_url.as_ref().unwrap(),
+        config.backup_path.as_ref().unwrap(),
+        config.backup_retention,
+    ));
+
+    if config.backup_interval > 0 {
+        let backups = backups.clone();
+        let interval = Duration::from_secs(config.backup_interval);
+        task_manager.spawn_graceful("Backups", |on_exit| async move {
+            start_backups(&backups, interval, on_exit).await?;
+            Ok(())
+        });
+    }
+
+    // Prune entries and payloads and reclaim unused space of the database in the configured
+    // interval
+    let retention = Retention::new(&config).context("Invalid retention configuration")?;
+    if !retention.is_empty() && config.database_maintenance_interval == 0 {
+        anyhow::bail!(
+            "LOG_RETENTION and PAYLOAD_RETENTION require DATABASE_MAINTENANCE_INTERVAL to be set"
+        );
+    }
+
+    // Activity of the node streamed to clients and webhooks
+    let events = Events::new();
+
+    // Create RPC API handler with SQL storage using the shared database connection pool
+    let options = RpcApiOptions {
+        node_info: NodeInfo {
+            peer_id: Some(PeerId::from(keypair.public()).to_string()),
+            public_key: public_key_hex(&keypair),
+            replication_ignored_schemas: config.replication_ignored_schemas.clone(),
+            replication_schemas: config.replication_schemas.clone(),
+        },
+        policy: EntryPolicy::new(&config).context("Invalid entry policy configuration")?,
+        admin_token: config.admin_token.clone(),
+        write_tokens: config.write_tokens.clone(),
+        cors: CorsPolicy::new(&config).context("Invalid CORS configuration")?,
+        limits: RequestLimits::new(&config),
+        backups: Some(backups),
+        events: events.clone(),
+        signing_key: if config.rpc_sign_responses {
+            Some(keypair.clone())
+        } else {
+            None
+        },
+    };
+    let storage =
+        SqlStorage::with_read_pool(pool.clone(), read_pool).with_history(config.document_history);
+
+    // Pruning shares the caches of the storage with the RPC API
+    if config.database_maintenance_interval > 0 {
+        let storage = storage.clone();
+        let interval = Duration::from_secs(config.database_maintenance_interval);
+        task_manager.spawn_graceful("Database Maintenance", |on_exit| async move {
+            start_maintenance(&storage, &retention, interval, on_exit).await?;
+            Ok(())
+        });
+    }
+
+    // Remove expired logs of ephemeral schemas, the storage shares its caches with the RPC API
+    let ephemeral =
+        EphemeralSchemas::new(&config).context("Invalid ephemeral schemas configuration")?;
+    if !ephemeral.is_empty() {
+        let storage = storage.clone();
+        task_manager.spawn_graceful("Ephemeral Schemas", |on_exit| async move {
+            start_expiry(&storage, &ephemeral, on_exit).await?;
+            Ok(())
+        });
+    }
+
+    let rpc_api = build_rpc_api_service(storage, options);
+
+    // Post created and updated documents to the configured webhooks
+    if let Some(webhooks) = Webhooks::new(&config).context("Invalid webhook configuration")? {
+        let events = events.clone();
+        task_manager.spawn_supervised("Webhooks", move || {
+            let webhooks = webhooks.clone();
+            let receiver = events.subscribe();
+            async move {
+                start_webhooks(webhooks, receiver).await?;
+                Ok(())
+            }
+        });
+    }
+
+    // Materialize documents from all newly published entries
+    let (_, receiver) = rpc_api.subscriptions.subscribe(None, None);
+    let materializer_pool = pool.clone();
+    let materializer_events = events.clone();
+    let history = config.document_history;
+    task_manager.spawn_graceful("Materializer", move |on_exit| async move {
+        start_materializer(
+            materializer_pool,
+            receiver,
+            materializer_events,
+            history,
+            on_exit,
+        )
+        .await?;
+        Ok(())
+    });
+
+    // Start peer-to-peer networking service with the node's persisted identity, it is created
+    // here first to fail early on invalid network configurations
+    let network = Network::new(
+        &config,
+        keypair.clone(),
+        pool.clone(),
+        rpc_api.subscriptions.clone(),
+        events.clone(),
+    )
+    .await
+    .context("Could not initialize network")?;
+    let network = Mutex::new(Some(network));
+    let network_config = config.clone();
+    let subscriptions = rpc_api.subscriptions.clone();
+    task_manager.spawn_supervised("Network", move || {
+        // Create networking service again when restarting after a crash
+        let network = network.lock().unwrap().take();
+        let (_, receiver) = subscriptions.subscribe(None, None);
+        let config = network_config.clone();
+        let keypair = keypair.clone();
+        let pool = pool.clone();
+        let subscriptions = subscriptions.clone();
+        let events = events.clone();
+
+        async move {
+            let network = match network {
+                Some(network) => network,
+                None => Network::new(&config, keypair, pool, subscriptions, events).await?,
+            };
+            network.run(receiver).await?;
+            Ok(())
+        }
+    });
+
+    // Start gRPC API server sharing the handlers of the JSON RPC API
+    if let Some(port) = config.grpc_port {
+        let address = SocketAddr::new(config.http_address, port);
+        spawn_grpc_server(task_manager, address, rpc_api.clone())?;
+    }
+
+    // Start JSON RPC API server
+    task_manager.spawn_supervised("JSON RPC Server", move || {
+        let config = config.clone();
+        let rpc_api = rpc_api.clone();
+        async move {
+            start_rpc_server(&config, rpc_api).await?;
+            Ok(())
+        }
+    });
+
+    Ok(())
+}
+
+/// p2panda node running its database workers, RPC API and networking service as tasks within
+/// the async runtime of the host application.
 #[allow(missing_debug_implementations)]
-pub struct Runtime {
+pub struct Node {
     pool: Pool,
+    read_pool: Pool,
     task_manager: TaskManager,
 }
 
-impl Runtime {
+impl Node {
     /// Start p2panda node with your configuration. This method can be used to run the node within
-    /// other applications.
-    pub async fn start(config: Configuration) -> Self {
+    /// other applications, for example desktop apps embedding a local node.
+    ///
+    /// Returns an error when the database, network identity or configuration could not be
+    /// initialized, tasks which were already spawned are shut down again.
+    pub async fn start(config: Configuration) -> Result<Self> {
         let mut task_manager = TaskManager::new();
 
         // Initialize database and get connection pool
         let pool = initialize_db(&config)
             .await
-            .expect("Could not initialize database");
+            .context("Could not initialize database")?;
 
-        // Create RPC API handler with shared database connection pool
-        let rpc_api = build_rpc_api_service(pool.clone());
+        let read_pool = match connect_read_pool(&config).await {
+            Ok(read_pool) => read_pool,
+            Err(err) => {
+                pool.close().await;
+                return Err(err.context("Could not connect to read database"));
+            }
+        };
 
-        // Start JSON RPC API server
-        task_manager.spawn("JSON RPC Server", async move {
-            start_rpc_server(&config, rpc_api).await?;
-            Ok(())
-        });
-
-        Self { pool, task_manager }
+        match spawn_tasks(config, pool.clone(), read_pool.clone(), &mut task_manager).await {
+            Ok(()) => Ok(Self {
+                pool,
+                read_pool,
+                task_manager,
+            }),
+            Err(err) => {
+                task_manager.shutdown().await;
+                pool.close().await;
+                read_pool.close().await;
+                Err(err)
+            }
+        }
     }
 
     /// Close all running concurrent tasks and wait until they are fully shut down.
+    ///
+    /// Tasks stop in the reverse order they were started in: the RPC servers and networking
+    /// service stop first, then the materializer finishes all pending tasks before webhooks and
+    /// backups stop and the database connections get closed.
     pub async fn shutdown(self) {
-        // Close connection pool
-        self.pool.close().await;
-
         // Wait until all tasks are shut down
         self.task_manager.shutdown().await;
+
+        // Close connection pools
+        self.pool.close().await;
+        self.read_pool.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use rand::Rng;
+
+    use super::Node;
+
+    use crate::client::RpcClient;
+    use crate::config::Configuration;
+
+    #[async_std::test]
+    async fn start_and_shutdown_node() {
+        let base_path = std::env::temp_dir().join(format!("aquadoggo-{}", rand::random::<u64>()));
+        std::fs::create_dir_all(&base_path).unwrap();
+
+        let http_port = rand::thread_rng().gen_range(40000..50000);
+        let config = Configuration {
+            database_url: Some(format!(
+                "sqlite:{}",
+                base_path.join("aquadoggo.sqlite3").display()
+            )),
+            backup_path: Some(base_path.join("backups")),
+            base_path: Some(base_path.clone()),
+            http_address: "127.0.0.1".parse().unwrap(),
+            http_port,
+            network_port: http_port + 1,
+            network_mdns: false,
+            ..Configuration::default()
+        };
+
+        let node = Node::start(config).await.unwrap();
+        async_std::task::sleep(Duration::from_millis(100)).await;
+
+        // RPC API is served by the embedded node
+        let client = RpcClient::new(&format!("http://127.0.0.1:{}", http_port)).unwrap();
+        let node_info = client.get_node_info().await.unwrap();
+        assert!(node_info.peer_id.is_some());
+        assert_eq!(node_info.entry_count, 0);
+
+        node.shutdown().await;
+        std::fs::remove_dir_all(base_path).unwrap();
     }
 }
@@ -1,38 +1,68 @@
 use std::error::Error;
+use std::time::{Duration, Instant};
 
 use async_std::{future::Future, task};
-use futures::future;
-use log::{debug, error};
+use futures::future::{self, Either};
+use tracing::{debug, error, warn};
 
 /// Generic Result type for all async tasks used by TaskManager.
 pub type FutureResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
 
+/// Time to wait before restarting a crashed task for the first time.
+const INITIAL_RESTART_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Maximum time to wait before restarting a crashed task, tasks running longer than this without
+/// crashing start again with the initial backoff.
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Task registered in the task manager with its own exit signal.
+struct ManagedTask {
+    name: &'static str,
+    exit_signal: exit_future::Signal,
+    handle: task::JoinHandle<()>,
+}
+
 /// Handles multiple concurrent tasks and exists them gracefully on shutdown.
+///
+/// Tasks are shut down one after another in the reverse order they were spawned in, so tasks
+/// spawned later can rely on the ones spawned before them until they are stopped.
 pub struct TaskManager {
-    on_exit: exit_future::Exit,
-    exit_signal: Option<exit_future::Signal>,
-    tasks: Vec<task::JoinHandle<()>>,
+    tasks: Vec<ManagedTask>,
+    restart_backoff: Duration,
 }
 
 impl TaskManager {
     /// Returns a new TaskManager instance.
     pub fn new() -> Self {
-        let (exit_signal, on_exit) = exit_future::signal();
-
         Self {
-            on_exit,
-            exit_signal: Some(exit_signal),
             tasks: Vec::new(),
+            restart_backoff: INITIAL_RESTART_BACKOFF,
         }
     }
 
+    /// Register task handle with its exit signal in the task manager.
+    fn register(
+        &mut self,
+        name: &'static str,
+        exit_signal: exit_future::Signal,
+        task: impl Future<Output = ()> + Send + 'static,
+    ) {
+        debug!("[{}]: Spawn", name);
+
+        self.tasks.push(ManagedTask {
+            name,
+            exit_signal,
+            handle: task::spawn(task),
+        });
+    }
+
     /// Spawn a new task and register it in the task manager.
     pub fn spawn(
         &mut self,
         name: &'static str,
         task: impl Future<Output = FutureResult<()>> + Send + 'static,
     ) {
-        let on_exit = self.on_exit.clone();
+        let (exit_signal, on_exit) = exit_future::signal();
 
         let task_with_error_log = async move {
             if let Err(e) = task.await {
@@ -46,19 +76,90 @@ impl TaskManager {
             debug!("[{}]: Completed", name);
         };
 
-        debug!("[{}]: Spawn", name);
+        self.register(name, exit_signal, run_task_until_exit);
+    }
+
+    /// Spawn a new task which handles the exit signal itself and register it in the task manager.
+    ///
+    /// Unlike tasks spawned with `spawn` it does not get dropped on shutdown, the task is created
+    /// with a future resolving on shutdown and should use it to finish its work and return.
+    /// Shutdown waits until it completed.
+    pub fn spawn_graceful<F>(
+        &mut self,
+        name: &'static str,
+        task: impl FnOnce(exit_future::Exit) -> F,
+    ) where
+        F: Future<Output = FutureResult<()>> + Send + 'static,
+    {
+        let (exit_signal, on_exit) = exit_future::signal();
+        let task = task(on_exit);
 
-        let task_handle = task::spawn(run_task_until_exit);
-        self.tasks.push(task_handle);
+        let task_with_error_log = async move {
+            if let Err(e) = task.await {
+                error!("[{}]: ERROR @ {}", name, e)
+            }
+            debug!("[{}]: Completed", name);
+        };
+
+        self.register(name, exit_signal, task_with_error_log);
+    }
+
+    /// Spawn a new task which gets restarted when it fails and register it in the task manager.
+    ///
+    /// The task is created again with `task` after every error, waiting longer before each
+    /// restart while it keeps crashing. Like tasks spawned with `spawn` it gets dropped on
+    /// shutdown.
+    pub fn spawn_supervised<F>(&mut self, name: &'static str, task: impl Fn() -> F + Send + 'static)
+    where
+        F: Future<Output = FutureResult<()>> + Send + 'static,
+    {
+        let (exit_signal, on_exit) = exit_future::signal();
+        let initial_backoff = self.restart_backoff;
+
+        let supervise_task = async move {
+            let mut backoff = initial_backoff;
+
+            loop {
+                let started = Instant::now();
+                let run = task();
+                futures::pin_mut!(run);
+
+                let error = match future::select(on_exit.clone(), run).await {
+                    Either::Left(_) | Either::Right((Ok(()), _)) => break,
+                    Either::Right((Err(e), _)) => e,
+                };
+
+                // Start again with a short backoff when the task was running fine for a while
+                if started.elapsed() > MAX_RESTART_BACKOFF {
+                    backoff = initial_backoff;
+                }
+
+                warn!("[{}]: ERROR @ {}, restarting in {:?}", name, error, backoff);
+
+                if let Either::Left(_) =
+                    future::select(on_exit.clone(), Box::pin(task::sleep(backoff))).await
+                {
+                    break;
+                }
+
+                backoff = (backoff * 2).min(MAX_RESTART_BACKOFF);
+            }
+
+            debug!("[{}]: Completed", name);
+        };
+
+        self.register(name, exit_signal, supervise_task);
     }
 
     /// Signal all tasks to exit and wait until they are actually shut down.
-    pub async fn shutdown(mut self) {
-        if let Some(exit_signal) = self.exit_signal.take() {
-            let _ = exit_signal.fire();
+    ///
+    /// Tasks are stopped one by one, starting with the one spawned last.
+    pub async fn shutdown(self) {
+        for managed_task in self.tasks.into_iter().rev() {
+            debug!("[{}]: Shutdown", managed_task.name);
+            let _ = managed_task.exit_signal.fire();
+            managed_task.handle.await;
         }
-
-        futures::future::join_all(self.tasks).await;
     }
 }
 
@@ -66,6 +167,7 @@ impl TaskManager {
 mod tests {
     use super::{FutureResult, TaskManager};
 
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
     use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
@@ -132,4 +234,63 @@ mod tests {
         task::block_on(task_manager.shutdown());
         assert_eq!(drop_tester, 0);
     }
+
+    #[test]
+    fn wait_for_graceful_tasks_on_shutdown() {
+        let mut task_manager = TaskManager::new();
+        let completed = Arc::new(AtomicBool::new(false));
+
+        let completed_ref = completed.clone();
+        task_manager.spawn_graceful("task", |on_exit| async move {
+            on_exit.await;
+            task::sleep(Duration::from_millis(100)).await;
+            completed_ref.store(true, Ordering::SeqCst);
+            Ok(())
+        });
+
+        task::block_on(task_manager.shutdown());
+        assert!(completed.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn shutdown_tasks_in_reverse_order() {
+        let mut task_manager = TaskManager::new();
+        let stopped = Arc::new(Mutex::new(Vec::new()));
+
+        for name in ["first", "second", "third"] {
+            let stopped_ref = stopped.clone();
+            task_manager.spawn_graceful(name, move |on_exit| async move {
+                on_exit.await;
+                stopped_ref.lock().unwrap().push(name);
+                Ok(())
+            });
+        }
+
+        task::block_on(task_manager.shutdown());
+        assert_eq!(*stopped.lock().unwrap(), vec!["third", "second", "first"]);
+    }
+
+    #[test]
+    fn restart_failing_tasks() {
+        let mut task_manager = TaskManager::new();
+        task_manager.restart_backoff = Duration::from_millis(10);
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        // Task fails twice before it completes successfully
+        let runs_ref = runs.clone();
+        task_manager.spawn_supervised("task", move || {
+            let runs = runs_ref.fetch_add(1, Ordering::SeqCst) + 1;
+            async move {
+                if runs < 3 {
+                    return Err("crashed".into());
+                }
+                Ok(())
+            }
+        });
+
+        task::block_on(task::sleep(Duration::from_millis(200)));
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+
+        task::block_on(task_manager.shutdown());
+    }
 }
@@ -1,14 +1,22 @@
+use std::convert::TryFrom;
+
+use p2panda_rs::entry::{sign_and_encode, Entry, EntrySigned, LogId, SeqNum};
 use p2panda_rs::hash::Hash;
+use p2panda_rs::identity::{Author, KeyPair};
+use p2panda_rs::message::{Message, MessageEncoded, MessageFields, MessageValue};
 use rand::Rng;
 use sqlx::any::Any;
 use sqlx::migrate::MigrateDatabase;
 use tide_testing::TideTestingExt;
 
-use crate::db::{connection_pool, create_database, run_pending_migrations, Pool};
+use crate::db::models::{SchemaDefinition, SchemaField};
+use crate::db::{connection_pool, create_database, run_pending_migrations, Pool, PoolOptions};
 use crate::rpc::RpcServer;
 
 const DB_URL: &str = "sqlite::memory:";
 
+const SCHEMA_AUTHOR: &str = "58223678ab378f1b07d1d8c789e6da01d16a06b1a4d17cc10119a0109181156c";
+
 // Create test database
 pub async fn initialize_db() -> Pool {
     // Reset database first
@@ -16,7 +24,11 @@ pub async fn initialize_db() -> Pool {
     create_database(DB_URL).await.unwrap();
 
     // Create connection pool and run all migrations
-    let pool = connection_pool(DB_URL, 5).await.unwrap();
+    let options = PoolOptions {
+        max_connections: 5,
+        ..PoolOptions::default()
+    };
+    let pool = connection_pool(DB_URL, &options).await.unwrap();
     run_pending_migrations(&pool).await.unwrap();
 
     pool
@@ -29,6 +41,20 @@ pub async fn drop_database() {
     }
 }
 
+// Register a schema definition with the given field names and types without publishing it
+pub async fn define_test_schema(pool: &Pool, schema: &Hash, fields: &[(&str, &str)]) {
+    let author = Author::new(SCHEMA_AUTHOR).unwrap();
+    SchemaDefinition::insert(pool, schema, &author)
+        .await
+        .unwrap();
+
+    for (name, field_type) in fields {
+        SchemaField::insert(pool, schema, name, field_type)
+            .await
+            .unwrap();
+    }
+}
+
 // Generate random entry hash
 pub fn random_entry_hash() -> String {
     let random_data = rand::thread_rng().gen::<[u8; 32]>().to_vec();
@@ -39,6 +65,51 @@ pub fn random_entry_hash() -> String {
         .to_owned()
 }
 
+// Helper method to create encoded entries and messages
+pub fn create_test_entry(
+    key_pair: &KeyPair,
+    schema: &Hash,
+    log_id: &LogId,
+    skiplink: Option<&EntrySigned>,
+    backlink: Option<&EntrySigned>,
+    seq_num: &SeqNum,
+) -> (EntrySigned, MessageEncoded) {
+    // Create message with dummy data
+    let mut fields = MessageFields::new();
+    fields
+        .add("test", MessageValue::Text("Hello".to_owned()))
+        .unwrap();
+    let message = Message::new_create(schema.clone(), fields).unwrap();
+
+    create_test_entry_with_message(key_pair, &message, log_id, skiplink, backlink, seq_num)
+}
+
+// Helper method to create encoded entries for a given message
+pub fn create_test_entry_with_message(
+    key_pair: &KeyPair,
+    message: &Message,
+    log_id: &LogId,
+    skiplink: Option<&EntrySigned>,
+    backlink: Option<&EntrySigned>,
+    seq_num: &SeqNum,
+) -> (EntrySigned, MessageEncoded) {
+    // Encode message
+    let message_encoded = MessageEncoded::try_from(message).unwrap();
+
+    // Create, sign and encode entry
+    let entry = Entry::new(
+        log_id,
+        Some(message),
+        skiplink.map(|e| e.hash()).as_ref(),
+        backlink.map(|e| e.hash()).as_ref(),
+        seq_num,
+    )
+    .unwrap();
+    let entry_encoded = sign_and_encode(&entry, key_pair).unwrap();
+
+    (entry_encoded, message_encoded)
+}
+
 // Helper method to generate valid JSON RPC request string
 pub fn rpc_request(method: &str, params: &str) -> String {
     format!(
@@ -0,0 +1,282 @@
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use async_std::channel::Receiver;
+use async_std::task;
+use hmac::{Hmac, Mac, NewMac};
+use p2panda_rs::hash::Hash;
+use serde::Serialize;
+use sha2::Sha256;
+use surf::Url;
+use tracing::{debug, error, warn};
+
+use crate::config::Configuration;
+use crate::events::NodeEvent;
+use crate::materializer::{DocumentChange, DocumentFields};
+
+/// HTTP header carrying the signature of a webhook payload.
+const SIGNATURE_HEADER: &str = "X-Aquadoggo-Signature";
+
+/// Maximum number of attempts to deliver a payload before giving up.
+const MAX_ATTEMPTS: u32 = 5;
+
+/// Time to wait before retrying a failed delivery, doubled after every attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(2);
+
+/// JSON body posted to webhook URLs.
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct WebhookPayload<'a> {
+    action: &'static str,
+    schema: &'a Hash,
+    document_id: &'a str,
+    author: &'a str,
+    entry_hash: &'a str,
+    seq_num: i64,
+    fields: &'a DocumentFields,
+}
+
+/// Posts created and updated documents to the URLs configured for their schema.
+///
+/// Payloads are signed with HMAC-SHA256 using the configured secret, receivers can verify the
+/// hex encoded signature given in the `X-Aquadoggo-Signature` header.
+#[derive(Debug, Clone)]
+pub struct Webhooks {
+    hooks: Vec<(Hash, Url)>,
+    secret: String,
+    backoff: Duration,
+    client: surf::Client,
+}
+
+impl Webhooks {
+    /// Returns the webhooks configured for this node, `None` when there are none.
+    pub fn new(config: &Configuration) -> Result<Option<Self>> {
+        if config.webhooks.is_empty() {
+            return Ok(None);
+        }
+
+        let secret = match &config.webhook_secret {
+            Some(secret) if !secret.is_empty() => secret.to_owned(),
+            _ => bail!("WEBHOOK_SECRET is required to sign webhook payloads"),
+        };
+
+        let hooks = config
+            .webhooks
+            .iter()
+            .map(|webhook| {
+                let mut parts = webhook.splitn(2, '=');
+                match (parts.next(), parts.next()) {
+                    (Some(schema), Some(url)) => Ok((Hash::new(schema)?, Url::parse(url)?)),
+                    _ => bail!("Invalid webhook {}, expected <schema hash>=<url>", webhook),
+                }
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Some(Self {
+            hooks,
+            secret,
+            backoff: INITIAL_BACKOFF,
+            client: surf::Client::new(),
+        }))
+    }
+
+    /// Returns the hex encoded HMAC-SHA256 signature of a payload.
+    fn sign(&self, body: &[u8]) -> String {
+        // HMAC can take keys of any size
+        let mut mac = Hmac::<Sha256>::new_from_slice(self.secret.as_bytes()).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    /// Returns the URLs configured for a schema.
+    fn urls(&self, schema: &Hash) -> Vec<Url> {
+        self.hooks
+            .iter()
+            .filter(|(hook_schema, _)| hook_schema == schema)
+            .map(|(_, url)| url.clone())
+            .collect()
+    }
+
+    /// Post a payload to a URL, retrying failed attempts with exponential backoff.
+    ///
+    /// Returns true when the receiver responded with a successful status code.
+    async fn deliver(&self, url: Url, body: Vec<u8>) -> bool {
+        let signature = self.sign(&body);
+        let mut backoff = self.backoff;
+
+        for attempt in 1..=MAX_ATTEMPTS {
+            let result = self
+                .client
+                .post(url.clone())
+                .header(SIGNATURE_HEADER, signature.as_str())
+                .content_type(surf::http::mime::JSON)
+                .body(body.clone())
+                .await;
+
+            match result {
+                Ok(response) if response.status().is_success() => {
+                    debug!("Delivered webhook to {}", url);
+                    return true;
+                }
+                Ok(response) => warn!(
+                    "Webhook {} responded with {} (attempt {})",
+                    url,
+                    response.status(),
+                    attempt
+                ),
+                Err(err) => warn!(
+                    "Could not deliver webhook {}: {} (attempt {})",
+                    url, err, attempt
+                ),
+            }
+
+            if attempt < MAX_ATTEMPTS {
+                task::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+
+        error!("Giving up to deliver webhook to {}", url);
+        false
+    }
+
+    /// Post a created or updated document to all URLs configured for its schema.
+    ///
+    /// Deliveries run in the background so that slow receivers do not hold up others.
+    pub fn notify(&self, change: &DocumentChange) {
+        let urls = self.urls(&change.schema);
+        if urls.is_empty() {
+            return;
+        }
+
+        let payload = WebhookPayload {
+            action: if change.created { "create" } else { "update" },
+            schema: &change.schema,
+            document_id: &change.document.document_id,
+            author: &change.document.author,
+            entry_hash: &change.document.entry_hash,
+            seq_num: change.document.seq_num,
+            fields: &change.document.fields,
+        };
+
+        // Serializing documents can not fail
+        let body = serde_json::to_vec(&payload).unwrap();
+
+        for url in urls {
+            let webhooks = self.clone();
+            let body = body.clone();
+            task::spawn(async move {
+                webhooks.deliver(url, body).await;
+            });
+        }
+    }
+}
+
+/// Post every document materialized by the node to its webhooks until the channel got closed.
+///
+/// Deliveries which are still retried when the node shuts down are dropped.
+pub async fn start_webhooks(webhooks: Webhooks, receiver: Receiver<NodeEvent>) -> Result<()> {
+    while let Ok(event) = receiver.recv().await {
+        if let NodeEvent::DocumentMaterialized(change) = event {
+            webhooks.notify(&change);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use p2panda_rs::hash::Hash;
+    use rand::Rng;
+    use surf::Url;
+    use tide::{Request, Response, StatusCode};
+
+    use super::{Webhooks, SIGNATURE_HEADER};
+
+    use crate::config::Configuration;
+
+    fn webhooks(url: &str) -> Webhooks {
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+
+        let config = Configuration {
+            webhooks: vec![format!("{}={}", schema.as_str(), url)],
+            webhook_secret: Some("Jefe".to_owned()),
+            ..Configuration::default()
+        };
+
+        let mut webhooks = Webhooks::new(&config).unwrap().unwrap();
+        webhooks.backoff = Duration::from_millis(10);
+        webhooks
+    }
+
+    #[test]
+    fn parse_configuration() {
+        assert!(Webhooks::new(&Configuration::default()).unwrap().is_none());
+
+        let webhooks = webhooks("http://localhost:3000/hook");
+        let schema = Hash::new_from_bytes(vec![1, 2, 3]).unwrap();
+        let other_schema = Hash::new_from_bytes(vec![4, 5, 6]).unwrap();
+        assert_eq!(webhooks.urls(&schema).len(), 1);
+        assert!(webhooks.urls(&other_schema).is_empty());
+
+        // Payloads can not be signed without a secret
+        let config = Configuration {
+            webhooks: vec![format!("{}=http://localhost:3000", schema.as_str())],
+            ..Configuration::default()
+        };
+        assert!(Webhooks::new(&config).is_err());
+
+        let config = Configuration {
+            webhooks: vec!["http://localhost:3000".to_owned()],
+            webhook_secret: Some("Jefe".to_owned()),
+            ..Configuration::default()
+        };
+        assert!(Webhooks::new(&config).is_err());
+    }
+
+    #[test]
+    fn sign_payloads() {
+        let webhooks = webhooks("http://localhost:3000/hook");
+
+        // Test vector from RFC 4231
+        assert_eq!(
+            webhooks.sign(b"what do ya want for nothing?"),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[async_std::test]
+    async fn retry_failed_deliveries() {
+        // Start a receiver failing the first request
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut app = tide::with_state(received.clone());
+        app.at("/hook")
+            .post(|request: Request<Arc<Mutex<Vec<String>>>>| async move {
+                let signature = request.header(SIGNATURE_HEADER).unwrap().as_str();
+
+                let mut received = request.state().lock().unwrap();
+                received.push(signature.to_owned());
+
+                match received.len() {
+                    1 => Ok(Response::new(StatusCode::InternalServerError)),
+                    _ => Ok(Response::new(StatusCode::Ok)),
+                }
+            });
+
+        let port = rand::thread_rng().gen_range(20000..30000);
+        async_std::task::spawn(app.listen(format!("127.0.0.1:{}", port)));
+        async_std::task::sleep(Duration::from_millis(100)).await;
+
+        let webhooks = webhooks(&format!("http://127.0.0.1:{}/hook", port));
+        let url = Url::parse(&format!("http://127.0.0.1:{}/hook", port)).unwrap();
+        assert!(webhooks.deliver(url, b"{}".to_vec()).await);
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(received[0], webhooks.sign(b"{}"));
+    }
+}
@@ -1,34 +1,105 @@
+use std::path::PathBuf;
+
 use async_ctrlc::CtrlC;
-use async_std::task;
 use structopt::StructOpt;
+use tracing_subscriber::EnvFilter;
 
-use aquadoggo::{Configuration, Runtime};
+use aquadoggo::{export_entries, import_entries, keygen, rebuild_views, Configuration, Node};
 
 #[derive(StructOpt, Debug)]
 #[structopt(name = "aquadoggo Node", about = "Node server for the p2panda network")]
 struct Opt {
     /// Path to data folder, $HOME/.local/share/aquadoggo by default on Linux.
     #[structopt(short, long, parse(from_os_str))]
-    data_dir: Option<std::path::PathBuf>,
+    data_dir: Option<PathBuf>,
+
+    /// Path to configuration file, <data-dir>/config.toml by default.
+    #[structopt(short, long, parse(from_os_str))]
+    config: Option<PathBuf>,
+
+    #[structopt(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(StructOpt, Debug)]
+enum Command {
+    /// Start the node, this is the default when no subcommand is given.
+    Serve,
+
+    /// Print the peer id of this node, generating a new network key when none exists yet.
+    Keygen,
+
+    /// Write all stored entries to a file, one JSON object per line.
+    Export {
+        /// Path to export file, use "-" to write to standard output.
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Validate and store entries from a file written by the export command.
+    Import {
+        /// Path to export file, use "-" to read from standard input.
+        #[structopt(parse(from_os_str))]
+        file: PathBuf,
+    },
+
+    /// Drop all materialized documents and materialize them again from the stored entries.
+    RebuildViews,
 }
 
 #[async_std::main]
 async fn main() {
-    env_logger::init();
+    // Log spans and events filtered by `RUST_LOG`, this includes records of the `log` crate. Logs
+    // are written to stderr to keep stdout free for exported entries
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .with_writer(std::io::stderr)
+        .init();
 
     // Parse command line arguments and load configuration
     let opt = Opt::from_args();
-    let config = Configuration::new(opt.data_dir).expect("Could not load configuration");
+    let config =
+        Configuration::new(opt.data_dir, opt.config).expect("Could not load configuration");
+
+    // Run maintenance commands instead of starting the node
+    match opt.command.unwrap_or(Command::Serve) {
+        Command::Serve => (),
+        Command::Keygen => {
+            let peer_id = keygen(&config).expect("Could not load network key pair");
+            println!("{}", peer_id);
+            return;
+        }
+        Command::Export { file } => {
+            let exported = export_entries(config, &file)
+                .await
+                .expect("Could not export entries");
+            eprintln!("Exported {} entries", exported);
+            return;
+        }
+        Command::Import { file } => {
+            let imported = import_entries(config, &file)
+                .await
+                .expect("Could not import entries");
+            println!("Imported {} entries", imported);
+            return;
+        }
+        Command::RebuildViews => {
+            rebuild_views(config)
+                .await
+                .expect("Could not rebuild materialized views");
+            return;
+        }
+    }
 
     // Start p2panda node in async runtime
-    let node = Runtime::start(config).await;
+    let node = Node::start(config).await.expect("Could not start node");
 
-    // Run this until [CTRL] + [C] got pressed
-    task::block_on(async {
-        let ctrlc = CtrlC::new().unwrap();
-        ctrlc.await;
-    });
+    // Run this until [CTRL] + [C] got pressed or the process got terminated (SIGINT / SIGTERM)
+    CtrlC::new()
+        .expect("Could not register signal handler")
+        .await;
+    println!("Shutting down node, this might take a moment");
 
     // Wait until all tasks are gracefully shut down and exit
-    task::block_on(node.shutdown());
+    node.shutdown().await;
 }
@@ -0,0 +1,31 @@
+/* C API to run an embedded aquadoggo node. */
+#ifndef AQUADOGGO_H
+#define AQUADOGGO_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Opaque handle of a running node. */
+typedef struct AquadoggoNode AquadoggoNode;
+
+/*
+ * Start a node storing its data in the given directory and return a handle to it.
+ *
+ * The data directory of the platform is used when `data_dir` is NULL. Settings are read from
+ * `config.toml` in the data directory. Returns NULL when the node could not be started.
+ */
+AquadoggoNode *aquadoggo_start(const char *data_dir);
+
+/*
+ * Shut down a node and free its handle, blocks until all tasks of the node are stopped.
+ *
+ * Passing NULL does nothing.
+ */
+void aquadoggo_shutdown(AquadoggoNode *node);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* AQUADOGGO_H */
@@ -0,0 +1,119 @@
+//! C API to run an embedded aquadoggo node, for example inside Android and iOS apps.
+//!
+//! Nodes are started with `aquadoggo_start` which returns an opaque handle, the node keeps
+//! running in background threads until the handle is passed to `aquadoggo_shutdown`. See
+//! `include/aquadoggo.h` for the C declarations.
+#![warn(
+    missing_copy_implementations,
+    missing_debug_implementations,
+    missing_docs,
+    trivial_casts,
+    trivial_numeric_casts,
+    unused_import_braces,
+    unused_qualifications
+)]
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::path::PathBuf;
+use std::ptr;
+
+use aquadoggo::{Configuration, Node};
+use async_std::task;
+use tracing::error;
+
+/// Running node, handed out to the host application as an opaque pointer.
+#[allow(missing_debug_implementations)]
+pub struct AquadoggoNode(Node);
+
+/// Load the configuration from the data directory and start the node.
+async fn start(data_dir: Option<PathBuf>) -> anyhow::Result<Node> {
+    let config = Configuration::new(data_dir, None)?;
+    Node::start(config).await
+}
+
+/// Start a node storing its data in the given directory and return a handle to it.
+///
+/// The data directory of the platform is used when `data_dir` is null. Settings are read from
+/// `config.toml` in the data directory and from environment variables, like with the command line
+/// application. Returns null when the node could not be started.
+///
+/// # Safety
+///
+/// `data_dir` must be null or point to a null-terminated UTF-8 string.
+#[no_mangle]
+pub unsafe extern "C" fn aquadoggo_start(data_dir: *const c_char) -> *mut AquadoggoNode {
+    let data_dir = if data_dir.is_null() {
+        None
+    } else {
+        match CStr::from_ptr(data_dir).to_str() {
+            Ok(path) => Some(PathBuf::from(path)),
+            Err(_) => {
+                error!("Data directory path is not valid UTF-8");
+                return ptr::null_mut();
+            }
+        }
+    };
+
+    match task::block_on(start(data_dir)) {
+        Ok(node) => Box::into_raw(Box::new(AquadoggoNode(node))),
+        Err(err) => {
+            error!("Could not start node: {:#}", err);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Shut down a node and free its handle, blocks until all tasks of the node are stopped.
+///
+/// Passing null does nothing.
+///
+/// # Safety
+///
+/// `node` must be null or a handle returned by `aquadoggo_start` which was not shut down yet.
+#[no_mangle]
+pub unsafe extern "C" fn aquadoggo_shutdown(node: *mut AquadoggoNode) {
+    if node.is_null() {
+        return;
+    }
+
+    let node = Box::from_raw(node);
+    task::block_on(node.0.shutdown());
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::fs;
+    use std::ptr;
+
+    use rand::Rng;
+
+    use super::{aquadoggo_shutdown, aquadoggo_start};
+
+    #[test]
+    fn start_and_shutdown_node() {
+        let data_dir = std::env::temp_dir().join(format!("aquadoggo-{}", rand::random::<u64>()));
+        fs::create_dir_all(&data_dir).unwrap();
+
+        let http_port = rand::thread_rng().gen_range(40000..50000);
+        fs::write(
+            data_dir.join("config.toml"),
+            format!(
+                "http_address = \"127.0.0.1\"\nhttp_port = {}\nnetwork_port = {}\nnetwork_mdns = false\n",
+                http_port,
+                http_port + 1
+            ),
+        )
+        .unwrap();
+
+        let path = CString::new(data_dir.to_str().unwrap()).unwrap();
+        let node = unsafe { aquadoggo_start(path.as_ptr()) };
+        assert!(!node.is_null());
+        unsafe { aquadoggo_shutdown(node) };
+
+        // Shutting down without a node does nothing
+        unsafe { aquadoggo_shutdown(ptr::null_mut()) };
+
+        fs::remove_dir_all(data_dir).unwrap();
+    }
+}